@@ -0,0 +1,1187 @@
+// 声明式工具适配器：用一份 spec 描述 OpenAI 兼容 CLI 的配置文件、格式以及 Base URL / Key / 模型所在的键，
+// 渠道的增删改、切换、回滚与当前渠道识别对所有 spec 通用，不必再为每个工具单独编写模块
+use crate::channel::{
+    normalize_base_url, Channel, ChannelBackend, ChannelTestTarget, SwitchSnapshot, TestAuth,
+    ToolKind,
+};
+use crate::fs_utils::{
+    conflict_error, ensure_unchanged, home_dir, read_env_value, read_stamped_text,
+    restore_files_with_rollback, run_blocking, update_env_content, with_dir_lock,
+    write_files_with_rollback, write_text_file, FileStamp,
+};
+use crate::ApiResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item as TomlEditItem, Table as TomlEditTable, TableLike};
+
+const ADAPTER_STORE_VERSION: u32 = 1;
+// 用户自定义 spec，与内置 spec 同 id 时覆盖内置
+const ADAPTER_SPEC_FILE_NAME: &str = "tool_adapters.json";
+const ADAPTER_STORE_PREFIX: &str = "adapter-";
+const ADAPTER_ID_MAX_LEN: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+    Env,
+}
+
+// 一个配置文件中的映射；键路径以 . 分隔，.env 中即变量名
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct AdapterFile {
+    // 以 ~/ 开头或相对路径均相对用户主目录
+    path: String,
+    format: ConfigFormat,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    // 切换时额外写入的值，键与值中的 {base_url}、{api_key}、{model} 会被替换；引用的字段为空时跳过
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct AdapterSpec {
+    id: String,
+    name: String,
+    files: Vec<AdapterFile>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct AdapterInfo {
+    #[serde(flatten)]
+    spec: AdapterSpec,
+    builtin: bool,
+    // 最近一次切换前的配置是否可回滚
+    can_rollback: bool,
+}
+
+// 切换前的文件内容，None 表示切换前文件不存在
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+struct FileSnapshot {
+    path: PathBuf,
+    content: Option<String>,
+    // 切换前该文件由 values 写入的键，回滚时一并恢复
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    written: Vec<KeyPath>,
+}
+
+// 按段拆分的键路径，{model} 等占位符展开后的值始终是一段，可以包含 .
+type KeyPath = Vec<String>;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct AdapterStore {
+    #[serde(default = "adapter_store_version")]
+    version: u32,
+    #[serde(default)]
+    revision: u64,
+    #[serde(default)]
+    channels: Vec<Channel>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rollback: Vec<FileSnapshot>,
+    // 上次切换时 values 在各文件中写入的键，下次切换不再写入时删除，避免旧模型等条目堆积
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    written: BTreeMap<PathBuf, Vec<KeyPath>>,
+}
+
+fn adapter_store_version() -> u32 {
+    ADAPTER_STORE_VERSION
+}
+
+fn adapter_file(path: &str, format: ConfigFormat) -> AdapterFile {
+    AdapterFile {
+        path: path.to_string(),
+        format,
+        base_url: None,
+        api_key: None,
+        model: None,
+        values: BTreeMap::new(),
+    }
+}
+
+fn builtin_specs() -> Vec<AdapterSpec> {
+    let qwen = AdapterFile {
+        base_url: Some("OPENAI_BASE_URL".to_string()),
+        api_key: Some("OPENAI_API_KEY".to_string()),
+        model: Some("OPENAI_MODEL".to_string()),
+        ..adapter_file("~/.qwen/.env", ConfigFormat::Env)
+    };
+
+    // opencode 通过自定义 provider 接入 OpenAI 兼容接口，模型以 provider/model 引用
+    let provider = "provider.channel-switcher";
+    let opencode = AdapterFile {
+        base_url: Some(format!("{}.options.baseURL", provider)),
+        api_key: Some(format!("{}.options.apiKey", provider)),
+        values: BTreeMap::from([
+            (
+                format!("{}.npm", provider),
+                "@ai-sdk/openai-compatible".to_string(),
+            ),
+            (format!("{}.name", provider), "Channel Switcher".to_string()),
+            (
+                format!("{}.models.{{model}}.name", provider),
+                "{model}".to_string(),
+            ),
+            ("model".to_string(), "channel-switcher/{model}".to_string()),
+        ]),
+        ..adapter_file("~/.config/opencode/opencode.json", ConfigFormat::Json)
+    };
+
+    vec![
+        AdapterSpec {
+            id: "qwen-code".to_string(),
+            name: "Qwen Code".to_string(),
+            files: vec![qwen],
+        },
+        AdapterSpec {
+            id: "opencode".to_string(),
+            name: "opencode".to_string(),
+            files: vec![opencode],
+        },
+    ]
+}
+
+fn validate_spec(spec: &AdapterSpec) -> Result<(), String> {
+    let valid_id = !spec.id.is_empty()
+        && spec.id.len() <= ADAPTER_ID_MAX_LEN
+        && spec
+            .id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if !valid_id {
+        return Err(format!(
+            "适配器 id「{}」无效：仅支持英文字母、数字、-、_，最多{}位",
+            spec.id, ADAPTER_ID_MAX_LEN
+        ));
+    }
+    if spec.files.is_empty() {
+        return Err(format!("适配器 {} 未配置任何文件", spec.id));
+    }
+    if !spec.files.iter().any(|file| file.api_key.is_some()) {
+        return Err(format!("适配器 {} 未指定 API Key 所在的键", spec.id));
+    }
+    Ok(())
+}
+
+// 内置 spec 在前，自定义 spec 按 id 覆盖或追加
+fn load_specs(config_dir: &Path) -> Result<Vec<(AdapterSpec, bool)>, String> {
+    let mut specs: Vec<(AdapterSpec, bool)> = builtin_specs()
+        .into_iter()
+        .map(|spec| (spec, true))
+        .collect();
+
+    let custom_path = config_dir.join(ADAPTER_SPEC_FILE_NAME);
+    if !custom_path.exists() {
+        return Ok(specs);
+    }
+    let content = fs::read_to_string(&custom_path)
+        .map_err(|e| format!("读取 {} 失败: {}", ADAPTER_SPEC_FILE_NAME, e))?;
+    let custom: Vec<AdapterSpec> = serde_json::from_str(&content)
+        .map_err(|e| format!("解析 {} 失败: {}", ADAPTER_SPEC_FILE_NAME, e))?;
+
+    for spec in custom {
+        validate_spec(&spec)?;
+        match specs.iter_mut().find(|(current, _)| current.id == spec.id) {
+            Some(current) => *current = (spec, false),
+            None => specs.push((spec, false)),
+        }
+    }
+    Ok(specs)
+}
+
+fn find_spec(config_dir: &Path, id: &str) -> Result<AdapterSpec, String> {
+    load_specs(config_dir)?
+        .into_iter()
+        .map(|(spec, _)| spec)
+        .find(|spec| spec.id == id)
+        .ok_or_else(|| format!("未找到工具适配器: {}", id))
+}
+
+fn resolve_spec_path(path: &str, home: &Path) -> PathBuf {
+    let relative = path
+        .strip_prefix("~/")
+        .or_else(|| path.strip_prefix("~\\"))
+        .unwrap_or(path);
+    home.join(relative)
+}
+
+fn adapter_store_path(config_dir: &Path, id: &str) -> PathBuf {
+    config_dir.join(format!("{}{}.json", ADAPTER_STORE_PREFIX, id))
+}
+
+fn load_adapter_store(path: &Path) -> Result<AdapterStore, String> {
+    if !path.exists() {
+        return Ok(AdapterStore {
+            version: ADAPTER_STORE_VERSION,
+            revision: 0,
+            channels: vec![],
+            rollback: vec![],
+            written: BTreeMap::new(),
+        });
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("读取渠道存储失败: {}", e))?;
+    let store: AdapterStore =
+        serde_json::from_str(&content).map_err(|e| format!("解析渠道存储失败: {}", e))?;
+    if store.version > ADAPTER_STORE_VERSION {
+        return Err(format!(
+            "渠道存储版本 v{} 高于当前支持的 v{}，请升级应用",
+            store.version, ADAPTER_STORE_VERSION
+        ));
+    }
+    Ok(store)
+}
+
+fn serialize_adapter_store(store: &AdapterStore) -> Result<String, String> {
+    serde_json::to_string_pretty(store).map_err(|e| format!("序列化渠道存储失败: {}", e))
+}
+
+// ==================== 配置文件读写 ====================
+
+enum ConfigDocument {
+    Json(serde_json::Value),
+    Toml(DocumentMut),
+    Yaml(serde_norway::Value),
+    Env(String),
+}
+
+fn split_key_path(path: &str) -> Vec<&str> {
+    path.split('.').filter(|key| !key.is_empty()).collect()
+}
+
+// 先按 . 拆分再替换占位符，模型名中的 . 不会被当成层级
+fn expand_key_path(template: &str, channel: &Channel) -> Option<KeyPath> {
+    split_key_path(template)
+        .into_iter()
+        .map(|key| expand_template(key, channel))
+        .collect()
+}
+
+fn toml_parent_mut<'a>(
+    document: &'a mut DocumentMut,
+    parents: &[&str],
+) -> Result<&'a mut dyn TableLike, String> {
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for key in parents {
+        let item = table.entry(key).or_insert_with(|| {
+            let mut implicit = TomlEditTable::new();
+            implicit.set_implicit(true);
+            TomlEditItem::Table(implicit)
+        });
+        table = item
+            .as_table_like_mut()
+            .ok_or_else(|| format!("TOML 键 {} 不是表", key))?;
+    }
+    Ok(table)
+}
+
+fn yaml_parent_mut<'a>(
+    mut value: &'a mut serde_norway::Value,
+    parents: &[&str],
+) -> &'a mut serde_norway::Mapping {
+    for key in parents {
+        if !value.is_mapping() {
+            *value = serde_norway::Value::Mapping(serde_norway::Mapping::new());
+        }
+        value = value
+            .as_mapping_mut()
+            .unwrap()
+            .entry(serde_norway::Value::String(key.to_string()))
+            .or_insert(serde_norway::Value::Null);
+    }
+    if !value.is_mapping() {
+        *value = serde_norway::Value::Mapping(serde_norway::Mapping::new());
+    }
+    value.as_mapping_mut().unwrap()
+}
+
+fn json_parent_mut<'a>(
+    mut value: &'a mut serde_json::Value,
+    parents: &[&str],
+) -> &'a mut serde_json::Map<String, serde_json::Value> {
+    for key in parents {
+        if !value.is_object() {
+            *value = serde_json::json!({});
+        }
+        value = value
+            .as_object_mut()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert(serde_json::Value::Null);
+    }
+    if !value.is_object() {
+        *value = serde_json::json!({});
+    }
+    value.as_object_mut().unwrap()
+}
+
+fn json_remove_pruning(value: &mut serde_json::Value, keys: &[String]) {
+    let (Some(object), Some((first, rest))) = (value.as_object_mut(), keys.split_first()) else {
+        return;
+    };
+    if rest.is_empty() {
+        object.remove(first);
+        return;
+    }
+    if let Some(child) = object.get_mut(first) {
+        json_remove_pruning(child, rest);
+        if child.as_object().is_some_and(|child| child.is_empty()) {
+            object.remove(first);
+        }
+    }
+}
+
+fn toml_remove_pruning(table: &mut dyn TableLike, keys: &[String]) {
+    let Some((first, rest)) = keys.split_first() else {
+        return;
+    };
+    if rest.is_empty() {
+        table.remove(first);
+        return;
+    }
+    if let Some(child) = table
+        .get_mut(first)
+        .and_then(TomlEditItem::as_table_like_mut)
+    {
+        toml_remove_pruning(child, rest);
+        if child.is_empty() {
+            table.remove(first);
+        }
+    }
+}
+
+fn yaml_remove_pruning(value: &mut serde_norway::Value, keys: &[String]) {
+    let (Some(mapping), Some((first, rest))) = (value.as_mapping_mut(), keys.split_first()) else {
+        return;
+    };
+    let key = serde_norway::Value::String(first.clone());
+    if rest.is_empty() {
+        mapping.remove(&key);
+        return;
+    }
+    if let Some(child) = mapping.get_mut(&key) {
+        yaml_remove_pruning(child, rest);
+        if child.as_mapping().is_some_and(|child| child.is_empty()) {
+            mapping.remove(&key);
+        }
+    }
+}
+
+impl ConfigDocument {
+    fn parse(format: ConfigFormat, content: &str) -> Result<Self, String> {
+        let blank = content.trim().is_empty();
+        match format {
+            ConfigFormat::Json if blank => Ok(ConfigDocument::Json(serde_json::json!({}))),
+            ConfigFormat::Json => serde_json::from_str(content)
+                .map(ConfigDocument::Json)
+                .map_err(|e| format!("解析 JSON 失败: {}", e)),
+            ConfigFormat::Toml => content
+                .parse::<DocumentMut>()
+                .map(ConfigDocument::Toml)
+                .map_err(|e| format!("解析 TOML 失败: {}", e)),
+            ConfigFormat::Yaml if blank => Ok(ConfigDocument::Yaml(serde_norway::Value::Mapping(
+                serde_norway::Mapping::new(),
+            ))),
+            ConfigFormat::Yaml => serde_norway::from_str(content)
+                .map(ConfigDocument::Yaml)
+                .map_err(|e| format!("解析 YAML 失败: {}", e)),
+            ConfigFormat::Env => Ok(ConfigDocument::Env(content.to_string())),
+        }
+    }
+
+    fn get(&self, path: &str) -> Option<String> {
+        let keys = split_key_path(path);
+        match self {
+            ConfigDocument::Json(value) => keys
+                .iter()
+                .try_fold(value, |current, key| current.get(key))?
+                .as_str()
+                .map(str::to_string),
+            ConfigDocument::Toml(document) => {
+                let (last, parents) = keys.split_last()?;
+                let mut table: &dyn TableLike = document.as_table();
+                for key in parents {
+                    table = table.get(key)?.as_table_like()?;
+                }
+                table.get(last)?.as_str().map(str::to_string)
+            }
+            ConfigDocument::Yaml(value) => keys
+                .iter()
+                .try_fold(value, |current, key| current.get(key))?
+                .as_str()
+                .map(str::to_string),
+            ConfigDocument::Env(content) => read_env_value(content, path),
+        }
+    }
+
+    // 值为 None 时删除该键
+    fn set(&mut self, path: &str, value: Option<&str>) -> Result<(), String> {
+        self.set_keys(&split_key_path(path), value)
+    }
+
+    fn set_keys<K: AsRef<str>>(&mut self, keys: &[K], value: Option<&str>) -> Result<(), String> {
+        let keys: Vec<&str> = keys.iter().map(AsRef::as_ref).collect();
+        let (last, parents) = keys
+            .split_last()
+            .ok_or_else(|| "键路径无效：为空".to_string())?;
+        match self {
+            ConfigDocument::Json(document) => {
+                let parent = json_parent_mut(document, parents);
+                match value {
+                    Some(value) => {
+                        parent.insert(last.to_string(), serde_json::json!(value));
+                    }
+                    None => {
+                        parent.remove(*last);
+                    }
+                }
+            }
+            ConfigDocument::Toml(document) => {
+                let parent = toml_parent_mut(document, parents)?;
+                match value {
+                    Some(value) => {
+                        parent.insert(last, toml_edit::value(value));
+                    }
+                    None => {
+                        parent.remove(last);
+                    }
+                }
+            }
+            ConfigDocument::Yaml(document) => {
+                let parent = yaml_parent_mut(document, parents);
+                let key = serde_norway::Value::String(last.to_string());
+                match value {
+                    Some(value) => {
+                        parent.insert(key, serde_norway::Value::String(value.to_string()));
+                    }
+                    None => {
+                        parent.remove(&key);
+                    }
+                }
+            }
+            ConfigDocument::Env(content) => {
+                *content = update_env_content(content, &[(&keys.join("."), value)]);
+            }
+        }
+        Ok(())
+    }
+
+    // 删除键后逐级清理因此变空的父级表
+    fn remove_keys(&mut self, keys: &[String]) {
+        if keys.is_empty() {
+            return;
+        }
+        match self {
+            ConfigDocument::Json(value) => json_remove_pruning(value, keys),
+            ConfigDocument::Toml(document) => toml_remove_pruning(document.as_table_mut(), keys),
+            ConfigDocument::Yaml(value) => yaml_remove_pruning(value, keys),
+            ConfigDocument::Env(content) => {
+                *content = update_env_content(content, &[(&keys.join("."), None)]);
+            }
+        }
+    }
+
+    fn render(&self) -> Result<String, String> {
+        match self {
+            ConfigDocument::Json(value) => {
+                serde_json::to_string_pretty(value).map_err(|e| format!("序列化 JSON 失败: {}", e))
+            }
+            ConfigDocument::Toml(document) => Ok(document.to_string()),
+            ConfigDocument::Yaml(value) => {
+                serde_norway::to_string(value).map_err(|e| format!("序列化 YAML 失败: {}", e))
+            }
+            ConfigDocument::Env(content) => Ok(content.clone()),
+        }
+    }
+}
+
+// 替换模板中的占位符；引用了空字段时返回 None
+fn expand_template(template: &str, channel: &Channel) -> Option<String> {
+    let mut expanded = template.to_string();
+    for (placeholder, value) in [
+        ("{base_url}", &channel.base_url),
+        ("{api_key}", &channel.api_key),
+        ("{model}", &channel.model),
+    ] {
+        if expanded.contains(placeholder) {
+            if value.is_empty() {
+                return None;
+            }
+            expanded = expanded.replace(placeholder, value);
+        }
+    }
+    Some(expanded)
+}
+
+// 按 spec 生成写入后的文件内容与本次由 values 写入的键；Base URL 为空时删除对应键，模型为空时保留原值，
+// previous 中本次不再写入的键被删除
+fn render_file(
+    file: &AdapterFile,
+    existing: &str,
+    channel: &Channel,
+    previous: &[KeyPath],
+) -> Result<(String, Vec<KeyPath>), String> {
+    let mut document = ConfigDocument::parse(file.format, existing)?;
+
+    if let Some(path) = &file.api_key {
+        document.set(path, Some(&channel.api_key))?;
+    }
+    if let Some(path) = &file.base_url {
+        let base_url = Some(channel.base_url.as_str()).filter(|url| !url.is_empty());
+        document.set(path, base_url)?;
+    }
+    if let (Some(path), false) = (&file.model, channel.model.is_empty()) {
+        document.set(path, Some(&channel.model))?;
+    }
+    let mut written = Vec::new();
+    for (path, template) in &file.values {
+        if let (Some(keys), Some(value)) = (
+            expand_key_path(path, channel),
+            expand_template(template, channel),
+        ) {
+            document.set_keys(&keys, Some(&value))?;
+            written.push(keys);
+        }
+    }
+    for keys in previous.iter().filter(|keys| !written.contains(keys)) {
+        document.remove_keys(keys);
+    }
+
+    Ok((document.render()?, written))
+}
+
+// 各文件中当前生效的 (API Key, Base URL)
+fn read_current_values(spec: &AdapterSpec, home: &Path) -> (String, String) {
+    let mut api_key = String::new();
+    let mut base_url = String::new();
+    for file in &spec.files {
+        let path = resolve_spec_path(&file.path, home);
+        let Some(document) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| ConfigDocument::parse(file.format, &content).ok())
+        else {
+            continue;
+        };
+        if let Some(value) = file.api_key.as_deref().and_then(|key| document.get(key)) {
+            api_key = value;
+        }
+        if let Some(value) = file.base_url.as_deref().and_then(|key| document.get(key)) {
+            base_url = value;
+        }
+    }
+    (api_key, base_url)
+}
+
+fn find_active_channel<'a>(
+    channels: &'a [Channel],
+    api_key: &str,
+    base_url: &str,
+) -> Option<&'a Channel> {
+    if api_key.is_empty() {
+        return None;
+    }
+    let base_url = match base_url.trim() {
+        "" => String::new(),
+        url => normalize_base_url(url).unwrap_or_else(|_| url.to_string()),
+    };
+    channels
+        .iter()
+        .find(|channel| channel.api_key == api_key && channel.base_url == base_url)
+}
+
+fn normalize_channel(channel: Channel) -> Result<Channel, String> {
+    let base_url = match channel.base_url.trim() {
+        "" => String::new(),
+        url => normalize_base_url(url)?,
+    };
+    let channel = Channel {
+        name: channel.name.trim().to_string(),
+        api_key: channel.api_key.trim().to_string(),
+        model: channel.model.trim().to_string(),
+        base_url,
+        ..channel
+    };
+
+    if channel.name.is_empty() || channel.name.chars().any(char::is_control) {
+        return Err("请输入有效的渠道名称".to_string());
+    }
+    if channel.api_key.is_empty() || channel.api_key.contains(['\n', '\r']) {
+        return Err("请输入有效的 API Key".to_string());
+    }
+    Ok(channel)
+}
+
+fn new_channel_id(channels: &[Channel]) -> String {
+    let mut seed = chrono::Utc::now()
+        .timestamp_nanos_opt()
+        .unwrap_or_default()
+        .unsigned_abs();
+    loop {
+        let id = format!("ad{:x}", seed);
+        if !channels.iter().any(|channel| channel.id == id) {
+            return id;
+        }
+        seed += 1;
+    }
+}
+
+// ==================== 统一渠道接口 ====================
+
+// 渠道存储与自定义 spec 都放在 Claude 配置目录下
+pub(crate) struct AdapterBackend {
+    config_dir: PathBuf,
+    home: PathBuf,
+    spec: AdapterSpec,
+}
+
+impl AdapterBackend {
+    pub(crate) fn load(config_dir: &Path, adapter_id: &str) -> Result<Self, String> {
+        Ok(AdapterBackend {
+            config_dir: config_dir.to_path_buf(),
+            home: home_dir()?,
+            spec: find_spec(config_dir, adapter_id)?,
+        })
+    }
+
+    fn store_path(&self) -> PathBuf {
+        adapter_store_path(&self.config_dir, &self.spec.id)
+    }
+
+    fn mutate_store<T, F>(&self, mutate: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut AdapterStore) -> Result<T, String>,
+    {
+        with_dir_lock(&self.config_dir, || {
+            let store_path = self.store_path();
+            let mut store = load_adapter_store(&store_path)?;
+            let result = mutate(&mut store)?;
+            store.revision += 1;
+            write_text_file(&store_path, &serialize_adapter_store(&store)?)?;
+            Ok(result)
+        })
+    }
+
+    fn load_store(&self) -> Result<AdapterStore, String> {
+        with_dir_lock(&self.config_dir, || load_adapter_store(&self.store_path()))
+    }
+
+    // 在目录锁内切换：所有配置文件与记录回滚快照的存储一起写入，任一失败全部回滚
+    fn apply_switch(&self, id: &str) -> Result<(), String> {
+        let store_path = self.store_path();
+        let mut store = load_adapter_store(&store_path)?;
+        let channel = store
+            .channels
+            .iter()
+            .find(|channel| channel.id == id)
+            .cloned()
+            .ok_or_else(|| "渠道不存在".to_string())?;
+
+        let mut targets: Vec<(PathBuf, Option<FileStamp>, String)> = Vec::new();
+        let mut snapshots = Vec::new();
+        let mut written = BTreeMap::new();
+        for file in &self.spec.files {
+            let path = resolve_spec_path(&file.path, &self.home);
+            let (existing, stamp) = read_stamped_text(&path)?;
+            let previous = store.written.remove(&path).unwrap_or_default();
+            let (updated, keys) = render_file(
+                file,
+                existing.as_deref().unwrap_or_default(),
+                &channel,
+                &previous,
+            )
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+            snapshots.push(FileSnapshot {
+                path: path.clone(),
+                content: existing,
+                written: previous,
+            });
+            if !keys.is_empty() {
+                written.insert(path.clone(), keys);
+            }
+            targets.push((path, stamp, updated));
+        }
+
+        store.rollback = snapshots;
+        store.written = written;
+        let serialized_store = serialize_adapter_store(&store)?;
+
+        for (path, stamp, _) in &targets {
+            ensure_unchanged(path, stamp)?;
+        }
+        let mut updates: Vec<(&Path, &str)> = targets
+            .iter()
+            .map(|(path, _, content)| (path.as_path(), content.as_str()))
+            .collect();
+        updates.push((store_path.as_path(), serialized_store.as_str()));
+        write_files_with_rollback(&updates)
+    }
+
+    // 恢复最近一次切换前的配置文件
+    fn rollback(&self) -> Result<(), String> {
+        with_dir_lock(&self.config_dir, || {
+            let store_path = self.store_path();
+            let mut store = load_adapter_store(&store_path)?;
+            if store.rollback.is_empty() {
+                return Err(conflict_error("没有可回滚的切换记录"));
+            }
+
+            // 与切换相同：先记录各文件的状态，写回前确认未被修改，再整体恢复，任一失败时全部复原
+            let snapshots = std::mem::take(&mut store.rollback);
+            let mut stamps = Vec::with_capacity(snapshots.len());
+            store.written.clear();
+            for snapshot in &snapshots {
+                let (_, stamp) = read_stamped_text(&snapshot.path)?;
+                stamps.push(stamp);
+                if !snapshot.written.is_empty() {
+                    store
+                        .written
+                        .insert(snapshot.path.clone(), snapshot.written.clone());
+                }
+            }
+            let serialized_store = serialize_adapter_store(&store)?;
+
+            for (snapshot, stamp) in snapshots.iter().zip(&stamps) {
+                ensure_unchanged(&snapshot.path, stamp)?;
+            }
+            let mut restores: Vec<(&Path, Option<String>)> = snapshots
+                .iter()
+                .map(|snapshot| (snapshot.path.as_path(), snapshot.content.clone()))
+                .collect();
+            restores.push((store_path.as_path(), Some(serialized_store)));
+            restore_files_with_rollback(&restores)
+        })
+    }
+}
+
+impl ChannelBackend for AdapterBackend {
+    fn tool(&self) -> ToolKind {
+        ToolKind::Adapter
+    }
+
+    fn list(&self) -> Result<Vec<Channel>, String> {
+        Ok(self.load_store()?.channels)
+    }
+
+    fn save(&self, channel: Channel) -> Result<String, String> {
+        let mut channel = normalize_channel(channel)?;
+        self.mutate_store(|store| {
+            let duplicate = store
+                .channels
+                .iter()
+                .any(|current| current.id != channel.id && current.name == channel.name);
+            if duplicate {
+                return Err("渠道名称已存在".to_string());
+            }
+
+            if channel.id.is_empty() {
+                channel.id = new_channel_id(&store.channels);
+                store.channels.insert(0, channel.clone());
+            } else {
+                let current = store
+                    .channels
+                    .iter_mut()
+                    .find(|current| current.id == channel.id)
+                    .ok_or_else(|| "渠道不存在".to_string())?;
+                *current = channel.clone();
+            }
+            Ok(channel.id)
+        })
+    }
+
+    fn delete(&self, id: &str) -> Result<(), String> {
+        self.mutate_store(|store| {
+            let position = store
+                .channels
+                .iter()
+                .position(|channel| channel.id == id)
+                .ok_or_else(|| "渠道不存在".to_string())?;
+            store.channels.remove(position);
+            Ok(())
+        })
+    }
+
+    fn switch(&self, id: &str) -> Result<(), String> {
+        with_dir_lock(&self.config_dir, || self.apply_switch(id))
+    }
+
+    fn current(&self) -> Result<Option<String>, String> {
+        let store = self.load_store()?;
+        let (api_key, base_url) = read_current_values(&self.spec, &self.home);
+        Ok(find_active_channel(&store.channels, &api_key, &base_url).map(|c| c.id.clone()))
+    }
+
+    fn test_target(&self, channel: &Channel) -> Result<ChannelTestTarget, String> {
+        if channel.base_url.is_empty() {
+            return Err("未设置 Base URL，无法检测".to_string());
+        }
+        Ok(ChannelTestTarget {
+            endpoint: format!("{}/models", channel.base_url.trim_end_matches('/')),
+            api_key: channel.api_key.clone(),
+            auth: TestAuth::Bearer,
+        })
+    }
+
+    fn switch_snapshot(&self) -> Result<SwitchSnapshot, String> {
+        let mut files: Vec<PathBuf> = self
+            .spec
+            .files
+            .iter()
+            .map(|file| resolve_spec_path(&file.path, &self.home))
+            .collect();
+        files.push(self.store_path());
+        SwitchSnapshot::of_files(files)
+    }
+}
+
+// ==================== 适配器管理 ====================
+
+#[tauri::command]
+pub(crate) async fn list_tool_adapters(config_path: String) -> ApiResponse<Vec<AdapterInfo>> {
+    let config_dir = Path::new(&config_path);
+    let result = load_specs(config_dir).map(|specs| {
+        specs
+            .into_iter()
+            .map(|(spec, builtin)| {
+                let can_rollback = load_adapter_store(&adapter_store_path(config_dir, &spec.id))
+                    .is_ok_and(|store| !store.rollback.is_empty());
+                AdapterInfo {
+                    spec,
+                    builtin,
+                    can_rollback,
+                }
+            })
+            .collect()
+    });
+
+    match result {
+        Ok(adapters) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(adapters),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn rollback_adapter_switch(
+    config_path: String,
+    adapter_id: String,
+) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        AdapterBackend::load(Path::new(&config_path), &adapter_id).and_then(|b| b.rollback())
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-adapter-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn channel(name: &str, base_url: &str, api_key: &str, model: &str) -> Channel {
+        Channel {
+            id: String::new(),
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    fn backend(dir: &Path, spec: AdapterSpec) -> AdapterBackend {
+        AdapterBackend {
+            config_dir: dir.join("claude"),
+            home: dir.to_path_buf(),
+            spec,
+        }
+    }
+
+    fn mapped_file(path: &str, format: ConfigFormat, prefix: &str) -> AdapterFile {
+        AdapterFile {
+            base_url: Some(format!("{}base_url", prefix)),
+            api_key: Some(format!("{}api_key", prefix)),
+            model: Some(format!("{}model", prefix)),
+            ..adapter_file(path, format)
+        }
+    }
+
+    #[test]
+    fn sets_and_reads_nested_keys_in_every_format() {
+        let cases = [
+            (ConfigFormat::Json, r#"{"keep":true,"llm":{"other":1}}"#),
+            (
+                ConfigFormat::Toml,
+                "# comment\nkeep = true\n[llm]\nother = 1\n",
+            ),
+            (ConfigFormat::Yaml, "keep: true\nllm:\n  other: 1\n"),
+        ];
+        for (format, existing) in cases {
+            let file = mapped_file("cfg", format, "llm.");
+            let (rendered, _) = render_file(
+                &file,
+                existing,
+                &channel("a", "https://relay.example.com", "sk-1", "gpt-4o"),
+                &[],
+            )
+            .unwrap();
+            assert!(rendered.contains("keep"), "{:?}: {}", format, rendered);
+            assert!(rendered.contains("other"), "{:?}: {}", format, rendered);
+
+            let document = ConfigDocument::parse(format, &rendered).unwrap();
+            assert_eq!(document.get("llm.api_key").as_deref(), Some("sk-1"));
+            assert_eq!(
+                document.get("llm.base_url").as_deref(),
+                Some("https://relay.example.com")
+            );
+            assert_eq!(document.get("llm.model").as_deref(), Some("gpt-4o"));
+        }
+    }
+
+    #[test]
+    fn removes_empty_base_url_and_keeps_model_when_unset() {
+        let file = mapped_file(".env", ConfigFormat::Env, "OPENAI_");
+        let (rendered, _) = render_file(
+            &file,
+            "OPENAI_base_url=https://old\nOPENAI_model=qwen3-coder\nOTHER=1\n",
+            &channel("a", "", "sk-2", ""),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "OPENAI_model=qwen3-coder\nOTHER=1\nOPENAI_api_key=sk-2\n"
+        );
+    }
+
+    #[test]
+    fn expands_value_templates_and_skips_ones_with_empty_fields() {
+        let spec = builtin_specs()
+            .into_iter()
+            .find(|spec| spec.id == "opencode")
+            .unwrap();
+        let file = &spec.files[0];
+
+        let (rendered, _) = render_file(
+            file,
+            "",
+            &channel("a", "https://r.example.com", "k", "m1"),
+            &[],
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        let provider = &value["provider"]["channel-switcher"];
+        assert_eq!(provider["options"]["baseURL"], "https://r.example.com");
+        assert_eq!(provider["models"]["m1"]["name"], "m1");
+        assert_eq!(value["model"], "channel-switcher/m1");
+
+        let (rendered, _) = render_file(
+            file,
+            "",
+            &channel("a", "https://r.example.com", "k", ""),
+            &[],
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert!(value.get("model").is_none());
+    }
+
+    #[test]
+    fn keeps_dotted_model_ids_as_one_key_and_drops_previous_models() {
+        let spec = builtin_specs()
+            .into_iter()
+            .find(|spec| spec.id == "opencode")
+            .unwrap();
+        let file = &spec.files[0];
+        let existing = r#"{"provider":{"channel-switcher":{"models":{"mine":{"name":"x"}}}}}"#;
+
+        let (rendered, written) = render_file(
+            file,
+            existing,
+            &channel("a", "https://r.example.com", "k", "gpt-4.1"),
+            &[],
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        let models = &value["provider"]["channel-switcher"]["models"];
+        assert_eq!(models["gpt-4.1"]["name"], "gpt-4.1");
+        assert!(models.get("gpt-4").is_none());
+        assert_eq!(value["model"], "channel-switcher/gpt-4.1");
+
+        let (rendered, written) = render_file(
+            file,
+            &rendered,
+            &channel("b", "https://r.example.com", "k", "qwen2.5-coder"),
+            &written,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        let models = value["provider"]["channel-switcher"]["models"]
+            .as_object()
+            .unwrap();
+        let ids: Vec<&str> = models.keys().map(String::as_str).collect();
+        assert_eq!(ids, vec!["mine", "qwen2.5-coder"]);
+        assert_eq!(value["model"], "channel-switcher/qwen2.5-coder");
+
+        // 没有模型的渠道不再写入 model，上次写入的模型条目与 model 一并移除
+        let (rendered, _) = render_file(
+            file,
+            &rendered,
+            &channel("c", "https://r.example.com", "k", ""),
+            &written,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert!(value.get("model").is_none());
+        let models = value["provider"]["channel-switcher"]["models"]
+            .as_object()
+            .unwrap();
+        assert_eq!(models.keys().collect::<Vec<_>>(), vec!["mine"]);
+    }
+
+    #[test]
+    fn removes_previous_keys_and_prunes_empty_tables_in_every_format() {
+        let file = AdapterFile {
+            values: BTreeMap::from([("llm.models.{model}.id".to_string(), "{model}".to_string())]),
+            ..adapter_file("cfg", ConfigFormat::Json)
+        };
+        for format in [ConfigFormat::Json, ConfigFormat::Toml, ConfigFormat::Yaml] {
+            let file = AdapterFile {
+                format,
+                ..file.clone()
+            };
+            let (rendered, written) =
+                render_file(&file, "", &channel("a", "", "k", "m-1.5"), &[]).unwrap();
+            assert_eq!(written, vec![vec!["llm", "models", "m-1.5", "id"]]);
+
+            let (rendered, _) =
+                render_file(&file, &rendered, &channel("b", "", "k", ""), &written).unwrap();
+            let document = ConfigDocument::parse(format, &rendered).unwrap();
+            assert!(!rendered.contains("m-1.5"), "{:?}: {}", format, rendered);
+            assert!(!rendered.contains("llm"), "{:?}: {}", format, rendered);
+            assert_eq!(document.get("llm.models"), None);
+        }
+    }
+
+    #[test]
+    fn switches_across_files_detects_current_and_rolls_back() {
+        let dir = create_temp_dir("switch");
+        let spec = AdapterSpec {
+            id: "tool".to_string(),
+            name: "Tool".to_string(),
+            files: vec![
+                AdapterFile {
+                    model: None,
+                    ..mapped_file(".tool/.env", ConfigFormat::Env, "TOOL_")
+                },
+                AdapterFile {
+                    model: Some("model.name".to_string()),
+                    ..adapter_file(".tool/config.yaml", ConfigFormat::Yaml)
+                },
+            ],
+        };
+        let env_path = dir.join(".tool/.env");
+        fs::create_dir_all(env_path.parent().unwrap()).unwrap();
+        fs::write(&env_path, "TOOL_api_key=manual\n").unwrap();
+
+        let backend = backend(&dir, spec);
+        let first = backend
+            .save(channel("a", "https://a.example.com/", "sk-a", "m-a"))
+            .unwrap();
+        let second = backend.save(channel("b", "", "sk-b", "")).unwrap();
+        assert_eq!(backend.current().unwrap(), None);
+
+        backend.switch(&first).unwrap();
+        assert_eq!(backend.current().unwrap().as_deref(), Some(first.as_str()));
+        let yaml = fs::read_to_string(dir.join(".tool/config.yaml")).unwrap();
+        assert!(yaml.contains("name: m-a"));
+
+        backend.switch(&second).unwrap();
+        assert_eq!(backend.current().unwrap().as_deref(), Some(second.as_str()));
+
+        backend.rollback().unwrap();
+        assert_eq!(backend.current().unwrap().as_deref(), Some(first.as_str()));
+        assert!(backend.rollback().is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn custom_specs_override_builtins_and_are_validated() {
+        let dir = create_temp_dir("specs");
+        let custom = r#"[
+            {"id": "qwen-code", "name": "Qwen (custom)", "files": [
+                {"path": "~/.qwen/.env", "format": "env", "api_key": "DASHSCOPE_API_KEY"}
+            ]},
+            {"id": "aider", "name": "Aider", "files": [
+                {"path": ".aider.conf.yml", "format": "yaml", "api_key": "openai-api-key",
+                 "base_url": "openai-api-base", "model": "model"}
+            ]}
+        ]"#;
+        fs::write(dir.join(ADAPTER_SPEC_FILE_NAME), custom).unwrap();
+
+        let specs = load_specs(&dir).unwrap();
+        let ids: Vec<&str> = specs.iter().map(|(spec, _)| spec.id.as_str()).collect();
+        assert_eq!(ids, vec!["qwen-code", "opencode", "aider"]);
+        assert_eq!(specs[0].0.name, "Qwen (custom)");
+        assert!(!specs[0].1 && specs[1].1);
+
+        fs::write(
+            dir.join(ADAPTER_SPEC_FILE_NAME),
+            r#"[{"id": "bad id", "name": "x", "files": []}]"#,
+        )
+        .unwrap();
+        assert!(load_specs(&dir).unwrap_err().contains("bad id"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn resolves_home_relative_paths() {
+        let home = Path::new("/home/u");
+        assert_eq!(
+            resolve_spec_path("~/.qwen/.env", home),
+            PathBuf::from("/home/u/.qwen/.env")
+        );
+        assert_eq!(
+            resolve_spec_path(".aider.conf.yml", home),
+            PathBuf::from("/home/u/.aider.conf.yml")
+        );
+        assert_eq!(
+            resolve_spec_path("/etc/tool.toml", home),
+            PathBuf::from("/etc/tool.toml")
+        );
+    }
+}
@@ -0,0 +1,787 @@
+// 各工具渠道的统一抽象：Claude、Codex、Droid、Gemini 与声明式适配器分别实现 ChannelBackend，
+// 列表、保存、删除、切换、当前渠道与连通性检测只需面向该 trait 编写一次
+use crate::adapter::AdapterBackend;
+use crate::codex::CodexBackend;
+use crate::droid::DroidBackend;
+use crate::fs_utils::{
+    ensure_unchanged, file_stamp, restore_text_snapshot, run_blocking, snapshot_text_file,
+    FileStamp,
+};
+use crate::gemini::GeminiBackend;
+use crate::terminal::{open_terminal_with, LaunchSpec};
+use crate::{ApiResponse, ClaudeBackend};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const CHANNEL_TEST_DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ToolKind {
+    Claude,
+    Codex,
+    Droid,
+    Gemini,
+    // 由 tool_adapters.json 等 spec 描述的 OpenAI 兼容工具，需同时指定适配器 id
+    Adapter,
+}
+
+// 各工具共有的渠道字段；工具特有的配置（Codex provider、余额查询等）在保存时保留原值
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Channel {
+    #[serde(default)]
+    pub(crate) id: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) base_url: String,
+    #[serde(default)]
+    pub(crate) api_key: String,
+    #[serde(default)]
+    pub(crate) model: String,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct ToolChannelList {
+    tool: ToolKind,
+    active: Option<String>,
+    channels: Vec<Channel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ChannelTestCategory {
+    Ok,
+    Unauthorized,
+    RateLimited,
+    ServerError,
+    UnexpectedStatus,
+    Timeout,
+    Network,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct ChannelTestResult {
+    pub(crate) valid: bool,
+    pub(crate) category: ChannelTestCategory,
+    pub(crate) latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) message: Option<String>,
+    pub(crate) tested_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TestAuth {
+    // Authorization: Bearer <key>
+    Bearer,
+    // Anthropic 官方使用 x-api-key，中转站多数只认 Bearer，两者都发送
+    Anthropic,
+    // Google AI Studio 只认 x-goog-api-key，附带 Bearer 反而会被拒绝
+    Google,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ChannelTestTarget {
+    pub(crate) endpoint: String,
+    pub(crate) api_key: String,
+    pub(crate) auth: TestAuth,
+}
+
+// 切换前的生效状态；同时切换多个工具时，任一工具失败则已切换的工具按此恢复
+pub(crate) struct SwitchSnapshot {
+    files: Vec<(PathBuf, Option<String>)>,
+    // 切换成功后各文件的状态，恢复前据此确认文件没有再被其他程序修改
+    switched: Option<Vec<Option<FileStamp>>>,
+    // 文件之外的状态（如环境变量），在文件恢复后执行
+    restore_extra: Option<Box<dyn FnOnce() -> Result<(), String>>>,
+}
+
+impl SwitchSnapshot {
+    pub(crate) fn of_files(paths: Vec<PathBuf>) -> Result<Self, String> {
+        let files = paths
+            .into_iter()
+            .map(|path| snapshot_text_file(&path).map(|content| (path, content)))
+            .collect::<Result<_, _>>()?;
+        Ok(SwitchSnapshot {
+            files,
+            switched: None,
+            restore_extra: None,
+        })
+    }
+
+    pub(crate) fn with_restore<F>(mut self, restore: F) -> Self
+    where
+        F: FnOnce() -> Result<(), String> + 'static,
+    {
+        self.restore_extra = Some(Box::new(restore));
+        self
+    }
+
+    // 切换成功后调用，记录切换写入后的文件状态
+    pub(crate) fn mark_switched(&mut self) -> Result<(), String> {
+        let stamps = self
+            .files
+            .iter()
+            .map(|(path, _)| file_stamp(path))
+            .collect::<Result<_, _>>()?;
+        self.switched = Some(stamps);
+        Ok(())
+    }
+
+    // 尽量恢复全部内容，返回遇到的第一个错误；切换后又被其他程序修改的文件保持不变并报告冲突
+    pub(crate) fn restore(self) -> Result<(), String> {
+        let mut result = Ok(());
+        for (index, (path, content)) in self.files.iter().enumerate().rev() {
+            let unchanged = match &self.switched {
+                Some(stamps) => ensure_unchanged(path, &stamps[index]),
+                None => Ok(()),
+            };
+            if let Err(e) = unchanged.and_then(|_| restore_text_snapshot(path, content)) {
+                result = result.and(Err(e));
+            }
+        }
+        if let Some(restore) = self.restore_extra {
+            result = result.and(restore());
+        }
+        result
+    }
+}
+
+pub(crate) trait ChannelBackend {
+    fn tool(&self) -> ToolKind;
+
+    fn list(&self) -> Result<Vec<Channel>, String>;
+
+    // id 为空时新建，否则更新该渠道；返回保存后的 id
+    fn save(&self, channel: Channel) -> Result<String, String>;
+
+    fn delete(&self, id: &str) -> Result<(), String>;
+
+    fn switch(&self, id: &str) -> Result<(), String>;
+
+    // 当前生效渠道的 id，无法匹配到渠道时为 None
+    fn current(&self) -> Result<Option<String>, String>;
+
+    fn test_target(&self, channel: &Channel) -> Result<ChannelTestTarget, String>;
+
+    // switch 会改写的配置，需在切换前调用
+    fn switch_snapshot(&self) -> Result<SwitchSnapshot, String>;
+
+    // 以该渠道单次启动 CLI 所需的参数与环境变量，不修改任何配置文件
+    fn launch_spec(&self, _id: &str) -> Result<LaunchSpec, String> {
+        Err("该工具暂不支持以指定渠道启动".to_string())
+    }
+
+    fn find(&self, id: &str) -> Result<Channel, String> {
+        self.list()?
+            .into_iter()
+            .find(|channel| channel.id == id)
+            .ok_or_else(|| "渠道不存在".to_string())
+    }
+}
+
+pub(crate) fn channel_backend(
+    tool: ToolKind,
+    dir: &Path,
+    adapter: Option<&str>,
+) -> Result<Box<dyn ChannelBackend>, String> {
+    Ok(match tool {
+        ToolKind::Claude => Box::new(ClaudeBackend::new(dir)),
+        ToolKind::Codex => Box::new(CodexBackend::new(dir)),
+        ToolKind::Droid => Box::new(DroidBackend::new(dir)),
+        ToolKind::Gemini => Box::new(GeminiBackend::new(dir)),
+        ToolKind::Adapter => {
+            let adapter = adapter.ok_or_else(|| "未指定工具适配器".to_string())?;
+            Box::new(AdapterBackend::load(dir, adapter)?)
+        }
+    })
+}
+
+// 调用方已持有 dir 的目录锁时使用；只有渠道存储在该目录中的 Claude 与 Droid 需要跳过加锁
+pub(crate) fn channel_backend_with_lock_held(
+    tool: ToolKind,
+    dir: &Path,
+) -> Result<Box<dyn ChannelBackend>, String> {
+    match tool {
+        ToolKind::Claude => Ok(Box::new(ClaudeBackend::with_lock_held(dir))),
+        ToolKind::Droid => Ok(Box::new(DroidBackend::with_lock_held(dir))),
+        _ => channel_backend(tool, dir, None),
+    }
+}
+
+fn now_timestamp() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+pub(crate) fn classify_status(status: u16) -> ChannelTestCategory {
+    match status {
+        200..=299 => ChannelTestCategory::Ok,
+        401 | 403 => ChannelTestCategory::Unauthorized,
+        429 => ChannelTestCategory::RateLimited,
+        500..=599 => ChannelTestCategory::ServerError,
+        _ => ChannelTestCategory::UnexpectedStatus,
+    }
+}
+
+pub(crate) async fn probe_endpoint(
+    target: &ChannelTestTarget,
+    timeout: Duration,
+) -> ChannelTestResult {
+    let started = Instant::now();
+    let client = match reqwest::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            return ChannelTestResult {
+                valid: false,
+                category: ChannelTestCategory::Network,
+                latency_ms: 0,
+                status: None,
+                message: Some(format!("创建 HTTP 客户端失败: {}", e)),
+                tested_at: now_timestamp(),
+            }
+        }
+    };
+
+    let request = client.get(&target.endpoint);
+    let request = match target.auth {
+        TestAuth::Bearer => request.bearer_auth(&target.api_key),
+        TestAuth::Anthropic => request
+            .bearer_auth(&target.api_key)
+            .header("x-api-key", &target.api_key)
+            .header("anthropic-version", "2023-06-01"),
+        TestAuth::Google => request.header("x-goog-api-key", &target.api_key),
+    };
+    let response = request.send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match response {
+        Ok(resp) => {
+            let status = resp.status().as_u16();
+            let category = classify_status(status);
+            ChannelTestResult {
+                valid: category == ChannelTestCategory::Ok,
+                category,
+                latency_ms,
+                status: Some(status),
+                message: (category != ChannelTestCategory::Ok).then(|| {
+                    resp.status()
+                        .canonical_reason()
+                        .unwrap_or("Unexpected status")
+                        .to_string()
+                }),
+                tested_at: now_timestamp(),
+            }
+        }
+        Err(e) => ChannelTestResult {
+            valid: false,
+            category: if e.is_timeout() {
+                ChannelTestCategory::Timeout
+            } else {
+                ChannelTestCategory::Network
+            },
+            latency_ms,
+            status: None,
+            message: Some(e.to_string()),
+            tested_at: now_timestamp(),
+        },
+    }
+}
+
+// 统一 Base URL 的写法：小写主机名、去掉片段和末尾的 /
+pub(crate) fn normalize_base_url(baseurl: &str) -> Result<String, String> {
+    let trimmed = baseurl.trim();
+    let mut url = Url::parse(trimmed)
+        .map_err(|_| "Base URL 格式无效，请输入 http:// 或 https:// 地址".to_string())?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("Base URL 格式无效，请输入 http:// 或 https:// 地址".to_string());
+    }
+
+    url.set_fragment(None);
+
+    let trimmed_path = url.path().trim_end_matches('/').to_string();
+    if trimmed_path.is_empty() {
+        url.set_path("/");
+    } else {
+        url.set_path(&trimmed_path);
+    }
+
+    let mut normalized = url.to_string();
+    if url.query().is_none() && url.path() == "/" && normalized.ends_with('/') {
+        normalized.pop();
+    }
+
+    Ok(normalized)
+}
+
+pub(crate) fn test_timeout(timeout_ms: Option<u64>) -> Duration {
+    Duration::from_millis(
+        timeout_ms
+            .unwrap_or(CHANNEL_TEST_DEFAULT_TIMEOUT_MS)
+            .clamp(500, 60_000),
+    )
+}
+
+fn list_with_active(backend: &dyn ChannelBackend) -> Result<ToolChannelList, String> {
+    Ok(ToolChannelList {
+        tool: backend.tool(),
+        channels: backend.list()?,
+        // 读取当前渠道失败不影响列表展示
+        active: backend.current().ok().flatten(),
+    })
+}
+
+pub(crate) fn data_response<T>(result: Result<T, String>) -> ApiResponse<T> {
+    match result {
+        Ok(data) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(data),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+// config_path 为该工具的配置目录：Claude、Droid 与适配器使用 Claude 配置目录，Codex、Gemini 使用各自的配置目录；
+// adapter 仅在 tool 为 adapter 时使用，为适配器 spec 的 id
+#[tauri::command]
+pub(crate) async fn list_tool_channels(
+    tool: ToolKind,
+    config_path: String,
+    adapter: Option<String>,
+) -> ApiResponse<ToolChannelList> {
+    data_response(
+        run_blocking(move || {
+            channel_backend(tool, Path::new(&config_path), adapter.as_deref())
+                .and_then(|backend| list_with_active(backend.as_ref()))
+        })
+        .await,
+    )
+}
+
+#[tauri::command]
+pub(crate) async fn save_tool_channel(
+    tool: ToolKind,
+    config_path: String,
+    adapter: Option<String>,
+    channel: Channel,
+) -> ApiResponse<String> {
+    data_response(
+        run_blocking(move || {
+            channel_backend(tool, Path::new(&config_path), adapter.as_deref())
+                .and_then(|backend| backend.save(channel))
+        })
+        .await,
+    )
+}
+
+#[tauri::command]
+pub(crate) async fn delete_tool_channel(
+    tool: ToolKind,
+    config_path: String,
+    adapter: Option<String>,
+    channel_id: String,
+) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        channel_backend(tool, Path::new(&config_path), adapter.as_deref())
+            .and_then(|backend| backend.delete(&channel_id))
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn switch_tool_channel(
+    tool: ToolKind,
+    config_path: String,
+    adapter: Option<String>,
+    channel_id: String,
+) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        channel_backend(tool, Path::new(&config_path), adapter.as_deref())
+            .and_then(|backend| backend.switch(&channel_id))
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn test_channel(
+    tool: ToolKind,
+    config_path: String,
+    adapter: Option<String>,
+    channel_id: String,
+    timeout_ms: Option<u64>,
+) -> ApiResponse<ChannelTestResult> {
+    let target = run_blocking(move || {
+        let backend = channel_backend(tool, Path::new(&config_path), adapter.as_deref())?;
+        backend
+            .find(&channel_id)
+            .and_then(|channel| backend.test_target(&channel))
+    })
+    .await;
+    match target {
+        Ok(target) => data_response(Ok(probe_endpoint(&target, test_timeout(timeout_ms)).await)),
+        Err(e) => data_response(Err(e)),
+    }
+}
+
+// 只在新终端的子进程中使用该渠道，全局配置保持不变
+#[tauri::command]
+pub(crate) async fn launch_with_channel(
+    tool: ToolKind,
+    config_path: String,
+    adapter: Option<String>,
+    channel_id: String,
+    terminal_dir: String,
+) -> ApiResponse<()> {
+    let spec = run_blocking(move || {
+        channel_backend(tool, Path::new(&config_path), adapter.as_deref())
+            .and_then(|backend| backend.launch_spec(&channel_id))
+    })
+    .await;
+    match spec {
+        Ok(spec) => open_terminal_with(&spec, &terminal_dir),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-channel-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn spawn_mock_server(status_line: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 4096];
+            let read = stream.read(&mut buffer).unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                status_line
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&buffer[..read]).to_string()
+        });
+        (format!("http://{}/v1/models", addr), handle)
+    }
+
+    fn run_probe(endpoint: &str, auth: TestAuth, timeout: Duration) -> ChannelTestResult {
+        let target = ChannelTestTarget {
+            endpoint: endpoint.to_string(),
+            api_key: "fk-test".to_string(),
+            auth,
+        };
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(probe_endpoint(&target, timeout))
+    }
+
+    #[test]
+    fn probes_endpoint_with_bearer_key() {
+        let (endpoint, server) = spawn_mock_server("200 OK");
+        let result = run_probe(&endpoint, TestAuth::Bearer, Duration::from_secs(5));
+        let request = server.join().unwrap().to_ascii_lowercase();
+
+        assert!(result.valid);
+        assert_eq!(result.category, ChannelTestCategory::Ok);
+        assert_eq!(result.status, Some(200));
+        assert!(request.starts_with("get /v1/models "));
+        assert!(request.contains("authorization: bearer fk-test"));
+        assert!(!request.contains("x-api-key"));
+    }
+
+    #[test]
+    fn sends_anthropic_key_headers() {
+        let (endpoint, server) = spawn_mock_server("200 OK");
+        run_probe(&endpoint, TestAuth::Anthropic, Duration::from_secs(5));
+        let request = server.join().unwrap().to_ascii_lowercase();
+
+        assert!(request.contains("x-api-key: fk-test"));
+        assert!(request.contains("anthropic-version: 2023-06-01"));
+    }
+
+    #[test]
+    fn sends_google_key_without_bearer() {
+        let (endpoint, server) = spawn_mock_server("200 OK");
+        run_probe(&endpoint, TestAuth::Google, Duration::from_secs(5));
+        let request = server.join().unwrap().to_ascii_lowercase();
+
+        assert!(request.contains("x-goog-api-key: fk-test"));
+        assert!(!request.contains("authorization:"));
+    }
+
+    #[test]
+    fn categorizes_rejected_keys_and_network_errors() {
+        let (endpoint, server) = spawn_mock_server("401 Unauthorized");
+        let result = run_probe(&endpoint, TestAuth::Bearer, Duration::from_secs(5));
+        server.join().unwrap();
+        assert!(!result.valid);
+        assert_eq!(result.category, ChannelTestCategory::Unauthorized);
+        assert_eq!(result.message.as_deref(), Some("Unauthorized"));
+
+        // 绑定后立即释放端口，连接会被拒绝
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let result = run_probe(
+            &format!("http://127.0.0.1:{}/", port),
+            TestAuth::Bearer,
+            Duration::from_secs(5),
+        );
+        assert_eq!(result.category, ChannelTestCategory::Network);
+        assert_eq!(result.status, None);
+
+        assert_eq!(classify_status(429), ChannelTestCategory::RateLimited);
+        assert_eq!(classify_status(503), ChannelTestCategory::ServerError);
+        assert_eq!(classify_status(404), ChannelTestCategory::UnexpectedStatus);
+    }
+
+    // 同一组操作在每个工具上的行为一致
+    fn exercise_backend(tool: ToolKind, dir: &Path) {
+        let backend = channel_backend(tool, dir, None).unwrap();
+        assert_eq!(backend.tool(), tool);
+        assert!(backend.list().unwrap().is_empty());
+
+        let id = backend
+            .save(Channel {
+                id: String::new(),
+                name: "relay".to_string(),
+                base_url: "https://relay.example.com".to_string(),
+                api_key: "sk-1".to_string(),
+                model: "m-1".to_string(),
+            })
+            .unwrap();
+        let saved = backend.find(&id).unwrap();
+        assert_eq!(saved.name, "relay");
+        assert_eq!(saved.api_key, "sk-1");
+
+        let updated_id = backend
+            .save(Channel {
+                api_key: "sk-2".to_string(),
+                ..saved
+            })
+            .unwrap();
+        assert_eq!(updated_id, id);
+        assert_eq!(backend.list().unwrap().len(), 1);
+        assert_eq!(backend.find(&id).unwrap().api_key, "sk-2");
+
+        let target = backend.test_target(&backend.find(&id).unwrap()).unwrap();
+        assert!(target.endpoint.starts_with("https://relay.example.com"));
+        assert_eq!(target.api_key, "sk-2");
+
+        backend.delete(&id).unwrap();
+        assert!(backend.list().unwrap().is_empty());
+        assert!(backend.find(&id).is_err());
+    }
+
+    #[test]
+    fn claude_backend_implements_channel_operations() {
+        let dir = create_temp_dir("claude");
+        exercise_backend(ToolKind::Claude, &dir);
+    }
+
+    #[test]
+    fn claude_backend_rejects_names_that_escape_the_config_dir() {
+        let dir = create_temp_dir("claude-names");
+        let backend = channel_backend(ToolKind::Claude, &dir.join("config"), None).unwrap();
+        let channel = |id: &str, name: &str| Channel {
+            id: id.to_string(),
+            name: name.to_string(),
+            base_url: String::new(),
+            api_key: "sk-1".to_string(),
+            model: String::new(),
+        };
+
+        for name in ["", "../evil", "a/b", "a\\b", "a..b", "a:b", "a\nb"] {
+            assert!(backend.save(channel("", name)).is_err(), "{:?}", name);
+        }
+        assert!(backend.save(channel("../evil", "ok")).is_err());
+        assert!(backend.delete("../evil").is_err());
+        std::fs::write(dir.join("settings-evil.json"), r#"{"env":{"A":"1"}}"#).unwrap();
+        assert!(backend.switch("../settings-evil").is_err());
+        assert!(backend.launch_spec("../settings-evil").is_err());
+        assert!(!dir.join("config").join("settings.json").exists());
+        assert!(!dir.join("evil.json").exists());
+        assert!(!dir.join("settings-.json").exists());
+
+        assert_eq!(
+            backend.save(channel("", "My Relay 中转")).unwrap(),
+            "My Relay 中转"
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn codex_backend_implements_channel_operations() {
+        let dir = create_temp_dir("codex");
+        exercise_backend(ToolKind::Codex, &dir);
+    }
+
+    #[test]
+    fn droid_backend_implements_channel_operations() {
+        let dir = create_temp_dir("droid");
+        exercise_backend(ToolKind::Droid, &dir);
+    }
+
+    #[test]
+    fn gemini_backend_implements_channel_operations() {
+        let dir = create_temp_dir("gemini");
+        exercise_backend(ToolKind::Gemini, &dir);
+    }
+
+    #[test]
+    fn switches_and_reports_current_channel() {
+        for tool in [ToolKind::Claude, ToolKind::Codex, ToolKind::Gemini] {
+            let dir = create_temp_dir("switch");
+            let backend = channel_backend(tool, &dir, None).unwrap();
+            let first = backend
+                .save(Channel {
+                    id: String::new(),
+                    name: "a".to_string(),
+                    base_url: "https://a.example.com".to_string(),
+                    api_key: "sk-a".to_string(),
+                    model: "m".to_string(),
+                })
+                .unwrap();
+            let second = backend
+                .save(Channel {
+                    id: String::new(),
+                    name: "b".to_string(),
+                    base_url: "https://b.example.com".to_string(),
+                    api_key: "sk-b".to_string(),
+                    model: "m".to_string(),
+                })
+                .unwrap();
+
+            backend.switch(&second).unwrap();
+            assert_eq!(backend.current().unwrap().as_deref(), Some(second.as_str()));
+            backend.switch(&first).unwrap();
+            assert_eq!(backend.current().unwrap().as_deref(), Some(first.as_str()));
+        }
+    }
+
+    #[test]
+    fn builds_launch_specs_without_touching_config_files() {
+        for (tool, program, key_var) in [
+            (ToolKind::Claude, "claude", "ANTHROPIC_AUTH_TOKEN"),
+            (ToolKind::Codex, "codex", "OPENAI_API_KEY"),
+            (ToolKind::Droid, "droid", "FACTORY_API_KEY"),
+        ] {
+            let dir = create_temp_dir("launch");
+            let backend = channel_backend(tool, &dir, None).unwrap();
+            let id = backend
+                .save(Channel {
+                    id: String::new(),
+                    name: "relay".to_string(),
+                    base_url: "https://relay.example.com/v1".to_string(),
+                    api_key: "sk-session".to_string(),
+                    model: "m-1".to_string(),
+                })
+                .unwrap();
+            let before: Vec<_> = std::fs::read_dir(&dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+
+            let spec = backend.launch_spec(&id).unwrap();
+            assert_eq!(spec.program, program);
+            assert!(spec
+                .env
+                .contains(&(key_var.to_string(), "sk-session".to_string())));
+            let after: Vec<_> = std::fs::read_dir(&dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            assert_eq!(before, after, "{:?}", tool);
+
+            match tool {
+                ToolKind::Claude => assert_eq!(spec.args[0], "--settings"),
+                ToolKind::Codex => {
+                    assert!(spec
+                        .args
+                        .iter()
+                        .any(|arg| arg.ends_with(".base_url=https://relay.example.com/v1")));
+                    assert!(spec.args.contains(&"model=m-1".to_string()));
+                }
+                _ => assert!(spec.args.is_empty()),
+            }
+            assert!(backend.launch_spec("missing").is_err());
+        }
+
+        let dir = create_temp_dir("launch-gemini");
+        let gemini = channel_backend(ToolKind::Gemini, &dir, None).unwrap();
+        assert!(gemini.launch_spec("any").is_err());
+    }
+
+    #[test]
+    fn normalizes_base_url() {
+        assert_eq!(
+            normalize_base_url("https://API.Example.com/").unwrap(),
+            "https://api.example.com"
+        );
+        assert_eq!(
+            normalize_base_url("https://api.example.com/v1/").unwrap(),
+            "https://api.example.com/v1"
+        );
+    }
+
+    #[test]
+    fn snapshot_restore_keeps_files_edited_after_the_switch() {
+        let dir = create_temp_dir("snapshot-stamp");
+        let edited = dir.join("settings.json");
+        let untouched = dir.join("auth.json");
+        std::fs::write(&edited, "before").unwrap();
+
+        let mut snapshot =
+            SwitchSnapshot::of_files(vec![edited.clone(), untouched.clone()]).unwrap();
+        std::fs::write(&edited, "switched").unwrap();
+        std::fs::write(&untouched, "switched").unwrap();
+        snapshot.mark_switched().unwrap();
+        std::fs::write(&edited, "edited by user").unwrap();
+
+        let error = snapshot.restore().unwrap_err();
+        assert!(error.starts_with("[CONFLICT]"), "{}", error);
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "edited by user");
+        assert!(!untouched.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
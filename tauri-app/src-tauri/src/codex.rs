@@ -1,36 +1,170 @@
-use crate::{open_terminal, ApiResponse};
-use reqwest::Url;
+use crate::channel::{
+    data_response, normalize_base_url, Channel, ChannelBackend, ChannelTestTarget, SwitchSnapshot,
+    TestAuth, ToolKind,
+};
+use crate::fs_utils::{
+    conflict_error, ensure_unchanged, read_stamped_text, run_blocking, snapshot_text_file,
+    with_dir_lock, write_files_with_rollback, write_text_file,
+};
+use crate::terminal::{open_terminal, LaunchSpec};
+use crate::ApiResponse;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
 use toml::{Table as TomlTable, Value as TomlValue};
-
-const CODEX_STORE_VERSION: u32 = 1;
-const CODEX_STORE_FILE_NAME: &str = "channels.json";
+use toml_edit::{
+    DocumentMut, InlineTable, Item as TomlEditItem, Table as TomlEditTable, TableLike,
+    Value as TomlEditValue,
+};
+
+const CODEX_STORE_VERSION: u32 = 2;
+pub(crate) const CODEX_STORE_FILE_NAME: &str = "channels.json";
+// 第 n 项把 vn 的存储升级到 v(n+1)，长度始终等于 CODEX_STORE_VERSION
+const CODEX_STORE_MIGRATIONS: [CodexStoreMigration; 2] =
+    [migrate_codex_store_v0_to_v1, migrate_codex_store_v1_to_v2];
+const CODEX_NAME_MAX_LEN: usize = 18;
+// Codex 内置的 provider 不允许在 config.toml 中覆盖
+const RESERVED_PROVIDER_IDS: [&str; 2] = ["openai", "oss"];
+const SUPPORTED_WIRE_APIS: [&str; 2] = ["chat", "responses"];
+// 新机器上没有任何 provider 时创建的默认条目
+const DEFAULT_PROVIDER_ID: &str = "OpenAI";
+const DEFAULT_WIRE_API: &str = "responses";
+// 渠道附加配置：根级键合并到 config.toml，[provider] 表合并到目标 provider
+const EXTRA_CONFIG_STATE_FILE: &str = ".applied_extra_config.json";
+const EXTRA_PROVIDER_TABLE: &str = "provider";
+const MANAGED_ROOT_KEYS: [&str; 3] = ["model", "model_provider", "model_providers"];
+const MANAGED_PROVIDER_KEYS: [&str; 1] = ["base_url"];
+// ChatGPT 登录只能走 Codex 内置的 openai provider
+const CHATGPT_PROVIDER_ID: &str = "openai";
+// 单次启动时通过 -c 定义的 provider 及其读取 Key 的环境变量
+const CODEX_SESSION_PROVIDER_ID: &str = "channel-switcher-session";
+const CODEX_SESSION_KEY_VAR: &str = "OPENAI_API_KEY";
+// 没有渠道保存的 ChatGPT 登录在切走前备份到 auth.chatgpt-<account>.json
+const CHATGPT_AUTH_BACKUP_PREFIX: &str = "auth.chatgpt-";
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub(crate) struct CodexChannel {
+    // 稳定标识，所有修改操作按 id 定位，不再依赖列表下标
+    #[serde(default)]
+    id: String,
     name: String,
     baseurl: String,
     apikey: String,
     model: String,
+    // 为空时沿用 config.toml 中已有的 provider，只改写其 base_url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provider: Option<CodexProviderConfig>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    extra_config: String,
+    #[serde(default, skip_serializing_if = "CodexAuthMode::is_api_key")]
+    auth_mode: CodexAuthMode,
+    // ChatGPT 登录渠道保存的完整 auth.json，不返回给前端
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chatgpt_auth: Option<serde_json::Value>,
+    // 置顶渠道始终排在列表最前
+    #[serde(default)]
+    pinned: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CodexAuthMode {
+    #[default]
+    ApiKey,
+    Chatgpt,
+}
+
+impl CodexAuthMode {
+    fn is_api_key(&self) -> bool {
+        *self == CodexAuthMode::ApiKey
+    }
+}
+
+// 渠道独占的 [model_providers.<id>] 配置，切换时创建或更新并设为 model_provider
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct CodexProviderConfig {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wire_api: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    env_key: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    query_params: BTreeMap<String, String>,
+}
+
+// 上一次切换写入的附加配置，切走时据此还原
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+struct CodexAppliedExtra {
+    channel: String,
+    provider: String,
+    #[serde(default)]
+    root: Vec<CodexAppliedKey>,
+    #[serde(default)]
+    provider_keys: Vec<CodexAppliedKey>,
+}
+
+// 值以 TOML 文本保存；previous 为空表示切换前不存在该键
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+struct CodexAppliedKey {
+    key: String,
+    value: String,
+    #[serde(default)]
+    previous: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct CodexExtraConfig {
+    root: Vec<(String, TomlEditValue)>,
+    provider: Vec<(String, TomlEditValue)>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct CodexChannelStore {
     #[serde(default = "codex_store_version")]
     version: u32,
+    // 每次修改渠道列表递增，用于乐观并发校验
+    #[serde(default)]
+    revision: u64,
     #[serde(default)]
     channels: Vec<CodexChannel>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct CodexChannelList {
+    revision: u64,
+    channels: Vec<CodexChannel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct CodexStoreChange {
+    revision: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_id: Option<String>,
+}
+
+type CodexStoreMigration = fn(serde_json::Value) -> Result<serde_json::Value, String>;
+
+// 存储版本高于当前应用时以只读方式加载，避免旧版本覆盖新格式的数据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct CodexStoreStatus {
+    version: u32,
+    supported_version: u32,
+    read_only: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct CodexActiveInfo {
     api_key: String,
     base_url: String,
     model: String,
     model_provider: String,
+    auth_mode: CodexAuthMode,
+    active_channel: Option<String>,
 }
 
 fn find_target_provider_name(root: &TomlTable) -> Option<String> {
@@ -70,6 +204,7 @@ fn codex_store_version() -> u32 {
 fn default_codex_store() -> CodexChannelStore {
     CodexChannelStore {
         version: CODEX_STORE_VERSION,
+        revision: 0,
         channels: vec![],
     }
 }
@@ -81,78 +216,146 @@ fn codex_store_path(dir: &Path) -> PathBuf {
 fn normalize_loaded_channel(channel: CodexChannel) -> CodexChannel {
     CodexChannel {
         name: channel.name.trim().to_string(),
-        baseurl: normalize_codex_baseurl(&channel.baseurl)
+        baseurl: normalize_base_url(&channel.baseurl)
             .unwrap_or_else(|_| channel.baseurl.trim().to_string()),
         apikey: channel.apikey.trim().to_string(),
         model: channel.model.trim().to_string(),
+        provider: channel.provider.map(normalize_codex_provider),
+        extra_config: channel.extra_config.trim().to_string(),
+        auth_mode: channel.auth_mode,
+        chatgpt_auth: channel.chatgpt_auth,
+        id: channel.id.trim().to_string(),
+        pinned: channel.pinned,
     }
 }
 
 fn normalize_codex_channel(channel: CodexChannel) -> Result<CodexChannel, String> {
+    // ChatGPT 登录渠道不使用 Base URL，留空时不做校验
+    let baseurl = match (channel.auth_mode, channel.baseurl.trim()) {
+        (CodexAuthMode::Chatgpt, "") => String::new(),
+        (_, baseurl) => normalize_base_url(baseurl)?,
+    };
+
     Ok(CodexChannel {
         name: channel.name.trim().to_string(),
-        baseurl: normalize_codex_baseurl(&channel.baseurl)?,
+        baseurl,
         apikey: channel.apikey.trim().to_string(),
         model: channel.model.trim().to_string(),
+        provider: channel
+            .provider
+            .map(normalize_codex_provider)
+            .filter(|provider| !provider.id.is_empty()),
+        extra_config: channel.extra_config.trim().to_string(),
+        auth_mode: channel.auth_mode,
+        chatgpt_auth: channel.chatgpt_auth,
+        id: channel.id.trim().to_string(),
+        pinned: channel.pinned,
     })
 }
 
-fn is_valid_codex_name(name: &str) -> bool {
-    !name.is_empty()
-        && name.len() <= 18
-        && name
-            .chars()
-            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+fn trimmed_non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
 }
 
-fn normalize_codex_baseurl(baseurl: &str) -> Result<String, String> {
-    let trimmed = baseurl.trim();
-    let mut url = Url::parse(trimmed)
-        .map_err(|_| "Base URL 格式无效，请输入 http:// 或 https:// 地址".to_string())?;
+fn normalize_codex_provider(provider: CodexProviderConfig) -> CodexProviderConfig {
+    let id = provider.id.trim().to_string();
+    let name = match provider.name.trim() {
+        "" => id.clone(),
+        name => name.to_string(),
+    };
 
-    if !matches!(url.scheme(), "http" | "https") {
-        return Err("Base URL 格式无效，请输入 http:// 或 https:// 地址".to_string());
+    CodexProviderConfig {
+        id,
+        name,
+        wire_api: trimmed_non_empty(provider.wire_api).map(|value| value.to_ascii_lowercase()),
+        env_key: trimmed_non_empty(provider.env_key),
+        query_params: provider
+            .query_params
+            .into_iter()
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .filter(|(key, _)| !key.is_empty())
+            .collect(),
     }
+}
 
-    url.set_fragment(None);
-
-    let trimmed_path = url.path().trim_end_matches('/').to_string();
-    if trimmed_path.is_empty() {
-        url.set_path("/");
-    } else {
-        url.set_path(&trimmed_path);
+fn validate_codex_provider(provider: &CodexProviderConfig) -> Result<(), String> {
+    if !is_valid_codex_name(&provider.id) {
+        return Err("Provider ID 仅支持英文字母、数字、-、_，最多18位".to_string());
     }
-
-    let mut normalized = url.to_string();
-    if url.query().is_none() && url.path() == "/" && normalized.ends_with('/') {
-        normalized.pop();
+    if RESERVED_PROVIDER_IDS.contains(&provider.id.as_str()) {
+        return Err(format!(
+            "Provider ID {} 为 Codex 内置，不能覆盖",
+            provider.id
+        ));
+    }
+    if let Some(wire_api) = &provider.wire_api {
+        if !SUPPORTED_WIRE_APIS.contains(&wire_api.as_str()) {
+            return Err(format!("不支持的 wire_api: {}", wire_api));
+        }
+    }
+    if let Some(env_key) = &provider.env_key {
+        let valid = !env_key.starts_with(|ch: char| ch.is_ascii_digit())
+            && env_key
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+        if !valid {
+            return Err(format!("无效的环境变量名: {}", env_key));
+        }
     }
+    Ok(())
+}
 
-    Ok(normalized)
+pub(crate) fn is_valid_codex_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= CODEX_NAME_MAX_LEN
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
 }
 
 fn validate_codex_channel(
     channel: &CodexChannel,
     existing: &[CodexChannel],
-    edit_index: Option<usize>,
+    editing_id: Option<&str>,
 ) -> Result<(), String> {
     if !is_valid_codex_name(&channel.name) {
         return Err("渠道名称仅支持英文字母、数字、-、_，最多18位".to_string());
     }
-    if channel.baseurl.is_empty() {
-        return Err("请输入 Base URL".to_string());
-    }
-    if channel.apikey.is_empty() {
-        return Err("请输入 API Key".to_string());
-    }
-    if channel.model.is_empty() {
-        return Err("请输入模型名称".to_string());
+    match channel.auth_mode {
+        CodexAuthMode::ApiKey => {
+            if channel.baseurl.is_empty() {
+                return Err("请输入 Base URL".to_string());
+            }
+            if channel.apikey.is_empty() {
+                return Err("请输入 API Key".to_string());
+            }
+            if channel.model.is_empty() {
+                return Err("请输入模型名称".to_string());
+            }
+            if let Some(provider) = &channel.provider {
+                validate_codex_provider(provider)?;
+            }
+        }
+        CodexAuthMode::Chatgpt => {
+            if channel.provider.is_some() {
+                return Err("ChatGPT 登录渠道只能使用内置 provider".to_string());
+            }
+            if !channel
+                .chatgpt_auth
+                .as_ref()
+                .is_some_and(has_chatgpt_tokens)
+            {
+                return Err("ChatGPT 登录渠道缺少登录信息，请先运行 codex login".to_string());
+            }
+        }
     }
+    parse_codex_extra_config(&channel.extra_config)?;
 
     let duplicate_exists = existing
         .iter()
-        .enumerate()
-        .any(|(idx, current)| Some(idx) != edit_index && current.name == channel.name);
+        .any(|current| Some(current.id.as_str()) != editing_id && current.name == channel.name);
 
     if duplicate_exists {
         return Err("渠道名称已存在".to_string());
@@ -161,124 +364,289 @@ fn validate_codex_channel(
     Ok(())
 }
 
-fn load_codex_store(dir: &Path) -> Result<CodexChannelStore, String> {
-    let store_path = codex_store_path(dir);
-    if store_path.exists() {
-        let content = fs::read_to_string(&store_path)
-            .map_err(|e| format!("读取 Codex 存储文件失败: {}", e))?;
-        let mut store: CodexChannelStore = serde_json::from_str(&content)
-            .map_err(|e| format!("解析 Codex 存储文件失败: {}", e))?;
+// 缺少 version 字段的文件视为 v0
+fn stored_codex_store_version(raw: &serde_json::Value) -> Result<u32, String> {
+    match raw.get("version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("无效的 Codex 存储版本: {}", version)),
+    }
+}
 
-        if store.version != CODEX_STORE_VERSION {
-            return Err(format!("不支持的 Codex 存储版本: {}", store.version));
-        }
+// v0：早期手写的渠道数组或没有 version 的对象
+fn migrate_codex_store_v0_to_v1(raw: serde_json::Value) -> Result<serde_json::Value, String> {
+    let mut store = match raw {
+        serde_json::Value::Array(channels) => serde_json::json!({ "channels": channels }),
+        serde_json::Value::Object(store) => serde_json::Value::Object(store),
+        _ => return Err("Codex 存储文件格式无效".to_string()),
+    };
+    store["version"] = serde_json::json!(1);
+    Ok(store)
+}
 
-        store.channels = store
-            .channels
-            .into_iter()
-            .map(normalize_loaded_channel)
-            .collect();
-        return Ok(store);
+// v2：渠道增加稳定 id 与置顶标记，存储增加修订号
+fn migrate_codex_store_v1_to_v2(mut raw: serde_json::Value) -> Result<serde_json::Value, String> {
+    let store = raw
+        .as_object_mut()
+        .ok_or_else(|| "Codex 存储文件格式无效".to_string())?;
+    let channels = store
+        .entry("channels")
+        .or_insert_with(|| serde_json::json!([]))
+        .as_array_mut()
+        .ok_or_else(|| "Codex 存储中的 channels 必须是数组".to_string())?;
+
+    let mut used_ids: Vec<String> = vec![];
+    for channel in channels.iter_mut() {
+        let channel = channel
+            .as_object_mut()
+            .ok_or_else(|| "Codex 渠道必须是对象".to_string())?;
+        let id = new_codex_channel_id(&used_ids);
+        channel.insert("id".to_string(), serde_json::json!(id));
+        channel
+            .entry("pinned")
+            .or_insert_with(|| serde_json::json!(false));
+        used_ids.push(id);
     }
 
-    Ok(default_codex_store())
+    store.insert("revision".to_string(), serde_json::json!(0));
+    store.insert("version".to_string(), serde_json::json!(2));
+    Ok(raw)
 }
 
-fn save_codex_store(dir: &Path, store: &CodexChannelStore) -> Result<(), String> {
-    if !dir.exists() {
-        fs::create_dir_all(dir).map_err(|e| format!("创建 Codex 目录失败: {}", e))?;
+// 基于当前时间生成十六进制 id，与已有 id 冲突时递增
+fn new_codex_channel_id<S: AsRef<str>>(existing: &[S]) -> String {
+    let mut seed = chrono::Utc::now()
+        .timestamp_nanos_opt()
+        .unwrap_or_default()
+        .unsigned_abs();
+    loop {
+        let id = format!("cx{:x}", seed);
+        if !existing.iter().any(|current| current.as_ref() == id) {
+            return id;
+        }
+        seed += 1;
     }
+}
 
-    let serialized =
-        serde_json::to_string_pretty(store).map_err(|e| format!("序列化 Codex 存储失败: {}", e))?;
-    write_text_file(&codex_store_path(dir), &serialized)?;
-    Ok(())
+// 手工编辑或测试写入的渠道可能缺少 id，加载时补齐
+fn ensure_codex_channel_ids(channels: &mut [CodexChannel]) -> bool {
+    let mut changed = false;
+    for index in 0..channels.len() {
+        let duplicated = channels[..index]
+            .iter()
+            .any(|current| current.id == channels[index].id);
+        if channels[index].id.is_empty() || duplicated {
+            let used: Vec<&str> = channels.iter().map(|channel| channel.id.as_str()).collect();
+            let id = new_codex_channel_id(&used);
+            channels[index].id = id;
+            changed = true;
+        }
+    }
+    changed
 }
 
-fn temp_path_for(target: &Path, suffix: &str) -> Result<PathBuf, String> {
-    let parent = target
-        .parent()
-        .ok_or_else(|| format!("无法获取文件父目录: {}", target.display()))?;
-    let file_name = target
-        .file_name()
-        .and_then(|name| name.to_str())
-        .ok_or_else(|| format!("无法获取文件名: {}", target.display()))?;
-    let stamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos();
-
-    Ok(parent
-        .join(format!(
-            ".{}.{}.{}.tmp",
-            file_name,
-            std::process::id(),
-            stamp
-        ))
-        .with_extension(suffix))
+fn migrate_codex_store(
+    mut raw: serde_json::Value,
+    from_version: u32,
+) -> Result<serde_json::Value, String> {
+    for (index, migration) in CODEX_STORE_MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(from_version as usize)
+    {
+        raw = migration(raw).map_err(|e| format!("迁移 Codex 存储 v{} 失败: {}", index, e))?;
+    }
+    Ok(raw)
 }
 
-fn write_text_file(target: &Path, content: &str) -> Result<(), String> {
-    let parent = target
-        .parent()
-        .ok_or_else(|| format!("无法获取文件父目录: {}", target.display()))?;
-    fs::create_dir_all(parent)
-        .map_err(|e| format!("创建文件目录失败 {}: {}", parent.display(), e))?;
+// 迁移前保留原文件，文件名带上原版本号和时间
+fn backup_codex_store(dir: &Path, content: &str, version: u32) -> Result<PathBuf, String> {
+    let backup_path = dir.join(format!(
+        "{}.v{}-{}.bak",
+        CODEX_STORE_FILE_NAME,
+        version,
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    ));
+    write_text_file(&backup_path, content)?;
+    Ok(backup_path)
+}
 
-    let temp_path = temp_path_for(target, "tmp")?;
-    fs::write(&temp_path, content)
-        .map_err(|e| format!("写入临时文件失败 {}: {}", temp_path.display(), e))?;
+fn load_codex_store(dir: &Path) -> Result<CodexChannelStore, String> {
+    let store_path = codex_store_path(dir);
+    if !store_path.exists() {
+        return Ok(default_codex_store());
+    }
 
-    if target.exists() {
-        let backup_path = temp_path_for(target, "bak")?;
-        fs::rename(target, &backup_path).map_err(|e| {
-            let _ = fs::remove_file(&temp_path);
-            format!("备份原文件失败 {}: {}", target.display(), e)
-        })?;
+    let content =
+        fs::read_to_string(&store_path).map_err(|e| format!("读取 Codex 存储文件失败: {}", e))?;
+    let raw: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("解析 Codex 存储文件失败: {}", e))?;
+    let version = stored_codex_store_version(&raw)?;
 
-        if let Err(e) = fs::rename(&temp_path, target) {
-            let _ = fs::rename(&backup_path, target);
-            let _ = fs::remove_file(&temp_path);
-            return Err(format!("替换文件失败 {}: {}", target.display(), e));
-        }
+    let mut store: CodexChannelStore = if version < CODEX_STORE_VERSION {
+        let migrated = migrate_codex_store(raw, version)?;
+        let store = serde_json::from_value(migrated)
+            .map_err(|e| format!("解析 Codex 存储文件失败: {}", e))?;
+        backup_codex_store(dir, &content, version)?;
+        save_codex_store(dir, &store)?;
+        store
+    } else {
+        // 更新版本写入的文件：未知字段被忽略，只读加载
+        serde_json::from_value(raw).map_err(|e| format!("解析 Codex 存储文件失败: {}", e))?
+    };
 
-        let _ = fs::remove_file(&backup_path);
-    } else if let Err(e) = fs::rename(&temp_path, target) {
-        let _ = fs::remove_file(&temp_path);
-        return Err(format!("写入文件失败 {}: {}", target.display(), e));
+    store.channels = store
+        .channels
+        .into_iter()
+        .map(normalize_loaded_channel)
+        .collect();
+    if ensure_codex_channel_ids(&mut store.channels) && !is_codex_store_read_only(&store) {
+        save_codex_store(dir, &store)?;
     }
+    Ok(store)
+}
 
+fn check_codex_store_revision(store: &CodexChannelStore, expected: u64) -> Result<(), String> {
+    if store.revision != expected {
+        return Err(conflict_error(&format!(
+            "渠道列表已被其他操作修改（当前修订号 {}，期望 {}），请刷新后重试",
+            store.revision, expected
+        )));
+    }
     Ok(())
 }
 
-fn snapshot_text_file(path: &Path) -> Result<Option<String>, String> {
-    if !path.exists() {
-        return Ok(None);
-    }
+// 在目录锁内校验修订号后修改并保存存储，返回新的修订号与受影响的渠道
+fn mutate_codex_store<F>(
+    dir: &Path,
+    expected_revision: u64,
+    mutate: F,
+) -> Result<CodexStoreChange, String>
+where
+    F: FnOnce(&mut CodexChannelStore) -> Result<Option<String>, String>,
+{
+    with_dir_lock(dir, || {
+        let mut store = load_codex_store(dir)?;
+        check_codex_store_revision(&store, expected_revision)?;
+
+        let channel_id = mutate(&mut store)?;
+        store.revision += 1;
+        save_codex_store(dir, &store)?;
 
-    fs::read_to_string(path)
-        .map(Some)
-        .map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))
+        Ok(CodexStoreChange {
+            revision: store.revision,
+            channel_id,
+        })
+    })
 }
 
-fn restore_text_snapshot(path: &Path, snapshot: &Option<String>) -> Result<(), String> {
-    match snapshot {
-        Some(content) => write_text_file(path, content),
-        None => {
-            if !path.exists() {
-                return Ok(());
-            }
+// 加载时可能迁移并写回存储，因此读取也在目录锁内进行
+fn load_codex_store_locked(dir: &Path) -> Result<CodexChannelStore, String> {
+    with_dir_lock(dir, || load_codex_store(dir))
+}
+
+fn codex_channel_position(channels: &[CodexChannel], id: &str) -> Result<usize, String> {
+    channels
+        .iter()
+        .position(|channel| channel.id == id)
+        .ok_or_else(|| "未找到指定的 Codex 渠道".to_string())
+}
 
-            if path.is_dir() {
-                fs::remove_dir_all(path)
-                    .map_err(|e| format!("删除目录失败 {}: {}", path.display(), e))?;
+fn pinned_count(channels: &[CodexChannel]) -> usize {
+    channels.iter().filter(|channel| channel.pinned).count()
+}
+
+// 置顶与非置顶渠道各自成段，移动时目标位置限制在所属分段内
+fn move_codex_channel_in(
+    channels: &mut Vec<CodexChannel>,
+    id: &str,
+    to_index: usize,
+) -> Result<(), String> {
+    let from = codex_channel_position(channels, id)?;
+    let channel = channels.remove(from);
+    let pinned = pinned_count(channels);
+    let target = if channel.pinned {
+        to_index.min(pinned)
+    } else {
+        to_index.clamp(pinned, channels.len())
+    };
+    channels.insert(target, channel);
+    Ok(())
+}
+
+fn set_codex_channel_pinned_in(
+    channels: &mut Vec<CodexChannel>,
+    id: &str,
+    pinned: bool,
+) -> Result<(), String> {
+    let from = codex_channel_position(channels, id)?;
+    let mut channel = channels.remove(from);
+    channel.pinned = pinned;
+    // 置顶时排到置顶分段末尾，取消置顶时排到普通分段开头
+    let target = pinned_count(channels);
+    channels.insert(target, channel);
+    Ok(())
+}
+
+fn copy_name_for(name: &str, channels: &[CodexChannel]) -> String {
+    (1..)
+        .map(|n| {
+            let suffix = if n == 1 {
+                "-copy".to_string()
             } else {
-                fs::remove_file(path)
-                    .map_err(|e| format!("删除文件失败 {}: {}", path.display(), e))?;
+                format!("-copy{}", n)
+            };
+            // 手动编辑过的渠道名可能含非 ASCII 字符，只在字符边界处截断
+            let mut keep = CODEX_NAME_MAX_LEN
+                .saturating_sub(suffix.len())
+                .min(name.len());
+            while !name.is_char_boundary(keep) {
+                keep -= 1;
             }
-            Ok(())
-        }
+            format!("{}{}", &name[..keep], suffix)
+        })
+        .find(|candidate| channels.iter().all(|channel| &channel.name != candidate))
+        .unwrap_or_default()
+}
+
+fn duplicate_codex_channel_in(
+    channels: &mut Vec<CodexChannel>,
+    id: &str,
+) -> Result<String, String> {
+    let from = codex_channel_position(channels, id)?;
+    let used: Vec<&str> = channels.iter().map(|channel| channel.id.as_str()).collect();
+    let copy = CodexChannel {
+        id: new_codex_channel_id(&used),
+        name: copy_name_for(&channels[from].name, channels),
+        ..channels[from].clone()
+    };
+    let copy_id = copy.id.clone();
+    channels.insert(from + 1, copy);
+    Ok(copy_id)
+}
+
+fn is_codex_store_read_only(store: &CodexChannelStore) -> bool {
+    store.version > CODEX_STORE_VERSION
+}
+
+fn save_codex_store(dir: &Path, store: &CodexChannelStore) -> Result<(), String> {
+    if is_codex_store_read_only(store) {
+        return Err(format!(
+            "Codex 存储版本 v{} 高于当前支持的 v{}，请升级应用后再修改渠道",
+            store.version, CODEX_STORE_VERSION
+        ));
     }
+
+    if !dir.exists() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建 Codex 目录失败: {}", e))?;
+    }
+
+    let serialized =
+        serde_json::to_string_pretty(store).map_err(|e| format!("序列化 Codex 存储失败: {}", e))?;
+    write_text_file(&codex_store_path(dir), &serialized)?;
+    Ok(())
 }
 
 fn read_codex_active_info_from_toml(content: &str) -> Result<CodexActiveInfo, String> {
@@ -300,7 +668,7 @@ fn read_codex_active_info_from_toml(content: &str) -> Result<CodexActiveInfo, St
 
     Ok(CodexActiveInfo {
         api_key: String::new(),
-        base_url: normalize_codex_baseurl(raw_base_url)
+        base_url: normalize_base_url(raw_base_url)
             .unwrap_or_else(|_| raw_base_url.trim().to_string()),
         model: root
             .get("model")
@@ -314,209 +682,707 @@ fn read_codex_active_info_from_toml(content: &str) -> Result<CodexActiveInfo, St
             .unwrap_or("")
             .trim()
             .to_string(),
+        auth_mode: CodexAuthMode::ApiKey,
+        active_channel: None,
     })
 }
 
-fn update_codex_config_toml(existing: &str, channel: &CodexChannel) -> Result<String, String> {
-    let mut root: TomlTable =
+// 基于 toml_edit 的文档模型修改，注释、键顺序和其余格式保持原样；
+// 先还原上一个渠道的附加配置，再写入当前渠道的附加配置
+fn update_codex_config_toml(
+    existing: &str,
+    channel: &CodexChannel,
+    previous_extra: Option<&CodexAppliedExtra>,
+) -> Result<(String, CodexAppliedExtra), String> {
+    let extra = parse_codex_extra_config(&channel.extra_config)?;
+    let root: TomlTable =
         toml::from_str(existing).map_err(|e| format!("解析 config.toml 失败: {}", e))?;
-    let target_provider_name = find_target_provider_name(&root)
-        .ok_or_else(|| "未找到可更新的 model_providers 配置".to_string())?;
+    let mut document: DocumentMut = existing
+        .parse()
+        .map_err(|e| format!("解析 config.toml 失败: {}", e))?;
 
-    root.insert(
-        "model".to_string(),
-        TomlValue::String(channel.model.clone()),
-    );
+    if let Some(previous_extra) = previous_extra {
+        revert_codex_extra_config(&mut document, previous_extra);
+    }
 
-    let providers = root
-        .get_mut("model_providers")
-        .and_then(TomlValue::as_table_mut)
-        .ok_or_else(|| "model_providers 必须是表".to_string())?;
-    let provider = providers
-        .get_mut(&target_provider_name)
-        .and_then(TomlValue::as_table_mut)
-        .ok_or_else(|| format!("model_providers.{} 必须是表", target_provider_name))?;
-    provider.insert(
-        "base_url".to_string(),
-        TomlValue::String(channel.baseurl.clone()),
+    if !channel.model.is_empty() {
+        set_toml_string(document.as_table_mut(), "model", &channel.model);
+    }
+
+    let target_provider_name = match channel.auth_mode {
+        CodexAuthMode::ApiKey => update_api_key_provider(&mut document, &root, channel)?,
+        CodexAuthMode::Chatgpt => CHATGPT_PROVIDER_ID.to_string(),
+    };
+    set_toml_string(
+        document.as_table_mut(),
+        "model_provider",
+        &target_provider_name,
     );
 
-    toml::to_string_pretty(&root).map_err(|e| format!("序列化 config.toml 失败: {}", e))
+    let mut applied = CodexAppliedExtra {
+        channel: channel.name.clone(),
+        provider: target_provider_name,
+        root: apply_extra_values(document.as_table_mut(), &extra.root),
+        provider_keys: vec![],
+    };
+    if let Some(provider) = codex_provider_table_mut(&mut document, &applied.provider) {
+        applied.provider_keys = apply_extra_values(provider, &extra.provider);
+    }
+
+    Ok((document.to_string(), applied))
 }
 
-fn update_auth_json(existing: Option<&str>, api_key: &str) -> Result<String, String> {
-    let mut auth_json: serde_json::Value = match existing {
-        Some(content) => serde_json::from_str(content).unwrap_or_else(|_| serde_json::json!({})),
-        None => serde_json::json!({}),
+// 写入 API Key 渠道对应的 provider，返回其名称
+fn update_api_key_provider(
+    document: &mut DocumentMut,
+    root: &TomlTable,
+    channel: &CodexChannel,
+) -> Result<String, String> {
+    let target_provider_name = match &channel.provider {
+        Some(provider) => provider.id.clone(),
+        None => match find_target_provider_name(root) {
+            Some(provider_name) => provider_name,
+            None => bootstrap_codex_provider(document, root)?,
+        },
     };
 
-    if !auth_json.is_object() {
-        auth_json = serde_json::json!({});
+    let providers = document
+        .entry("model_providers")
+        .or_insert_with(implicit_table)
+        .as_table_like_mut()
+        .ok_or_else(|| "model_providers 必须是表".to_string())?;
+    if providers.get(&target_provider_name).is_none() {
+        providers.insert(
+            &target_provider_name,
+            TomlEditItem::Table(TomlEditTable::new()),
+        );
     }
+    let provider = providers
+        .get_mut(&target_provider_name)
+        .and_then(TomlEditItem::as_table_like_mut)
+        .ok_or_else(|| format!("model_providers.{} 必须是表", target_provider_name))?;
+    set_toml_string(provider, "base_url", &channel.baseurl);
 
-    if let Some(obj) = auth_json.as_object_mut() {
-        obj.insert("OPENAI_API_KEY".to_string(), serde_json::json!(api_key));
+    if let Some(config) = &channel.provider {
+        apply_codex_provider_config(provider, config);
     }
 
-    serde_json::to_string_pretty(&auth_json).map_err(|e| format!("序列化 auth.json 失败: {}", e))
+    Ok(target_provider_name)
 }
 
-fn find_codex_channel<'a>(channels: &'a [CodexChannel], name: &str) -> Option<&'a CodexChannel> {
-    channels.iter().find(|channel| channel.name == name)
+fn codex_provider_table_mut<'a>(
+    document: &'a mut DocumentMut,
+    provider_name: &str,
+) -> Option<&'a mut dyn TableLike> {
+    document
+        .get_mut("model_providers")
+        .and_then(TomlEditItem::as_table_like_mut)?
+        .get_mut(provider_name)
+        .and_then(TomlEditItem::as_table_like_mut)
 }
 
-fn apply_switch_updates(
-    config_path: &Path,
-    updated_config: &str,
-    auth_path: &Path,
-    updated_auth: &str,
-) -> Result<(), String> {
-    apply_switch_updates_with_writer(
-        config_path,
-        updated_config,
-        auth_path,
-        updated_auth,
-        write_text_file,
-    )
+fn item_to_value(item: &TomlEditItem) -> Option<TomlEditValue> {
+    item.clone().into_value().ok()
 }
 
-fn apply_switch_updates_with_writer<F>(
-    config_path: &Path,
-    updated_config: &str,
-    auth_path: &Path,
-    updated_auth: &str,
-    mut writer: F,
-) -> Result<(), String>
-where
-    F: FnMut(&Path, &str) -> Result<(), String>,
-{
-    let config_snapshot = snapshot_text_file(config_path)?;
-    let auth_snapshot = snapshot_text_file(auth_path)?;
-
-    writer(config_path, updated_config)?;
+// 去掉空白与注释后的规范文本，用于比较和持久化
+fn toml_value_repr(value: &TomlEditValue) -> String {
+    let mut value = value.clone();
+    value.decor_mut().clear();
+    match &mut value {
+        TomlEditValue::Array(array) => array.fmt(),
+        TomlEditValue::InlineTable(table) => table.fmt(),
+        _ => {}
+    }
+    value.to_string()
+}
 
-    if let Err(write_auth_error) = writer(auth_path, updated_auth) {
-        let rollback_config = restore_text_snapshot(config_path, &config_snapshot);
-        let rollback_auth = restore_text_snapshot(auth_path, &auth_snapshot);
+fn same_toml_value(left: &TomlEditValue, right: &TomlEditValue) -> bool {
+    match (left.as_str(), right.as_str()) {
+        (Some(left), Some(right)) => left == right,
+        _ => toml_value_repr(left) == toml_value_repr(right),
+    }
+}
 
-        let mut message = format!("写入 auth.json 失败: {}", write_auth_error);
-        if let Err(e) = rollback_config {
-            message.push_str(&format!("；回滚 config.toml 失败: {}", e));
+fn set_toml_value(table: &mut dyn TableLike, key: &str, value: &TomlEditValue) {
+    if let Some(existing) = table.get_mut(key).and_then(TomlEditItem::as_value_mut) {
+        if same_toml_value(existing, value) {
+            return;
         }
-        if let Err(e) = rollback_auth {
-            message.push_str(&format!("；回滚 auth.json 失败: {}", e));
-        }
-
-        return Err(message);
+        let decor = existing.decor().clone();
+        *existing = value.clone();
+        *existing.decor_mut() = decor;
+        return;
     }
 
-    Ok(())
+    let mut value = value.clone();
+    value.decor_mut().clear();
+    table.insert(key, TomlEditItem::Value(value));
 }
 
-#[tauri::command]
-pub(crate) async fn get_codex_channels(
-    codex_config_path: String,
-) -> ApiResponse<Vec<CodexChannel>> {
-    let dir = Path::new(&codex_config_path);
+fn parse_codex_extra_config(fragment: &str) -> Result<CodexExtraConfig, String> {
+    let mut extra = CodexExtraConfig::default();
+    if fragment.trim().is_empty() {
+        return Ok(extra);
+    }
 
-    match load_codex_store(dir) {
-        Ok(store) => ApiResponse {
-            success: true,
-            error: None,
-            channels: None,
-            config: None,
-            data: Some(store.channels),
-        },
-        Err(e) => ApiResponse {
-            success: false,
-            error: Some(e),
-            channels: None,
-            config: None,
-            data: None,
-        },
+    let document: DocumentMut = fragment
+        .parse()
+        .map_err(|e| format!("附加配置不是有效的 TOML: {}", e))?;
+
+    for (key, item) in document.iter() {
+        if key == EXTRA_PROVIDER_TABLE {
+            let provider = item
+                .as_table_like()
+                .ok_or_else(|| format!("附加配置中的 {} 必须是表", EXTRA_PROVIDER_TABLE))?;
+            for (provider_key, provider_item) in provider.iter() {
+                if MANAGED_PROVIDER_KEYS.contains(&provider_key) {
+                    return Err(format!(
+                        "附加配置不能覆盖由渠道管理的 provider 字段: {}",
+                        provider_key
+                    ));
+                }
+                let value = item_to_value(provider_item)
+                    .ok_or_else(|| format!("附加配置中的 {} 无法写入", provider_key))?;
+                extra.provider.push((provider_key.to_string(), value));
+            }
+            continue;
+        }
+
+        if MANAGED_ROOT_KEYS.contains(&key) {
+            return Err(format!("附加配置不能覆盖由渠道管理的字段: {}", key));
+        }
+        let value = item_to_value(item).ok_or_else(|| format!("附加配置中的 {} 无法写入", key))?;
+        extra.root.push((key.to_string(), value));
     }
-}
 
-#[tauri::command]
-pub(crate) async fn save_codex_channel(
-    codex_config_path: String,
-    name: String,
-    baseurl: String,
-    apikey: String,
-    model: String,
-    edit_index: i32,
-) -> ApiResponse<()> {
-    let dir = Path::new(&codex_config_path);
-    let mut store = match load_codex_store(dir) {
-        Ok(store) => store,
-        Err(e) => return ApiResponse::error(e),
-    };
+    Ok(extra)
+}
 
-    let new_channel = match normalize_codex_channel(CodexChannel {
-        name,
-        baseurl,
-        apikey,
-        model,
-    }) {
-        Ok(channel) => channel,
-        Err(e) => return ApiResponse::error(e),
-    };
-    let edit_index = if edit_index >= 0 {
-        Some(edit_index as usize)
-    } else {
-        None
-    };
+fn apply_extra_values(
+    table: &mut dyn TableLike,
+    values: &[(String, TomlEditValue)],
+) -> Vec<CodexAppliedKey> {
+    values
+        .iter()
+        .map(|(key, value)| {
+            let previous = table
+                .get(key)
+                .and_then(item_to_value)
+                .map(|value| toml_value_repr(&value));
+            set_toml_value(table, key, value);
+            CodexAppliedKey {
+                key: key.clone(),
+                value: toml_value_repr(value),
+                previous,
+            }
+        })
+        .collect()
+}
 
-    if let Err(e) = validate_codex_channel(&new_channel, &store.channels, edit_index) {
-        return ApiResponse::error(e);
-    }
+// 只还原仍保持写入时取值的键，用户在切换后手动修改过的键保持不变
+fn revert_extra_values(table: &mut dyn TableLike, applied: &[CodexAppliedKey]) {
+    for applied_key in applied.iter().rev() {
+        let Ok(applied_value) = applied_key.value.parse::<TomlEditValue>() else {
+            continue;
+        };
+        let unchanged = table
+            .get(&applied_key.key)
+            .and_then(item_to_value)
+            .map(|current| same_toml_value(&current, &applied_value))
+            .unwrap_or(false);
+        if !unchanged {
+            continue;
+        }
 
-    if let Some(idx) = edit_index {
-        if idx >= store.channels.len() {
-            return ApiResponse::error("索引越界".to_string());
+        match applied_key
+            .previous
+            .as_deref()
+            .map(str::parse::<TomlEditValue>)
+        {
+            Some(Ok(previous)) => set_toml_value(table, &applied_key.key, &previous),
+            Some(Err(_)) => {}
+            None => {
+                table.remove(&applied_key.key);
+            }
         }
-        store.channels[idx] = new_channel;
-    } else {
-        store.channels.insert(0, new_channel);
     }
+}
 
-    match save_codex_store(dir, &store) {
-        Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse::error(e),
+fn revert_codex_extra_config(document: &mut DocumentMut, applied: &CodexAppliedExtra) {
+    if let Some(provider) = codex_provider_table_mut(document, &applied.provider) {
+        revert_extra_values(provider, &applied.provider_keys);
     }
+    revert_extra_values(document.as_table_mut(), &applied.root);
 }
 
-#[tauri::command]
-pub(crate) async fn delete_codex_channel(
-    codex_config_path: String,
-    delete_index: usize,
-) -> ApiResponse<()> {
-    let dir = Path::new(&codex_config_path);
-    let mut store = match load_codex_store(dir) {
-        Ok(store) => store,
-        Err(e) => return ApiResponse::error(e),
-    };
+fn extra_config_state_path(dir: &Path) -> PathBuf {
+    dir.join(EXTRA_CONFIG_STATE_FILE)
+}
 
-    if delete_index >= store.channels.len() {
-        return ApiResponse::error("索引越界".to_string());
+// 状态文件损坏时视为没有需要还原的附加配置
+fn load_applied_extra(dir: &Path) -> Option<CodexAppliedExtra> {
+    let content = fs::read_to_string(extra_config_state_path(dir)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn implicit_table() -> TomlEditItem {
+    let mut table = TomlEditTable::new();
+    table.set_implicit(true);
+    TomlEditItem::Table(table)
+}
+
+// 值未变化时不改动原文；变化时沿用原值前后的空白和行尾注释
+fn set_toml_string(table: &mut dyn TableLike, key: &str, value: &str) {
+    if let Some(existing) = table.get_mut(key).and_then(TomlEditItem::as_value_mut) {
+        if existing.as_str() == Some(value) {
+            return;
+        }
+        let decor = existing.decor().clone();
+        *existing = TomlEditValue::from(value);
+        *existing.decor_mut() = decor;
+        return;
+    }
+    table.insert(key, toml_edit::value(value));
+}
+
+// config.toml 不存在或没有任何 provider 时，创建默认 provider 并设为当前 provider
+fn bootstrap_codex_provider(
+    document: &mut DocumentMut,
+    root: &TomlTable,
+) -> Result<String, String> {
+    let has_providers = match root.get("model_providers") {
+        None => false,
+        Some(TomlValue::Table(table)) => !table.is_empty(),
+        Some(_) => return Err("model_providers 必须是表".to_string()),
+    };
+    if has_providers {
+        return Err("未找到可更新的 model_providers 配置".to_string());
     }
 
-    store.channels.remove(delete_index);
+    let mut provider = TomlEditTable::new();
+    provider.insert("name", toml_edit::value(DEFAULT_PROVIDER_ID));
+    provider.insert("wire_api", toml_edit::value(DEFAULT_WIRE_API));
 
-    match save_codex_store(dir, &store) {
-        Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse::error(e),
+    let providers = document
+        .entry("model_providers")
+        .or_insert_with(implicit_table)
+        .as_table_like_mut()
+        .ok_or_else(|| "model_providers 必须是表".to_string())?;
+    providers.insert(DEFAULT_PROVIDER_ID, TomlEditItem::Table(provider));
+    set_toml_string(
+        document.as_table_mut(),
+        "model_provider",
+        DEFAULT_PROVIDER_ID,
+    );
+
+    Ok(DEFAULT_PROVIDER_ID.to_string())
+}
+
+// name、wire_api、env_key、query_params 以渠道为准，用户手写的其他键（如 http_headers）保持不变
+fn apply_codex_provider_config(provider: &mut dyn TableLike, config: &CodexProviderConfig) {
+    set_toml_string(provider, "name", &config.name);
+
+    let optional_fields = [
+        ("wire_api", config.wire_api.as_ref()),
+        ("env_key", config.env_key.as_ref()),
+    ];
+    for (key, value) in optional_fields {
+        match value {
+            Some(value) => set_toml_string(provider, key, value),
+            None => {
+                provider.remove(key);
+            }
+        }
+    }
+
+    if config.query_params.is_empty() {
+        provider.remove("query_params");
+        return;
+    }
+
+    let unchanged = provider
+        .get("query_params")
+        .and_then(TomlEditItem::as_table_like)
+        .map(|current| {
+            current.len() == config.query_params.len()
+                && config.query_params.iter().all(|(key, value)| {
+                    current.get(key).and_then(TomlEditItem::as_str) == Some(value.as_str())
+                })
+        })
+        .unwrap_or(false);
+    if !unchanged {
+        let mut query_params = InlineTable::new();
+        for (key, value) in &config.query_params {
+            query_params.insert(key, TomlEditValue::from(value.as_str()));
+        }
+        provider.insert("query_params", toml_edit::value(query_params));
+    }
+}
+
+fn has_chatgpt_tokens(auth: &serde_json::Value) -> bool {
+    auth.get("tokens")
+        .and_then(serde_json::Value::as_object)
+        .is_some_and(|tokens| !tokens.is_empty())
+}
+
+fn chatgpt_account_id(auth: &serde_json::Value) -> Option<&str> {
+    auth.get("tokens")?
+        .get("account_id")?
+        .as_str()
+        .map(str::trim)
+        .filter(|account_id| !account_id.is_empty())
+}
+
+fn is_same_chatgpt_account(left: &serde_json::Value, right: &serde_json::Value) -> bool {
+    matches!(
+        (chatgpt_account_id(left), chatgpt_account_id(right)),
+        (Some(left), Some(right)) if left == right
+    )
+}
+
+fn parse_auth_json(existing: Option<&str>) -> Option<serde_json::Value> {
+    existing
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(content).ok())
+        .filter(serde_json::Value::is_object)
+}
+
+// API Key 模式只保留 OPENAI_API_KEY，避免 Codex 优先使用残留的 ChatGPT 登录；
+// ChatGPT 模式直接写回渠道保存的 auth.json
+fn update_auth_json(existing: Option<&str>, channel: &CodexChannel) -> Result<String, String> {
+    let auth_json = match channel.auth_mode {
+        CodexAuthMode::ApiKey => {
+            let mut auth_json = parse_auth_json(existing).unwrap_or_else(|| serde_json::json!({}));
+            if let Some(obj) = auth_json.as_object_mut() {
+                obj.insert(
+                    "OPENAI_API_KEY".to_string(),
+                    serde_json::json!(channel.apikey),
+                );
+                obj.remove("tokens");
+                obj.remove("last_refresh");
+            }
+            auth_json
+        }
+        CodexAuthMode::Chatgpt => channel
+            .chatgpt_auth
+            .clone()
+            .ok_or_else(|| "ChatGPT 登录渠道缺少登录信息，请先运行 codex login".to_string())?,
+    };
+
+    serde_json::to_string_pretty(&auth_json).map_err(|e| format!("序列化 auth.json 失败: {}", e))
+}
+
+// 用当前 auth.json 刷新同一账号的 ChatGPT 渠道（Codex 会自动续期令牌），返回是否有渠道保存了该登录
+fn refresh_chatgpt_snapshots(channels: &mut [CodexChannel], auth: &serde_json::Value) -> bool {
+    let mut refreshed = false;
+    for channel in channels
+        .iter_mut()
+        .filter(|channel| channel.auth_mode == CodexAuthMode::Chatgpt)
+    {
+        if channel
+            .chatgpt_auth
+            .as_ref()
+            .is_some_and(|saved| is_same_chatgpt_account(saved, auth))
+        {
+            channel.chatgpt_auth = Some(auth.clone());
+            refreshed = true;
+        }
+    }
+    refreshed
+}
+
+fn chatgpt_backup_path(dir: &Path, auth: &serde_json::Value) -> PathBuf {
+    let account: String = chatgpt_account_id(auth)
+        .unwrap_or("unknown")
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}{}.json", CHATGPT_AUTH_BACKUP_PREFIX, account))
+}
+
+// 前端不持有登录信息：编辑时沿用已保存的快照，新建时读取当前 auth.json
+fn resolve_chatgpt_auth(
+    dir: &Path,
+    channel: &CodexChannel,
+    previous: Option<&CodexChannel>,
+) -> Result<Option<serde_json::Value>, String> {
+    if channel.auth_mode != CodexAuthMode::Chatgpt {
+        return Ok(None);
+    }
+    if let Some(saved) = previous.and_then(|previous| previous.chatgpt_auth.clone()) {
+        return Ok(Some(saved));
+    }
+
+    let current = parse_auth_json(snapshot_text_file(&dir.join("auth.json"))?.as_deref())
+        .filter(has_chatgpt_tokens);
+    match current {
+        Some(auth) => Ok(Some(auth)),
+        None => Err("当前 auth.json 中没有 ChatGPT 登录信息，请先运行 codex login".to_string()),
+    }
+}
+
+fn find_active_codex_channel<'a>(
+    channels: &'a [CodexChannel],
+    info: &CodexActiveInfo,
+    auth: Option<&serde_json::Value>,
+) -> Option<&'a CodexChannel> {
+    if info.auth_mode == CodexAuthMode::Chatgpt {
+        let auth = auth?;
+        return channels.iter().find(|channel| {
+            channel.auth_mode == CodexAuthMode::Chatgpt
+                && channel
+                    .chatgpt_auth
+                    .as_ref()
+                    .is_some_and(|saved| is_same_chatgpt_account(saved, auth))
+        });
+    }
+
+    if info.api_key.is_empty() {
+        return None;
+    }
+    let candidates = channels.iter().filter(|channel| {
+        channel.auth_mode == CodexAuthMode::ApiKey
+            && channel.apikey == info.api_key
+            && channel.baseurl == info.base_url
+    });
+    // 同一 Key 和地址对应多个渠道时，优先 provider 与模型都一致的
+    candidates
+        .clone()
+        .find(|channel| {
+            channel.model == info.model
+                && channel
+                    .provider
+                    .as_ref()
+                    .is_none_or(|provider| provider.id == info.model_provider)
+        })
+        .or_else(|| candidates.clone().next())
+}
+
+fn find_codex_channel<'a>(channels: &'a [CodexChannel], name: &str) -> Option<&'a CodexChannel> {
+    channels.iter().find(|channel| channel.name == name)
+}
+
+// id 为空时在非置顶分段顶部新建，否则按 id 原位更新；返回渠道 id
+fn upsert_codex_channel(
+    dir: &Path,
+    store: &mut CodexChannelStore,
+    mut new_channel: CodexChannel,
+) -> Result<String, String> {
+    let editing = if new_channel.id.is_empty() {
+        None
+    } else {
+        Some(codex_channel_position(&store.channels, &new_channel.id)?)
+    };
+    let previous = editing.map(|idx| &store.channels[idx]);
+    new_channel.chatgpt_auth = resolve_chatgpt_auth(dir, &new_channel, previous)?;
+    validate_codex_channel(
+        &new_channel,
+        &store.channels,
+        previous.map(|channel| channel.id.as_str()),
+    )?;
+
+    match editing {
+        Some(idx) => {
+            new_channel.pinned = store.channels[idx].pinned;
+            store.channels[idx] = new_channel.clone();
+        }
+        None => {
+            let used: Vec<&str> = store
+                .channels
+                .iter()
+                .map(|channel| channel.id.as_str())
+                .collect();
+            new_channel.id = new_codex_channel_id(&used);
+            new_channel.pinned = false;
+            let position = pinned_count(&store.channels);
+            store.channels.insert(position, new_channel.clone());
+        }
+    }
+    Ok(new_channel.id)
+}
+
+fn store_change_response(
+    result: Result<CodexStoreChange, String>,
+) -> ApiResponse<CodexStoreChange> {
+    match result {
+        Ok(change) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(change),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_codex_channels(codex_config_path: String) -> ApiResponse<CodexChannelList> {
+    match run_blocking(move || load_codex_store_locked(Path::new(&codex_config_path))).await {
+        Ok(store) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(CodexChannelList {
+                revision: store.revision,
+                channels: store
+                    .channels
+                    .into_iter()
+                    .map(|channel| CodexChannel {
+                        chatgpt_auth: None,
+                        ..channel
+                    })
+                    .collect(),
+            }),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_codex_store_status(
+    codex_config_path: String,
+) -> ApiResponse<CodexStoreStatus> {
+    match run_blocking(move || load_codex_store_locked(Path::new(&codex_config_path))).await {
+        Ok(store) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(CodexStoreStatus {
+                version: store.version,
+                supported_version: CODEX_STORE_VERSION,
+                read_only: is_codex_store_read_only(&store),
+            }),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
     }
 }
 
+// channel.id 为空时新建，否则按 id 更新；id 与置顶状态以存储中的为准
+#[tauri::command]
+pub(crate) async fn save_codex_channel(
+    codex_config_path: String,
+    channel: CodexChannel,
+    expected_revision: u64,
+) -> ApiResponse<CodexStoreChange> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&codex_config_path);
+        normalize_codex_channel(channel).and_then(|new_channel| {
+            mutate_codex_store(dir, expected_revision, |store| {
+                upsert_codex_channel(dir, store, new_channel).map(Some)
+            })
+        })
+    })
+    .await;
+
+    store_change_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn delete_codex_channel(
+    codex_config_path: String,
+    channel_id: String,
+    expected_revision: u64,
+) -> ApiResponse<CodexStoreChange> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&codex_config_path);
+        mutate_codex_store(dir, expected_revision, |store| {
+            let position = codex_channel_position(&store.channels, &channel_id)?;
+            store.channels.remove(position);
+            Ok(None)
+        })
+    })
+    .await;
+    store_change_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn move_codex_channel(
+    codex_config_path: String,
+    channel_id: String,
+    to_index: usize,
+    expected_revision: u64,
+) -> ApiResponse<CodexStoreChange> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&codex_config_path);
+        mutate_codex_store(dir, expected_revision, |store| {
+            move_codex_channel_in(&mut store.channels, &channel_id, to_index)?;
+            Ok(Some(channel_id.clone()))
+        })
+    })
+    .await;
+    store_change_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn duplicate_codex_channel(
+    codex_config_path: String,
+    channel_id: String,
+    expected_revision: u64,
+) -> ApiResponse<CodexStoreChange> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&codex_config_path);
+        mutate_codex_store(dir, expected_revision, |store| {
+            duplicate_codex_channel_in(&mut store.channels, &channel_id).map(Some)
+        })
+    })
+    .await;
+    store_change_response(result)
+}
+
 #[tauri::command]
-pub(crate) fn get_current_codex_env(codex_config_path: String) -> ApiResponse<CodexActiveInfo> {
-    let dir = Path::new(&codex_config_path);
+pub(crate) async fn set_codex_channel_pinned(
+    codex_config_path: String,
+    channel_id: String,
+    pinned: bool,
+    expected_revision: u64,
+) -> ApiResponse<CodexStoreChange> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&codex_config_path);
+        mutate_codex_store(dir, expected_revision, |store| {
+            set_codex_channel_pinned_in(&mut store.channels, &channel_id, pinned)?;
+            Ok(Some(channel_id.clone()))
+        })
+    })
+    .await;
+    store_change_response(result)
+}
+
+// 读取 config.toml 与 auth.json 中当前生效的配置，并匹配对应的渠道
+fn read_current_codex_env(dir: &Path) -> CodexActiveInfo {
     let mut info = CodexActiveInfo {
         api_key: String::new(),
         base_url: String::new(),
         model: String::new(),
         model_provider: String::new(),
+        auth_mode: CodexAuthMode::ApiKey,
+        active_channel: None,
     };
 
     let config_path = dir.join("config.toml");
@@ -528,24 +1394,114 @@ pub(crate) fn get_current_codex_env(codex_config_path: String) -> ApiResponse<Co
         }
     }
 
+    let auth = parse_auth_json(
+        snapshot_text_file(&dir.join("auth.json"))
+            .ok()
+            .flatten()
+            .as_deref(),
+    );
+    if let Some(json) = &auth {
+        if let Some(key) = json.get("OPENAI_API_KEY").and_then(|v| v.as_str()) {
+            info.api_key = key.trim().to_string();
+        }
+        // 使用内置 provider 且存在登录令牌时，Codex 走 ChatGPT 登录
+        let builtin_provider =
+            info.model_provider.is_empty() || info.model_provider == CHATGPT_PROVIDER_ID;
+        if builtin_provider && has_chatgpt_tokens(json) {
+            info.auth_mode = CodexAuthMode::Chatgpt;
+        }
+    }
+
+    if let Ok(store) = load_codex_store_locked(dir) {
+        info.active_channel = find_active_codex_channel(&store.channels, &info, auth.as_ref())
+            .map(|channel| channel.name.clone());
+    }
+
+    info
+}
+
+#[tauri::command]
+pub(crate) async fn get_current_codex_env(
+    codex_config_path: String,
+) -> ApiResponse<CodexActiveInfo> {
+    // 读取渠道存储需要目录锁，放到阻塞线程池中执行，避免卡住界面
+    data_response(
+        run_blocking(move || Ok(read_current_codex_env(Path::new(&codex_config_path)))).await,
+    )
+}
+
+// 在目录锁内完成切换；config.toml 与 auth.json 可能被 Codex 自身改写，写回前确认读取后未被修改
+fn apply_codex_switch(dir: &Path, channel_name: &str) -> Result<(), String> {
+    let config_path = dir.join("config.toml");
     let auth_path = dir.join("auth.json");
-    if auth_path.exists() {
-        if let Ok(content) = fs::read_to_string(&auth_path) {
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
-                if let Some(key) = json.get("OPENAI_API_KEY").and_then(|v| v.as_str()) {
-                    info.api_key = key.trim().to_string();
-                }
-            }
+
+    let mut store = load_codex_store(dir)?;
+    if find_codex_channel(&store.channels, channel_name).is_none() {
+        return Err("未找到指定的 Codex 渠道".to_string());
+    }
+
+    let (existing_auth, auth_stamp) = read_stamped_text(&auth_path)?;
+
+    // 覆盖 auth.json 前保存其中的 ChatGPT 登录：刷新对应渠道的快照，没有对应渠道时另存备份
+    let current_login = parse_auth_json(existing_auth.as_deref()).filter(has_chatgpt_tokens);
+    let mut login_saved_in_store = false;
+    if let Some(login) = &current_login {
+        if !is_codex_store_read_only(&store) {
+            login_saved_in_store = refresh_chatgpt_snapshots(&mut store.channels, login);
         }
     }
 
-    ApiResponse {
-        success: true,
-        error: None,
-        channels: None,
-        config: None,
-        data: Some(info),
+    let channel = find_codex_channel(&store.channels, channel_name)
+        .cloned()
+        .ok_or_else(|| "未找到指定的 Codex 渠道".to_string())?;
+
+    // 缺失的 config.toml / auth.json 按所选渠道初始化
+    let (existing_config, config_stamp) = read_stamped_text(&config_path)?;
+    let previous_extra = load_applied_extra(dir);
+    let (updated_config, applied_extra) = update_codex_config_toml(
+        existing_config.as_deref().unwrap_or_default(),
+        &channel,
+        previous_extra.as_ref(),
+    )?;
+    let updated_auth = update_auth_json(existing_auth.as_deref(), &channel)?;
+
+    let mut updates = vec![
+        (config_path.as_path(), updated_config.as_str()),
+        (auth_path.as_path(), updated_auth.as_str()),
+    ];
+
+    // 没有附加配置且从未记录过时不创建状态文件
+    let state_path = extra_config_state_path(dir);
+    let serialized_extra;
+    if previous_extra.is_some()
+        || !applied_extra.root.is_empty()
+        || !applied_extra.provider_keys.is_empty()
+    {
+        serialized_extra = serde_json::to_string_pretty(&applied_extra)
+            .map_err(|e| format!("序列化附加配置状态失败: {}", e))?;
+        updates.push((state_path.as_path(), serialized_extra.as_str()));
+    }
+
+    let store_path = codex_store_path(dir);
+    let serialized_store;
+    let backup_path;
+    let serialized_login;
+    if let Some(login) = &current_login {
+        if login_saved_in_store {
+            serialized_store = serde_json::to_string_pretty(&store)
+                .map_err(|e| format!("序列化 Codex 存储失败: {}", e))?;
+            updates.push((store_path.as_path(), serialized_store.as_str()));
+        } else {
+            backup_path = chatgpt_backup_path(dir, login);
+            serialized_login = serde_json::to_string_pretty(login)
+                .map_err(|e| format!("序列化 auth.json 失败: {}", e))?;
+            updates.push((backup_path.as_path(), serialized_login.as_str()));
+        }
     }
+
+    ensure_unchanged(&config_path, &config_stamp)?;
+    ensure_unchanged(&auth_path, &auth_stamp)?;
+    write_files_with_rollback(&updates)
 }
 
 #[tauri::command]
@@ -553,301 +1509,1661 @@ pub(crate) async fn switch_codex_channel(
     codex_config_path: String,
     channel_name: String,
 ) -> ApiResponse<()> {
-    let dir = Path::new(&codex_config_path);
-    let config_path = dir.join("config.toml");
-    let auth_path = dir.join("auth.json");
+    let result = run_blocking(move || {
+        let dir = Path::new(&codex_config_path);
+        with_dir_lock(dir, || apply_codex_switch(dir, channel_name.trim()))
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) async fn launch_codex(terminal_dir: String) -> ApiResponse<()> {
+    open_terminal("codex", &terminal_dir)
+}
+
+// ==================== 单次启动 ====================
+
+// 以 -c 覆盖定义仅本次会话使用的 provider，Key 由子进程环境变量提供；
+// 值不加引号，Codex 无法按 TOML 解析时按原字符串处理，避免引号在 PowerShell 传参时丢失
+fn codex_session_overrides(channel: &CodexChannel) -> Result<Vec<String>, String> {
+    if channel.auth_mode == CodexAuthMode::Chatgpt {
+        return Err("ChatGPT 登录渠道不支持单次启动，请切换后再启动".to_string());
+    }
+
+    let provider = format!("model_providers.{}", CODEX_SESSION_PROVIDER_ID);
+    let wire_api = channel
+        .provider
+        .as_ref()
+        .and_then(|provider| provider.wire_api.as_deref())
+        .unwrap_or(DEFAULT_WIRE_API);
+    let mut overrides = vec![
+        format!("model_provider={}", CODEX_SESSION_PROVIDER_ID),
+        format!("{}.name={}", provider, CODEX_SESSION_PROVIDER_ID),
+        format!("{}.base_url={}", provider, channel.baseurl),
+        format!("{}.wire_api={}", provider, wire_api),
+        format!("{}.env_key={}", provider, CODEX_SESSION_KEY_VAR),
+    ];
+    if !channel.model.is_empty() {
+        overrides.push(format!("model={}", channel.model));
+    }
+
+    Ok(overrides
+        .into_iter()
+        .flat_map(|value| ["-c".to_string(), value])
+        .collect())
+}
+
+// ==================== 项目级 profile ====================
+
+// 项目级配置写入 [profiles.<name>] 与同名 provider，Key 放在 provider 的 experimental_bearer_token 中，
+// 全局 model_provider 与 auth.json 保持不变；在项目目录中以 codex --profile <name> 启动即可使用
+pub(crate) fn codex_project_profile_name(project: &Path) -> String {
+    let slug: String = project
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+
+    let mut hasher = DefaultHasher::new();
+    project.hash(&mut hasher);
+    format!(
+        "project-{}-{:08x}",
+        slug.trim_matches('-'),
+        hasher.finish() as u32
+    )
+}
+
+fn update_codex_profile_toml(
+    existing: &str,
+    profile: &str,
+    channel: &CodexChannel,
+) -> Result<String, String> {
+    if channel.auth_mode == CodexAuthMode::Chatgpt {
+        return Err("ChatGPT 登录渠道不支持项目级配置".to_string());
+    }
+
+    let mut document: DocumentMut = existing
+        .parse()
+        .map_err(|e| format!("解析 config.toml 失败: {}", e))?;
+
+    let providers = document
+        .entry("model_providers")
+        .or_insert_with(implicit_table)
+        .as_table_like_mut()
+        .ok_or_else(|| "model_providers 必须是表".to_string())?;
+    if providers.get(profile).is_none() {
+        providers.insert(profile, TomlEditItem::Table(TomlEditTable::new()));
+    }
+    let provider = providers
+        .get_mut(profile)
+        .and_then(TomlEditItem::as_table_like_mut)
+        .ok_or_else(|| format!("model_providers.{} 必须是表", profile))?;
+    match &channel.provider {
+        Some(config) => apply_codex_provider_config(provider, config),
+        None => set_toml_string(provider, "name", &channel.name),
+    }
+    if provider.get("wire_api").is_none() {
+        set_toml_string(provider, "wire_api", DEFAULT_WIRE_API);
+    }
+    provider.remove("env_key");
+    set_toml_string(provider, "base_url", &channel.baseurl);
+    set_toml_string(provider, "experimental_bearer_token", &channel.apikey);
+
+    let profiles = document
+        .entry("profiles")
+        .or_insert_with(implicit_table)
+        .as_table_like_mut()
+        .ok_or_else(|| "profiles 必须是表".to_string())?;
+    if profiles.get(profile).is_none() {
+        profiles.insert(profile, TomlEditItem::Table(TomlEditTable::new()));
+    }
+    let profile_table = profiles
+        .get_mut(profile)
+        .and_then(TomlEditItem::as_table_like_mut)
+        .ok_or_else(|| format!("profiles.{} 必须是表", profile))?;
+    set_toml_string(profile_table, "model_provider", profile);
+    if channel.model.is_empty() {
+        profile_table.remove("model");
+    } else {
+        set_toml_string(profile_table, "model", &channel.model);
+    }
+
+    Ok(document.to_string())
+}
+
+// 删除 profile 及同名 provider，表因此变空时一并删除
+fn remove_codex_profile_toml(existing: &str, profile: &str) -> Result<String, String> {
+    let mut document: DocumentMut = existing
+        .parse()
+        .map_err(|e| format!("解析 config.toml 失败: {}", e))?;
+
+    for section in ["profiles", "model_providers"] {
+        let Some(table) = document
+            .get_mut(section)
+            .and_then(TomlEditItem::as_table_like_mut)
+        else {
+            continue;
+        };
+        table.remove(profile);
+        if table.is_empty() {
+            document.remove(section);
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+// 返回所用渠道的名称
+pub(crate) fn apply_codex_project_profile(
+    dir: &Path,
+    channel_id: &str,
+    profile: &str,
+) -> Result<String, String> {
+    with_dir_lock(dir, || {
+        let store = load_codex_store(dir)?;
+        let channel = &store.channels[codex_channel_position(&store.channels, channel_id)?];
+
+        let config_path = dir.join("config.toml");
+        let (existing, stamp) = read_stamped_text(&config_path)?;
+        let updated =
+            update_codex_profile_toml(existing.as_deref().unwrap_or_default(), profile, channel)?;
+        ensure_unchanged(&config_path, &stamp)?;
+        write_text_file(&config_path, &updated)?;
+        Ok(channel.name.clone())
+    })
+}
+
+pub(crate) fn remove_codex_project_profile(dir: &Path, profile: &str) -> Result<(), String> {
+    with_dir_lock(dir, || {
+        let config_path = dir.join("config.toml");
+        let (existing, stamp) = read_stamped_text(&config_path)?;
+        let Some(existing) = existing else {
+            return Ok(());
+        };
+        let updated = remove_codex_profile_toml(&existing, profile)?;
+        ensure_unchanged(&config_path, &stamp)?;
+        write_text_file(&config_path, &updated)
+    })
+}
+
+// 统一渠道接口；编辑时保留 provider、附加配置等 Codex 专有字段
+pub(crate) struct CodexBackend {
+    dir: PathBuf,
+}
+
+impl CodexBackend {
+    pub(crate) fn new(dir: &Path) -> Self {
+        CodexBackend {
+            dir: dir.to_path_buf(),
+        }
+    }
+}
+
+impl ChannelBackend for CodexBackend {
+    fn tool(&self) -> ToolKind {
+        ToolKind::Codex
+    }
+
+    fn list(&self) -> Result<Vec<Channel>, String> {
+        let store = load_codex_store_locked(&self.dir)?;
+        Ok(store
+            .channels
+            .into_iter()
+            .map(|channel| Channel {
+                id: channel.id,
+                name: channel.name,
+                base_url: channel.baseurl,
+                api_key: channel.apikey,
+                model: channel.model,
+            })
+            .collect())
+    }
+
+    fn save(&self, channel: Channel) -> Result<String, String> {
+        let store = load_codex_store_locked(&self.dir)?;
+        let existing = if channel.id.is_empty() {
+            None
+        } else {
+            let position = codex_channel_position(&store.channels, &channel.id)?;
+            Some(store.channels[position].clone())
+        };
+        let codex_channel = match existing {
+            Some(existing) => CodexChannel {
+                name: channel.name,
+                baseurl: channel.base_url,
+                apikey: channel.api_key,
+                model: channel.model,
+                ..existing
+            },
+            None => CodexChannel {
+                id: String::new(),
+                name: channel.name,
+                baseurl: channel.base_url,
+                apikey: channel.api_key,
+                model: channel.model,
+                provider: None,
+                extra_config: String::new(),
+                auth_mode: CodexAuthMode::ApiKey,
+                chatgpt_auth: None,
+                pinned: false,
+            },
+        };
+
+        let codex_channel = normalize_codex_channel(codex_channel)?;
+        let change = mutate_codex_store(&self.dir, store.revision, |store| {
+            upsert_codex_channel(&self.dir, store, codex_channel).map(Some)
+        })?;
+        Ok(change.channel_id.unwrap_or_default())
+    }
+
+    fn delete(&self, id: &str) -> Result<(), String> {
+        with_dir_lock(&self.dir, || {
+            let mut store = load_codex_store(&self.dir)?;
+            let position = codex_channel_position(&store.channels, id)?;
+            store.channels.remove(position);
+            store.revision += 1;
+            save_codex_store(&self.dir, &store)
+        })
+    }
+
+    fn switch(&self, id: &str) -> Result<(), String> {
+        with_dir_lock(&self.dir, || {
+            let store = load_codex_store(&self.dir)?;
+            let position = codex_channel_position(&store.channels, id)?;
+            apply_codex_switch(&self.dir, &store.channels[position].name)
+        })
+    }
+
+    fn current(&self) -> Result<Option<String>, String> {
+        let Some(name) = read_current_codex_env(&self.dir).active_channel else {
+            return Ok(None);
+        };
+        let store = load_codex_store_locked(&self.dir)?;
+        Ok(find_codex_channel(&store.channels, &name).map(|channel| channel.id.clone()))
+    }
+
+    fn test_target(&self, channel: &Channel) -> Result<ChannelTestTarget, String> {
+        let store = load_codex_store_locked(&self.dir)?;
+        let position = codex_channel_position(&store.channels, &channel.id)?;
+        if store.channels[position].auth_mode == CodexAuthMode::Chatgpt {
+            return Err("ChatGPT 登录渠道不支持连通性检测".to_string());
+        }
+        Ok(ChannelTestTarget {
+            endpoint: format!("{}/models", channel.base_url.trim_end_matches('/')),
+            api_key: channel.api_key.clone(),
+            auth: TestAuth::Bearer,
+        })
+    }
+
+    // ChatGPT 登录的备份文件只增不改，无需恢复
+    fn switch_snapshot(&self) -> Result<SwitchSnapshot, String> {
+        SwitchSnapshot::of_files(vec![
+            self.dir.join("config.toml"),
+            self.dir.join("auth.json"),
+            extra_config_state_path(&self.dir),
+            codex_store_path(&self.dir),
+        ])
+    }
+
+    fn launch_spec(&self, id: &str) -> Result<LaunchSpec, String> {
+        let store = load_codex_store_locked(&self.dir)?;
+        let channel = &store.channels[codex_channel_position(&store.channels, id)?];
+        Ok(LaunchSpec {
+            program: "codex",
+            args: codex_session_overrides(channel)?,
+            env: vec![(CODEX_SESSION_KEY_VAR.to_string(), channel.apikey.clone())],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn update_config(existing: &str, channel: &CodexChannel) -> Result<String, String> {
+        update_codex_config_toml(existing, channel, None).map(|(content, _)| content)
+    }
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-codex-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    #[test]
+    fn returns_empty_store_when_json_missing() {
+        let dir = create_temp_dir("empty");
+        let store = load_codex_store(&dir).unwrap();
+        assert_eq!(store.version, CODEX_STORE_VERSION);
+        assert!(store.channels.is_empty());
+    }
+
+    #[test]
+    fn saves_versioned_json_store() {
+        let dir = create_temp_dir("save");
+        let store = CodexChannelStore {
+            version: CODEX_STORE_VERSION,
+            revision: 0,
+            channels: vec![CodexChannel {
+                name: "main".to_string(),
+                baseurl: "https://api.example.com".to_string(),
+                apikey: "key-1".to_string(),
+                model: "o3".to_string(),
+                provider: None,
+                extra_config: String::new(),
+                auth_mode: CodexAuthMode::ApiKey,
+                chatgpt_auth: None,
+                id: String::new(),
+                pinned: false,
+            }],
+        };
+
+        save_codex_store(&dir, &store).unwrap();
+
+        let content = fs::read_to_string(codex_store_path(&dir)).unwrap();
+        let saved: CodexChannelStore = serde_json::from_str(&content).unwrap();
+        assert_eq!(saved.version, CODEX_STORE_VERSION);
+        assert_eq!(saved.channels, store.channels);
+    }
+
+    #[test]
+    fn updates_codex_config_with_structured_toml() {
+        let existing = r#"model = "old-model"
+base_url = "https://root.example.com"
+model_provider = "OpenAI"
+
+[model_providers.OpenAI]
+name = "OpenAI"
+base_url = "https://old.example.com"
+wire_api = "responses"
+"#;
+
+        let channel = CodexChannel {
+            name: "main".to_string(),
+            baseurl: "https://api.example.com".to_string(),
+            apikey: "key-1".to_string(),
+            model: "o3".to_string(),
+            provider: None,
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::ApiKey,
+            chatgpt_auth: None,
+            id: String::new(),
+            pinned: false,
+        };
+
+        let updated = update_config(existing, &channel).unwrap();
+        let root: TomlTable = toml::from_str(&updated).unwrap();
+        assert_eq!(root.get("model").and_then(TomlValue::as_str), Some("o3"));
+        assert_eq!(
+            root.get("base_url").and_then(TomlValue::as_str),
+            Some("https://root.example.com")
+        );
+        assert_eq!(
+            root.get("model_provider").and_then(TomlValue::as_str),
+            Some("OpenAI")
+        );
+        let providers = root
+            .get("model_providers")
+            .and_then(TomlValue::as_table)
+            .unwrap();
+        assert_eq!(
+            providers["OpenAI"]
+                .as_table()
+                .unwrap()
+                .get("wire_api")
+                .and_then(TomlValue::as_str),
+            Some("responses")
+        );
+        assert_eq!(
+            providers["OpenAI"]
+                .as_table()
+                .unwrap()
+                .get("base_url")
+                .and_then(TomlValue::as_str),
+            Some("https://api.example.com")
+        );
+        assert!(providers.get("main").is_none());
+    }
+
+    fn channel_with_provider(name: &str, provider: CodexProviderConfig) -> CodexChannel {
+        CodexChannel {
+            name: name.to_string(),
+            baseurl: format!("https://{}.example.com/v1", name),
+            apikey: format!("key-{}", name),
+            model: format!("model-{}", name),
+            provider: Some(provider),
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::ApiKey,
+            chatgpt_auth: None,
+            id: String::new(),
+            pinned: false,
+        }
+    }
+
+    fn provider_config(id: &str, wire_api: Option<&str>) -> CodexProviderConfig {
+        CodexProviderConfig {
+            id: id.to_string(),
+            name: String::new(),
+            wire_api: wire_api.map(str::to_string),
+            env_key: None,
+            query_params: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn creates_dedicated_model_providers_per_channel() {
+        let existing = r#"model = "old-model"
+model_provider = "OpenAI"
+
+[model_providers.OpenAI]
+name = "OpenAI"
+base_url = "https://old.example.com"
+wire_api = "responses"
+"#;
+
+        let mut azure = provider_config("azure", Some("responses"));
+        azure.env_key = Some("AZURE_OPENAI_API_KEY".to_string());
+        azure
+            .query_params
+            .insert("api-version".to_string(), "2025-04-01-preview".to_string());
+        let azure = normalize_codex_channel(channel_with_provider("azure", azure)).unwrap();
+        let chat = normalize_codex_channel(channel_with_provider(
+            "relay",
+            provider_config("relay", Some("chat")),
+        ))
+        .unwrap();
+
+        let first = update_config(existing, &azure).unwrap();
+        let second = update_config(&first, &chat).unwrap();
+        let root: TomlTable = toml::from_str(&second).unwrap();
+
+        assert_eq!(root["model_provider"].as_str(), Some("relay"));
+        assert_eq!(root["model"].as_str(), Some("model-relay"));
+
+        let providers = root["model_providers"].as_table().unwrap();
+        assert_eq!(
+            providers["OpenAI"]["base_url"].as_str(),
+            Some("https://old.example.com")
+        );
+        assert_eq!(providers["azure"]["name"].as_str(), Some("azure"));
+        assert_eq!(providers["azure"]["wire_api"].as_str(), Some("responses"));
+        assert_eq!(
+            providers["azure"]["env_key"].as_str(),
+            Some("AZURE_OPENAI_API_KEY")
+        );
+        assert_eq!(
+            providers["azure"]["query_params"]["api-version"].as_str(),
+            Some("2025-04-01-preview")
+        );
+        assert_eq!(
+            providers["relay"]["base_url"].as_str(),
+            Some("https://relay.example.com/v1")
+        );
+        assert_eq!(providers["relay"]["wire_api"].as_str(), Some("chat"));
+        assert!(providers["relay"].get("env_key").is_none());
+
+        let active = read_codex_active_info_from_toml(&second).unwrap();
+        assert_eq!(active.model_provider, "relay");
+        assert_eq!(active.base_url, "https://relay.example.com/v1");
+    }
+
+    #[test]
+    fn keeps_unmanaged_keys_when_updating_dedicated_provider() {
+        let existing = r#"[model_providers.relay]
+name = "Relay"
+base_url = "https://old.example.com"
+wire_api = "responses"
+http_headers = { "X-Team" = "core" }
+"#;
+        let channel = normalize_codex_channel(channel_with_provider(
+            "relay",
+            provider_config("relay", Some("chat")),
+        ))
+        .unwrap();
+
+        let updated = update_config(existing, &channel).unwrap();
+        let root: TomlTable = toml::from_str(&updated).unwrap();
+        let relay = root["model_providers"]["relay"].as_table().unwrap();
+
+        assert_eq!(relay["wire_api"].as_str(), Some("chat"));
+        assert_eq!(relay["http_headers"]["X-Team"].as_str(), Some("core"));
+        assert_eq!(root["model_provider"].as_str(), Some("relay"));
+    }
+
+    #[test]
+    fn validates_codex_provider_config() {
+        let valid = normalize_codex_channel(channel_with_provider(
+            "main",
+            provider_config(" relay ", Some("Chat")),
+        ))
+        .unwrap();
+        assert!(validate_codex_channel(&valid, &[], None).is_ok());
+        assert_eq!(valid.provider.as_ref().unwrap().id, "relay");
+        assert_eq!(valid.provider.as_ref().unwrap().name, "relay");
+
+        let cleared =
+            normalize_codex_channel(channel_with_provider("main", provider_config("  ", None)))
+                .unwrap();
+        assert!(cleared.provider.is_none());
+
+        let invalid_cases = [
+            provider_config("openai", None),
+            provider_config("bad id", None),
+            provider_config("relay", Some("grpc")),
+            CodexProviderConfig {
+                env_key: Some("1KEY".to_string()),
+                ..provider_config("relay", None)
+            },
+        ];
+        for provider in invalid_cases {
+            let channel = normalize_codex_channel(channel_with_provider("main", provider)).unwrap();
+            assert!(validate_codex_channel(&channel, &[], None).is_err());
+        }
+    }
+
+    #[test]
+    fn validates_duplicate_codex_channel_names() {
+        let existing = vec![CodexChannel {
+            name: "main".to_string(),
+            baseurl: "https://api.example.com".to_string(),
+            apikey: "key-1".to_string(),
+            model: "o3".to_string(),
+            provider: None,
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::ApiKey,
+            chatgpt_auth: None,
+            id: "cx1".to_string(),
+            pinned: false,
+        }];
+
+        let duplicate = CodexChannel {
+            name: "main".to_string(),
+            baseurl: "https://api.other.com".to_string(),
+            apikey: "key-2".to_string(),
+            model: "o4-mini".to_string(),
+            provider: None,
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::ApiKey,
+            chatgpt_auth: None,
+            id: String::new(),
+            pinned: false,
+        };
+
+        assert_eq!(
+            validate_codex_channel(&duplicate, &existing, None).unwrap_err(),
+            "渠道名称已存在"
+        );
+        assert!(validate_codex_channel(&duplicate, &existing, Some("cx1")).is_ok());
+    }
+
+    #[test]
+    fn validates_codex_baseurl_format() {
+        let invalid = normalize_codex_channel(CodexChannel {
+            name: "main".to_string(),
+            baseurl: "not-a-url".to_string(),
+            apikey: "key-1".to_string(),
+            model: "o3".to_string(),
+            provider: None,
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::ApiKey,
+            chatgpt_auth: None,
+            id: String::new(),
+            pinned: false,
+        });
+
+        assert_eq!(
+            invalid.unwrap_err(),
+            "Base URL 格式无效，请输入 http:// 或 https:// 地址"
+        );
+    }
+
+    #[test]
+    fn switches_codex_channel_by_name_using_filesystem_state() {
+        let dir = create_temp_dir("switch");
+        let store = CodexChannelStore {
+            version: CODEX_STORE_VERSION,
+            revision: 0,
+            channels: vec![CodexChannel {
+                name: "main".to_string(),
+                baseurl: "https://api.example.com/".to_string(),
+                apikey: "key-1".to_string(),
+                model: "o3".to_string(),
+                provider: None,
+                extra_config: String::new(),
+                auth_mode: CodexAuthMode::ApiKey,
+                chatgpt_auth: None,
+                id: String::new(),
+                pinned: false,
+            }],
+        };
+        save_codex_store(&dir, &store).unwrap();
+        fs::write(
+            dir.join("config.toml"),
+            r#"model = "old"
+base_url = "https://root.example.com"
+model_provider = "OpenAI"
+
+[model_providers.OpenAI]
+name = "OpenAI"
+base_url = "https://old.example.com"
+wire_api = "responses"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("auth.json"),
+            "{\n  \"OPENAI_API_KEY\": \"old-key\",\n  \"keep\": true\n}",
+        )
+        .unwrap();
+
+        let result = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(switch_codex_channel(
+                dir.to_string_lossy().to_string(),
+                "main".to_string(),
+            ));
+
+        assert!(result.success);
+
+        let updated_config = fs::read_to_string(dir.join("config.toml")).unwrap();
+        let root: TomlTable = toml::from_str(&updated_config).unwrap();
+        assert_eq!(root.get("model").and_then(TomlValue::as_str), Some("o3"));
+        assert_eq!(
+            root.get("base_url").and_then(TomlValue::as_str),
+            Some("https://root.example.com")
+        );
+        assert_eq!(
+            root.get("model_provider").and_then(TomlValue::as_str),
+            Some("OpenAI")
+        );
+        assert_eq!(
+            root["model_providers"]["OpenAI"]["wire_api"].as_str(),
+            Some("responses")
+        );
+        assert_eq!(
+            root["model_providers"]["OpenAI"]["base_url"].as_str(),
+            Some("https://api.example.com")
+        );
+        assert!(root["model_providers"]
+            .as_table()
+            .unwrap()
+            .get("main")
+            .is_none());
+
+        let updated_auth: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("auth.json")).unwrap()).unwrap();
+        assert_eq!(updated_auth["OPENAI_API_KEY"].as_str(), Some("key-1"));
+        assert_eq!(updated_auth["keep"].as_bool(), Some(true));
+    }
+
+    fn save_single_channel(dir: &Path, provider: Option<CodexProviderConfig>) {
+        let store = CodexChannelStore {
+            version: CODEX_STORE_VERSION,
+            revision: 0,
+            channels: vec![CodexChannel {
+                name: "main".to_string(),
+                baseurl: "https://api.example.com".to_string(),
+                apikey: "key-1".to_string(),
+                model: "o3".to_string(),
+                provider,
+                extra_config: String::new(),
+                auth_mode: CodexAuthMode::ApiKey,
+                chatgpt_auth: None,
+                id: String::new(),
+                pinned: false,
+            }],
+        };
+        save_codex_store(dir, &store).unwrap();
+    }
+
+    fn switch_to_main(dir: &Path) -> ApiResponse<()> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(switch_codex_channel(
+                dir.to_string_lossy().to_string(),
+                "main".to_string(),
+            ))
+    }
+
+    #[test]
+    fn bootstraps_missing_config_and_auth_on_fresh_setup() {
+        let dir = create_temp_dir("bootstrap-fresh");
+        save_single_channel(&dir, None);
+
+        let result = switch_to_main(&dir);
+        assert!(result.success, "{:?}", result.error);
+
+        let root: TomlTable =
+            toml::from_str(&fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        assert_eq!(root["model"].as_str(), Some("o3"));
+        assert_eq!(root["model_provider"].as_str(), Some(DEFAULT_PROVIDER_ID));
+        let provider = &root["model_providers"][DEFAULT_PROVIDER_ID];
+        assert_eq!(provider["name"].as_str(), Some(DEFAULT_PROVIDER_ID));
+        assert_eq!(
+            provider["base_url"].as_str(),
+            Some("https://api.example.com")
+        );
+        assert_eq!(provider["wire_api"].as_str(), Some(DEFAULT_WIRE_API));
+
+        let auth: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("auth.json")).unwrap()).unwrap();
+        assert_eq!(auth["OPENAI_API_KEY"].as_str(), Some("key-1"));
+
+        let active =
+            read_codex_active_info_from_toml(&fs::read_to_string(dir.join("config.toml")).unwrap())
+                .unwrap();
+        assert_eq!(active.base_url, "https://api.example.com");
+    }
+
+    #[test]
+    fn bootstraps_provider_table_and_auth_on_partial_setup() {
+        let dir = create_temp_dir("bootstrap-partial");
+        save_single_channel(&dir, None);
+        fs::write(
+            dir.join("config.toml"),
+            "model = \"old\"\napproval_policy = \"on-request\"\n",
+        )
+        .unwrap();
+
+        let result = switch_to_main(&dir);
+        assert!(result.success, "{:?}", result.error);
+
+        let root: TomlTable =
+            toml::from_str(&fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        assert_eq!(root["approval_policy"].as_str(), Some("on-request"));
+        assert_eq!(root["model"].as_str(), Some("o3"));
+        assert_eq!(
+            root["model_providers"][DEFAULT_PROVIDER_ID]["base_url"].as_str(),
+            Some("https://api.example.com")
+        );
+        assert!(dir.join("auth.json").exists());
+    }
+
+    #[test]
+    fn bootstraps_dedicated_provider_without_default_entry() {
+        let dir = create_temp_dir("bootstrap-provider");
+        save_single_channel(&dir, Some(provider_config("relay", Some("chat"))));
+
+        let result = switch_to_main(&dir);
+        assert!(result.success, "{:?}", result.error);
+
+        let root: TomlTable =
+            toml::from_str(&fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        let providers = root["model_providers"].as_table().unwrap();
+        assert_eq!(root["model_provider"].as_str(), Some("relay"));
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers["relay"]["wire_api"].as_str(), Some("chat"));
+    }
+
+    #[test]
+    fn keeps_existing_ambiguous_providers_untouched() {
+        let existing = r#"[model_providers.a]
+base_url = "https://a.example.com"
+
+[model_providers.b]
+base_url = "https://b.example.com"
+"#;
+        let channel = CodexChannel {
+            name: "main".to_string(),
+            baseurl: "https://api.example.com".to_string(),
+            apikey: "key-1".to_string(),
+            model: "o3".to_string(),
+            provider: None,
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::ApiKey,
+            chatgpt_auth: None,
+            id: String::new(),
+            pinned: false,
+        };
+
+        assert_eq!(
+            update_config(existing, &channel).unwrap_err(),
+            "未找到可更新的 model_providers 配置"
+        );
+        assert!(update_config("model_providers = 1\n", &channel).is_err());
+    }
+
+    #[test]
+    fn does_not_bootstrap_for_unknown_channels() {
+        let dir = create_temp_dir("bootstrap-unknown");
+
+        let result = switch_to_main(&dir);
+        assert!(!result.success);
+        assert!(!dir.join("config.toml").exists());
+        assert!(!dir.join("auth.json").exists());
+    }
+
+    const COMMENTED_CONFIG: &str = r#"# Codex 配置，手工维护
+model = "old-model"   # 当前模型
+model_reasoning_effort = "high"
+sandbox_mode = "workspace-write"
+model_provider = "OpenAI"
+
+# 项目信任列表
+[projects."/home/me/work"]
+trust_level = "trusted"
+
+[model_providers.OpenAI]
+name = "OpenAI"
+# 中转地址
+base_url = 'https://old.example.com'   # 行尾注释
+wire_api = "responses"
+http_headers = { "X-Team" = "core", "X-Env" = "prod" }
+
+[mcp_servers.docs]
+command = "npx"
+args = [
+    "-y",
+    "docs-mcp",  # 保留数组格式
+]
+"#;
+
+    fn plain_channel(baseurl: &str, model: &str) -> CodexChannel {
+        CodexChannel {
+            name: "main".to_string(),
+            baseurl: baseurl.to_string(),
+            apikey: "key-1".to_string(),
+            model: model.to_string(),
+            provider: None,
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::ApiKey,
+            chatgpt_auth: None,
+            id: String::new(),
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn preserves_comments_and_formatting_when_switching() {
+        let channel = plain_channel("https://api.example.com", "o3");
+
+        let updated = update_config(COMMENTED_CONFIG, &channel).unwrap();
+
+        let expected = COMMENTED_CONFIG
+            .replace(
+                "model = \"old-model\"   # 当前模型",
+                "model = \"o3\"   # 当前模型",
+            )
+            .replace(
+                "base_url = 'https://old.example.com'   # 行尾注释",
+                "base_url = \"https://api.example.com\"   # 行尾注释",
+            );
+        assert_eq!(updated, expected);
+    }
+
+    #[test]
+    fn leaves_config_byte_identical_when_nothing_changes() {
+        let channel = plain_channel("https://old.example.com", "old-model");
+
+        assert_eq!(
+            update_config(COMMENTED_CONFIG, &channel).unwrap(),
+            COMMENTED_CONFIG
+        );
+    }
+
+    #[test]
+    fn adds_and_removes_project_profile_without_touching_global_settings() {
+        let channel = plain_channel("https://relay.example.com/v1", "gpt-5-codex");
+        let profile = codex_project_profile_name(Path::new("/home/me/My Repo"));
+        assert!(profile.starts_with("project-my-repo-"));
+
+        let updated = update_codex_profile_toml(COMMENTED_CONFIG, &profile, &channel).unwrap();
+        let parsed: TomlTable = toml::from_str(&updated).unwrap();
+        assert_eq!(parsed["model"].as_str(), Some("old-model"));
+        assert_eq!(parsed["model_provider"].as_str(), Some("OpenAI"));
+        assert_eq!(
+            parsed["profiles"][&profile]["model_provider"].as_str(),
+            Some(profile.as_str())
+        );
+        let provider = &parsed["model_providers"][&profile];
+        assert_eq!(
+            provider["base_url"].as_str(),
+            Some("https://relay.example.com/v1")
+        );
+        assert_eq!(
+            provider["experimental_bearer_token"].as_str(),
+            Some("key-1")
+        );
+
+        assert_eq!(
+            remove_codex_profile_toml(&updated, &profile).unwrap(),
+            COMMENTED_CONFIG
+        );
+    }
+
+    #[test]
+    fn appends_dedicated_provider_without_touching_other_sections() {
+        let channel = normalize_codex_channel(channel_with_provider(
+            "relay",
+            provider_config("relay", Some("chat")),
+        ))
+        .unwrap();
+
+        let updated = update_config(COMMENTED_CONFIG, &channel).unwrap();
+
+        let expected = COMMENTED_CONFIG
+            .replace(
+                "model = \"old-model\"   # 当前模型",
+                "model = \"model-relay\"   # 当前模型",
+            )
+            .replace(
+                "model_provider = \"OpenAI\"",
+                "model_provider = \"relay\"",
+            )
+            .replace(
+                "\n[mcp_servers.docs]",
+                "\n[model_providers.relay]\nbase_url = \"https://relay.example.com/v1\"\nname = \"relay\"\nwire_api = \"chat\"\n\n[mcp_servers.docs]",
+            );
+        assert_eq!(updated, expected);
+        assert_eq!(update_config(&updated, &channel).unwrap(), updated);
+    }
+
+    #[test]
+    fn renders_minimal_config_when_bootstrapping() {
+        let channel = plain_channel("https://api.example.com", "o3");
+
+        assert_eq!(
+            update_config("", &channel).unwrap(),
+            r#"model = "o3"
+model_provider = "OpenAI"
+
+[model_providers.OpenAI]
+name = "OpenAI"
+wire_api = "responses"
+base_url = "https://api.example.com"
+"#
+        );
+    }
+
+    fn channel_with_extra(name: &str, extra_config: &str) -> CodexChannel {
+        CodexChannel {
+            extra_config: extra_config.to_string(),
+            name: name.to_string(),
+            ..plain_channel("https://api.example.com", "o3")
+        }
+    }
+
+    #[test]
+    fn validates_codex_extra_config() {
+        let extra = parse_codex_extra_config(
+            r#"model_reasoning_effort = "high"
+sandbox_mode = "danger-full-access"
+
+[provider]
+env_key = "RELAY_API_KEY"
+
+[provider.http_headers]
+"X-Team" = "core"
+"#,
+        )
+        .unwrap();
+        assert_eq!(extra.root.len(), 2);
+        assert_eq!(extra.provider.len(), 2);
+        assert_eq!(
+            toml_value_repr(&extra.provider[1].1),
+            r#"{ "X-Team" = "core" }"#
+        );
+
+        for invalid in [
+            "model = \"o3\"",
+            "model_provider = \"x\"",
+            "[model_providers.x]\nbase_url = \"https://x\"",
+            "provider = \"x\"",
+            "[provider]\nbase_url = \"https://x\"",
+            "not toml",
+        ] {
+            assert!(
+                parse_codex_extra_config(invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+
+        let mut channel = channel_with_extra("main", "approval_policy = ");
+        channel = normalize_codex_channel(channel).unwrap();
+        assert!(validate_codex_channel(&channel, &[], None).is_err());
+    }
+
+    #[test]
+    fn merges_and_reverts_extra_config_between_channels() {
+        let fast = channel_with_extra(
+            "fast",
+            r#"model_reasoning_effort = "low"
+approval_policy = "never"
+
+[provider]
+http_headers = { "X-Relay" = "fast" }
+"#,
+        );
+        let plain = channel_with_extra("plain", "");
+
+        let (switched, applied) = update_codex_config_toml(COMMENTED_CONFIG, &fast, None).unwrap();
+        let root: TomlTable = toml::from_str(&switched).unwrap();
+        assert_eq!(root["model_reasoning_effort"].as_str(), Some("low"));
+        assert_eq!(root["approval_policy"].as_str(), Some("never"));
+        assert_eq!(
+            root["model_providers"]["OpenAI"]["http_headers"]["X-Relay"].as_str(),
+            Some("fast")
+        );
+        assert_eq!(applied.channel, "fast");
+        assert_eq!(applied.provider, "OpenAI");
+        assert_eq!(
+            applied.root[0].previous.as_deref(),
+            Some("\"high\""),
+            "原有取值需要记录下来"
+        );
+        assert_eq!(applied.root[1].previous, None);
+        assert!(switched.contains("model_reasoning_effort = \"low\"\n"));
+
+        let (reverted, applied) =
+            update_codex_config_toml(&switched, &plain, Some(&applied)).unwrap();
+        assert!(applied.root.is_empty() && applied.provider_keys.is_empty());
+        let expected = COMMENTED_CONFIG
+            .replace(
+                "model = \"old-model\"   # 当前模型",
+                "model = \"o3\"   # 当前模型",
+            )
+            .replace(
+                "base_url = 'https://old.example.com'   # 行尾注释",
+                "base_url = \"https://api.example.com\"   # 行尾注释",
+            );
+        assert_eq!(reverted, expected);
+    }
+
+    #[test]
+    fn keeps_user_edits_made_after_extra_config_was_applied() {
+        let fast = channel_with_extra("fast", "approval_policy = \"never\"");
+        let plain = channel_with_extra("plain", "");
+
+        let (switched, applied) = update_codex_config_toml("", &fast, None).unwrap();
+        let edited = switched.replace(
+            "approval_policy = \"never\"",
+            "approval_policy = \"on-request\"",
+        );
+
+        let (reverted, _) = update_codex_config_toml(&edited, &plain, Some(&applied)).unwrap();
+        let root: TomlTable = toml::from_str(&reverted).unwrap();
+        assert_eq!(root["approval_policy"].as_str(), Some("on-request"));
+    }
+
+    #[test]
+    fn records_extra_config_state_when_switching() {
+        let dir = create_temp_dir("extra-state");
+        let store = CodexChannelStore {
+            version: CODEX_STORE_VERSION,
+            revision: 0,
+            channels: vec![
+                channel_with_extra("main", "model_verbosity = \"low\""),
+                channel_with_extra("plain", ""),
+            ],
+        };
+        save_codex_store(&dir, &store).unwrap();
+
+        assert!(switch_to_main(&dir).success);
+        let state = load_applied_extra(&dir).unwrap();
+        assert_eq!(state.channel, "main");
+        assert_eq!(state.root[0].key, "model_verbosity");
+
+        let result = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(switch_codex_channel(
+                dir.to_string_lossy().to_string(),
+                "plain".to_string(),
+            ));
+        assert!(result.success, "{:?}", result.error);
+
+        let root: TomlTable =
+            toml::from_str(&fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        assert!(root.get("model_verbosity").is_none());
+        let state = load_applied_extra(&dir).unwrap();
+        assert_eq!(state.channel, "plain");
+        assert!(state.root.is_empty());
+    }
+
+    #[test]
+    fn skips_extra_config_state_file_without_extras() {
+        let dir = create_temp_dir("extra-none");
+        save_single_channel(&dir, None);
+
+        assert!(switch_to_main(&dir).success);
+        assert!(!extra_config_state_path(&dir).exists());
+    }
+
+    fn codex_store_backups(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .ends_with(".bak")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_migration_chain_in_sync_with_store_version() {
+        assert_eq!(CODEX_STORE_MIGRATIONS.len(), CODEX_STORE_VERSION as usize);
+    }
+
+    #[test]
+    fn migrates_legacy_channel_array_and_keeps_backup() {
+        let dir = create_temp_dir("migrate-array");
+        let legacy = r#"[
+  { "name": "main", "baseurl": "https://api.example.com/", "apikey": "key-1", "model": "o3" }
+]"#;
+        fs::write(codex_store_path(&dir), legacy).unwrap();
+
+        let store = load_codex_store(&dir).unwrap();
+        assert_eq!(store.version, CODEX_STORE_VERSION);
+        assert_eq!(store.channels.len(), 1);
+        assert_eq!(store.channels[0].baseurl, "https://api.example.com");
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(codex_store_path(&dir)).unwrap()).unwrap();
+        assert_eq!(saved["version"], serde_json::json!(CODEX_STORE_VERSION));
+
+        let backups = codex_store_backups(&dir);
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("channels.json.v0-"));
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), legacy);
+
+        load_codex_store(&dir).unwrap();
+        assert_eq!(codex_store_backups(&dir).len(), 1);
+    }
+
+    #[test]
+    fn migrates_unversioned_store_object() {
+        let dir = create_temp_dir("migrate-object");
+        fs::write(
+            codex_store_path(&dir),
+            r#"{ "channels": [{ "name": "main", "baseurl": "https://api.example.com", "apikey": "key-1", "model": "o3" }] }"#,
+        )
+        .unwrap();
 
-    if !config_path.exists() || !auth_path.exists() {
-        return ApiResponse::error("未找到配置文件，请检查Codex路径配置".to_string());
+        let store = load_codex_store(&dir).unwrap();
+        assert_eq!(store.version, CODEX_STORE_VERSION);
+        assert_eq!(store.channels[0].name, "main");
     }
 
-    let store = match load_codex_store(dir) {
-        Ok(store) => store,
-        Err(e) => return ApiResponse::error(e),
-    };
-
-    let Some(channel) = find_codex_channel(&store.channels, channel_name.trim()).cloned() else {
-        return ApiResponse::error("未找到指定的 Codex 渠道".to_string());
-    };
+    #[test]
+    fn rejects_invalid_store_versions() {
+        let dir = create_temp_dir("migrate-invalid");
+        fs::write(codex_store_path(&dir), r#"{ "version": "two" }"#).unwrap();
+        assert!(load_codex_store(&dir).is_err());
+
+        fs::write(codex_store_path(&dir), "\"channels\"").unwrap();
+        assert!(load_codex_store(&dir).is_err());
+        assert!(codex_store_backups(&dir).is_empty());
+    }
 
-    let existing_config = match fs::read_to_string(&config_path) {
-        Ok(content) => content,
-        Err(e) => return ApiResponse::error(format!("读取 config.toml 失败: {}", e)),
-    };
-    let updated_config = match update_codex_config_toml(&existing_config, &channel) {
-        Ok(content) => content,
-        Err(e) => return ApiResponse::error(e),
-    };
+    #[test]
+    fn loads_newer_store_versions_read_only() {
+        let dir = create_temp_dir("newer");
+        let newer = r#"{
+  "version": 99,
+  "channels": [
+    { "name": "main", "baseurl": "https://api.example.com", "apikey": "key-1", "model": "o3", "future_field": true }
+  ],
+  "future_settings": {}
+}"#;
+        fs::write(codex_store_path(&dir), newer).unwrap();
 
-    let existing_auth = match snapshot_text_file(&auth_path) {
-        Ok(content) => content,
-        Err(e) => return ApiResponse::error(e),
-    };
-    let updated_auth = match update_auth_json(existing_auth.as_deref(), &channel.apikey) {
-        Ok(content) => content,
-        Err(e) => return ApiResponse::error(e),
-    };
+        let store = load_codex_store(&dir).unwrap();
+        assert!(is_codex_store_read_only(&store));
+        assert_eq!(store.channels.len(), 1);
+        assert!(save_codex_store(&dir, &store).is_err());
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let path = dir.to_string_lossy().to_string();
+        let status = runtime
+            .block_on(get_codex_store_status(path.clone()))
+            .data
+            .unwrap();
+        assert_eq!(status.version, 99);
+        assert!(status.read_only);
+        let channel_id = store.channels[0].id.clone();
+        assert!(
+            !runtime
+                .block_on(delete_codex_channel(path, channel_id, store.revision))
+                .success
+        );
+        assert_eq!(fs::read_to_string(codex_store_path(&dir)).unwrap(), newer);
 
-    match apply_switch_updates(&config_path, &updated_config, &auth_path, &updated_auth) {
-        Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse::error(e),
+        assert!(switch_to_main(&dir).success);
+        assert_eq!(fs::read_to_string(codex_store_path(&dir)).unwrap(), newer);
     }
-}
 
-#[tauri::command(rename_all = "camelCase")]
-pub(crate) async fn launch_codex(terminal_dir: String) -> ApiResponse<()> {
-    open_terminal("codex", &terminal_dir)
-}
+    fn chatgpt_login(account_id: &str, access_token: &str) -> serde_json::Value {
+        serde_json::json!({
+            "OPENAI_API_KEY": null,
+            "tokens": {
+                "id_token": "id",
+                "access_token": access_token,
+                "refresh_token": "refresh",
+                "account_id": account_id
+            },
+            "last_refresh": "2026-10-01T00:00:00Z"
+        })
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::atomic::{AtomicU64, Ordering};
+    fn chatgpt_channel(name: &str, auth: serde_json::Value) -> CodexChannel {
+        CodexChannel {
+            name: name.to_string(),
+            baseurl: String::new(),
+            apikey: String::new(),
+            model: String::new(),
+            provider: None,
+            extra_config: String::new(),
+            auth_mode: CodexAuthMode::Chatgpt,
+            chatgpt_auth: Some(auth),
+            id: String::new(),
+            pinned: false,
+        }
+    }
 
-    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+    fn read_json(path: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
 
-    fn create_temp_dir(label: &str) -> PathBuf {
-        let dir = std::env::temp_dir().join(format!(
-            "claude-channel-switcher-codex-{}-{}",
-            label,
-            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
-        ));
-        let _ = fs::remove_dir_all(&dir);
-        fs::create_dir_all(&dir).expect("failed to create temp dir");
-        dir
+    fn switch_to(dir: &Path, name: &str) -> ApiResponse<()> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(switch_codex_channel(
+                dir.to_string_lossy().to_string(),
+                name.to_string(),
+            ))
     }
 
-    #[test]
-    fn normalizes_codex_baseurl() {
-        assert_eq!(
-            normalize_codex_baseurl("https://API.Example.com/").unwrap(),
-            "https://api.example.com"
-        );
-        assert_eq!(
-            normalize_codex_baseurl("https://api.example.com/v1/").unwrap(),
-            "https://api.example.com/v1"
-        );
+    fn active_channel(dir: &Path) -> Option<String> {
+        read_current_codex_env(dir).active_channel
     }
 
     #[test]
-    fn returns_empty_store_when_json_missing() {
-        let dir = create_temp_dir("empty");
-        let store = load_codex_store(&dir).unwrap();
-        assert_eq!(store.version, CODEX_STORE_VERSION);
-        assert!(store.channels.is_empty());
+    fn api_key_auth_drops_chatgpt_tokens() {
+        let mut existing = chatgpt_login("acct-1", "access");
+        existing["keep"] = serde_json::json!(true);
+        let channel = plain_channel("https://api.example.com", "o3");
+
+        let updated: serde_json::Value =
+            serde_json::from_str(&update_auth_json(Some(&existing.to_string()), &channel).unwrap())
+                .unwrap();
+
+        assert_eq!(updated["OPENAI_API_KEY"].as_str(), Some("key-1"));
+        assert!(updated.get("tokens").is_none());
+        assert!(updated.get("last_refresh").is_none());
+        assert_eq!(updated["keep"].as_bool(), Some(true));
     }
 
     #[test]
-    fn saves_versioned_json_store() {
-        let dir = create_temp_dir("save");
+    fn switches_between_api_key_and_chatgpt_login_without_losing_either() {
+        let dir = create_temp_dir("chatgpt-roundtrip");
+        let stale = chatgpt_login("acct-1", "stale");
+        let fresh = chatgpt_login("acct-1", "fresh");
         let store = CodexChannelStore {
             version: CODEX_STORE_VERSION,
-            channels: vec![CodexChannel {
-                name: "main".to_string(),
-                baseurl: "https://api.example.com".to_string(),
-                apikey: "key-1".to_string(),
-                model: "o3".to_string(),
-            }],
+            revision: 0,
+            channels: vec![
+                plain_channel("https://api.example.com", "o3"),
+                chatgpt_channel("gpt", stale),
+            ],
         };
-
         save_codex_store(&dir, &store).unwrap();
+        fs::write(dir.join("config.toml"), COMMENTED_CONFIG).unwrap();
+        fs::write(dir.join("auth.json"), fresh.to_string()).unwrap();
+        assert_eq!(active_channel(&dir), None);
+
+        assert!(switch_to(&dir, "main").success);
+        let auth = read_json(&dir.join("auth.json"));
+        assert_eq!(auth["OPENAI_API_KEY"].as_str(), Some("key-1"));
+        assert!(auth.get("tokens").is_none());
+        let saved = load_codex_store(&dir).unwrap();
+        assert_eq!(saved.channels[1].chatgpt_auth.as_ref(), Some(&fresh));
+        assert_eq!(active_channel(&dir).as_deref(), Some("main"));
+
+        assert!(switch_to(&dir, "gpt").success);
+        assert_eq!(read_json(&dir.join("auth.json")), fresh);
+        let root: TomlTable =
+            toml::from_str(&fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        assert_eq!(root["model_provider"].as_str(), Some(CHATGPT_PROVIDER_ID));
+        assert_eq!(
+            root["model_providers"]["OpenAI"]["base_url"].as_str(),
+            Some("https://api.example.com")
+        );
+        assert_eq!(active_channel(&dir).as_deref(), Some("gpt"));
 
-        let content = fs::read_to_string(codex_store_path(&dir)).unwrap();
-        let saved: CodexChannelStore = serde_json::from_str(&content).unwrap();
-        assert_eq!(saved.version, CODEX_STORE_VERSION);
-        assert_eq!(saved.channels, store.channels);
+        assert!(switch_to(&dir, "main").success);
+        let root: TomlTable =
+            toml::from_str(&fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        assert_eq!(root["model_provider"].as_str(), Some("OpenAI"));
+        assert_eq!(active_channel(&dir).as_deref(), Some("main"));
     }
 
     #[test]
-    fn updates_codex_config_with_structured_toml() {
-        let existing = r#"model = "old-model"
-base_url = "https://root.example.com"
-model_provider = "OpenAI"
+    fn backs_up_unsaved_chatgpt_login_before_switching_away() {
+        let dir = create_temp_dir("chatgpt-backup");
+        save_single_channel(&dir, None);
+        let login = chatgpt_login("acct/2", "access");
+        fs::write(dir.join("auth.json"), login.to_string()).unwrap();
 
-[model_providers.OpenAI]
-name = "OpenAI"
-base_url = "https://old.example.com"
-wire_api = "responses"
-"#;
+        assert!(switch_to_main(&dir).success);
 
-        let channel = CodexChannel {
-            name: "main".to_string(),
-            baseurl: "https://api.example.com".to_string(),
-            apikey: "key-1".to_string(),
-            model: "o3".to_string(),
-        };
+        assert_eq!(read_json(&dir.join("auth.chatgpt-acct_2.json")), login);
+        assert!(read_json(&dir.join("auth.json")).get("tokens").is_none());
+    }
 
-        let updated = update_codex_config_toml(existing, &channel).unwrap();
-        let root: TomlTable = toml::from_str(&updated).unwrap();
-        assert_eq!(root.get("model").and_then(TomlValue::as_str), Some("o3"));
-        assert_eq!(
-            root.get("base_url").and_then(TomlValue::as_str),
-            Some("https://root.example.com")
-        );
-        assert_eq!(
-            root.get("model_provider").and_then(TomlValue::as_str),
-            Some("OpenAI")
-        );
-        let providers = root
-            .get("model_providers")
-            .and_then(TomlValue::as_table)
+    #[test]
+    fn captures_chatgpt_login_when_saving_channel() {
+        let dir = create_temp_dir("chatgpt-save");
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let path = dir.to_string_lossy().to_string();
+        let mut request = chatgpt_channel("gpt", serde_json::json!({}));
+        request.chatgpt_auth = None;
+
+        let missing = runtime.block_on(save_codex_channel(path.clone(), request.clone(), 0));
+        assert!(!missing.success);
+
+        let login = chatgpt_login("acct-3", "access");
+        fs::write(dir.join("auth.json"), login.to_string()).unwrap();
+        let created = runtime
+            .block_on(save_codex_channel(path.clone(), request.clone(), 0))
+            .data
             .unwrap();
         assert_eq!(
-            providers["OpenAI"]
-                .as_table()
-                .unwrap()
-                .get("wire_api")
-                .and_then(TomlValue::as_str),
-            Some("responses")
+            load_codex_store(&dir).unwrap().channels[0].chatgpt_auth,
+            Some(login.clone())
         );
-        assert_eq!(
-            providers["OpenAI"]
-                .as_table()
-                .unwrap()
-                .get("base_url")
-                .and_then(TomlValue::as_str),
-            Some("https://api.example.com")
+
+        // 编辑时沿用已保存的登录，即使 auth.json 已换成其他账号
+        fs::write(
+            dir.join("auth.json"),
+            chatgpt_login("acct-4", "x").to_string(),
+        )
+        .unwrap();
+        request.model = "gpt-5".to_string();
+        request.id = created.channel_id.unwrap();
+        assert!(
+            runtime
+                .block_on(save_codex_channel(path.clone(), request, created.revision))
+                .success
         );
-        assert!(providers.get("main").is_none());
+        let saved = load_codex_store(&dir).unwrap();
+        assert_eq!(saved.channels[0].model, "gpt-5");
+        assert_eq!(saved.channels[0].chatgpt_auth, Some(login));
+
+        let listed = runtime.block_on(get_codex_channels(path)).data.unwrap();
+        assert!(listed.channels[0].chatgpt_auth.is_none());
     }
 
     #[test]
-    fn validates_duplicate_codex_channel_names() {
-        let existing = vec![CodexChannel {
-            name: "main".to_string(),
-            baseurl: "https://api.example.com".to_string(),
-            apikey: "key-1".to_string(),
-            model: "o3".to_string(),
-        }];
-
-        let duplicate = CodexChannel {
-            name: "main".to_string(),
-            baseurl: "https://api.other.com".to_string(),
-            apikey: "key-2".to_string(),
-            model: "o4-mini".to_string(),
+    fn prefers_exact_match_when_detecting_active_api_key_channel() {
+        let first = plain_channel("https://api.example.com", "o3");
+        let second = CodexChannel {
+            name: "second".to_string(),
+            ..plain_channel("https://api.example.com", "gpt-5")
+        };
+        let channels = vec![first, second];
+        let info = CodexActiveInfo {
+            api_key: "key-1".to_string(),
+            base_url: "https://api.example.com".to_string(),
+            model: "gpt-5".to_string(),
+            model_provider: "OpenAI".to_string(),
+            auth_mode: CodexAuthMode::ApiKey,
+            active_channel: None,
         };
 
         assert_eq!(
-            validate_codex_channel(&duplicate, &existing, None).unwrap_err(),
-            "渠道名称已存在"
+            find_active_codex_channel(&channels, &info, None).map(|channel| channel.name.as_str()),
+            Some("second")
         );
-        assert!(validate_codex_channel(&duplicate, &existing, Some(0)).is_ok());
+
+        let other_key = CodexActiveInfo {
+            api_key: "key-2".to_string(),
+            ..info
+        };
+        assert!(find_active_codex_channel(&channels, &other_key, None).is_none());
+    }
+
+    fn named_channel(name: &str, pinned: bool) -> CodexChannel {
+        CodexChannel {
+            id: format!("id-{}", name),
+            name: name.to_string(),
+            pinned,
+            ..plain_channel("https://api.example.com", "o3")
+        }
+    }
+
+    fn names(channels: &[CodexChannel]) -> Vec<&str> {
+        channels
+            .iter()
+            .map(|channel| channel.name.as_str())
+            .collect()
     }
 
     #[test]
-    fn validates_codex_baseurl_format() {
-        let invalid = normalize_codex_channel(CodexChannel {
-            name: "main".to_string(),
-            baseurl: "not-a-url".to_string(),
-            apikey: "key-1".to_string(),
-            model: "o3".to_string(),
-        });
+    fn migrates_v1_store_by_assigning_stable_ids() {
+        let dir = create_temp_dir("migrate-v1");
+        fs::write(
+            codex_store_path(&dir),
+            r#"{ "version": 1, "channels": [
+  { "name": "a", "baseurl": "https://a.example.com", "apikey": "k", "model": "o3" },
+  { "name": "b", "baseurl": "https://b.example.com", "apikey": "k", "model": "o3" }
+] }"#,
+        )
+        .unwrap();
 
-        assert_eq!(
-            invalid.unwrap_err(),
-            "Base URL 格式无效，请输入 http:// 或 https:// 地址"
-        );
+        let store = load_codex_store(&dir).unwrap();
+        assert_eq!(store.version, 2);
+        assert_eq!(store.revision, 0);
+        assert!(store.channels.iter().all(|channel| !channel.id.is_empty()));
+        assert_ne!(store.channels[0].id, store.channels[1].id);
+        assert_eq!(codex_store_backups(&dir).len(), 1);
+
+        let reloaded = load_codex_store(&dir).unwrap();
+        assert_eq!(reloaded.channels, store.channels);
     }
 
     #[test]
-    fn rolls_back_config_when_auth_write_fails() {
-        let dir = create_temp_dir("rollback");
-        let config_path = dir.join("config.toml");
-        let auth_path = dir.join("auth.json");
-        let old_config = "model = \"old\"\n";
-        let old_auth = "{\n  \"OPENAI_API_KEY\": \"old\"\n}";
-
-        fs::write(&config_path, old_config).unwrap();
-        fs::write(&auth_path, old_auth).unwrap();
-
-        let result = apply_switch_updates_with_writer(
-            &config_path,
-            "model = \"new\"\n",
-            &auth_path,
-            "{\n  \"OPENAI_API_KEY\": \"new\"\n}",
-            |path, content| {
-                if path == auth_path {
-                    return Err("forced auth write failure".to_string());
-                }
-                fs::write(path, content).map_err(|e| e.to_string())
-            },
-        );
+    fn rejects_mutations_with_stale_revision() {
+        let dir = create_temp_dir("revision");
+        let path = dir.to_string_lossy().to_string();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let first = runtime
+            .block_on(save_codex_channel(
+                path.clone(),
+                channel_with_extra("a", ""),
+                0,
+            ))
+            .data
+            .unwrap();
+        assert_eq!(first.revision, 1);
 
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("forced auth write failure"));
-        assert_eq!(fs::read_to_string(&config_path).unwrap(), old_config);
-        assert_eq!(fs::read_to_string(&auth_path).unwrap(), old_auth);
+        let stale = runtime.block_on(save_codex_channel(
+            path.clone(),
+            channel_with_extra("b", ""),
+            0,
+        ));
+        assert!(!stale.success);
+        assert!(stale
+            .error
+            .unwrap()
+            .starts_with(crate::fs_utils::CONFLICT_ERROR_PREFIX));
+
+        let id = first.channel_id.unwrap();
+        let deleted = runtime.block_on(delete_codex_channel(
+            path.clone(),
+            id.clone(),
+            first.revision,
+        ));
+        assert_eq!(deleted.data.unwrap().revision, 2);
+        assert!(!runtime.block_on(delete_codex_channel(path, id, 2)).success);
     }
 
     #[test]
-    fn switches_codex_channel_by_name_using_filesystem_state() {
-        let dir = create_temp_dir("switch");
+    fn inserts_new_channels_below_pinned_ones_and_keeps_id_on_edit() {
+        let dir = create_temp_dir("insert");
+        let path = dir.to_string_lossy().to_string();
         let store = CodexChannelStore {
             version: CODEX_STORE_VERSION,
-            channels: vec![CodexChannel {
-                name: "main".to_string(),
-                baseurl: "https://api.example.com/".to_string(),
-                apikey: "key-1".to_string(),
-                model: "o3".to_string(),
-            }],
+            revision: 3,
+            channels: vec![named_channel("pinned", true), named_channel("old", false)],
         };
         save_codex_store(&dir, &store).unwrap();
-        fs::write(
-            dir.join("config.toml"),
-            r#"model = "old"
-base_url = "https://root.example.com"
-model_provider = "OpenAI"
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let created = runtime
+            .block_on(save_codex_channel(
+                path.clone(),
+                channel_with_extra("new", ""),
+                3,
+            ))
+            .data
+            .unwrap();
+        let saved = load_codex_store(&dir).unwrap();
+        assert_eq!(names(&saved.channels), vec!["pinned", "new", "old"]);
+
+        let mut renamed = channel_with_extra("renamed", "");
+        renamed.id = "id-pinned".to_string();
+        assert!(
+            runtime
+                .block_on(save_codex_channel(path, renamed, created.revision))
+                .success
+        );
+        let saved = load_codex_store(&dir).unwrap();
+        assert_eq!(saved.channels[0].id, "id-pinned");
+        assert_eq!(saved.channels[0].name, "renamed");
+        assert!(saved.channels[0].pinned);
+    }
 
-[model_providers.OpenAI]
-name = "OpenAI"
-base_url = "https://old.example.com"
-wire_api = "responses"
-"#,
-        )
-        .unwrap();
-        fs::write(
-            dir.join("auth.json"),
-            "{\n  \"OPENAI_API_KEY\": \"old-key\",\n  \"keep\": true\n}",
-        )
-        .unwrap();
+    #[test]
+    fn moves_channels_within_their_pinned_section() {
+        let mut channels = vec![
+            named_channel("p1", true),
+            named_channel("p2", true),
+            named_channel("a", false),
+            named_channel("b", false),
+            named_channel("c", false),
+        ];
 
-        let result = tokio::runtime::Runtime::new()
-            .unwrap()
-            .block_on(switch_codex_channel(
-                dir.to_string_lossy().to_string(),
-                "main".to_string(),
-            ));
+        move_codex_channel_in(&mut channels, "id-c", 0).unwrap();
+        assert_eq!(names(&channels), vec!["p1", "p2", "c", "a", "b"]);
 
-        assert!(result.success);
+        move_codex_channel_in(&mut channels, "id-p1", 99).unwrap();
+        assert_eq!(names(&channels), vec!["p2", "p1", "c", "a", "b"]);
+
+        move_codex_channel_in(&mut channels, "id-c", 99).unwrap();
+        assert_eq!(names(&channels), vec!["p2", "p1", "a", "b", "c"]);
+
+        assert!(move_codex_channel_in(&mut channels, "missing", 0).is_err());
+    }
+
+    #[test]
+    fn pins_and_unpins_channels_at_section_boundary() {
+        let mut channels = vec![
+            named_channel("p1", true),
+            named_channel("a", false),
+            named_channel("b", false),
+        ];
+
+        set_codex_channel_pinned_in(&mut channels, "id-b", true).unwrap();
+        assert_eq!(names(&channels), vec!["p1", "b", "a"]);
+        assert!(channels[1].pinned);
+
+        set_codex_channel_pinned_in(&mut channels, "id-p1", false).unwrap();
+        assert_eq!(names(&channels), vec!["b", "p1", "a"]);
+        assert!(!channels[1].pinned);
+    }
+
+    #[test]
+    fn duplicates_channels_with_unique_names_and_ids() {
+        let mut channels = vec![
+            named_channel("relay-very-long-1", false),
+            named_channel("b", false),
+        ];
+
+        let first = duplicate_codex_channel_in(&mut channels, "id-relay-very-long-1").unwrap();
+        let second = duplicate_codex_channel_in(&mut channels, "id-relay-very-long-1").unwrap();
 
-        let updated_config = fs::read_to_string(dir.join("config.toml")).unwrap();
-        let root: TomlTable = toml::from_str(&updated_config).unwrap();
-        assert_eq!(root.get("model").and_then(TomlValue::as_str), Some("o3"));
-        assert_eq!(
-            root.get("base_url").and_then(TomlValue::as_str),
-            Some("https://root.example.com")
-        );
-        assert_eq!(
-            root.get("model_provider").and_then(TomlValue::as_str),
-            Some("OpenAI")
-        );
-        assert_eq!(
-            root["model_providers"]["OpenAI"]["wire_api"].as_str(),
-            Some("responses")
-        );
         assert_eq!(
-            root["model_providers"]["OpenAI"]["base_url"].as_str(),
-            Some("https://api.example.com")
+            names(&channels),
+            vec![
+                "relay-very-long-1",
+                "relay-very-l-copy2",
+                "relay-very-lo-copy",
+                "b"
+            ]
         );
-        assert!(root["model_providers"]
-            .as_table()
-            .unwrap()
-            .get("main")
-            .is_none());
+        assert_ne!(first, second);
+        assert_eq!(channels[2].id, first);
+        assert_eq!(channels[2].apikey, channels[0].apikey);
+        assert!(channels
+            .iter()
+            .all(|channel| is_valid_codex_name(&channel.name)));
+    }
 
-        let updated_auth: serde_json::Value =
-            serde_json::from_str(&fs::read_to_string(dir.join("auth.json")).unwrap()).unwrap();
-        assert_eq!(updated_auth["OPENAI_API_KEY"].as_str(), Some("key-1"));
-        assert_eq!(updated_auth["keep"].as_bool(), Some(true));
+    #[test]
+    fn copy_names_truncate_non_ascii_names_on_char_boundaries() {
+        let channels = vec![named_channel("中转站中转站", false)];
+        assert_eq!(copy_name_for("中转站中转站", &channels), "中转站中-copy");
+    }
+
+    #[test]
+    fn serializes_concurrent_store_mutations_without_lost_updates() {
+        let dir = create_temp_dir("concurrent");
+        let store = CodexChannelStore {
+            version: CODEX_STORE_VERSION,
+            revision: 0,
+            channels: vec![named_channel("base", false)],
+        };
+        save_codex_store(&dir, &store).unwrap();
+
+        let workers: Vec<_> = (0..6)
+            .map(|_| {
+                let dir = dir.clone();
+                std::thread::spawn(move || loop {
+                    let revision = load_codex_store_locked(&dir).unwrap().revision;
+                    let result = mutate_codex_store(&dir, revision, |store| {
+                        duplicate_codex_channel_in(&mut store.channels, "id-base").map(Some)
+                    });
+                    match result {
+                        Ok(_) => break,
+                        Err(e) => assert!(e.starts_with(crate::fs_utils::CONFLICT_ERROR_PREFIX)),
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let saved = load_codex_store(&dir).unwrap();
+        assert_eq!(saved.revision, 6);
+        assert_eq!(saved.channels.len(), 7);
     }
 }
@@ -0,0 +1,1380 @@
+use crate::channel::{
+    normalize_base_url, probe_endpoint, test_timeout, Channel, ChannelBackend, ChannelTestResult,
+    ChannelTestTarget, SwitchSnapshot, TestAuth, ToolKind,
+};
+use crate::fs_utils::{
+    home_dir, run_blocking, with_dir_lock, with_dir_lock_unless_held, write_text_file,
+};
+use crate::terminal::LaunchSpec;
+use crate::ApiResponse;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const DROID_STORE_VERSION: u32 = 1;
+pub(crate) const DROID_STORE_FILE_NAME: &str = "droid_channels.json";
+pub(crate) const DROID_LEGACY_FILE_NAME: &str = "key.txt";
+const DROID_LEGACY_BACKUP_FILE_NAME: &str = "key.txt.bak";
+const DROID_DEFAULT_BASE_URL: &str = "https://api.factory.ai";
+const DROID_TEST_PATH: &str = "/api/llm/o/v1/models";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct DroidChannel {
+    name: String,
+    api_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base_url: Option<String>,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    created_at: i64,
+    #[serde(default)]
+    updated_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_switched_at: Option<i64>,
+    // 最近一次连通性检测结果，随渠道列表一起返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_test: Option<ChannelTestResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct DroidChannelStore {
+    #[serde(default = "droid_store_version")]
+    version: u32,
+    #[serde(default)]
+    channels: Vec<DroidChannel>,
+    // 最近一次通过本应用切换到的渠道
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct DroidActiveInfo {
+    name: Option<String>,
+    api_key: String,
+    last_switched_at: Option<i64>,
+}
+
+fn droid_store_version() -> u32 {
+    DROID_STORE_VERSION
+}
+
+fn default_droid_store() -> DroidChannelStore {
+    DroidChannelStore {
+        version: DROID_STORE_VERSION,
+        channels: vec![],
+        active: None,
+    }
+}
+
+fn droid_store_path(dir: &Path) -> PathBuf {
+    dir.join(DROID_STORE_FILE_NAME)
+}
+
+fn now_timestamp() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn is_valid_droid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().count() <= 32 && !name.chars().any(char::is_control)
+}
+
+fn normalize_droid_channel(channel: DroidChannel) -> Result<DroidChannel, String> {
+    let base_url = match channel
+        .base_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+    {
+        Some(url) => Some(normalize_base_url(url)?),
+        None => None,
+    };
+
+    Ok(DroidChannel {
+        name: channel.name.trim().to_string(),
+        api_key: channel.api_key.trim().to_string(),
+        base_url,
+        notes: channel.notes.trim().to_string(),
+        ..channel
+    })
+}
+
+fn validate_droid_channel(
+    channel: &DroidChannel,
+    existing: &[DroidChannel],
+    edit_index: Option<usize>,
+) -> Result<(), String> {
+    if !is_valid_droid_name(&channel.name) {
+        return Err("渠道名称不能为空，最多32个字符且不能包含控制字符".to_string());
+    }
+    if channel.api_key.is_empty() {
+        return Err("请输入 API Key".to_string());
+    }
+    if channel
+        .api_key
+        .chars()
+        .any(|ch| ch.is_whitespace() || ch.is_control())
+    {
+        return Err("API Key 不能包含空白或控制字符".to_string());
+    }
+    if channel.notes.chars().count() > 200 {
+        return Err("备注最多200个字符".to_string());
+    }
+
+    let duplicate_exists = existing
+        .iter()
+        .enumerate()
+        .any(|(idx, current)| Some(idx) != edit_index && current.name == channel.name);
+
+    if duplicate_exists {
+        return Err("渠道名称已存在".to_string());
+    }
+
+    Ok(())
+}
+
+// 旧格式：每行 `名称 API Key`，行尾可能带有 [active] 标记，返回渠道列表与激活的渠道名
+fn parse_legacy_key_file(content: &str, timestamp: i64) -> (Vec<DroidChannel>, Option<String>) {
+    let mut channels: Vec<DroidChannel> = Vec::new();
+    let mut active = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        let is_active = line.ends_with("[active]");
+        let line = line.trim_end_matches("[active]").trim();
+        let Some((name, api_key)) = line.split_once(' ') else {
+            continue;
+        };
+        let (name, api_key) = (name.trim(), api_key.trim());
+        if name.is_empty() || api_key.is_empty() || channels.iter().any(|c| c.name == name) {
+            continue;
+        }
+
+        if is_active {
+            active = Some(name.to_string());
+        }
+        channels.push(DroidChannel {
+            name: name.to_string(),
+            api_key: api_key.to_string(),
+            base_url: None,
+            notes: String::new(),
+            created_at: timestamp,
+            updated_at: timestamp,
+            last_switched_at: None,
+            last_test: None,
+        });
+    }
+
+    (channels, active)
+}
+
+// 首次读取时将 key.txt 迁移为 JSON 存储，原文件重命名为 key.txt.bak 保留
+fn migrate_legacy_key_file(dir: &Path) -> Result<Option<DroidChannelStore>, String> {
+    let legacy_path = dir.join(DROID_LEGACY_FILE_NAME);
+    if !legacy_path.exists() {
+        return Ok(None);
+    }
+
+    let content =
+        fs::read_to_string(&legacy_path).map_err(|e| format!("读取 key.txt 失败: {}", e))?;
+    let (channels, active) = parse_legacy_key_file(&content, now_timestamp());
+    let store = DroidChannelStore {
+        version: DROID_STORE_VERSION,
+        channels,
+        active,
+    };
+    save_droid_store(dir, &store)?;
+
+    fs::rename(&legacy_path, dir.join(DROID_LEGACY_BACKUP_FILE_NAME))
+        .map_err(|e| format!("备份 key.txt 失败: {}", e))?;
+
+    Ok(Some(store))
+}
+
+fn load_droid_store(dir: &Path) -> Result<DroidChannelStore, String> {
+    let store_path = droid_store_path(dir);
+    if store_path.exists() {
+        let content = fs::read_to_string(&store_path)
+            .map_err(|e| format!("读取 Droid 存储文件失败: {}", e))?;
+        let store: DroidChannelStore = serde_json::from_str(&content)
+            .map_err(|e| format!("解析 Droid 存储文件失败: {}", e))?;
+
+        if store.version != DROID_STORE_VERSION {
+            return Err(format!("不支持的 Droid 存储版本: {}", store.version));
+        }
+
+        return Ok(store);
+    }
+
+    Ok(migrate_legacy_key_file(dir)?.unwrap_or_else(default_droid_store))
+}
+
+fn save_droid_store(dir: &Path, store: &DroidChannelStore) -> Result<(), String> {
+    if !dir.exists() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建 Droid 目录失败: {}", e))?;
+    }
+
+    let serialized =
+        serde_json::to_string_pretty(store).map_err(|e| format!("序列化 Droid 存储失败: {}", e))?;
+    write_text_file(&droid_store_path(dir), &serialized)
+}
+
+// 在目录锁内读取、修改并保存存储，避免多个窗口或进程互相覆盖
+fn mutate_droid_store<T, F>(dir: &Path, mutate: F) -> Result<T, String>
+where
+    F: FnOnce(&mut DroidChannelStore) -> Result<T, String>,
+{
+    mutate_droid_store_in(dir, false, mutate)
+}
+
+fn mutate_droid_store_in<T, F>(dir: &Path, lock_held: bool, mutate: F) -> Result<T, String>
+where
+    F: FnOnce(&mut DroidChannelStore) -> Result<T, String>,
+{
+    with_dir_lock_unless_held(dir, lock_held, || {
+        let mut store = load_droid_store(dir)?;
+        let result = mutate(&mut store)?;
+        save_droid_store(dir, &store)?;
+        Ok(result)
+    })
+}
+
+// key.txt 的迁移会写入存储，因此读取也在目录锁内进行
+fn load_droid_store_locked(dir: &Path) -> Result<DroidChannelStore, String> {
+    with_dir_lock(dir, || load_droid_store(dir))
+}
+
+fn upsert_droid_channel(
+    store: &mut DroidChannelStore,
+    channel: DroidChannel,
+    old_name: &str,
+    timestamp: i64,
+) -> Result<(), String> {
+    let channel = normalize_droid_channel(channel)?;
+    let old_name = old_name.trim();
+
+    if old_name.is_empty() {
+        validate_droid_channel(&channel, &store.channels, None)?;
+        // 在顶部插入新渠道
+        store.channels.insert(
+            0,
+            DroidChannel {
+                created_at: timestamp,
+                updated_at: timestamp,
+                ..channel
+            },
+        );
+        return Ok(());
+    }
+
+    // 编辑模式：在原位置更新，保留创建时间
+    let index = store
+        .channels
+        .iter()
+        .position(|c| c.name == old_name)
+        .ok_or_else(|| "渠道不存在".to_string())?;
+    validate_droid_channel(&channel, &store.channels, Some(index))?;
+
+    // Key 或地址变化后旧的检测结果不再可信
+    let previous = &store.channels[index];
+    let last_test = if previous.api_key == channel.api_key && previous.base_url == channel.base_url
+    {
+        previous.last_test.clone()
+    } else {
+        None
+    };
+    store.channels[index] = DroidChannel {
+        created_at: previous.created_at,
+        updated_at: timestamp,
+        last_switched_at: previous.last_switched_at,
+        last_test,
+        ..channel
+    };
+
+    // 重命名时同步激活标记
+    if store.active.as_deref() == Some(old_name) {
+        store.active = Some(store.channels[index].name.clone());
+    }
+    Ok(())
+}
+
+fn remove_droid_channel(store: &mut DroidChannelStore, name: &str) -> Result<(), String> {
+    let index = store
+        .channels
+        .iter()
+        .position(|c| c.name == name)
+        .ok_or_else(|| "渠道不存在".to_string())?;
+    store.channels.remove(index);
+
+    if store.active.as_deref() == Some(name) {
+        store.active = None;
+    }
+    Ok(())
+}
+
+// 记录切换时间与激活标记，返回该渠道的 API Key
+fn mark_droid_channel_active(
+    store: &mut DroidChannelStore,
+    name: &str,
+    timestamp: i64,
+) -> Result<String, String> {
+    let channel = store
+        .channels
+        .iter_mut()
+        .find(|c| c.name == name)
+        .ok_or_else(|| "渠道不存在".to_string())?;
+    channel.last_switched_at = Some(timestamp);
+    let api_key = channel.api_key.clone();
+
+    store.active = Some(name.to_string());
+    Ok(api_key)
+}
+
+// 以当前生效的 Key 为准匹配渠道；多个渠道 Key 相同时优先使用激活标记。
+// 读取不到 Key 时（例如终端尚未加载 env.sh）退回到激活标记
+fn detect_active_droid_channel(store: &DroidChannelStore, current_key: &str) -> DroidActiveInfo {
+    let marked = store
+        .active
+        .as_deref()
+        .and_then(|name| store.channels.iter().find(|c| c.name == name));
+
+    let channel = if current_key.is_empty() {
+        marked
+    } else {
+        marked
+            .filter(|c| c.api_key == current_key)
+            .or_else(|| store.channels.iter().find(|c| c.api_key == current_key))
+    };
+
+    DroidActiveInfo {
+        name: channel.map(|c| c.name.clone()),
+        api_key: channel
+            .map(|c| c.api_key.clone())
+            .unwrap_or_else(|| current_key.to_string()),
+        last_switched_at: channel.and_then(|c| c.last_switched_at),
+    }
+}
+
+// ==================== Droid 连通性检测 ====================
+
+// 显式指定的地址优先（便于指向本地 Mock），其次是渠道的 Base URL，最后是官方地址
+fn resolve_droid_test_endpoint(
+    endpoint: Option<&str>,
+    channel: &DroidChannel,
+) -> Result<String, String> {
+    if let Some(endpoint) = endpoint.map(str::trim).filter(|e| !e.is_empty()) {
+        let url = reqwest::Url::parse(endpoint).map_err(|_| "检测地址格式无效".to_string())?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("检测地址格式无效".to_string());
+        }
+        return Ok(url.to_string());
+    }
+
+    let base = channel
+        .base_url
+        .as_deref()
+        .unwrap_or(DROID_DEFAULT_BASE_URL);
+    Ok(format!("{}{}", base.trim_end_matches('/'), DROID_TEST_PATH))
+}
+
+// ==================== Shell 环境变量持久化（Linux / macOS） ====================
+
+const SHELL_ENV_DIR_NAME: &str = "claude-channel-switcher";
+const SHELL_HOOK_BEGIN: &str = "# >>> claude-channel-switcher >>>";
+const SHELL_HOOK_END: &str = "# <<< claude-channel-switcher <<<";
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+// 遵循 XDG 规范，未设置时为 ~/.config
+fn config_home(home: &Path) -> PathBuf {
+    match std::env::var("XDG_CONFIG_HOME") {
+        Ok(config_home) if !config_home.trim().is_empty() => PathBuf::from(config_home),
+        _ => home.join(".config"),
+    }
+}
+
+fn shell_env_dir(home: &Path) -> PathBuf {
+    config_home(home).join(SHELL_ENV_DIR_NAME)
+}
+
+fn quote_posix(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn quote_fish(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn render_env_sh(api_key: &str) -> String {
+    format!(
+        "# 由 Claude Channel Switcher 自动生成，切换 Droid 渠道时会被覆盖\nexport FACTORY_API_KEY={}\n",
+        quote_posix(api_key)
+    )
+}
+
+fn render_env_fish(api_key: &str) -> String {
+    format!(
+        "# 由 Claude Channel Switcher 自动生成，切换 Droid 渠道时会被覆盖\nset -gx FACTORY_API_KEY {}\n",
+        quote_fish(api_key)
+    )
+}
+
+// 文件中包含 API Key，仅允许当前用户读写
+fn write_private_file(path: &Path, content: &str) -> Result<(), String> {
+    write_text_file(path, content)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("设置文件权限失败: {}", e))?;
+    }
+
+    Ok(())
+}
+
+fn write_shell_env_files(env_dir: &Path, api_key: &str) -> Result<(), String> {
+    fs::create_dir_all(env_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    write_private_file(&env_dir.join("env.sh"), &render_env_sh(api_key))?;
+    write_private_file(&env_dir.join("env.fish"), &render_env_fish(api_key))
+}
+
+// bash / zsh 在 rc 文件中追加带标记的代码块；fish 使用 conf.d 下的独立文件
+fn shell_hook_target(home: &Path, shell: ShellKind) -> PathBuf {
+    match shell {
+        ShellKind::Bash => home.join(".bashrc"),
+        ShellKind::Zsh => home.join(".zshrc"),
+        ShellKind::Fish => config_home(home)
+            .join("fish")
+            .join("conf.d")
+            .join(format!("{}.fish", SHELL_ENV_DIR_NAME)),
+    }
+}
+
+fn render_shell_hook(env_dir: &Path, shell: ShellKind) -> String {
+    match shell {
+        ShellKind::Bash | ShellKind::Zsh => {
+            let env_file = quote_posix(&env_dir.join("env.sh").to_string_lossy());
+            format!(
+                "{}\n[ -f {} ] && . {}\n{}\n",
+                SHELL_HOOK_BEGIN, env_file, env_file, SHELL_HOOK_END
+            )
+        }
+        ShellKind::Fish => {
+            let env_file = quote_fish(&env_dir.join("env.fish").to_string_lossy());
+            format!(
+                "{}\ntest -f {}; and source {}\n{}\n",
+                SHELL_HOOK_BEGIN, env_file, env_file, SHELL_HOOK_END
+            )
+        }
+    }
+}
+
+// 删除已有的标记块，返回剩余内容与是否找到标记
+fn strip_shell_hook(content: &str) -> (String, bool) {
+    let mut result = String::with_capacity(content.len());
+    let mut inside = false;
+    let mut found = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == SHELL_HOOK_BEGIN {
+            inside = true;
+            found = true;
+        } else if inside && trimmed == SHELL_HOOK_END {
+            inside = false;
+        } else if !inside {
+            result.push_str(line);
+        }
+    }
+
+    (result, found)
+}
+
+// rc 文件常由 dotfile 管理工具软链接到其他位置：写入链接指向的真实文件并保留其权限，
+// 避免把软链接替换成普通文件
+fn write_rc_file(target: &Path, content: &str) -> Result<(), String> {
+    if !target.exists() {
+        return write_text_file(target, content);
+    }
+
+    let real_target =
+        fs::canonicalize(target).map_err(|e| format!("解析 {} 失败: {}", target.display(), e))?;
+    let permissions = fs::metadata(&real_target)
+        .map_err(|e| format!("读取 {} 失败: {}", real_target.display(), e))?
+        .permissions();
+    write_text_file(&real_target, content)?;
+    fs::set_permissions(&real_target, permissions)
+        .map_err(|e| format!("设置文件权限失败 {}: {}", real_target.display(), e))
+}
+
+// 幂等：重复安装只会替换原有代码块
+fn install_shell_hook(target: &Path, hook: &str) -> Result<(), String> {
+    let existing = if target.exists() {
+        fs::read_to_string(target).map_err(|e| format!("读取 {} 失败: {}", target.display(), e))?
+    } else {
+        String::new()
+    };
+
+    let (mut content, _) = strip_shell_hook(&existing);
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    if !content.is_empty() && !content.ends_with("\n\n") {
+        content.push('\n');
+    }
+    content.push_str(hook);
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    write_rc_file(target, &content)
+}
+
+fn uninstall_shell_hook(target: &Path, shell: ShellKind) -> Result<bool, String> {
+    if !target.exists() {
+        return Ok(false);
+    }
+
+    // fish 的 conf.d 文件由本应用独占，直接删除
+    if shell == ShellKind::Fish {
+        fs::remove_file(target).map_err(|e| format!("删除 {} 失败: {}", target.display(), e))?;
+        return Ok(true);
+    }
+
+    let existing =
+        fs::read_to_string(target).map_err(|e| format!("读取 {} 失败: {}", target.display(), e))?;
+    let (content, found) = strip_shell_hook(&existing);
+    if found {
+        // 去掉安装时补充的空行
+        let remaining = content.trim_end_matches('\n');
+        let content = if remaining.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", remaining)
+        };
+        write_rc_file(target, &content)?;
+    }
+    Ok(found)
+}
+
+// 未指定 Shell 时根据 $SHELL 推断
+fn resolve_shells(shells: Option<Vec<ShellKind>>) -> Vec<ShellKind> {
+    match shells.filter(|shells| !shells.is_empty()) {
+        Some(shells) => shells,
+        None => {
+            let shell = std::env::var("SHELL").unwrap_or_default();
+            let shell = shell.rsplit('/').next().unwrap_or_default().to_string();
+            match shell.as_str() {
+                "zsh" => vec![ShellKind::Zsh],
+                "fish" => vec![ShellKind::Fish],
+                _ => vec![ShellKind::Bash],
+            }
+        }
+    }
+}
+
+// ==================== 统一渠道接口 ====================
+
+// Droid 渠道以名称作为 id
+pub(crate) struct DroidBackend {
+    dir: PathBuf,
+    // 目录锁已由调用方持有
+    lock_held: bool,
+}
+
+impl DroidBackend {
+    pub(crate) fn new(dir: &Path) -> Self {
+        DroidBackend {
+            dir: dir.to_path_buf(),
+            lock_held: false,
+        }
+    }
+
+    pub(crate) fn with_lock_held(dir: &Path) -> Self {
+        DroidBackend {
+            dir: dir.to_path_buf(),
+            lock_held: true,
+        }
+    }
+
+    fn load_store(&self) -> Result<DroidChannelStore, String> {
+        with_dir_lock_unless_held(&self.dir, self.lock_held, || load_droid_store(&self.dir))
+    }
+}
+
+impl ChannelBackend for DroidBackend {
+    fn tool(&self) -> ToolKind {
+        ToolKind::Droid
+    }
+
+    fn list(&self) -> Result<Vec<Channel>, String> {
+        let store = self.load_store()?;
+        Ok(store
+            .channels
+            .into_iter()
+            .map(|channel| Channel {
+                id: channel.name.clone(),
+                name: channel.name,
+                base_url: channel.base_url.unwrap_or_default(),
+                api_key: channel.api_key,
+                model: String::new(),
+            })
+            .collect())
+    }
+
+    fn save(&self, channel: Channel) -> Result<String, String> {
+        mutate_droid_store_in(&self.dir, self.lock_held, |store| {
+            let notes = store
+                .channels
+                .iter()
+                .find(|c| c.name == channel.id)
+                .map(|c| c.notes.clone())
+                .unwrap_or_default();
+            let name = channel.name.trim().to_string();
+            let droid_channel = DroidChannel {
+                name: name.clone(),
+                api_key: channel.api_key,
+                base_url: Some(channel.base_url).filter(|url| !url.trim().is_empty()),
+                notes,
+                created_at: 0,
+                updated_at: 0,
+                last_switched_at: None,
+                last_test: None,
+            };
+            upsert_droid_channel(store, droid_channel, &channel.id, now_timestamp())?;
+            Ok(name)
+        })
+    }
+
+    fn delete(&self, id: &str) -> Result<(), String> {
+        mutate_droid_store_in(&self.dir, self.lock_held, |store| {
+            remove_droid_channel(store, id)
+        })
+    }
+
+    fn switch(&self, id: &str) -> Result<(), String> {
+        switch_droid_channel_in(&self.dir, self.lock_held, id)
+    }
+
+    fn current(&self) -> Result<Option<String>, String> {
+        let store = self.load_store()?;
+        Ok(detect_active_droid_channel(&store, &current_factory_api_key()).name)
+    }
+
+    fn test_target(&self, channel: &Channel) -> Result<ChannelTestTarget, String> {
+        let store = self.load_store()?;
+        let droid_channel = store
+            .channels
+            .iter()
+            .find(|c| c.name == channel.id)
+            .ok_or_else(|| "渠道不存在".to_string())?;
+        Ok(ChannelTestTarget {
+            endpoint: resolve_droid_test_endpoint(None, droid_channel)?,
+            api_key: droid_channel.api_key.clone(),
+            auth: TestAuth::Bearer,
+        })
+    }
+
+    // 恢复文件后重新应用原来的 Key，使环境变量与 Shell 环境变量文件的权限一并复原
+    fn switch_snapshot(&self) -> Result<SwitchSnapshot, String> {
+        let mut files = vec![droid_store_path(&self.dir)];
+        if cfg!(not(target_os = "windows")) {
+            let env_dir = shell_env_dir(&home_dir()?);
+            files.push(env_dir.join("env.sh"));
+            files.push(env_dir.join("env.fish"));
+        }
+
+        let previous_key = current_factory_api_key();
+        Ok(SwitchSnapshot::of_files(files)?
+            .with_restore(move || restore_factory_api_key(&previous_key)))
+    }
+
+    fn launch_spec(&self, id: &str) -> Result<LaunchSpec, String> {
+        let store = load_droid_store_locked(&self.dir)?;
+        let channel = store
+            .channels
+            .iter()
+            .find(|c| c.name == id)
+            .ok_or_else(|| "渠道不存在".to_string())?;
+        Ok(LaunchSpec {
+            program: "droid",
+            args: vec![],
+            env: vec![("FACTORY_API_KEY".to_string(), channel.api_key.clone())],
+        })
+    }
+}
+
+// ==================== Droid 渠道管理 ====================
+
+// 优先读取当前进程的环境变量，Windows 上再尝试读取用户环境变量
+fn current_factory_api_key() -> String {
+    if let Ok(key) = std::env::var("FACTORY_API_KEY") {
+        if !key.is_empty() {
+            return key;
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(key) = crate::terminal::get_user_env_var("FACTORY_API_KEY") {
+            // 同步到当前进程的环境变量
+            std::env::set_var("FACTORY_API_KEY", &key);
+            return key;
+        }
+    }
+
+    String::new()
+}
+
+fn apply_factory_api_key(api_key: &str) -> Result<(), String> {
+    // 设置当前进程的环境变量（子进程会继承）
+    std::env::set_var("FACTORY_API_KEY", api_key);
+
+    // 设置用户级别环境变量（写入注册表，新终端可用）
+    #[cfg(target_os = "windows")]
+    {
+        crate::terminal::set_user_env_var("FACTORY_API_KEY", api_key)?;
+    }
+
+    // 写入 Shell 环境变量文件，已安装 rc 钩子的新终端会自动加载
+    #[cfg(not(target_os = "windows"))]
+    {
+        home_dir()
+            .and_then(|home| write_shell_env_files(&shell_env_dir(&home), api_key))
+            .map_err(|e| format!("写入 Shell 环境变量文件失败: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// 原来没有 Key 时只能清除当前进程的环境变量，Windows 用户环境变量保持不变
+fn restore_factory_api_key(previous_key: &str) -> Result<(), String> {
+    if previous_key.is_empty() {
+        std::env::remove_var("FACTORY_API_KEY");
+        Ok(())
+    } else {
+        apply_factory_api_key(previous_key)
+    }
+}
+
+// 先保存渠道标记再修改环境变量，保存失败时环境保持不变；
+// 环境变量写入失败时恢复存储与原来的 Key，使标记与实际生效的 Key 一致
+fn switch_droid_channel_in(dir: &Path, lock_held: bool, name: &str) -> Result<(), String> {
+    with_dir_lock_unless_held(dir, lock_held, || {
+        let mut store = load_droid_store(dir)?;
+        let previous = store.clone();
+        let previous_key = current_factory_api_key();
+        let api_key = mark_droid_channel_active(&mut store, name, now_timestamp())?;
+        save_droid_store(dir, &store)?;
+
+        if let Err(e) = apply_factory_api_key(&api_key) {
+            let restored = save_droid_store(dir, &previous)
+                .and_then(|_| restore_factory_api_key(&previous_key));
+            return Err(match restored {
+                Ok(()) => e,
+                Err(restore_error) => format!("{}；恢复原配置时出错: {}", e, restore_error),
+            });
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub(crate) fn get_current_factory_api_key() -> ApiResponse<String> {
+    ApiResponse {
+        success: true,
+        error: None,
+        channels: None,
+        config: None,
+        data: Some(current_factory_api_key()),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_active_droid_channel(config_path: String) -> ApiResponse<DroidActiveInfo> {
+    match run_blocking(move || load_droid_store_locked(Path::new(&config_path))).await {
+        Ok(store) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(detect_active_droid_channel(
+                &store,
+                &current_factory_api_key(),
+            )),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_droid_channels(config_path: String) -> ApiResponse<Vec<DroidChannel>> {
+    match run_blocking(move || load_droid_store_locked(Path::new(&config_path))).await {
+        Ok(store) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(store.channels),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn switch_droid_channel(config_path: String, name: String) -> ApiResponse<()> {
+    let result =
+        run_blocking(move || switch_droid_channel_in(Path::new(&config_path), false, &name)).await;
+
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn save_droid_channel(
+    config_path: String,
+    name: String,
+    api_key: String,
+    old_name: String,
+    base_url: Option<String>,
+    notes: Option<String>,
+) -> ApiResponse<()> {
+    let channel = DroidChannel {
+        name,
+        api_key,
+        base_url,
+        notes: notes.unwrap_or_default(),
+        created_at: 0,
+        updated_at: 0,
+        last_switched_at: None,
+        last_test: None,
+    };
+    let result = run_blocking(move || {
+        mutate_droid_store(Path::new(&config_path), |store| {
+            upsert_droid_channel(store, channel, &old_name, now_timestamp())
+        })
+    })
+    .await;
+
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn delete_droid_channel(config_path: String, name: String) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        mutate_droid_store(Path::new(&config_path), |store| {
+            remove_droid_channel(store, &name)
+        })
+    })
+    .await;
+
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn test_droid_channel(
+    config_path: String,
+    name: String,
+    endpoint: Option<String>,
+    timeout_ms: Option<u64>,
+) -> ApiResponse<ChannelTestResult> {
+    let dir = PathBuf::from(config_path);
+    let prepared = {
+        let (dir, name) = (dir.clone(), name.clone());
+        run_blocking(move || {
+            let store = load_droid_store_locked(&dir)?;
+            let channel = store
+                .channels
+                .into_iter()
+                .find(|c| c.name == name)
+                .ok_or_else(|| "渠道不存在".to_string())?;
+            let endpoint = resolve_droid_test_endpoint(endpoint.as_deref(), &channel)?;
+            Ok((channel, endpoint))
+        })
+        .await
+    };
+    let (channel, endpoint) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            return ApiResponse {
+                success: false,
+                error: Some(e),
+                channels: None,
+                config: None,
+                data: None,
+            }
+        }
+    };
+    let target = ChannelTestTarget {
+        endpoint,
+        api_key: channel.api_key.clone(),
+        auth: TestAuth::Bearer,
+    };
+    let result = probe_endpoint(&target, test_timeout(timeout_ms)).await;
+
+    // 检测期间渠道可能被修改，在锁内重新读取后再写回结果
+    let last_test = result.clone();
+    let saved = run_blocking(move || {
+        mutate_droid_store(&dir, |store| {
+            if let Some(saved) = store
+                .channels
+                .iter_mut()
+                .find(|c| c.name == name && c.api_key == channel.api_key)
+            {
+                saved.last_test = Some(last_test);
+            }
+            Ok(())
+        })
+    })
+    .await;
+
+    // 结果未能保存时仍返回检测结果，但标记失败，避免界面显示未持久化的状态
+    let error = saved
+        .err()
+        .map(|e| format!("检测已完成，但保存检测结果失败: {}", e));
+    ApiResponse {
+        success: error.is_none(),
+        error,
+        channels: None,
+        config: None,
+        data: Some(result),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn install_droid_shell_hook(
+    shells: Option<Vec<ShellKind>>,
+) -> ApiResponse<Vec<String>> {
+    if cfg!(target_os = "windows") {
+        return ApiResponse {
+            success: false,
+            error: Some("Windows 已通过用户环境变量持久化，无需安装 Shell 钩子".to_string()),
+            channels: None,
+            config: None,
+            data: None,
+        };
+    }
+
+    let result = home_dir().and_then(|home| {
+        let env_dir = shell_env_dir(&home);
+        // 确保被 source 的文件存在，内容为当前进程中的 Key
+        if !env_dir.join("env.sh").exists() {
+            let api_key = std::env::var("FACTORY_API_KEY").unwrap_or_default();
+            write_shell_env_files(&env_dir, &api_key)?;
+        }
+
+        resolve_shells(shells)
+            .into_iter()
+            .map(|shell| {
+                let target = shell_hook_target(&home, shell);
+                install_shell_hook(&target, &render_shell_hook(&env_dir, shell))?;
+                Ok(target.to_string_lossy().to_string())
+            })
+            .collect::<Result<Vec<_>, String>>()
+    });
+
+    match result {
+        Ok(files) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(files),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn uninstall_droid_shell_hook(
+    shells: Option<Vec<ShellKind>>,
+) -> ApiResponse<Vec<String>> {
+    let result = home_dir().and_then(|home| {
+        let shells =
+            shells.unwrap_or_else(|| vec![ShellKind::Bash, ShellKind::Zsh, ShellKind::Fish]);
+        let mut removed = Vec::new();
+        for shell in shells {
+            let target = shell_hook_target(&home, shell);
+            if uninstall_shell_hook(&target, shell)? {
+                removed.push(target.to_string_lossy().to_string());
+            }
+        }
+        Ok(removed)
+    });
+
+    match result {
+        Ok(files) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(files),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-droid-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn channel(name: &str, api_key: &str) -> DroidChannel {
+        DroidChannel {
+            name: name.to_string(),
+            api_key: api_key.to_string(),
+            base_url: None,
+            notes: String::new(),
+            created_at: 0,
+            updated_at: 0,
+            last_switched_at: None,
+            last_test: None,
+        }
+    }
+
+    #[test]
+    fn parses_legacy_key_file_lines() {
+        let (channels, active) = parse_legacy_key_file(
+            "main fk-111\n\n  backup   fk-222 [active] \nbroken\nmain fk-333\n",
+            42,
+        );
+
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].name, "main");
+        assert_eq!(channels[0].api_key, "fk-111");
+        assert_eq!(channels[0].created_at, 42);
+        assert_eq!(channels[1].name, "backup");
+        assert_eq!(channels[1].api_key, "fk-222");
+        assert_eq!(active.as_deref(), Some("backup"));
+    }
+
+    #[test]
+    fn migrates_key_txt_once() {
+        let dir = create_temp_dir("migrate");
+        fs::write(
+            dir.join(DROID_LEGACY_FILE_NAME),
+            "main fk-111\nbackup fk-222",
+        )
+        .unwrap();
+
+        let store = load_droid_store(&dir).unwrap();
+        assert_eq!(store.channels.len(), 2);
+        assert!(droid_store_path(&dir).exists());
+        assert!(!dir.join(DROID_LEGACY_FILE_NAME).exists());
+        assert!(dir.join(DROID_LEGACY_BACKUP_FILE_NAME).exists());
+
+        // 迁移后再次出现的 key.txt 不会覆盖 JSON 存储
+        fs::write(dir.join(DROID_LEGACY_FILE_NAME), "other fk-999").unwrap();
+        let reloaded = load_droid_store(&dir).unwrap();
+        assert_eq!(reloaded.channels, store.channels);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn returns_empty_store_without_any_files() {
+        let dir = create_temp_dir("empty");
+        let store = load_droid_store(&dir).unwrap();
+        assert_eq!(store.version, DROID_STORE_VERSION);
+        assert!(store.channels.is_empty());
+        assert!(!droid_store_path(&dir).exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn upserts_channels_with_spaces_in_names_and_keeps_created_at() {
+        let mut store = default_droid_store();
+        upsert_droid_channel(&mut store, channel(" 我的 渠道 ", "fk-1"), "", 10).unwrap();
+        assert_eq!(store.channels[0].name, "我的 渠道");
+        assert_eq!(store.channels[0].created_at, 10);
+
+        let mut edited = channel("我的 渠道", "fk-2");
+        edited.base_url = Some("https://API.example.com/".to_string());
+        edited.notes = "团队共享".to_string();
+        upsert_droid_channel(&mut store, edited, "我的 渠道", 20).unwrap();
+
+        let saved = &store.channels[0];
+        assert_eq!(saved.api_key, "fk-2");
+        assert_eq!(saved.base_url.as_deref(), Some("https://api.example.com"));
+        assert_eq!((saved.created_at, saved.updated_at), (10, 20));
+    }
+
+    #[test]
+    fn rejects_invalid_droid_channels() {
+        let mut store = default_droid_store();
+        upsert_droid_channel(&mut store, channel("main", "fk-1"), "", 1).unwrap();
+
+        assert!(upsert_droid_channel(&mut store, channel("main", "fk-2"), "", 2).is_err());
+        assert!(upsert_droid_channel(&mut store, channel("", "fk-2"), "", 2).is_err());
+        assert!(upsert_droid_channel(&mut store, channel("x", "fk 2"), "", 2).is_err());
+        assert!(upsert_droid_channel(&mut store, channel("x", "fk-2"), "missing", 2).is_err());
+
+        let mut bad_url = channel("x", "fk-2");
+        bad_url.base_url = Some("ftp://example.com".to_string());
+        assert!(upsert_droid_channel(&mut store, bad_url, "", 2).is_err());
+    }
+
+    #[test]
+    fn saves_versioned_droid_store() {
+        let dir = create_temp_dir("save");
+        let mut store = default_droid_store();
+        upsert_droid_channel(&mut store, channel("main", "fk-1"), "", 5).unwrap();
+        save_droid_store(&dir, &store).unwrap();
+
+        let content = fs::read_to_string(droid_store_path(&dir)).unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(saved["version"], DROID_STORE_VERSION);
+        assert_eq!(saved["channels"][0]["name"], "main");
+        assert_eq!(load_droid_store(&dir).unwrap().channels, store.channels);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn renders_env_files_with_quoted_keys() {
+        assert_eq!(
+            render_env_sh("fk-a'b"),
+            "# 由 Claude Channel Switcher 自动生成，切换 Droid 渠道时会被覆盖\nexport FACTORY_API_KEY='fk-a'\\''b'\n"
+        );
+        assert!(render_env_fish("fk-a'b\\c").ends_with("set -gx FACTORY_API_KEY 'fk-a\\'b\\\\c'\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_private_env_files_sourceable_by_sh() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = create_temp_dir("env");
+        write_shell_env_files(&dir, "fk-$(touch pwned)'x").unwrap();
+
+        let mode = fs::metadata(dir.join("env.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(". \"$1\" && printf %s \"$FACTORY_API_KEY\"")
+            .arg("sh")
+            .arg(dir.join("env.sh"))
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "fk-$(touch pwned)'x"
+        );
+        assert!(!dir.join("pwned").exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn installs_shell_hook_idempotently_and_uninstalls() {
+        let dir = create_temp_dir("hook");
+        let rc = dir.join(".bashrc");
+        fs::write(&rc, "alias ll='ls -l'").unwrap();
+        let hook = render_shell_hook(Path::new("/cfg/claude-channel-switcher"), ShellKind::Bash);
+
+        install_shell_hook(&rc, &hook).unwrap();
+        install_shell_hook(&rc, &hook).unwrap();
+
+        let content = fs::read_to_string(&rc).unwrap();
+        assert_eq!(content.matches(SHELL_HOOK_BEGIN).count(), 1);
+        assert!(content.starts_with("alias ll='ls -l'\n\n"));
+        assert!(content.contains("[ -f '/cfg/claude-channel-switcher/env.sh' ]"));
+
+        assert!(uninstall_shell_hook(&rc, ShellKind::Bash).unwrap());
+        assert_eq!(fs::read_to_string(&rc).unwrap(), "alias ll='ls -l'\n");
+        assert!(!uninstall_shell_hook(&rc, ShellKind::Bash).unwrap());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_symlinked_rc_files_and_their_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = create_temp_dir("hook-symlink");
+        let dotfiles = dir.join("dotfiles");
+        fs::create_dir_all(&dotfiles).unwrap();
+        let real_rc = dotfiles.join("bashrc");
+        fs::write(&real_rc, "alias ll='ls -l'\n").unwrap();
+        fs::set_permissions(&real_rc, fs::Permissions::from_mode(0o640)).unwrap();
+        let rc = dir.join(".bashrc");
+        std::os::unix::fs::symlink(&real_rc, &rc).unwrap();
+
+        let hook = render_shell_hook(Path::new("/cfg"), ShellKind::Bash);
+        install_shell_hook(&rc, &hook).unwrap();
+        assert!(fs::symlink_metadata(&rc).unwrap().file_type().is_symlink());
+        assert!(fs::read_to_string(&real_rc)
+            .unwrap()
+            .contains(SHELL_HOOK_BEGIN));
+        let mode = fs::metadata(&real_rc).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+
+        assert!(uninstall_shell_hook(&rc, ShellKind::Bash).unwrap());
+        assert!(fs::symlink_metadata(&rc).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&real_rc).unwrap(), "alias ll='ls -l'\n");
+        assert_eq!(
+            fs::metadata(&real_rc).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn places_fish_hook_in_conf_d() {
+        // 其他测试可能临时设置 XDG_CONFIG_HOME，这里只检查 conf.d 下的相对位置
+        let target = shell_hook_target(Path::new("/home/me"), ShellKind::Fish);
+        assert!(target.ends_with("fish/conf.d/claude-channel-switcher.fish"));
+        let hook = render_shell_hook(Path::new("/cfg"), ShellKind::Fish);
+        assert!(hook.contains("test -f '/cfg/env.fish'; and source '/cfg/env.fish'"));
+    }
+
+    #[test]
+    fn marks_switched_channel_and_detects_it_by_key() {
+        let mut store = default_droid_store();
+        upsert_droid_channel(&mut store, channel("a", "fk-1"), "", 1).unwrap();
+        upsert_droid_channel(&mut store, channel("b", "fk-2"), "", 1).unwrap();
+        upsert_droid_channel(&mut store, channel("b-copy", "fk-2"), "", 1).unwrap();
+
+        assert_eq!(
+            mark_droid_channel_active(&mut store, "b", 100).unwrap(),
+            "fk-2"
+        );
+        assert_eq!(store.active.as_deref(), Some("b"));
+
+        // Key 相同的渠道以激活标记为准
+        let info = detect_active_droid_channel(&store, "fk-2");
+        assert_eq!(info.name.as_deref(), Some("b"));
+        assert_eq!(info.last_switched_at, Some(100));
+
+        // 外部修改了环境变量时按 Key 匹配
+        let info = detect_active_droid_channel(&store, "fk-1");
+        assert_eq!(info.name.as_deref(), Some("a"));
+        assert_eq!(info.last_switched_at, None);
+
+        // 未知 Key 不匹配任何渠道；读不到 Key 时退回激活标记
+        assert_eq!(detect_active_droid_channel(&store, "fk-x").name, None);
+        assert_eq!(
+            detect_active_droid_channel(&store, "").name.as_deref(),
+            Some("b")
+        );
+
+        assert!(mark_droid_channel_active(&mut store, "missing", 1).is_err());
+    }
+
+    #[test]
+    fn keeps_active_marker_in_sync_on_rename_and_delete() {
+        let mut store = default_droid_store();
+        upsert_droid_channel(&mut store, channel("a", "fk-1"), "", 1).unwrap();
+        mark_droid_channel_active(&mut store, "a", 5).unwrap();
+
+        upsert_droid_channel(&mut store, channel("renamed", "fk-1"), "a", 6).unwrap();
+        assert_eq!(store.active.as_deref(), Some("renamed"));
+        assert_eq!(store.channels[0].last_switched_at, Some(5));
+
+        remove_droid_channel(&mut store, "renamed").unwrap();
+        assert_eq!(store.active, None);
+        assert!(remove_droid_channel(&mut store, "renamed").is_err());
+    }
+
+    #[test]
+    fn resolves_test_endpoint_from_override_or_base_url() {
+        let mut target = channel("a", "fk-1");
+        assert_eq!(
+            resolve_droid_test_endpoint(None, &target).unwrap(),
+            "https://api.factory.ai/api/llm/o/v1/models"
+        );
+
+        target.base_url = Some("https://relay.example.com/".to_string());
+        assert_eq!(
+            resolve_droid_test_endpoint(Some("  "), &target).unwrap(),
+            "https://relay.example.com/api/llm/o/v1/models"
+        );
+        assert_eq!(
+            resolve_droid_test_endpoint(Some("http://127.0.0.1:9000/mock"), &target).unwrap(),
+            "http://127.0.0.1:9000/mock"
+        );
+        assert!(resolve_droid_test_endpoint(Some("file:///etc/passwd"), &target).is_err());
+    }
+
+    #[test]
+    fn clears_last_test_when_key_changes() {
+        let mut store = default_droid_store();
+        upsert_droid_channel(&mut store, channel("a", "fk-1"), "", 1).unwrap();
+        store.channels[0].last_test = Some(ChannelTestResult {
+            valid: true,
+            category: crate::channel::ChannelTestCategory::Ok,
+            latency_ms: 12,
+            status: Some(200),
+            message: None,
+            tested_at: 1,
+        });
+
+        let mut renamed = channel("b", "fk-1");
+        renamed.notes = "note".to_string();
+        upsert_droid_channel(&mut store, renamed, "a", 2).unwrap();
+        assert!(store.channels[0].last_test.is_some());
+
+        upsert_droid_channel(&mut store, channel("b", "fk-2"), "b", 3).unwrap();
+        assert!(store.channels[0].last_test.is_none());
+    }
+}
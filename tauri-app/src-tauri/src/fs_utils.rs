@@ -0,0 +1,504 @@
+// 通用文件读写工具：原子写入、快照与回滚、目录锁与外部修改检测
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// 锁文件放在被保护的目录中；原子写入会替换目标文件，因此不能直接锁目标文件
+const LOCK_FILE_NAME: &str = ".channel-switcher.lock";
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+// 前端据此前缀识别冲突错误并重新加载数据
+pub(crate) const CONFLICT_ERROR_PREFIX: &str = "[CONFLICT] ";
+
+fn temp_path_for(target: &Path, suffix: &str) -> Result<PathBuf, String> {
+    let parent = target
+        .parent()
+        .ok_or_else(|| format!("无法获取文件父目录: {}", target.display()))?;
+    let file_name = target
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("无法获取文件名: {}", target.display()))?;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+
+    Ok(parent
+        .join(format!(
+            ".{}.{}.{}.tmp",
+            file_name,
+            std::process::id(),
+            stamp
+        ))
+        .with_extension(suffix))
+}
+
+pub(crate) fn write_text_file(target: &Path, content: &str) -> Result<(), String> {
+    let parent = target
+        .parent()
+        .ok_or_else(|| format!("无法获取文件父目录: {}", target.display()))?;
+    fs::create_dir_all(parent)
+        .map_err(|e| format!("创建文件目录失败 {}: {}", parent.display(), e))?;
+
+    let temp_path = temp_path_for(target, "tmp")?;
+    fs::write(&temp_path, content)
+        .map_err(|e| format!("写入临时文件失败 {}: {}", temp_path.display(), e))?;
+
+    if target.exists() {
+        let backup_path = temp_path_for(target, "bak")?;
+        fs::rename(target, &backup_path).map_err(|e| {
+            let _ = fs::remove_file(&temp_path);
+            format!("备份原文件失败 {}: {}", target.display(), e)
+        })?;
+
+        if let Err(e) = fs::rename(&temp_path, target) {
+            let _ = fs::rename(&backup_path, target);
+            let _ = fs::remove_file(&temp_path);
+            return Err(format!("替换文件失败 {}: {}", target.display(), e));
+        }
+
+        let _ = fs::remove_file(&backup_path);
+    } else if let Err(e) = fs::rename(&temp_path, target) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(format!("写入文件失败 {}: {}", target.display(), e));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn snapshot_text_file(path: &Path) -> Result<Option<String>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    fs::read_to_string(path)
+        .map(Some)
+        .map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))
+}
+
+pub(crate) fn restore_text_snapshot(path: &Path, snapshot: &Option<String>) -> Result<(), String> {
+    match snapshot {
+        Some(content) => write_text_file(path, content),
+        None => {
+            if !path.exists() {
+                return Ok(());
+            }
+
+            if path.is_dir() {
+                fs::remove_dir_all(path)
+                    .map_err(|e| format!("删除目录失败 {}: {}", path.display(), e))?;
+            } else {
+                fs::remove_file(path)
+                    .map_err(|e| format!("删除文件失败 {}: {}", path.display(), e))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn write_files_with_rollback(updates: &[(&Path, &str)]) -> Result<(), String> {
+    write_files_with_rollback_using(updates, |path, content| write_text_file(path, content))
+}
+
+// 把多个文件恢复为快照内容（None 表示删除），任一失败时把已恢复的文件改回恢复前的状态
+pub(crate) fn restore_files_with_rollback(
+    snapshots: &[(&Path, Option<String>)],
+) -> Result<(), String> {
+    write_files_with_rollback_using(snapshots, restore_text_snapshot)
+}
+
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+// 依次写入所有文件，任一写入失败时把已写入的文件回滚到快照
+fn write_files_with_rollback_using<C, F>(
+    updates: &[(&Path, C)],
+    mut writer: F,
+) -> Result<(), String>
+where
+    F: FnMut(&Path, &C) -> Result<(), String>,
+{
+    let snapshots = updates
+        .iter()
+        .map(|(path, _)| snapshot_text_file(path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (index, (path, content)) in updates.iter().enumerate() {
+        let Err(write_error) = writer(path, content) else {
+            continue;
+        };
+
+        let mut message = format!("写入 {} 失败: {}", file_label(path), write_error);
+        for ((written_path, _), snapshot) in updates.iter().zip(&snapshots).take(index + 1) {
+            if let Err(e) = restore_text_snapshot(written_path, snapshot) {
+                message.push_str(&format!("；回滚 {} 失败: {}", file_label(written_path), e));
+            }
+        }
+
+        return Err(message);
+    }
+
+    Ok(())
+}
+
+// 与应用其他位置一致，优先使用 Windows 的 USERPROFILE
+pub(crate) fn home_dir() -> Result<PathBuf, String> {
+    std::env::var("USERPROFILE")
+        .or_else(|_| std::env::var("HOME"))
+        .map(PathBuf::from)
+        .map_err(|_| "Failed to get home directory".to_string())
+}
+
+// .env 文件中的 KEY=value 行，兼容 export 前缀、引号与行尾注释
+fn env_line_key(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    if line.starts_with('#') {
+        return None;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (key, _) = line.split_once('=')?;
+    Some(key.trim())
+}
+
+fn unquote_env_value(value: &str) -> String {
+    let value = value.trim();
+    if let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        return inner.replace("\\\"", "\"").replace("\\\\", "\\");
+    }
+    if let Some(inner) = value
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+    {
+        return inner.to_string();
+    }
+    // 未加引号时 # 之后为注释
+    match value.find(" #") {
+        Some(index) => value[..index].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+// 同一个键出现多次时以最后一次为准
+pub(crate) fn read_env_value(content: &str, key: &str) -> Option<String> {
+    content
+        .lines()
+        .rev()
+        .find(|line| env_line_key(line) == Some(key))
+        .and_then(|line| line.split_once('='))
+        .map(|(_, value)| unquote_env_value(value))
+}
+
+fn quote_env_value(value: &str) -> String {
+    if value
+        .chars()
+        .any(|ch| ch.is_whitespace() || matches!(ch, '#' | '"' | '\'' | '\\'))
+    {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// 原位替换或追加键，值为 None 时删除该键；保留其他变量与注释
+pub(crate) fn update_env_content(content: &str, updates: &[(&str, Option<&str>)]) -> String {
+    let mut written = vec![false; updates.len()];
+    let mut lines = Vec::new();
+
+    for line in content.lines() {
+        let Some(index) = env_line_key(line)
+            .and_then(|key| updates.iter().position(|(target, _)| *target == key))
+        else {
+            lines.push(line.to_string());
+            continue;
+        };
+
+        let (key, value) = updates[index];
+        if let (Some(value), false) = (value, written[index]) {
+            lines.push(format!("{}={}", key, quote_env_value(value)));
+        }
+        written[index] = true;
+    }
+
+    for ((key, value), written) in updates.iter().zip(written) {
+        if let (Some(value), false) = (value, written) {
+            lines.push(format!("{}={}", key, quote_env_value(value)));
+        }
+    }
+
+    let mut updated = lines.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    updated
+}
+
+pub(crate) fn conflict_error(message: &str) -> String {
+    format!("{}{}", CONFLICT_ERROR_PREFIX, message)
+}
+
+// 持有期间独占目录锁，释放文件句柄即解锁
+pub(crate) struct DirLock {
+    _file: File,
+}
+
+pub(crate) fn lock_dir(dir: &Path) -> Result<DirLock, String> {
+    lock_dir_with_timeout(dir, LOCK_TIMEOUT)
+}
+
+fn lock_dir_with_timeout(dir: &Path, timeout: Duration) -> Result<DirLock, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("创建目录失败 {}: {}", dir.display(), e))?;
+    let lock_path = dir.join(LOCK_FILE_NAME);
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("打开锁文件失败 {}: {}", lock_path.display(), e))?;
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(DirLock { _file: file }),
+            Err(TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(format!(
+                    "{} 正被其他窗口或进程修改，请稍后重试",
+                    dir.display()
+                ));
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(format!("锁定目录失败 {}: {}", dir.display(), e));
+            }
+        }
+    }
+}
+
+// 在目录锁内执行一次读-改-写序列
+pub(crate) fn with_dir_lock<T, F>(dir: &Path, action: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String>,
+{
+    let _lock = lock_dir(dir)?;
+    action()
+}
+
+// 调用方已在外层持有该目录锁时直接执行：同一进程再次加锁会一直等到超时
+pub(crate) fn with_dir_lock_unless_held<T, F>(
+    dir: &Path,
+    held: bool,
+    action: F,
+) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String>,
+{
+    if held {
+        action()
+    } else {
+        with_dir_lock(dir, action)
+    }
+}
+
+// 等待目录锁会阻塞线程，异步命令中的加锁操作放到阻塞线程池执行，不占用运行时的工作线程
+pub(crate) async fn run_blocking<T, F>(action: F) -> Result<T, String>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(action)
+        .await
+        .map_err(|e| format!("后台任务执行失败: {}", e))?
+}
+
+// 文件读取时的状态；修改时间精度不足时由长度与内容哈希兜底
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+fn stamp_for(modified: Option<SystemTime>, content: &[u8]) -> FileStamp {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    FileStamp {
+        modified,
+        len: content.len() as u64,
+        hash: hasher.finish(),
+    }
+}
+
+// 读取文件内容并记录状态，文件不存在时两者均为 None
+pub(crate) fn read_stamped_text(
+    path: &Path,
+) -> Result<(Option<String>, Option<FileStamp>), String> {
+    if !path.exists() {
+        return Ok((None, None));
+    }
+
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))?;
+    let stamp = stamp_for(modified, content.as_bytes());
+    Ok((Some(content), Some(stamp)))
+}
+
+pub(crate) fn file_stamp(path: &Path) -> Result<Option<FileStamp>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let content = fs::read(path).map_err(|e| format!("读取文件失败 {}: {}", path.display(), e))?;
+    Ok(Some(stamp_for(modified, &content)))
+}
+
+// 写回前确认文件自读取后未被外部修改，否则返回冲突错误而不是覆盖
+pub(crate) fn ensure_unchanged(path: &Path, expected: &Option<FileStamp>) -> Result<(), String> {
+    if file_stamp(path)? != *expected {
+        return Err(conflict_error(&format!(
+            "{} 已被其他程序修改，请刷新后重试",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-fs-utils-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    #[test]
+    fn serializes_holders_of_the_same_dir_lock() {
+        let dir = create_temp_dir("lock");
+
+        let held = lock_dir(&dir).unwrap();
+        let busy = lock_dir_with_timeout(&dir, Duration::from_millis(50));
+        assert!(busy.err().unwrap().contains("请稍后重试"));
+
+        drop(held);
+        assert!(lock_dir_with_timeout(&dir, Duration::from_millis(50)).is_ok());
+    }
+
+    #[test]
+    fn waits_for_the_lock_to_be_released() {
+        let dir = create_temp_dir("lock-wait");
+        let held = lock_dir(&dir).unwrap();
+
+        let waiter_dir = dir.clone();
+        let waiter = thread::spawn(move || with_dir_lock(&waiter_dir, || Ok(Instant::now())));
+        thread::sleep(Duration::from_millis(100));
+        let released_at = Instant::now();
+        drop(held);
+
+        assert!(waiter.join().unwrap().unwrap() >= released_at);
+    }
+
+    #[test]
+    fn detects_external_modification_since_read() {
+        let dir = create_temp_dir("stamp");
+        let path = dir.join("settings.json");
+        fs::write(&path, "{}").unwrap();
+
+        let (content, stamp) = read_stamped_text(&path).unwrap();
+        assert_eq!(content.as_deref(), Some("{}"));
+        assert!(ensure_unchanged(&path, &stamp).is_ok());
+
+        fs::write(&path, "{\"a\":1}").unwrap();
+        let error = ensure_unchanged(&path, &stamp).unwrap_err();
+        assert!(error.starts_with(CONFLICT_ERROR_PREFIX));
+
+        let missing = dir.join("missing.json");
+        let (_, missing_stamp) = read_stamped_text(&missing).unwrap();
+        assert!(ensure_unchanged(&missing, &missing_stamp).is_ok());
+        fs::write(&missing, "{}").unwrap();
+        assert!(ensure_unchanged(&missing, &missing_stamp).is_err());
+    }
+
+    #[test]
+    fn rolls_back_config_when_auth_write_fails() {
+        let dir = create_temp_dir("rollback");
+        let config_path = dir.join("config.toml");
+        let auth_path = dir.join("auth.json");
+        let old_config = "model = \"old\"\n";
+        let old_auth = "{\n  \"OPENAI_API_KEY\": \"old\"\n}";
+
+        fs::write(&config_path, old_config).unwrap();
+        fs::write(&auth_path, old_auth).unwrap();
+
+        let result = write_files_with_rollback_using(
+            &[
+                (config_path.as_path(), "model = \"new\"\n"),
+                (auth_path.as_path(), "{\n  \"OPENAI_API_KEY\": \"new\"\n}"),
+            ],
+            |path, content| {
+                if path == auth_path {
+                    return Err("forced auth write failure".to_string());
+                }
+                fs::write(path, content).map_err(|e| e.to_string())
+            },
+        );
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("forced auth write failure"));
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), old_config);
+        assert_eq!(fs::read_to_string(&auth_path).unwrap(), old_auth);
+    }
+
+    #[test]
+    fn restores_files_all_or_nothing() {
+        let dir = create_temp_dir("restore-rollback");
+        let kept = dir.join("kept.json");
+        let created = dir.join("created.json");
+        let blocker = dir.join("blocker");
+        fs::write(&kept, "switched").unwrap();
+        fs::write(&created, "switched").unwrap();
+        fs::write(&blocker, "").unwrap();
+
+        // 父路径是普通文件，最后一项必然失败，前面已恢复的文件应被改回
+        let result = restore_files_with_rollback(&[
+            (kept.as_path(), Some("original".to_string())),
+            (created.as_path(), None),
+            (blocker.join("child.json").as_path(), Some("x".to_string())),
+        ]);
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "switched");
+        assert_eq!(fs::read_to_string(&created).unwrap(), "switched");
+
+        restore_files_with_rollback(&[
+            (kept.as_path(), Some("original".to_string())),
+            (created.as_path(), None),
+        ])
+        .unwrap();
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "original");
+        assert!(!created.exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,776 @@
+// Gemini CLI 渠道管理：API Key 与 Base URL 写入 ~/.gemini/.env，模型与认证方式写入 settings.json
+use crate::channel::{
+    data_response, normalize_base_url, Channel, ChannelBackend, ChannelTestTarget, SwitchSnapshot,
+    TestAuth, ToolKind,
+};
+use crate::fs_utils::{
+    conflict_error, ensure_unchanged, read_env_value, read_stamped_text, run_blocking,
+    snapshot_text_file, update_env_content, with_dir_lock, write_files_with_rollback,
+    write_text_file,
+};
+use crate::terminal::open_terminal;
+use crate::ApiResponse;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const GEMINI_STORE_VERSION: u32 = 1;
+pub(crate) const GEMINI_STORE_FILE_NAME: &str = "channels.json";
+pub(crate) const GEMINI_ENV_FILE_NAME: &str = ".env";
+pub(crate) const GEMINI_SETTINGS_FILE_NAME: &str = "settings.json";
+const GEMINI_NAME_MAX_LEN: usize = 32;
+
+const ENV_API_KEY: &str = "GEMINI_API_KEY";
+const ENV_BASE_URL: &str = "GOOGLE_GEMINI_BASE_URL";
+// 使用 API Key 认证，否则 Gemini CLI 启动时仍会要求 Google 登录
+const GEMINI_API_KEY_AUTH_TYPE: &str = "gemini-api-key";
+const GEMINI_DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+const GEMINI_TEST_PATH: &str = "/v1beta/models";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct GeminiChannel {
+    #[serde(default)]
+    id: String,
+    name: String,
+    // 为空时使用官方地址，切换时从 .env 中移除 GOOGLE_GEMINI_BASE_URL
+    #[serde(default)]
+    base_url: String,
+    api_key: String,
+    // 为空时保留 settings.json 中原有的模型
+    #[serde(default)]
+    model: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct GeminiChannelStore {
+    #[serde(default = "gemini_store_version")]
+    version: u32,
+    #[serde(default)]
+    revision: u64,
+    #[serde(default)]
+    channels: Vec<GeminiChannel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct GeminiChannelList {
+    revision: u64,
+    channels: Vec<GeminiChannel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct GeminiStoreChange {
+    revision: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub(crate) struct GeminiActiveInfo {
+    api_key: String,
+    base_url: String,
+    model: String,
+    auth_type: String,
+    active_channel: Option<String>,
+}
+
+fn gemini_store_version() -> u32 {
+    GEMINI_STORE_VERSION
+}
+
+fn default_gemini_store() -> GeminiChannelStore {
+    GeminiChannelStore {
+        version: GEMINI_STORE_VERSION,
+        revision: 0,
+        channels: vec![],
+    }
+}
+
+fn gemini_store_path(dir: &Path) -> PathBuf {
+    dir.join(GEMINI_STORE_FILE_NAME)
+}
+
+fn is_valid_gemini_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= GEMINI_NAME_MAX_LEN
+        && !name.chars().any(char::is_control)
+}
+
+fn normalize_gemini_channel(channel: GeminiChannel) -> Result<GeminiChannel, String> {
+    let base_url = match channel.base_url.trim() {
+        "" => String::new(),
+        url => normalize_base_url(url)?,
+    };
+
+    Ok(GeminiChannel {
+        name: channel.name.trim().to_string(),
+        api_key: channel.api_key.trim().to_string(),
+        model: channel.model.trim().to_string(),
+        base_url,
+        ..channel
+    })
+}
+
+fn validate_gemini_channel(
+    channel: &GeminiChannel,
+    existing: &[GeminiChannel],
+    editing_id: Option<&str>,
+) -> Result<(), String> {
+    if !is_valid_gemini_name(&channel.name) {
+        return Err(format!(
+            "渠道名称不能为空，最多{}个字符",
+            GEMINI_NAME_MAX_LEN
+        ));
+    }
+    if channel.api_key.is_empty() {
+        return Err("请输入 API Key".to_string());
+    }
+    if channel.api_key.contains(['\n', '\r']) {
+        return Err("API Key 不能包含换行".to_string());
+    }
+
+    let duplicate_exists = existing
+        .iter()
+        .any(|current| Some(current.id.as_str()) != editing_id && current.name == channel.name);
+    if duplicate_exists {
+        return Err("渠道名称已存在".to_string());
+    }
+
+    Ok(())
+}
+
+fn new_gemini_channel_id(existing: &[GeminiChannel]) -> String {
+    let mut seed = chrono::Utc::now()
+        .timestamp_nanos_opt()
+        .unwrap_or_default()
+        .unsigned_abs();
+    loop {
+        let id = format!("gm{:x}", seed);
+        if !existing.iter().any(|channel| channel.id == id) {
+            return id;
+        }
+        seed += 1;
+    }
+}
+
+fn is_gemini_store_read_only(store: &GeminiChannelStore) -> bool {
+    store.version > GEMINI_STORE_VERSION
+}
+
+fn load_gemini_store(dir: &Path) -> Result<GeminiChannelStore, String> {
+    let store_path = gemini_store_path(dir);
+    if !store_path.exists() {
+        return Ok(default_gemini_store());
+    }
+
+    let content =
+        fs::read_to_string(&store_path).map_err(|e| format!("读取 Gemini 存储文件失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析 Gemini 存储文件失败: {}", e))
+}
+
+fn save_gemini_store(dir: &Path, store: &GeminiChannelStore) -> Result<(), String> {
+    if is_gemini_store_read_only(store) {
+        return Err(format!(
+            "Gemini 存储版本 v{} 高于当前支持的 v{}，请升级应用后再修改渠道",
+            store.version, GEMINI_STORE_VERSION
+        ));
+    }
+
+    let serialized = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("序列化 Gemini 存储失败: {}", e))?;
+    write_text_file(&gemini_store_path(dir), &serialized)
+}
+
+// 在目录锁内校验修订号后修改并保存存储，返回新的修订号与受影响的渠道
+fn mutate_gemini_store<F>(
+    dir: &Path,
+    expected_revision: u64,
+    mutate: F,
+) -> Result<GeminiStoreChange, String>
+where
+    F: FnOnce(&mut GeminiChannelStore) -> Result<Option<String>, String>,
+{
+    with_dir_lock(dir, || {
+        let mut store = load_gemini_store(dir)?;
+        if store.revision != expected_revision {
+            return Err(conflict_error(&format!(
+                "渠道列表已被其他操作修改（当前修订号 {}，期望 {}），请刷新后重试",
+                store.revision, expected_revision
+            )));
+        }
+
+        let channel_id = mutate(&mut store)?;
+        store.revision += 1;
+        save_gemini_store(dir, &store)?;
+
+        Ok(GeminiStoreChange {
+            revision: store.revision,
+            channel_id,
+        })
+    })
+}
+
+fn load_gemini_store_locked(dir: &Path) -> Result<GeminiChannelStore, String> {
+    with_dir_lock(dir, || load_gemini_store(dir))
+}
+
+fn gemini_channel_position(channels: &[GeminiChannel], id: &str) -> Result<usize, String> {
+    channels
+        .iter()
+        .position(|channel| channel.id == id)
+        .ok_or_else(|| "未找到指定的 Gemini 渠道".to_string())
+}
+
+// id 为空时在顶部新建，否则按 id 原位更新；返回渠道 id
+fn upsert_gemini_channel(
+    store: &mut GeminiChannelStore,
+    channel: GeminiChannel,
+) -> Result<String, String> {
+    let mut channel = normalize_gemini_channel(channel)?;
+    let editing = if channel.id.is_empty() {
+        None
+    } else {
+        Some(gemini_channel_position(&store.channels, &channel.id)?)
+    };
+    validate_gemini_channel(
+        &channel,
+        &store.channels,
+        editing.map(|_| channel.id.as_str()),
+    )?;
+
+    match editing {
+        Some(index) => store.channels[index] = channel.clone(),
+        None => {
+            channel.id = new_gemini_channel_id(&store.channels);
+            store.channels.insert(0, channel.clone());
+        }
+    }
+    Ok(channel.id)
+}
+
+// ==================== .env 与 settings.json ====================
+
+// 兼容旧版本的 "model": "<name>" 与新版本的 "model": { "name": "<name>" }
+fn read_settings_model(settings: &serde_json::Value) -> String {
+    let model = settings.get("model");
+    model
+        .and_then(|value| value.as_str())
+        .or_else(|| {
+            model
+                .and_then(|value| value.get("name"))
+                .and_then(|v| v.as_str())
+        })
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn read_settings_auth_type(settings: &serde_json::Value) -> String {
+    settings
+        .pointer("/security/auth/selectedType")
+        .or_else(|| settings.get("selectedAuthType"))
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn object_entry<'a>(
+    object: &'a mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> &'a mut serde_json::Map<String, serde_json::Value> {
+    let entry = object
+        .entry(key.to_string())
+        .or_insert_with(|| serde_json::json!({}));
+    if !entry.is_object() {
+        *entry = serde_json::json!({});
+    }
+    entry.as_object_mut().unwrap()
+}
+
+fn update_settings_json(existing: Option<&str>, channel: &GeminiChannel) -> Result<String, String> {
+    let mut settings = match existing
+        .map(str::trim)
+        .filter(|content| !content.is_empty())
+    {
+        Some(content) => serde_json::from_str::<serde_json::Value>(content)
+            .map_err(|e| format!("解析 Gemini settings.json 失败: {}", e))?,
+        None => serde_json::json!({}),
+    };
+    let root = settings
+        .as_object_mut()
+        .ok_or_else(|| "Gemini settings.json 格式无效".to_string())?;
+
+    if root.contains_key("selectedAuthType") {
+        root.insert(
+            "selectedAuthType".to_string(),
+            serde_json::json!(GEMINI_API_KEY_AUTH_TYPE),
+        );
+    } else {
+        object_entry(object_entry(root, "security"), "auth").insert(
+            "selectedType".to_string(),
+            serde_json::json!(GEMINI_API_KEY_AUTH_TYPE),
+        );
+    }
+
+    if !channel.model.is_empty() {
+        if root.get("model").is_some_and(|model| model.is_string()) {
+            root.insert("model".to_string(), serde_json::json!(channel.model));
+        } else {
+            object_entry(root, "model")
+                .insert("name".to_string(), serde_json::json!(channel.model));
+        }
+    }
+
+    serde_json::to_string_pretty(&settings).map_err(|e| format!("序列化 settings.json 失败: {}", e))
+}
+
+fn read_active_info(env_content: &str, settings_content: Option<&str>) -> GeminiActiveInfo {
+    let settings = settings_content
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(content).ok())
+        .unwrap_or_default();
+
+    GeminiActiveInfo {
+        api_key: read_env_value(env_content, ENV_API_KEY).unwrap_or_default(),
+        base_url: read_env_value(env_content, ENV_BASE_URL).unwrap_or_default(),
+        model: read_settings_model(&settings),
+        auth_type: read_settings_auth_type(&settings),
+        active_channel: None,
+    }
+}
+
+// 以 .env 中的 Key 和 Base URL 匹配渠道，地址比较前先规范化
+fn find_active_gemini_channel<'a>(
+    channels: &'a [GeminiChannel],
+    info: &GeminiActiveInfo,
+) -> Option<&'a GeminiChannel> {
+    if info.api_key.is_empty() {
+        return None;
+    }
+    let base_url = match info.base_url.trim() {
+        "" => String::new(),
+        url => normalize_base_url(url).unwrap_or_else(|_| url.to_string()),
+    };
+    channels
+        .iter()
+        .find(|channel| channel.api_key == info.api_key && channel.base_url == base_url)
+}
+
+fn read_current_gemini_env(dir: &Path) -> GeminiActiveInfo {
+    let env_content = snapshot_text_file(&dir.join(GEMINI_ENV_FILE_NAME))
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let settings_content = snapshot_text_file(&dir.join(GEMINI_SETTINGS_FILE_NAME))
+        .ok()
+        .flatten();
+
+    let mut info = read_active_info(&env_content, settings_content.as_deref());
+    if let Ok(store) = load_gemini_store_locked(dir) {
+        info.active_channel =
+            find_active_gemini_channel(&store.channels, &info).map(|channel| channel.id.clone());
+    }
+    info
+}
+
+// 在目录锁内完成切换；两个文件都可能被 Gemini CLI 或用户改写，写回前确认读取后未被修改
+fn apply_gemini_switch(dir: &Path, channel_id: &str) -> Result<(), String> {
+    let store = load_gemini_store(dir)?;
+    let position = gemini_channel_position(&store.channels, channel_id)?;
+    let channel = &store.channels[position];
+
+    let env_path = dir.join(GEMINI_ENV_FILE_NAME);
+    let settings_path = dir.join(GEMINI_SETTINGS_FILE_NAME);
+    let (existing_env, env_stamp) = read_stamped_text(&env_path)?;
+    let (existing_settings, settings_stamp) = read_stamped_text(&settings_path)?;
+
+    let base_url = Some(channel.base_url.as_str()).filter(|url| !url.is_empty());
+    let updated_env = update_env_content(
+        existing_env.as_deref().unwrap_or_default(),
+        &[
+            (ENV_API_KEY, Some(channel.api_key.as_str())),
+            (ENV_BASE_URL, base_url),
+        ],
+    );
+    let updated_settings = update_settings_json(existing_settings.as_deref(), channel)?;
+
+    ensure_unchanged(&env_path, &env_stamp)?;
+    ensure_unchanged(&settings_path, &settings_stamp)?;
+    write_files_with_rollback(&[
+        (env_path.as_path(), updated_env.as_str()),
+        (settings_path.as_path(), updated_settings.as_str()),
+    ])
+}
+
+fn store_change_response(
+    result: Result<GeminiStoreChange, String>,
+) -> ApiResponse<GeminiStoreChange> {
+    match result {
+        Ok(change) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(change),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+// ==================== Gemini 渠道管理 ====================
+
+#[tauri::command]
+pub(crate) async fn get_gemini_channels(
+    gemini_config_path: String,
+) -> ApiResponse<GeminiChannelList> {
+    match run_blocking(move || load_gemini_store_locked(Path::new(&gemini_config_path))).await {
+        Ok(store) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(GeminiChannelList {
+                revision: store.revision,
+                channels: store.channels,
+            }),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn save_gemini_channel(
+    gemini_config_path: String,
+    channel: GeminiChannel,
+    expected_revision: u64,
+) -> ApiResponse<GeminiStoreChange> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&gemini_config_path);
+        mutate_gemini_store(dir, expected_revision, |store| {
+            upsert_gemini_channel(store, channel).map(Some)
+        })
+    })
+    .await;
+    store_change_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn delete_gemini_channel(
+    gemini_config_path: String,
+    channel_id: String,
+    expected_revision: u64,
+) -> ApiResponse<GeminiStoreChange> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&gemini_config_path);
+        mutate_gemini_store(dir, expected_revision, |store| {
+            let position = gemini_channel_position(&store.channels, &channel_id)?;
+            store.channels.remove(position);
+            Ok(None)
+        })
+    })
+    .await;
+    store_change_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn switch_gemini_channel(
+    gemini_config_path: String,
+    channel_id: String,
+) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        let dir = Path::new(&gemini_config_path);
+        with_dir_lock(dir, || apply_gemini_switch(dir, &channel_id))
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_current_gemini_env(
+    gemini_config_path: String,
+) -> ApiResponse<GeminiActiveInfo> {
+    // 读取渠道存储需要目录锁，放到阻塞线程池中执行，避免卡住界面
+    data_response(
+        run_blocking(move || Ok(read_current_gemini_env(Path::new(&gemini_config_path)))).await,
+    )
+}
+
+#[tauri::command(rename_all = "camelCase")]
+pub(crate) async fn launch_gemini(terminal_dir: String) -> ApiResponse<()> {
+    open_terminal("gemini", &terminal_dir)
+}
+
+// ==================== 统一渠道接口 ====================
+
+pub(crate) struct GeminiBackend {
+    dir: PathBuf,
+}
+
+impl GeminiBackend {
+    pub(crate) fn new(dir: &Path) -> Self {
+        GeminiBackend {
+            dir: dir.to_path_buf(),
+        }
+    }
+}
+
+impl ChannelBackend for GeminiBackend {
+    fn tool(&self) -> ToolKind {
+        ToolKind::Gemini
+    }
+
+    fn list(&self) -> Result<Vec<Channel>, String> {
+        let store = load_gemini_store_locked(&self.dir)?;
+        Ok(store
+            .channels
+            .into_iter()
+            .map(|channel| Channel {
+                id: channel.id,
+                name: channel.name,
+                base_url: channel.base_url,
+                api_key: channel.api_key,
+                model: channel.model,
+            })
+            .collect())
+    }
+
+    fn save(&self, channel: Channel) -> Result<String, String> {
+        let channel = GeminiChannel {
+            id: channel.id,
+            name: channel.name,
+            base_url: channel.base_url,
+            api_key: channel.api_key,
+            model: channel.model,
+        };
+        with_dir_lock(&self.dir, || {
+            let mut store = load_gemini_store(&self.dir)?;
+            let id = upsert_gemini_channel(&mut store, channel)?;
+            store.revision += 1;
+            save_gemini_store(&self.dir, &store)?;
+            Ok(id)
+        })
+    }
+
+    fn delete(&self, id: &str) -> Result<(), String> {
+        with_dir_lock(&self.dir, || {
+            let mut store = load_gemini_store(&self.dir)?;
+            let position = gemini_channel_position(&store.channels, id)?;
+            store.channels.remove(position);
+            store.revision += 1;
+            save_gemini_store(&self.dir, &store)
+        })
+    }
+
+    fn switch(&self, id: &str) -> Result<(), String> {
+        with_dir_lock(&self.dir, || apply_gemini_switch(&self.dir, id))
+    }
+
+    fn current(&self) -> Result<Option<String>, String> {
+        Ok(read_current_gemini_env(&self.dir).active_channel)
+    }
+
+    fn test_target(&self, channel: &Channel) -> Result<ChannelTestTarget, String> {
+        let base_url = match channel.base_url.trim() {
+            "" => GEMINI_DEFAULT_BASE_URL,
+            url => url,
+        };
+        Ok(ChannelTestTarget {
+            endpoint: format!("{}{}", base_url.trim_end_matches('/'), GEMINI_TEST_PATH),
+            api_key: channel.api_key.clone(),
+            auth: TestAuth::Google,
+        })
+    }
+
+    fn switch_snapshot(&self) -> Result<SwitchSnapshot, String> {
+        SwitchSnapshot::of_files(vec![
+            self.dir.join(GEMINI_ENV_FILE_NAME),
+            self.dir.join(GEMINI_SETTINGS_FILE_NAME),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-gemini-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn gemini_channel(name: &str, base_url: &str, api_key: &str, model: &str) -> GeminiChannel {
+        GeminiChannel {
+            id: String::new(),
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    fn save(dir: &Path, channel: GeminiChannel) -> String {
+        let revision = load_gemini_store(dir).unwrap().revision;
+        mutate_gemini_store(dir, revision, |store| {
+            upsert_gemini_channel(store, channel).map(Some)
+        })
+        .unwrap()
+        .channel_id
+        .unwrap()
+    }
+
+    #[test]
+    fn updates_env_keys_in_place_and_keeps_other_lines() {
+        let existing =
+            "# relay\nexport GEMINI_API_KEY=old\nOTHER=1\nGOOGLE_GEMINI_BASE_URL=https://old\n";
+        let updated = update_env_content(
+            existing,
+            &[(ENV_API_KEY, Some("new key")), (ENV_BASE_URL, None)],
+        );
+
+        assert_eq!(updated, "# relay\nGEMINI_API_KEY=\"new key\"\nOTHER=1\n");
+        assert_eq!(
+            read_env_value(&updated, ENV_API_KEY).as_deref(),
+            Some("new key")
+        );
+
+        let appended = update_env_content("", &[(ENV_BASE_URL, Some("https://relay"))]);
+        assert_eq!(appended, "GOOGLE_GEMINI_BASE_URL=https://relay\n");
+    }
+
+    #[test]
+    fn reads_env_values_with_quotes_and_comments() {
+        let content = "GEMINI_API_KEY='abc' \nGOOGLE_GEMINI_BASE_URL=https://relay #note\n#GEMINI_API_KEY=ignored\n";
+        assert_eq!(read_env_value(content, ENV_API_KEY).as_deref(), Some("abc"));
+        assert_eq!(
+            read_env_value(content, ENV_BASE_URL).as_deref(),
+            Some("https://relay")
+        );
+    }
+
+    #[test]
+    fn updates_settings_model_and_auth_type_in_both_layouts() {
+        let channel = gemini_channel("relay", "", "key", "gemini-2.5-pro");
+
+        let nested = update_settings_json(
+            Some(r#"{"theme":"Dracula","model":{"name":"old","maxSessionTurns":5}}"#),
+            &channel,
+        )
+        .unwrap();
+        let nested: serde_json::Value = serde_json::from_str(&nested).unwrap();
+        assert_eq!(nested["theme"], "Dracula");
+        assert_eq!(nested["model"]["name"], "gemini-2.5-pro");
+        assert_eq!(nested["model"]["maxSessionTurns"], 5);
+        assert_eq!(nested["security"]["auth"]["selectedType"], "gemini-api-key");
+
+        let legacy = update_settings_json(
+            Some(r#"{"model":"old","selectedAuthType":"oauth-personal"}"#),
+            &channel,
+        )
+        .unwrap();
+        let legacy: serde_json::Value = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(legacy["model"], "gemini-2.5-pro");
+        assert_eq!(legacy["selectedAuthType"], "gemini-api-key");
+        assert!(legacy.get("security").is_none());
+    }
+
+    #[test]
+    fn switches_channel_and_detects_it_as_active() {
+        let dir = create_temp_dir("switch");
+        fs::write(dir.join(GEMINI_ENV_FILE_NAME), "OTHER=1\n").unwrap();
+        let relay = save(
+            &dir,
+            gemini_channel(
+                "relay",
+                "https://relay.example.com/",
+                "k1",
+                "gemini-2.5-pro",
+            ),
+        );
+        let official = save(&dir, gemini_channel("official", "", "k2", ""));
+
+        with_dir_lock(&dir, || apply_gemini_switch(&dir, &relay)).unwrap();
+        let env = fs::read_to_string(dir.join(GEMINI_ENV_FILE_NAME)).unwrap();
+        assert_eq!(
+            env,
+            "OTHER=1\nGEMINI_API_KEY=k1\nGOOGLE_GEMINI_BASE_URL=https://relay.example.com\n"
+        );
+        let info = read_current_gemini_env(&dir);
+        assert_eq!(info.active_channel.as_deref(), Some(relay.as_str()));
+        assert_eq!(info.model, "gemini-2.5-pro");
+        assert_eq!(info.auth_type, GEMINI_API_KEY_AUTH_TYPE);
+
+        // 官方渠道移除 Base URL，模型沿用上一次的设置
+        with_dir_lock(&dir, || apply_gemini_switch(&dir, &official)).unwrap();
+        let info = read_current_gemini_env(&dir);
+        assert_eq!(info.active_channel.as_deref(), Some(official.as_str()));
+        assert_eq!(info.base_url, "");
+        assert_eq!(info.model, "gemini-2.5-pro");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn leaves_files_untouched_when_settings_json_is_invalid() {
+        let dir = create_temp_dir("invalid-settings");
+        fs::write(dir.join(GEMINI_ENV_FILE_NAME), "GEMINI_API_KEY=old\n").unwrap();
+        fs::write(dir.join(GEMINI_SETTINGS_FILE_NAME), "{ broken").unwrap();
+        let id = save(&dir, gemini_channel("relay", "", "new", ""));
+
+        let err = with_dir_lock(&dir, || apply_gemini_switch(&dir, &id)).unwrap_err();
+        assert!(err.contains("settings.json"));
+        assert_eq!(
+            fs::read_to_string(dir.join(GEMINI_ENV_FILE_NAME)).unwrap(),
+            "GEMINI_API_KEY=old\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_duplicate_names_and_stale_revisions() {
+        let dir = create_temp_dir("store");
+        let id = save(&dir, gemini_channel("relay", "", "k1", ""));
+
+        let err = mutate_gemini_store(&dir, 1, |store| {
+            upsert_gemini_channel(store, gemini_channel("relay", "", "k2", "")).map(Some)
+        })
+        .unwrap_err();
+        assert_eq!(err, "渠道名称已存在");
+
+        let err = mutate_gemini_store(&dir, 0, |store| {
+            store.channels.clear();
+            Ok(None)
+        })
+        .unwrap_err();
+        assert!(err.starts_with(crate::fs_utils::CONFLICT_ERROR_PREFIX));
+
+        // 编辑时保留 id，允许使用自身的名称
+        let mut edited = gemini_channel("relay", "", "k3", "");
+        edited.id = id.clone();
+        assert_eq!(save(&dir, edited), id);
+        assert_eq!(load_gemini_store(&dir).unwrap().channels[0].api_key, "k3");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
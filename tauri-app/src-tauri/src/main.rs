@@ -1,13 +1,30 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod adapter;
+mod channel;
 mod codex;
-
+mod droid;
+mod fs_utils;
+mod gemini;
+mod project;
+mod provider;
+mod statusline;
+mod terminal;
+mod watcher;
+
+use channel::{Channel, ChannelBackend, ChannelTestTarget, SwitchSnapshot, TestAuth, ToolKind};
+use fs_utils::{
+    conflict_error, ensure_unchanged, read_stamped_text, run_blocking, with_dir_lock,
+    with_dir_lock_unless_held, write_text_file,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use terminal::{open_terminal, LaunchSpec};
+
+const CLAUDE_DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct BalanceApi {
@@ -125,6 +142,7 @@ async fn save_channel(
     balance_url: String,
     balance_method: String,
     balance_field: String,
+    expected_ctime: Option<i64>,
 ) -> ApiResponse<()> {
     let mut env = HashMap::new();
     env.insert("ANTHROPIC_AUTH_TOKEN".to_string(), token);
@@ -166,203 +184,188 @@ async fn save_channel(
         ctime: None,
     };
 
-    if !old_name.is_empty() && old_name != channel_name {
-        let old_file_path = Path::new(&config_path).join(format!("settings-{}.json", old_name));
-        let _ = fs::remove_file(old_file_path);
-    }
-
-    let file_path = Path::new(&config_path).join(format!("settings-{}.json", channel_name));
+    let dir = PathBuf::from(config_path);
+    let result = run_blocking(move || {
+        with_dir_lock(&dir, || {
+            write_channel_file(&dir, &channel_name, &old_name, expected_ctime, &config)
+        })
+    })
+    .await;
 
-    match serde_json::to_string_pretty(&config) {
-        Ok(json_content) => match fs::write(&file_path, json_content) {
-            Ok(_) => ApiResponse::success(),
-            Err(e) => ApiResponse::error(e.to_string()),
-        },
-        Err(e) => ApiResponse::error(e.to_string()),
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
     }
 }
 
-#[tauri::command]
-async fn delete_channel(config_path: String, channel_name: String) -> ApiResponse<()> {
-    let source_path = Path::new(&config_path).join(format!("settings-{}.json", channel_name));
-    let target_path = Path::new(&config_path).join(format!("settings-{}.json.del", channel_name));
-
-    match fs::rename(&source_path, &target_path) {
-        Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse::error(e.to_string()),
+// 渠道名即文件名 settings-{name}.json，不能包含路径分隔符、.. 以及 Windows 文件名中的非法字符
+fn validate_claude_channel_name(name: &str) -> Result<(), String> {
+    let invalid = name.is_empty()
+        || name.contains("..")
+        || name.chars().any(|ch| {
+            ch.is_control() || matches!(ch, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        });
+    if invalid {
+        return Err(format!(
+            "渠道名称「{}」无效：不能为空，且不能包含 .. 或 / \\ : * ? \" < > | 等字符",
+            name
+        ));
     }
+    Ok(())
 }
 
-#[tauri::command]
-async fn switch_channel(config_path: String, channel_name: String) -> ApiResponse<()> {
-    let source_path = Path::new(&config_path).join(format!("settings-{}.json", channel_name));
-    let target_path = Path::new(&config_path).join("settings.json");
-
-    // 读取源渠道配置
-    let source_content = match fs::read_to_string(&source_path) {
-        Ok(content) => content,
-        Err(e) => return ApiResponse::error(e.to_string()),
-    };
-
-    let source_json: serde_json::Value = match serde_json::from_str(&source_content) {
-        Ok(v) => v,
-        Err(e) => return ApiResponse::error(e.to_string()),
-    };
+// 编辑时确认渠道文件自加载后未被修改；新建或改名时不覆盖其他窗口刚创建的同名渠道
+fn write_channel_file(
+    dir: &Path,
+    channel_name: &str,
+    old_name: &str,
+    expected_ctime: Option<i64>,
+    config: &ChannelConfig,
+) -> Result<(), String> {
+    validate_claude_channel_name(channel_name)?;
+    if !old_name.is_empty() {
+        validate_claude_channel_name(old_name)?;
+    }
+    let file_path = dir.join(format!("settings-{}.json", channel_name));
 
-    // 读取目标 settings.json（如果存在）
-    let mut target_json: serde_json::Value = if target_path.exists() {
-        match fs::read_to_string(&target_path) {
-            Ok(content) => match serde_json::from_str(&content) {
-                Ok(v) => v,
-                Err(_) => serde_json::json!({}),
-            },
-            Err(_) => serde_json::json!({}),
+    if !old_name.is_empty() {
+        let old_file_path = dir.join(format!("settings-{}.json", old_name));
+        if !old_file_path.exists() {
+            return Err(conflict_error(&format!(
+                "渠道 {} 已被删除，请刷新后重试",
+                old_name
+            )));
         }
-    } else {
-        serde_json::json!({})
-    };
-
-    // 只覆写 env 和 balanceApi 字段，保留 settings.json 中的其他配置
-    if let Some(target_obj) = target_json.as_object_mut() {
-        // 检查 env 是否存在且不为空
-        let env = source_json.get("env");
-        let env_is_valid = env
-            .and_then(|e| e.as_object())
-            .map(|obj| !obj.is_empty())
-            .unwrap_or(false);
-
-        if !env_is_valid {
-            return ApiResponse::error("渠道配置异常：env 为空，无法切换".to_string());
+        if expected_ctime.is_some() && file_ctime(&old_file_path) != expected_ctime {
+            return Err(conflict_error(&format!(
+                "渠道 {} 已被其他程序修改，请刷新后重试",
+                old_name
+            )));
         }
+    }
 
-        // 覆写 env
-        target_obj.insert("env".to_string(), env.unwrap().clone());
+    if old_name != channel_name && file_path.exists() {
+        return Err(conflict_error(&format!(
+            "渠道 {} 已存在，请刷新后重试",
+            channel_name
+        )));
+    }
 
-        // 覆写 balanceApi（如果源文件有则覆写，没有则移除）
-        if let Some(balance_api) = source_json.get("balanceApi") {
-            target_obj.insert("balanceApi".to_string(), balance_api.clone());
-        } else {
-            target_obj.remove("balanceApi");
-        }
+    let json_content = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+    write_text_file(&file_path, &json_content)?;
 
-        // 同步 model（如果源文件有则覆写，没有则保留 settings.json 中原有的 model）
-        if let Some(model) = source_json.get("model") {
-            target_obj.insert("model".to_string(), model.clone());
-        }
+    if !old_name.is_empty() && old_name != channel_name {
+        let old_file_path = dir.join(format!("settings-{}.json", old_name));
+        let _ = fs::remove_file(old_file_path);
     }
+    Ok(())
+}
 
-    // 写入合并后的配置
-    let merged_content = match serde_json::to_string_pretty(&target_json) {
-        Ok(json) => json,
-        Err(e) => return ApiResponse::error(e.to_string()),
-    };
+#[tauri::command]
+async fn delete_channel(config_path: String, channel_name: String) -> ApiResponse<()> {
+    let dir = PathBuf::from(config_path);
+    let result =
+        run_blocking(move || with_dir_lock(&dir, || delete_channel_file(&dir, &channel_name)))
+            .await;
 
-    match fs::write(&target_path, merged_content) {
+    match result {
         Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse::error(e.to_string()),
+        Err(e) => ApiResponse::error(e),
     }
 }
 
-#[tauri::command(rename_all = "camelCase")]
-async fn launch_claude(terminal_dir: String) -> ApiResponse<()> {
-    open_terminal("claude", &terminal_dir)
+// 删除只是改名为 .json.del，误删后可手动恢复
+fn delete_channel_file(dir: &Path, channel_name: &str) -> Result<(), String> {
+    validate_claude_channel_name(channel_name)?;
+    let source_path = dir.join(format!("settings-{}.json", channel_name));
+    let target_path = dir.join(format!("settings-{}.json.del", channel_name));
+    fs::rename(&source_path, &target_path).map_err(|e| e.to_string())
 }
 
-#[tauri::command(rename_all = "camelCase")]
-async fn launch_droid(terminal_dir: String) -> ApiResponse<()> {
-    open_terminal("droid", &terminal_dir)
-}
+#[tauri::command]
+async fn switch_channel(config_path: String, channel_name: String) -> ApiResponse<()> {
+    let dir = PathBuf::from(config_path);
+    let result = run_blocking(move || {
+        with_dir_lock(&dir, || merge_channel_into_settings(&dir, &channel_name))
+    })
+    .await;
 
-// ==================== 终端启动模块 ====================
-
-#[cfg(target_os = "windows")]
-const CREATE_NO_WINDOW: u32 = 0x08000000;
-
-#[cfg(target_os = "windows")]
-fn command_exists(cmd: &str) -> bool {
-    use std::os::windows::process::CommandExt;
-    use std::process::Command;
-    Command::new("where")
-        .arg(cmd)
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
 }
 
-fn open_terminal(command: &str, dir: &str) -> ApiResponse<()> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::process::Command;
+fn merge_channel_into_settings(dir: &Path, channel_name: &str) -> Result<(), String> {
+    merge_channel_into_file(dir, channel_name, &dir.join("settings.json"))
+}
 
-        // 检查工作目录是否存在，不存在则回退到用户主目录
-        let work_dir = if Path::new(dir).exists() {
-            dir.to_string()
-        } else {
-            std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\".to_string())
-        };
+// 目标为全局 settings.json 或项目的 .claude/settings.local.json；
+// 两者也会被 Claude Code 等程序改写，写回前确认读取后未被修改
+fn merge_channel_into_file(
+    dir: &Path,
+    channel_name: &str,
+    target_path: &Path,
+) -> Result<(), String> {
+    let source_path = dir.join(format!("settings-{}.json", channel_name));
 
-        // 检查是否有 pwsh (PowerShell 7)，没有则使用 powershell
-        let shell = if command_exists("pwsh") {
-            "pwsh"
-        } else {
-            "powershell"
-        };
+    // 读取源渠道配置
+    let source_content = fs::read_to_string(&source_path).map_err(|e| e.to_string())?;
+    let source_json: serde_json::Value =
+        serde_json::from_str(&source_content).map_err(|e| e.to_string())?;
+
+    // 读取目标文件（如果存在）；无法解析时报错，避免用空配置覆盖用户的其他设置
+    let (target_content, target_stamp) = read_stamped_text(target_path)?;
+    let mut target_json: serde_json::Value = match target_content {
+        Some(content) if !content.trim().is_empty() => serde_json::from_str(&content)
+            .map_err(|e| format!("解析 {} 失败: {}", target_path.display(), e))?,
+        _ => serde_json::json!({}),
+    };
 
-        // 优先尝试 Windows Terminal，失败则回退到直接启动 PowerShell
-        if let Some(result) = try_launch_with_wt(&work_dir, shell, command) {
-            return result;
-        }
+    // 只覆写 env 和 balanceApi 字段，保留目标文件中的其他配置
+    let target_obj = target_json
+        .as_object_mut()
+        .ok_or_else(|| format!("{} 不是 JSON 对象", target_path.display()))?;
+    // 检查 env 是否存在且不为空
+    let env = source_json.get("env");
+    let env_is_valid = env
+        .and_then(|e| e.as_object())
+        .map(|obj| !obj.is_empty())
+        .unwrap_or(false);
 
-        // 回退方案：直接启动 PowerShell 窗口
-        let result = Command::new(shell)
-            .args([
-                "-NoExit",
-                "-Command",
-                &format!("cd '{}'; {}", work_dir, command),
-            ])
-            .spawn()
-            .map(|_| ());
-
-        match result {
-            Ok(_) => ApiResponse::success(),
-            Err(e) => ApiResponse::error(format!("启动终端失败: {}", e)),
-        }
+    if !env_is_valid {
+        return Err("渠道配置异常：env 为空，无法切换".to_string());
     }
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        ApiResponse::error("仅支持 Windows".to_string())
-    }
-}
+    // 覆写 env
+    target_obj.insert("env".to_string(), env.unwrap().clone());
 
-#[cfg(target_os = "windows")]
-fn try_launch_with_wt(dir: &str, shell: &str, command: &str) -> Option<ApiResponse<()>> {
-    use std::os::windows::process::CommandExt;
-    use std::process::Command;
+    // 覆写 balanceApi（如果源文件有则覆写，没有则移除）
+    if let Some(balance_api) = source_json.get("balanceApi") {
+        target_obj.insert("balanceApi".to_string(), balance_api.clone());
+    } else {
+        target_obj.remove("balanceApi");
+    }
 
-    // 处理路径：移除末尾的反斜杠，防止与后续的引号组合成转义字符 (例如 "D:\" -> "D:")
-    let clean_dir = dir.trim_end_matches('\\');
+    // 同步 model（如果源文件有则覆写，没有则保留目标文件中原有的 model）
+    if let Some(model) = source_json.get("model") {
+        target_obj.insert("model".to_string(), model.clone());
+    }
 
-    // 使用 PowerShell Start-Process 启动 wt（Win10/Win11 兼容性最好）
-    // 注意：这里需要仔细处理引号转义
-    let ps_command = format!(
-        "Start-Process -FilePath wt -ArgumentList '-p \"PowerShell\" -d \"{}\" {} -NoExit -Command {}' -Wait -PassThru",
-        clean_dir, shell, command
-    );
+    // 写入合并后的配置
+    let merged_content = serde_json::to_string_pretty(&target_json).map_err(|e| e.to_string())?;
+    ensure_unchanged(target_path, &target_stamp)?;
+    write_text_file(target_path, &merged_content)
+}
 
-    if let Ok(output) = Command::new("powershell")
-        .args(["-NoProfile", "-Command", &ps_command])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-    {
-        // 检查 PowerShell 命令本身的执行状态
-        if output.status.success() {
-            return Some(ApiResponse::success());
-        }
-    }
+#[tauri::command(rename_all = "camelCase")]
+async fn launch_claude(terminal_dir: String) -> ApiResponse<()> {
+    open_terminal("claude", &terminal_dir)
+}
 
-    None
+#[tauri::command(rename_all = "camelCase")]
+async fn launch_droid(terminal_dir: String) -> ApiResponse<()> {
+    open_terminal("droid", &terminal_dir)
 }
 
 fn read_channels(
@@ -396,13 +399,7 @@ fn read_channels(
                         continue;
                     }
                     // 从文件系统读取修改时间
-                    if let Ok(metadata) = entry.metadata() {
-                        if let Ok(modified) = metadata.modified() {
-                            if let Ok(duration) = modified.duration_since(std::time::UNIX_EPOCH) {
-                                config.ctime = Some(duration.as_millis() as i64);
-                            }
-                        }
-                    }
+                    config.ctime = file_ctime(&entry.path());
                     channels.insert(channel_name, config);
                 }
             }
@@ -412,115 +409,315 @@ fn read_channels(
     Ok(channels)
 }
 
-// ==================== Droid 渠道管理 ====================
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct DroidChannel {
-    name: String,
-    api_key: String,
+// 文件修改时间（毫秒），同时用于列表排序和保存时的修改检测
+fn file_ctime(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()?;
+    let duration = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(duration.as_millis() as i64)
 }
 
-#[tauri::command]
-fn get_current_factory_api_key() -> ApiResponse<String> {
-    // 优先从当前进程的环境变量获取
-    if let Ok(key) = std::env::var("FACTORY_API_KEY") {
-        if !key.is_empty() {
-            return ApiResponse {
-                success: true,
-                error: None,
-                channels: None,
-                config: None,
-                data: Some(key),
-            };
-        }
-    }
+// 用 settings.json 中的 Token 和 Base URL 匹配渠道文件，返回当前生效的渠道名称
+fn find_active_channel_name(config_path: &str) -> Option<String> {
+    let content = fs::read_to_string(Path::new(config_path).join("settings.json")).ok()?;
+    let active = serde_json::from_str::<ChannelConfig>(&content).ok()?;
+    let token = active.env.get("ANTHROPIC_AUTH_TOKEN")?;
+    let url = active.env.get("ANTHROPIC_BASE_URL");
 
-    // 如果进程环境变量为空，尝试从注册表读取用户环境变量
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        use std::process::Command;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-
-        if let Ok(output) = Command::new("powershell")
-            .args(&[
-                "-Command",
-                "[Environment]::GetEnvironmentVariable('FACTORY_API_KEY', 'User')",
-            ])
-            .creation_flags(CREATE_NO_WINDOW)
-            .output()
-        {
-            if output.status.success() {
-                let key = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                if !key.is_empty() {
-                    // 同步到当前进程的环境变量
-                    std::env::set_var("FACTORY_API_KEY", &key);
-                    return ApiResponse {
-                        success: true,
-                        error: None,
-                        channels: None,
-                        config: None,
-                        data: Some(key),
-                    };
+    let channels = read_channels(config_path).ok()?;
+    let mut names: Vec<&String> = channels
+        .iter()
+        .filter(|(_, config)| {
+            config.env.get("ANTHROPIC_AUTH_TOKEN") == Some(token)
+                && config.env.get("ANTHROPIC_BASE_URL") == url
+        })
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names.first().map(|name| name.to_string())
+}
+
+// 统一渠道接口：Claude 渠道以名称作为 id，保存时保留余额查询与其他 env 变量
+struct ClaudeBackend {
+    dir: PathBuf,
+    // 目录锁已由调用方持有
+    lock_held: bool,
+}
+
+impl ClaudeBackend {
+    fn new(dir: &Path) -> Self {
+        ClaudeBackend {
+            dir: dir.to_path_buf(),
+            lock_held: false,
+        }
+    }
+
+    fn with_lock_held(dir: &Path) -> Self {
+        ClaudeBackend {
+            dir: dir.to_path_buf(),
+            lock_held: true,
+        }
+    }
+
+    fn config_path(&self) -> String {
+        self.dir.to_string_lossy().to_string()
+    }
+}
+
+impl ChannelBackend for ClaudeBackend {
+    fn tool(&self) -> ToolKind {
+        ToolKind::Claude
+    }
+
+    fn list(&self) -> Result<Vec<Channel>, String> {
+        let channels = read_channels(&self.config_path()).map_err(|e| e.to_string())?;
+        let mut list: Vec<Channel> = channels
+            .into_iter()
+            .map(|(name, config)| Channel {
+                id: name.clone(),
+                name,
+                base_url: config
+                    .env
+                    .get("ANTHROPIC_BASE_URL")
+                    .cloned()
+                    .unwrap_or_default(),
+                api_key: config
+                    .env
+                    .get("ANTHROPIC_AUTH_TOKEN")
+                    .cloned()
+                    .unwrap_or_default(),
+                model: config.model.unwrap_or_default(),
+            })
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(list)
+    }
+
+    fn save(&self, channel: Channel) -> Result<String, String> {
+        let name = channel.name.trim().to_string();
+        validate_claude_channel_name(&name)?;
+        if !channel.id.is_empty() {
+            validate_claude_channel_name(&channel.id)?;
+        }
+
+        with_dir_lock_unless_held(&self.dir, self.lock_held, || {
+            let mut config = if channel.id.is_empty() {
+                let mut env = HashMap::new();
+                env.insert(
+                    "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC".to_string(),
+                    "1".to_string(),
+                );
+                ChannelConfig {
+                    env,
+                    model: None,
+                    balance_api: None,
+                    ctime: None,
                 }
+            } else {
+                let path = self.dir.join(format!("settings-{}.json", channel.id));
+                let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+                serde_json::from_str::<ChannelConfig>(&content).map_err(|e| e.to_string())?
+            };
+
+            config.env.insert(
+                "ANTHROPIC_AUTH_TOKEN".to_string(),
+                channel.api_key.trim().to_string(),
+            );
+            let base_url = channel.base_url.trim();
+            if base_url.is_empty() {
+                config.env.remove("ANTHROPIC_BASE_URL");
+            } else {
+                config
+                    .env
+                    .insert("ANTHROPIC_BASE_URL".to_string(), base_url.to_string());
             }
-        }
+            let model = channel.model.trim();
+            config.model = if model.is_empty() {
+                None
+            } else {
+                Some(model.to_string())
+            };
+
+            write_channel_file(&self.dir, &name, &channel.id, None, &config)
+        })?;
+        Ok(name)
     }
 
-    ApiResponse {
-        success: true,
-        error: None,
-        channels: None,
-        config: None,
-        data: Some(String::new()),
+    fn delete(&self, id: &str) -> Result<(), String> {
+        with_dir_lock_unless_held(&self.dir, self.lock_held, || {
+            delete_channel_file(&self.dir, id)
+        })
     }
-}
 
-#[tauri::command]
-async fn get_droid_channels(config_path: String) -> ApiResponse<Vec<DroidChannel>> {
-    let key_file_path = Path::new(&config_path).join("key.txt");
+    fn switch(&self, id: &str) -> Result<(), String> {
+        validate_claude_channel_name(id)?;
+        with_dir_lock_unless_held(&self.dir, self.lock_held, || {
+            merge_channel_into_settings(&self.dir, id)
+        })
+    }
 
-    if !key_file_path.exists() {
-        return ApiResponse {
-            success: true,
-            error: None,
-            channels: None,
-            config: None,
-            data: Some(vec![]),
+    fn current(&self) -> Result<Option<String>, String> {
+        Ok(find_active_channel_name(&self.config_path()))
+    }
+
+    fn test_target(&self, channel: &Channel) -> Result<ChannelTestTarget, String> {
+        let base_url = match channel.base_url.trim() {
+            "" => CLAUDE_DEFAULT_BASE_URL,
+            url => url,
         };
+        Ok(ChannelTestTarget {
+            endpoint: format!("{}/v1/models", base_url.trim_end_matches('/')),
+            api_key: channel.api_key.clone(),
+            auth: TestAuth::Anthropic,
+        })
     }
 
-    match fs::read_to_string(&key_file_path) {
-        Ok(content) => {
-            let channels: Vec<DroidChannel> = content
-                .lines()
-                .filter(|line| !line.trim().is_empty())
-                .filter_map(|line| {
-                    // 移除可能存在的 [active] 标记
-                    let line_clean = line.trim().trim_end_matches("[active]").trim();
-                    let parts: Vec<&str> = line_clean.splitn(2, ' ').collect();
-                    if parts.len() == 2 {
-                        Some(DroidChannel {
-                            name: parts[0].trim().to_string(),
-                            api_key: parts[1].trim().to_string(),
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+    fn switch_snapshot(&self) -> Result<SwitchSnapshot, String> {
+        SwitchSnapshot::of_files(vec![self.dir.join("settings.json")])
+    }
+
+    // 同时以 --settings 加载渠道文件：其优先级高于全局 settings.json，避免其中的 env 覆盖注入的变量
+    fn launch_spec(&self, id: &str) -> Result<LaunchSpec, String> {
+        validate_claude_channel_name(id)?;
+        let path = self.dir.join(format!("settings-{}.json", id));
+        let content = fs::read_to_string(&path).map_err(|_| "渠道不存在".to_string())?;
+        let config: ChannelConfig = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        let mut env: Vec<(String, String)> = config.env.into_iter().collect();
+        env.sort();
+        Ok(LaunchSpec {
+            program: "claude",
+            args: vec!["--settings".to_string(), path.to_string_lossy().to_string()],
+            env,
+        })
+    }
+}
+
+fn main() {
+    // 作为 Claude Code 的 statusLine 命令运行时，只渲染状态栏，不启动窗口
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("statusline") {
+        std::process::exit(statusline::run_statusline_renderer(&args[2..]));
+    }
+
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(watcher::ConfigWatcher::default())
+        .invoke_handler(tauri::generate_handler![
+            get_channels,
+            get_active_channel,
+            save_channel,
+            delete_channel,
+            switch_channel,
+            launch_claude,
+            get_home_dir,
+            window_minimize,
+            window_maximize,
+            window_unmaximize,
+            window_close,
+            window_is_maximized,
+            query_balance,
+            // Droid 渠道管理
+            droid::get_droid_channels,
+            droid::get_current_factory_api_key,
+            droid::get_active_droid_channel,
+            droid::switch_droid_channel,
+            droid::save_droid_channel,
+            droid::delete_droid_channel,
+            droid::test_droid_channel,
+            droid::install_droid_shell_hook,
+            droid::uninstall_droid_shell_hook,
+            launch_droid,
+            // Codex 渠道管理
+            codex::get_codex_channels,
+            codex::get_codex_store_status,
+            codex::save_codex_channel,
+            codex::delete_codex_channel,
+            codex::move_codex_channel,
+            codex::duplicate_codex_channel,
+            codex::set_codex_channel_pinned,
+            codex::get_current_codex_env,
+            codex::switch_codex_channel,
+            codex::launch_codex,
+            // Gemini 渠道管理
+            gemini::get_gemini_channels,
+            gemini::save_gemini_channel,
+            gemini::delete_gemini_channel,
+            gemini::switch_gemini_channel,
+            gemini::get_current_gemini_env,
+            gemini::launch_gemini,
+            // StatusLine 管理
+            statusline::get_statusline_files,
+            statusline::read_statusline_file,
+            statusline::save_statusline_file,
+            statusline::delete_statusline_file,
+            statusline::apply_statusline_to_settings,
+            statusline::get_statusline_spec,
+            statusline::save_statusline_spec,
+            statusline::generate_statusline_script,
+            statusline::preview_statusline,
+            statusline::get_applied_statusline,
+            statusline::clear_statusline,
+            statusline::export_statusline,
+            statusline::import_statusline,
+            // 配置目录监听
+            watcher::watch_config_dirs,
+            // 统一渠道接口
+            channel::list_tool_channels,
+            channel::save_tool_channel,
+            channel::delete_tool_channel,
+            channel::switch_tool_channel,
+            channel::test_channel,
+            channel::launch_with_channel,
+            // 工具适配器
+            adapter::list_tool_adapters,
+            adapter::rollback_adapter_switch,
+            // 服务商
+            provider::get_providers,
+            provider::save_provider,
+            provider::delete_provider,
+            provider::switch_everywhere,
+            // 项目级渠道
+            project::switch_project_channel,
+            project::switch_project_codex_channel,
+            project::list_project_overrides,
+            project::clear_project_override
+        ])
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[tauri::command]
+async fn query_balance(url: String, method: String, token: String) -> ApiResponse<String> {
+    let final_url = url.replace("{key}", &token);
+    let client = reqwest::Client::new();
+
+    let request = match method.to_uppercase().as_str() {
+        "GET" => client.get(&final_url),
+        _ => client.post(&final_url),
+    };
 
-            ApiResponse {
+    match request.send().await {
+        Ok(resp) => match resp.text().await {
+            Ok(text) => ApiResponse {
                 success: true,
                 error: None,
                 channels: None,
                 config: None,
-                data: Some(channels),
-            }
-        }
+                data: Some(text),
+            },
+            Err(e) => ApiResponse {
+                success: false,
+                error: Some(format!("读取响应失败: {}", e)),
+                channels: None,
+                config: None,
+                data: None,
+            },
+        },
         Err(e) => ApiResponse {
             success: false,
-            error: Some(e.to_string()),
+            error: Some(format!("请求失败: {}", e)),
             channels: None,
             config: None,
             data: None,
@@ -529,129 +726,36 @@ async fn get_droid_channels(config_path: String) -> ApiResponse<Vec<DroidChannel
 }
 
 #[tauri::command]
-async fn switch_droid_channel(api_key: String) -> ApiResponse<()> {
-    // 设置当前进程的环境变量（子进程会继承）
-    std::env::set_var("FACTORY_API_KEY", &api_key);
-
-    // 设置用户级别环境变量（写入注册表，新终端可用）
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        use std::process::Command;
-        const CREATE_NO_WINDOW: u32 = 0x08000000;
-
-        let ps_command = format!(
-            "[Environment]::SetEnvironmentVariable('FACTORY_API_KEY', '{}', 'User')",
-            api_key.replace("'", "''")
-        );
-
-        if let Err(e) = Command::new("powershell")
-            .args(&["-Command", &ps_command])
-            .creation_flags(CREATE_NO_WINDOW)
-            .output()
-        {
-            return ApiResponse::error(format!("设置环境变量失败: {}", e));
-        }
+fn get_home_dir() -> Result<String, String> {
+    match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
+        Ok(home) => Ok(home),
+        Err(_) => Err("Failed to get home directory".to_string()),
     }
-
-    ApiResponse::success()
 }
 
 #[tauri::command]
-async fn save_droid_channel(
-    config_path: String,
-    name: String,
-    api_key: String,
-    old_name: String,
-) -> ApiResponse<()> {
-    let key_file_path = Path::new(&config_path).join("key.txt");
-
-    let mut channels: Vec<DroidChannel> = if key_file_path.exists() {
-        match fs::read_to_string(&key_file_path) {
-            Ok(content) => content
-                .lines()
-                .filter(|line| !line.trim().is_empty())
-                .filter_map(|line| {
-                    let line_clean = line.trim().trim_end_matches("[active]").trim();
-                    let parts: Vec<&str> = line_clean.splitn(2, ' ').collect();
-                    if parts.len() == 2 {
-                        Some(DroidChannel {
-                            name: parts[0].trim().to_string(),
-                            api_key: parts[1].trim().to_string(),
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .collect(),
-            Err(_) => vec![],
-        }
-    } else {
-        vec![]
-    };
-
-    if !old_name.is_empty() {
-        // 编辑模式：在原位置更新
-        if let Some(pos) = channels.iter().position(|c| c.name == old_name) {
-            // 检查新名称是否与其他渠道重复
-            if old_name != name && channels.iter().any(|c| c.name == name) {
-                return ApiResponse::error("渠道名称已存在".to_string());
-            }
-            channels[pos] = DroidChannel { name, api_key };
-        } else {
-            return ApiResponse::error("渠道不存在".to_string());
-        }
-    } else {
-        // 新增模式：检查名称是否已存在
-        if channels.iter().any(|c| c.name == name) {
-            return ApiResponse::error("渠道名称已存在".to_string());
-        }
-        // 在顶部插入新渠道
-        channels.insert(0, DroidChannel { name, api_key });
-    }
-
-    // 写回文件
-    let content: String = channels
-        .iter()
-        .map(|c| format!("{} {}", c.name, c.api_key))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    match fs::write(&key_file_path, content) {
-        Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse::error(e.to_string()),
-    }
+async fn window_minimize(window: tauri::Window) -> Result<(), String> {
+    window.minimize().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-async fn delete_droid_channel(config_path: String, name: String) -> ApiResponse<()> {
-    let key_file_path = Path::new(&config_path).join("key.txt");
-
-    if !key_file_path.exists() {
-        return ApiResponse::error("配置文件不存在".to_string());
-    }
+async fn window_maximize(window: tauri::Window) -> Result<(), String> {
+    window.maximize().map_err(|e| e.to_string())
+}
 
-    match fs::read_to_string(&key_file_path) {
-        Ok(content) => {
-            let channels: Vec<String> = content
-                .lines()
-                .filter(|line| !line.trim().is_empty())
-                .filter(|line| {
-                    let parts: Vec<&str> = line.splitn(2, ' ').collect();
-                    parts.len() != 2 || parts[0].trim() != name
-                })
-                .map(|s| s.to_string())
-                .collect();
+#[tauri::command]
+async fn window_unmaximize(window: tauri::Window) -> Result<(), String> {
+    window.unmaximize().map_err(|e| e.to_string())
+}
 
-            let new_content = channels.join("\n");
+#[tauri::command]
+async fn window_close(window: tauri::Window) -> Result<(), String> {
+    window.close().map_err(|e| e.to_string())
+}
 
-            match fs::write(&key_file_path, new_content) {
-                Ok(_) => ApiResponse::success(),
-                Err(e) => ApiResponse::error(e.to_string()),
-            }
-        }
-        Err(e) => ApiResponse::error(e.to_string()),
-    }
+#[tauri::command]
+async fn window_is_maximized(window: tauri::Window) -> Result<bool, String> {
+    window.is_maximized().map_err(|e| e.to_string())
 }
 
 #[cfg(test)]
@@ -687,6 +791,7 @@ mod claude_tests {
             String::new(),
             String::new(),
             String::new(),
+            None,
         ));
 
         assert!(result.success);
@@ -770,454 +875,102 @@ mod claude_tests {
             Some("balance")
         );
     }
-}
-
-fn main() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
-        .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![
-            get_channels,
-            get_active_channel,
-            save_channel,
-            delete_channel,
-            switch_channel,
-            launch_claude,
-            get_home_dir,
-            window_minimize,
-            window_maximize,
-            window_unmaximize,
-            window_close,
-            window_is_maximized,
-            query_balance,
-            // Droid 渠道管理
-            get_droid_channels,
-            get_current_factory_api_key,
-            switch_droid_channel,
-            save_droid_channel,
-            delete_droid_channel,
-            launch_droid,
-            // Codex 渠道管理
-            codex::get_codex_channels,
-            codex::save_codex_channel,
-            codex::delete_codex_channel,
-            codex::get_current_codex_env,
-            codex::switch_codex_channel,
-            codex::launch_codex,
-            // StatusLine 管理
-            get_statusline_files,
-            read_statusline_file,
-            save_statusline_file,
-            delete_statusline_file,
-            apply_statusline_to_settings
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
-
-#[tauri::command]
-async fn query_balance(url: String, method: String, token: String) -> ApiResponse<String> {
-    let final_url = url.replace("{key}", &token);
-    let client = reqwest::Client::new();
-
-    let request = match method.to_uppercase().as_str() {
-        "GET" => client.get(&final_url),
-        _ => client.post(&final_url),
-    };
-
-    match request.send().await {
-        Ok(resp) => match resp.text().await {
-            Ok(text) => ApiResponse {
-                success: true,
-                error: None,
-                channels: None,
-                config: None,
-                data: Some(text),
-            },
-            Err(e) => ApiResponse {
-                success: false,
-                error: Some(format!("读取响应失败: {}", e)),
-                channels: None,
-                config: None,
-                data: None,
-            },
-        },
-        Err(e) => ApiResponse {
-            success: false,
-            error: Some(format!("请求失败: {}", e)),
-            channels: None,
-            config: None,
-            data: None,
-        },
-    }
-}
-
-#[tauri::command]
-fn get_home_dir() -> Result<String, String> {
-    match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
-        Ok(home) => Ok(home),
-        Err(_) => Err("Failed to get home directory".to_string()),
-    }
-}
-
-#[tauri::command]
-async fn window_minimize(window: tauri::Window) -> Result<(), String> {
-    window.minimize().map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-async fn window_maximize(window: tauri::Window) -> Result<(), String> {
-    window.maximize().map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-async fn window_unmaximize(window: tauri::Window) -> Result<(), String> {
-    window.unmaximize().map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-async fn window_close(window: tauri::Window) -> Result<(), String> {
-    window.close().map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-async fn window_is_maximized(window: tauri::Window) -> Result<bool, String> {
-    window.is_maximized().map_err(|e| e.to_string())
-}
-
-// ==================== StatusLine 管理 ====================
-
-#[derive(Debug, Serialize, Deserialize)]
-struct StatuslineFile {
-    name: String,
-    file_name: String,
-    path: String,
-    modified: i64,
-}
-
-#[tauri::command]
-async fn get_statusline_files() -> ApiResponse<Vec<StatuslineFile>> {
-    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
-        Ok(home) => home,
-        Err(_) => {
-            return ApiResponse {
-                success: true,
-                error: None,
-                channels: None,
-                config: None,
-                data: Some(vec![]),
-            };
-        }
-    };
-
-    let statusline_dir = Path::new(&home_dir).join(".claude").join("statusline");
-
-    if !statusline_dir.exists() {
-        if let Err(e) = fs::create_dir_all(&statusline_dir) {
-            return ApiResponse {
-                success: false,
-                error: Some(format!("Failed to create statusline directory: {}", e)),
-                channels: None,
-                config: None,
-                data: Some(vec![]),
-            };
-        }
-        return ApiResponse {
-            success: true,
-            error: None,
-            channels: None,
-            config: None,
-            data: Some(vec![]),
-        };
-    }
-
-    let mut files = Vec::new();
-
-    let entries = match fs::read_dir(&statusline_dir) {
-        Ok(e) => e,
-        Err(_) => {
-            return ApiResponse {
-                success: true,
-                error: None,
-                channels: None,
-                config: None,
-                data: Some(vec![]),
-            };
-        }
-    };
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().and_then(|s| s.to_str()) != Some("ps1") {
-            continue;
-        }
-
-        let file_name = path
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_string();
-
-        // 提取显示名：先尝试移除 statusline_ 前缀和 .ps1 后缀
-        // 如果不匹配，只移除 .ps1 后缀
-        let name = file_name
-            .strip_prefix("statusline_")
-            .and_then(|s| s.strip_suffix(".ps1"))
-            .or_else(|| file_name.strip_suffix(".ps1"))
-            .unwrap_or(&file_name)
-            .to_string();
-
-        let modified = entry
-            .metadata()
-            .and_then(|m| m.modified())
-            .ok()
-            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0);
-
-        files.push(StatuslineFile {
-            name,
-            file_name,
-            path: path.to_string_lossy().to_string(),
-            modified,
-        });
-    }
-
-    files.sort_by(|a, b| b.modified.cmp(&a.modified));
-
-    ApiResponse {
-        success: true,
-        error: None,
-        channels: None,
-        config: None,
-        data: Some(files),
-    }
-}
 
-#[tauri::command]
-async fn read_statusline_file(file_name: String) -> ApiResponse<String> {
-    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
-        Ok(home) => home,
-        Err(_) => {
-            return ApiResponse {
-                success: false,
-                error: Some("Failed to get home directory".to_string()),
-                channels: None,
-                config: None,
-                data: None,
-            };
-        }
-    };
-
-    let file_path = Path::new(&home_dir)
-        .join(".claude")
-        .join("statusline")
-        .join(&file_name);
-
-    if !file_path.exists() {
-        return ApiResponse {
-            success: false,
-            error: Some("File not found".to_string()),
-            channels: None,
-            config: None,
-            data: None,
+    #[test]
+    fn finds_active_channel_by_token_and_base_url() {
+        let dir = create_temp_dir("active");
+        let write = |name: &str, token: &str, url: &str| {
+            fs::write(
+                dir.join(name),
+                serde_json::json!({
+                    "env": {
+                        "ANTHROPIC_AUTH_TOKEN": token,
+                        "ANTHROPIC_BASE_URL": url
+                    }
+                })
+                .to_string(),
+            )
+            .unwrap();
         };
-    }
-
-    match fs::read_to_string(&file_path) {
-        Ok(content) => ApiResponse {
-            success: true,
-            error: None,
-            channels: None,
-            config: None,
-            data: Some(content),
-        },
-        Err(e) => ApiResponse {
-            success: false,
-            error: Some(format!("Failed to read file: {}", e)),
-            channels: None,
-            config: None,
-            data: None,
-        },
-    }
-}
 
-#[tauri::command]
-async fn save_statusline_file(file_name: String, content: String) -> ApiResponse<()> {
-    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
-        Ok(home) => home,
-        Err(_) => {
-            return ApiResponse {
-                success: false,
-                error: Some("Failed to get home directory".to_string()),
-                channels: None,
-                config: None,
-                data: None,
-            };
-        }
-    };
+        write("settings-a.json", "token-1", "https://a.example.com");
+        write("settings-b.json", "token-1", "https://b.example.com");
+        write("settings.json", "token-1", "https://b.example.com");
 
-    let statusline_dir = Path::new(&home_dir).join(".claude").join("statusline");
+        let config_path = dir.to_string_lossy().to_string();
+        assert_eq!(
+            find_active_channel_name(&config_path),
+            Some("b".to_string())
+        );
 
-    if !statusline_dir.exists() {
-        if let Err(e) = fs::create_dir_all(&statusline_dir) {
-            return ApiResponse {
-                success: false,
-                error: Some(format!("Failed to create statusline directory: {}", e)),
-                channels: None,
-                config: None,
-                data: None,
-            };
-        }
+        write("settings.json", "token-2", "https://b.example.com");
+        assert_eq!(find_active_channel_name(&config_path), None);
+    }
+
+    fn save_named(
+        dir: &Path,
+        name: &str,
+        old_name: &str,
+        expected_ctime: Option<i64>,
+    ) -> ApiResponse<()> {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(save_channel(
+                dir.to_string_lossy().to_string(),
+                name.to_string(),
+                format!("{}-token", name),
+                String::new(),
+                String::new(),
+                old_name.to_string(),
+                String::new(),
+                String::new(),
+                String::new(),
+                expected_ctime,
+            ))
     }
 
-    let file_path = statusline_dir.join(&file_name);
-
-    // 使用 UTF-8 BOM 编码写入文件
-    // UTF-8 BOM 是 0xEF, 0xBB, 0xBF
-    let mut file = match fs::File::create(&file_path) {
-        Ok(f) => f,
-        Err(e) => {
-            return ApiResponse {
-                success: false,
-                error: Some(format!("Failed to create file: {}", e)),
-                channels: None,
-                config: None,
-                data: None,
-            };
-        }
-    };
-
-    // 写入 UTF-8 BOM
-    if let Err(e) = file.write_all(&[0xEF, 0xBB, 0xBF]) {
-        return ApiResponse {
-            success: false,
-            error: Some(format!("Failed to write BOM: {}", e)),
-            channels: None,
-            config: None,
-            data: None,
-        };
-    }
+    #[test]
+    fn save_channel_rejects_edits_of_externally_modified_files() {
+        let dir = create_temp_dir("conflict");
+        assert!(save_named(&dir, "main", "", None).success);
+        let loaded_ctime = file_ctime(&dir.join("settings-main.json"));
 
-    // 写入内容
-    match file.write_all(content.as_bytes()) {
-        Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse {
-            success: false,
-            error: Some(format!("Failed to write content: {}", e)),
-            channels: None,
-            config: None,
-            data: None,
-        },
-    }
-}
+        assert!(save_named(&dir, "main", "main", loaded_ctime).success);
 
-#[tauri::command]
-async fn delete_statusline_file(file_name: String) -> ApiResponse<()> {
-    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
-        Ok(home) => home,
-        Err(_) => {
-            return ApiResponse {
-                success: false,
-                error: Some("Failed to get home directory".to_string()),
-                channels: None,
-                config: None,
-                data: None,
-            };
-        }
-    };
+        let stale = save_named(&dir, "main", "main", loaded_ctime.map(|ctime| ctime - 1));
+        assert!(stale
+            .error
+            .unwrap()
+            .starts_with(fs_utils::CONFLICT_ERROR_PREFIX));
 
-    let file_path = Path::new(&home_dir)
-        .join(".claude")
-        .join("statusline")
-        .join(&file_name);
+        assert!(save_named(&dir, "backup", "", None).success);
+        let renamed_onto_existing = save_named(&dir, "backup", "main", None);
+        assert!(!renamed_onto_existing.success);
+        assert!(dir.join("settings-main.json").exists());
 
-    if file_path.exists() {
-        match fs::remove_file(&file_path) {
-            Ok(_) => ApiResponse::success(),
-            Err(e) => ApiResponse {
-                success: false,
-                error: Some(format!("Failed to delete file: {}", e)),
-                channels: None,
-                config: None,
-                data: None,
-            },
-        }
-    } else {
-        ApiResponse::success()
+        let duplicate = save_named(&dir, "backup", "", None);
+        assert!(!duplicate.success);
     }
-}
-
-#[tauri::command]
-async fn apply_statusline_to_settings(file_name: String) -> ApiResponse<()> {
-    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
-        Ok(home) => home,
-        Err(_) => {
-            return ApiResponse {
-                success: false,
-                error: Some("Failed to get home directory".to_string()),
-                channels: None,
-                config: None,
-                data: None,
-            };
-        }
-    };
-
-    let claude_dir = Path::new(&home_dir).join(".claude");
-    let settings_path = claude_dir.join("settings.json");
 
-    let ps1_full_path = claude_dir.join("statusline").join(&file_name);
-    // 路径不需要双重转义，serde_json 会自动处理
-    let ps1_path_str = ps1_full_path.to_string_lossy().to_string();
-    let command = format!(
-        "powershell -NoProfile -ExecutionPolicy Bypass -File {}",
-        ps1_path_str
-    );
+    #[test]
+    fn switch_merges_settings_edits_made_before_it_reads_the_file() {
+        let dir = create_temp_dir("switch-conflict");
+        assert!(save_named(&dir, "main", "", None).success);
+        let settings_file = dir.join("settings.json");
+        fs::write(&settings_file, "{}").unwrap();
 
-    let mut settings_json: serde_json::Value = if settings_path.exists() {
-        match fs::read_to_string(&settings_path) {
-            Ok(content) => match serde_json::from_str(&content) {
-                Ok(v) => v,
-                Err(_) => serde_json::json!({}),
-            },
-            Err(_) => serde_json::json!({}),
-        }
-    } else {
-        serde_json::json!({})
-    };
+        let (_, stamp) = read_stamped_text(&settings_file).unwrap();
+        fs::write(&settings_file, r#"{ "theme": "dark" }"#).unwrap();
+        assert!(ensure_unchanged(&settings_file, &stamp).is_err());
 
-    if let Some(obj) = settings_json.as_object_mut() {
-        // 使用正确的对象格式
-        obj.insert(
-            "statusLine".to_string(),
-            serde_json::json!({
-                "type": "command",
-                "command": command
-            }),
+        // 切换在锁内重新读取，外部修改会被合并而不是被覆盖
+        merge_channel_into_settings(&dir, "main").unwrap();
+        let switched: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&settings_file).unwrap()).unwrap();
+        assert_eq!(switched["theme"].as_str(), Some("dark"));
+        assert_eq!(
+            switched["env"]["ANTHROPIC_AUTH_TOKEN"].as_str(),
+            Some("main-token")
         );
     }
-
-    let updated_content = match serde_json::to_string_pretty(&settings_json) {
-        Ok(json) => json,
-        Err(e) => {
-            return ApiResponse {
-                success: false,
-                error: Some(format!("Failed to serialize settings: {}", e)),
-                channels: None,
-                config: None,
-                data: None,
-            };
-        }
-    };
-
-    match fs::write(&settings_path, updated_content) {
-        Ok(_) => ApiResponse::success(),
-        Err(e) => ApiResponse {
-            success: false,
-            error: Some(format!("Failed to write settings.json: {}", e)),
-            channels: None,
-            config: None,
-            data: None,
-        },
-    }
 }
@@ -0,0 +1,757 @@
+// 项目级渠道：Claude 渠道合并到项目的 .claude/settings.local.json，Codex 渠道写成项目专用 profile；
+// 覆盖记录保存在 Claude 配置目录下，用于列出和清除
+use crate::channel::{data_response, ToolKind};
+use crate::codex::{
+    apply_codex_project_profile, codex_project_profile_name, remove_codex_project_profile,
+};
+use crate::fs_utils::{
+    ensure_unchanged, read_stamped_text, run_blocking, with_dir_lock, write_text_file,
+};
+use crate::{merge_channel_into_file, validate_claude_channel_name, ApiResponse};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PROJECT_STORE_VERSION: u32 = 1;
+const PROJECT_STORE_FILE_NAME: &str = "project_overrides.json";
+const CLAUDE_PROJECT_DIR_NAME: &str = ".claude";
+const CLAUDE_PROJECT_SETTINGS_FILE_NAME: &str = "settings.local.json";
+const CLAUDE_PROJECT_GITIGNORE_FILE_NAME: &str = ".gitignore";
+// 切换时覆写的键，清除时恢复为首次切换前的值
+const CLAUDE_OVERRIDE_KEYS: [&str; 3] = ["env", "balanceApi", "model"];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct ClaudeProjectOverride {
+    channel: String,
+    // 首次切换前这些键的值，不存在的键不记录
+    #[serde(default)]
+    previous: serde_json::Map<String, serde_json::Value>,
+    // 首次切换前 settings.local.json 是否存在，不存在且清除后为空时删除该文件
+    #[serde(default)]
+    file_existed: bool,
+    // 切换时把 settings.local.json 加入了 .claude/.gitignore，清除时一并移除
+    #[serde(default)]
+    gitignore_added: bool,
+    switched_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+struct CodexProjectOverride {
+    channel_id: String,
+    channel_name: String,
+    profile: String,
+    switched_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct ProjectOverride {
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    claude: Option<ClaudeProjectOverride>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    codex: Option<CodexProjectOverride>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ProjectStore {
+    #[serde(default = "project_store_version")]
+    version: u32,
+    #[serde(default)]
+    projects: Vec<ProjectOverride>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct ProjectOverrideInfo {
+    path: String,
+    // 项目目录已不存在
+    missing: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    claude_channel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codex_channel: Option<String>,
+    // 在项目中以 codex --profile <name> 启动
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codex_profile: Option<String>,
+}
+
+fn project_store_version() -> u32 {
+    PROJECT_STORE_VERSION
+}
+
+fn now_timestamp() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn project_store_path(dir: &Path) -> PathBuf {
+    dir.join(PROJECT_STORE_FILE_NAME)
+}
+
+fn load_project_store(dir: &Path) -> Result<ProjectStore, String> {
+    let path = project_store_path(dir);
+    if !path.exists() {
+        return Ok(ProjectStore {
+            version: PROJECT_STORE_VERSION,
+            projects: vec![],
+        });
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取项目配置记录失败: {}", e))?;
+    let store: ProjectStore =
+        serde_json::from_str(&content).map_err(|e| format!("解析项目配置记录失败: {}", e))?;
+    if store.version > PROJECT_STORE_VERSION {
+        return Err(format!(
+            "项目配置记录版本 v{} 高于当前支持的 v{}，请升级应用",
+            store.version, PROJECT_STORE_VERSION
+        ));
+    }
+    Ok(store)
+}
+
+// 没有任何覆盖的项目不再记录
+fn save_project_store(dir: &Path, store: &mut ProjectStore) -> Result<(), String> {
+    store
+        .projects
+        .retain(|project| project.claude.is_some() || project.codex.is_some());
+    store.projects.sort_by(|a, b| a.path.cmp(&b.path));
+    let serialized = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("序列化项目配置记录失败: {}", e))?;
+    write_text_file(&project_store_path(dir), &serialized)
+}
+
+fn project_entry<'a>(store: &'a mut ProjectStore, path: &str) -> &'a mut ProjectOverride {
+    let position = match store.projects.iter().position(|p| p.path == path) {
+        Some(position) => position,
+        None => {
+            store.projects.push(ProjectOverride {
+                path: path.to_string(),
+                claude: None,
+                codex: None,
+            });
+            store.projects.len() - 1
+        }
+    };
+    &mut store.projects[position]
+}
+
+fn trim_project_path(project_path: &str) -> &str {
+    let trimmed = project_path.trim();
+    let without_separator = trimmed.trim_end_matches(['/', '\\']);
+    if without_separator.is_empty() || without_separator.ends_with(':') {
+        trimmed
+    } else {
+        without_separator
+    }
+}
+
+fn existing_project_dir(project_path: &str) -> Result<PathBuf, String> {
+    let project = PathBuf::from(trim_project_path(project_path));
+    if !project.is_absolute() || !project.is_dir() {
+        return Err(format!("项目目录不存在: {}", project_path.trim()));
+    }
+    Ok(project)
+}
+
+fn claude_project_settings_path(project: &Path) -> PathBuf {
+    project
+        .join(CLAUDE_PROJECT_DIR_NAME)
+        .join(CLAUDE_PROJECT_SETTINGS_FILE_NAME)
+}
+
+// 记录首次切换前会被覆写的键
+fn capture_claude_settings(
+    path: &Path,
+) -> Result<(serde_json::Map<String, serde_json::Value>, bool), String> {
+    let (content, _) = read_stamped_text(path)?;
+    let Some(content) = content else {
+        return Ok((serde_json::Map::new(), false));
+    };
+    if content.trim().is_empty() {
+        return Ok((serde_json::Map::new(), true));
+    }
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("解析 {} 失败: {}", path.display(), e))?;
+    let previous = CLAUDE_OVERRIDE_KEYS
+        .iter()
+        .filter_map(|key| Some((key.to_string(), json.get(key)?.clone())))
+        .collect();
+    Ok((previous, true))
+}
+
+// 把被覆写的键恢复为切换前的值；文件原本不存在且恢复后为空时删除
+fn restore_claude_settings(path: &Path, claude: &ClaudeProjectOverride) -> Result<(), String> {
+    let (content, stamp) = read_stamped_text(path)?;
+    let Some(content) = content else {
+        return Ok(());
+    };
+    let mut json: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("解析 {} 失败: {}", path.display(), e))?;
+    let settings = json
+        .as_object_mut()
+        .ok_or_else(|| format!("{} 不是 JSON 对象", path.display()))?;
+
+    for key in CLAUDE_OVERRIDE_KEYS {
+        match claude.previous.get(key) {
+            Some(value) => {
+                settings.insert(key.to_string(), value.clone());
+            }
+            None => {
+                settings.remove(key);
+            }
+        }
+    }
+
+    ensure_unchanged(path, &stamp)?;
+    if settings.is_empty() && !claude.file_existed {
+        fs::remove_file(path).map_err(|e| format!("删除文件失败 {}: {}", path.display(), e))?;
+        // 只删除空目录
+        if let Some(parent) = path.parent() {
+            let _ = fs::remove_dir(parent);
+        }
+        return Ok(());
+    }
+    let serialized = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+    write_text_file(path, &serialized)
+}
+
+// settings.local.json 中有 API Key，由 git 判断它是否已被忽略；
+// 不在 git 仓库中或未安装 git 时不会被提交，无需处理
+fn settings_need_gitignore(project: &Path) -> bool {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(project)
+        .args(["check-ignore", "-q", "--"])
+        .arg(format!(
+            "{}/{}",
+            CLAUDE_PROJECT_DIR_NAME, CLAUDE_PROJECT_SETTINGS_FILE_NAME
+        ));
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        command.creation_flags(crate::terminal::CREATE_NO_WINDOW);
+    }
+
+    // 退出码 0 表示已忽略，1 表示未忽略，其他表示不在仓库中等错误
+    matches!(command.output(), Ok(output) if output.status.code() == Some(1))
+}
+
+fn gitignore_lists_settings(line: &str) -> bool {
+    line.trim() == CLAUDE_PROJECT_SETTINGS_FILE_NAME
+}
+
+fn add_settings_to_gitignore(project: &Path) -> Result<(), String> {
+    let path = project
+        .join(CLAUDE_PROJECT_DIR_NAME)
+        .join(CLAUDE_PROJECT_GITIGNORE_FILE_NAME);
+    let (content, stamp) = read_stamped_text(&path)?;
+    let mut content = content.unwrap_or_default();
+    if content.lines().any(gitignore_lists_settings) {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(CLAUDE_PROJECT_SETTINGS_FILE_NAME);
+    content.push('\n');
+
+    ensure_unchanged(&path, &stamp)?;
+    write_text_file(&path, &content)
+}
+
+// 移除切换时加入的忽略规则，文件因此为空时删除
+fn remove_settings_from_gitignore(project: &Path) -> Result<(), String> {
+    let path = project
+        .join(CLAUDE_PROJECT_DIR_NAME)
+        .join(CLAUDE_PROJECT_GITIGNORE_FILE_NAME);
+    let (content, stamp) = read_stamped_text(&path)?;
+    let Some(content) = content else {
+        return Ok(());
+    };
+    let remaining: String = content
+        .split_inclusive('\n')
+        .filter(|line| !gitignore_lists_settings(line))
+        .collect();
+
+    ensure_unchanged(&path, &stamp)?;
+    if remaining.trim().is_empty() {
+        fs::remove_file(&path).map_err(|e| format!("删除文件失败 {}: {}", path.display(), e))?;
+        // 只删除空目录
+        if let Some(parent) = path.parent() {
+            let _ = fs::remove_dir(parent);
+        }
+        return Ok(());
+    }
+    write_text_file(&path, &remaining)
+}
+
+fn switch_project_claude(
+    config_dir: &Path,
+    project_path: &str,
+    channel_name: &str,
+) -> Result<(), String> {
+    let project = existing_project_dir(project_path)?;
+    let channel_name = channel_name.trim();
+    validate_claude_channel_name(channel_name)?;
+    if !config_dir
+        .join(format!("settings-{}.json", channel_name))
+        .exists()
+    {
+        return Err("渠道不存在".to_string());
+    }
+
+    let target = claude_project_settings_path(&project);
+    with_dir_lock(config_dir, || {
+        let mut store = load_project_store(config_dir)?;
+        let entry = project_entry(&mut store, &project.to_string_lossy());
+        let (previous, file_existed, mut gitignore_added) = match entry.claude.take() {
+            Some(existing) => (
+                existing.previous,
+                existing.file_existed,
+                existing.gitignore_added,
+            ),
+            None => {
+                let (previous, file_existed) = capture_claude_settings(&target)?;
+                (previous, file_existed, false)
+            }
+        };
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("创建目录失败 {}: {}", parent.display(), e))?;
+        }
+        // 写入 API Key 之前确保该文件不会被提交
+        if settings_need_gitignore(&project) {
+            add_settings_to_gitignore(&project)?;
+            gitignore_added = true;
+        }
+        merge_channel_into_file(config_dir, channel_name, &target)?;
+
+        entry.claude = Some(ClaudeProjectOverride {
+            channel: channel_name.to_string(),
+            previous,
+            file_existed,
+            gitignore_added,
+            switched_at: now_timestamp(),
+        });
+        save_project_store(config_dir, &mut store)
+    })
+}
+
+// 返回项目使用的 profile 名称
+fn switch_project_codex(
+    config_dir: &Path,
+    codex_dir: &Path,
+    project_path: &str,
+    channel_id: &str,
+) -> Result<String, String> {
+    let project = existing_project_dir(project_path)?;
+
+    with_dir_lock(config_dir, || {
+        let mut store = load_project_store(config_dir)?;
+        let entry = project_entry(&mut store, &project.to_string_lossy());
+        let profile = match &entry.codex {
+            Some(existing) => existing.profile.clone(),
+            None => codex_project_profile_name(&project),
+        };
+
+        let channel_name = apply_codex_project_profile(codex_dir, channel_id, &profile)?;
+        entry.codex = Some(CodexProjectOverride {
+            channel_id: channel_id.to_string(),
+            channel_name,
+            profile: profile.clone(),
+            switched_at: now_timestamp(),
+        });
+        save_project_store(config_dir, &mut store)?;
+        Ok(profile)
+    })
+}
+
+// tool 为空时清除该项目的全部覆盖；项目目录已不存在时只删除记录
+fn clear_project(
+    config_dir: &Path,
+    codex_dir: &Path,
+    project_path: &str,
+    tool: Option<ToolKind>,
+) -> Result<(), String> {
+    let path = trim_project_path(project_path);
+
+    with_dir_lock(config_dir, || {
+        let mut store = load_project_store(config_dir)?;
+        if !store.projects.iter().any(|project| project.path == path) {
+            return Err("该项目没有项目级配置".to_string());
+        }
+
+        // 每清除一个工具就保存记录，后面的工具失败时已恢复的工具不会在重试时被再次恢复
+        if matches!(tool, None | Some(ToolKind::Claude)) {
+            if let Some(entry) = store.projects.iter_mut().find(|p| p.path == path) {
+                if let Some(claude) = &entry.claude {
+                    let project = Path::new(&entry.path);
+                    if project.is_dir() {
+                        restore_claude_settings(&claude_project_settings_path(project), claude)?;
+                        if claude.gitignore_added {
+                            remove_settings_from_gitignore(project)?;
+                        }
+                    }
+                    entry.claude = None;
+                    save_project_store(config_dir, &mut store)?;
+                }
+            }
+        }
+        if matches!(tool, None | Some(ToolKind::Codex)) {
+            if let Some(entry) = store.projects.iter_mut().find(|p| p.path == path) {
+                if let Some(codex) = &entry.codex {
+                    remove_codex_project_profile(codex_dir, &codex.profile)?;
+                    entry.codex = None;
+                    save_project_store(config_dir, &mut store)?;
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+fn list_projects(config_dir: &Path) -> Result<Vec<ProjectOverrideInfo>, String> {
+    let store = with_dir_lock(config_dir, || load_project_store(config_dir))?;
+    Ok(store
+        .projects
+        .into_iter()
+        .map(|project| ProjectOverrideInfo {
+            missing: !Path::new(&project.path).is_dir(),
+            claude_channel: project.claude.map(|claude| claude.channel),
+            codex_channel: project.codex.as_ref().map(|c| c.channel_name.clone()),
+            codex_profile: project.codex.map(|codex| codex.profile),
+            path: project.path,
+        })
+        .collect())
+}
+
+// ==================== 项目级渠道 ====================
+
+#[tauri::command]
+pub(crate) async fn switch_project_channel(
+    config_path: String,
+    project_path: String,
+    channel_name: String,
+) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        switch_project_claude(Path::new(&config_path), &project_path, &channel_name)
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn switch_project_codex_channel(
+    config_path: String,
+    codex_config_path: String,
+    project_path: String,
+    channel_id: String,
+) -> ApiResponse<String> {
+    data_response(
+        run_blocking(move || {
+            switch_project_codex(
+                Path::new(&config_path),
+                Path::new(&codex_config_path),
+                &project_path,
+                &channel_id,
+            )
+        })
+        .await,
+    )
+}
+
+#[tauri::command]
+pub(crate) async fn list_project_overrides(
+    config_path: String,
+) -> ApiResponse<Vec<ProjectOverrideInfo>> {
+    data_response(run_blocking(move || list_projects(Path::new(&config_path))).await)
+}
+
+#[tauri::command]
+pub(crate) async fn clear_project_override(
+    config_path: String,
+    codex_config_path: String,
+    project_path: String,
+    tool: Option<ToolKind>,
+) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        clear_project(
+            Path::new(&config_path),
+            Path::new(&codex_config_path),
+            &project_path,
+            tool,
+        )
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::{channel_backend, Channel};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-project-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        for sub in ["claude", "codex", "repo"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        dir
+    }
+
+    fn write_claude_channel(dir: &Path, name: &str, token: &str) {
+        let config = serde_json::json!({
+            "env": { "ANTHROPIC_AUTH_TOKEN": token, "ANTHROPIC_BASE_URL": "https://relay.example.com" },
+            "model": "claude-project"
+        });
+        fs::write(
+            dir.join(format!("settings-{}.json", name)),
+            config.to_string(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn switches_and_restores_project_claude_settings() {
+        let root = create_temp_dir("claude");
+        let config_dir = root.join("claude");
+        let repo = root.join("repo");
+        write_claude_channel(&config_dir, "relay", "sk-1");
+        write_claude_channel(&config_dir, "backup", "sk-2");
+
+        let local = claude_project_settings_path(&repo);
+        fs::create_dir_all(local.parent().unwrap()).unwrap();
+        fs::write(
+            &local,
+            r#"{"permissions":{"allow":["Bash"]},"env":{"DEBUG":"1"}}"#,
+        )
+        .unwrap();
+        let repo_path = repo.to_string_lossy().to_string();
+
+        switch_project_claude(&config_dir, &repo_path, "relay").unwrap();
+        switch_project_claude(&config_dir, &format!("{}/", repo_path), "backup").unwrap();
+        let merged: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&local).unwrap()).unwrap();
+        assert_eq!(merged["env"]["ANTHROPIC_AUTH_TOKEN"], "sk-2");
+        assert_eq!(merged["model"], "claude-project");
+        assert_eq!(merged["permissions"]["allow"][0], "Bash");
+        assert!(!config_dir.join("settings.json").exists());
+
+        let listed = list_projects(&config_dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].path, repo_path);
+        assert_eq!(listed[0].claude_channel.as_deref(), Some("backup"));
+        assert!(!listed[0].missing);
+
+        clear_project(&config_dir, &root.join("codex"), &repo_path, None).unwrap();
+        let restored: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&local).unwrap()).unwrap();
+        assert_eq!(
+            restored,
+            serde_json::json!({"permissions":{"allow":["Bash"]},"env":{"DEBUG":"1"}})
+        );
+        assert!(list_projects(&config_dir).unwrap().is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn removes_created_settings_file_when_cleared() {
+        let root = create_temp_dir("created");
+        let config_dir = root.join("claude");
+        let repo = root.join("repo");
+        write_claude_channel(&config_dir, "relay", "sk-1");
+        let repo_path = repo.to_string_lossy().to_string();
+
+        assert!(switch_project_claude(&config_dir, &repo_path, "missing").is_err());
+        switch_project_claude(&config_dir, &repo_path, "relay").unwrap();
+        assert!(claude_project_settings_path(&repo).exists());
+
+        clear_project(
+            &config_dir,
+            &root.join("codex"),
+            &repo_path,
+            Some(ToolKind::Claude),
+        )
+        .unwrap();
+        assert!(!repo.join(CLAUDE_PROJECT_DIR_NAME).exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn writes_and_clears_codex_project_profile() {
+        let root = create_temp_dir("codex");
+        let config_dir = root.join("claude");
+        let codex_dir = root.join("codex");
+        let repo_path = root.join("repo").to_string_lossy().to_string();
+        fs::write(codex_dir.join("config.toml"), "model = \"gpt-5\"\n").unwrap();
+
+        let channel_id = channel_backend(ToolKind::Codex, &codex_dir, None)
+            .unwrap()
+            .save(Channel {
+                id: String::new(),
+                name: "relay".to_string(),
+                base_url: "https://relay.example.com/v1".to_string(),
+                api_key: "sk-project".to_string(),
+                model: "gpt-5-codex".to_string(),
+            })
+            .unwrap();
+
+        let profile =
+            switch_project_codex(&config_dir, &codex_dir, &repo_path, &channel_id).unwrap();
+        let config = fs::read_to_string(codex_dir.join("config.toml")).unwrap();
+        let parsed: toml::Table = toml::from_str(&config).unwrap();
+        assert_eq!(parsed["model"].as_str(), Some("gpt-5"));
+        assert_eq!(
+            parsed["profiles"][&profile]["model"].as_str(),
+            Some("gpt-5-codex")
+        );
+        assert_eq!(
+            parsed["model_providers"][&profile]["experimental_bearer_token"].as_str(),
+            Some("sk-project")
+        );
+
+        let listed = list_projects(&config_dir).unwrap();
+        assert_eq!(listed[0].codex_profile.as_deref(), Some(profile.as_str()));
+        assert_eq!(listed[0].codex_channel.as_deref(), Some("relay"));
+
+        clear_project(&config_dir, &codex_dir, &repo_path, Some(ToolKind::Codex)).unwrap();
+        assert_eq!(
+            fs::read_to_string(codex_dir.join("config.toml")).unwrap(),
+            "model = \"gpt-5\"\n"
+        );
+        assert!(list_projects(&config_dir).unwrap().is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn refuses_to_overwrite_unparsable_project_settings() {
+        let root = create_temp_dir("broken");
+        let config_dir = root.join("claude");
+        let repo = root.join("repo");
+        write_claude_channel(&config_dir, "relay", "sk-1");
+        let repo_path = repo.to_string_lossy().to_string();
+
+        let local = claude_project_settings_path(&repo);
+        fs::create_dir_all(local.parent().unwrap()).unwrap();
+        let broken = r#"{"permissions":{"allow":["Bash"]},"#;
+        fs::write(&local, broken).unwrap();
+
+        let error = switch_project_claude(&config_dir, &repo_path, "relay").unwrap_err();
+        assert!(error.contains("解析"), "{}", error);
+        assert_eq!(fs::read_to_string(&local).unwrap(), broken);
+        assert!(list_projects(&config_dir).unwrap().is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn keeps_cleared_tools_cleared_when_a_later_tool_fails() {
+        let root = create_temp_dir("partial");
+        let config_dir = root.join("claude");
+        let codex_dir = root.join("codex");
+        let repo = root.join("repo");
+        let repo_path = repo.to_string_lossy().to_string();
+        write_claude_channel(&config_dir, "relay", "sk-1");
+        fs::write(codex_dir.join("config.toml"), "model = \"gpt-5\"\n").unwrap();
+        let channel_id = channel_backend(ToolKind::Codex, &codex_dir, None)
+            .unwrap()
+            .save(Channel {
+                id: String::new(),
+                name: "relay".to_string(),
+                base_url: "https://relay.example.com/v1".to_string(),
+                api_key: "sk-project".to_string(),
+                model: "gpt-5-codex".to_string(),
+            })
+            .unwrap();
+
+        switch_project_claude(&config_dir, &repo_path, "relay").unwrap();
+        switch_project_codex(&config_dir, &codex_dir, &repo_path, &channel_id).unwrap();
+
+        // Codex 配置损坏时清除失败，但已恢复的 Claude 设置不再留在记录中
+        let config = fs::read_to_string(codex_dir.join("config.toml")).unwrap();
+        fs::write(codex_dir.join("config.toml"), "model = [").unwrap();
+        assert!(clear_project(&config_dir, &codex_dir, &repo_path, None).is_err());
+        assert!(!claude_project_settings_path(&repo).exists());
+        let listed = list_projects(&config_dir).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].claude_channel, None);
+        assert!(listed[0].codex_profile.is_some());
+
+        fs::write(codex_dir.join("config.toml"), config).unwrap();
+        clear_project(&config_dir, &codex_dir, &repo_path, None).unwrap();
+        assert!(list_projects(&config_dir).unwrap().is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn ignores_project_settings_in_git_repos_until_cleared() {
+        let root = create_temp_dir("gitignore");
+        let config_dir = root.join("claude");
+        let repo = root.join("repo");
+        write_claude_channel(&config_dir, "relay", "sk-1");
+        let repo_path = repo.to_string_lossy().to_string();
+        let status = Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(&repo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(settings_need_gitignore(&repo));
+
+        switch_project_claude(&config_dir, &repo_path, "relay").unwrap();
+        let gitignore = repo.join(CLAUDE_PROJECT_DIR_NAME).join(".gitignore");
+        assert_eq!(
+            fs::read_to_string(&gitignore).unwrap(),
+            "settings.local.json\n"
+        );
+        assert!(!settings_need_gitignore(&repo));
+
+        // 已有的忽略规则保留，清除后恢复原样
+        fs::write(&gitignore, "*.log\nsettings.local.json\n").unwrap();
+        switch_project_claude(&config_dir, &repo_path, "relay").unwrap();
+        clear_project(&config_dir, &root.join("codex"), &repo_path, None).unwrap();
+        assert_eq!(fs::read_to_string(&gitignore).unwrap(), "*.log\n");
+        assert!(!claude_project_settings_path(&repo).exists());
+
+        fs::write(&gitignore, "").unwrap();
+        switch_project_claude(&config_dir, &repo_path, "relay").unwrap();
+        clear_project(&config_dir, &root.join("codex"), &repo_path, None).unwrap();
+        assert!(!repo.join(CLAUDE_PROJECT_DIR_NAME).exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rejects_channel_names_outside_the_config_dir() {
+        let root = create_temp_dir("names");
+        let config_dir = root.join("claude");
+        let repo_path = root.join("repo").to_string_lossy().to_string();
+        fs::write(root.join("settings-x.json"), "{}").unwrap();
+
+        for name in ["../settings-x", "..", "a/b", ""] {
+            assert!(switch_project_claude(&config_dir, &repo_path, name).is_err());
+        }
+        assert!(!root.join("repo").join(CLAUDE_PROJECT_DIR_NAME).exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,794 @@
+// 服务商：同一中转站的 Key 与各协议的 Base URL，据此派生 Claude、Codex、Droid 渠道，
+// 并可把所有关联工具一次性切换到该服务商，任一工具失败时全部恢复
+use crate::channel::{
+    channel_backend, channel_backend_with_lock_held, data_response, Channel, ChannelBackend,
+    ToolKind,
+};
+use crate::codex::is_valid_codex_name;
+use crate::fs_utils::{conflict_error, run_blocking, with_dir_lock, write_text_file};
+use crate::ApiResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const PROVIDER_STORE_VERSION: u32 = 1;
+const PROVIDER_STORE_FILE_NAME: &str = "providers.json";
+// 同时也是切换顺序：Droid 的 Windows 用户环境变量无法完整恢复，放在最后
+const PROVIDER_TOOLS: [ToolKind; 3] = [ToolKind::Claude, ToolKind::Codex, ToolKind::Droid];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct Provider {
+    #[serde(default)]
+    id: String,
+    name: String,
+    api_key: String,
+    // Claude 使用，留空时为 Anthropic 官方地址
+    #[serde(default)]
+    anthropic_base_url: String,
+    // Codex 使用
+    #[serde(default)]
+    openai_base_url: String,
+    // Droid 使用，留空时为 Factory 官方地址
+    #[serde(default)]
+    factory_base_url: String,
+    #[serde(default)]
+    claude_model: String,
+    #[serde(default)]
+    codex_model: String,
+    // 需要派生渠道的工具
+    #[serde(default)]
+    tools: Vec<ToolKind>,
+    // 派生渠道在各工具中的 id，由后端维护，前端传入的值会被忽略
+    #[serde(default)]
+    links: BTreeMap<ToolKind, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ProviderStore {
+    #[serde(default = "provider_store_version")]
+    version: u32,
+    #[serde(default)]
+    revision: u64,
+    #[serde(default)]
+    providers: Vec<Provider>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub(crate) struct ProviderList {
+    revision: u64,
+    providers: Vec<Provider>,
+    // 所有关联工具当前都在使用其派生渠道的服务商
+    active: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct ProviderStoreChange {
+    revision: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider_id: Option<String>,
+}
+
+fn provider_store_version() -> u32 {
+    PROVIDER_STORE_VERSION
+}
+
+fn provider_store_path(dir: &Path) -> PathBuf {
+    dir.join(PROVIDER_STORE_FILE_NAME)
+}
+
+fn load_provider_store(dir: &Path) -> Result<ProviderStore, String> {
+    let path = provider_store_path(dir);
+    if !path.exists() {
+        return Ok(ProviderStore {
+            version: PROVIDER_STORE_VERSION,
+            revision: 0,
+            providers: vec![],
+        });
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取服务商存储失败: {}", e))?;
+    let store: ProviderStore =
+        serde_json::from_str(&content).map_err(|e| format!("解析服务商存储失败: {}", e))?;
+    if store.version > PROVIDER_STORE_VERSION {
+        return Err(format!(
+            "服务商存储版本 v{} 高于当前支持的 v{}，请升级应用",
+            store.version, PROVIDER_STORE_VERSION
+        ));
+    }
+    Ok(store)
+}
+
+fn load_provider_store_locked(dir: &Path) -> Result<ProviderStore, String> {
+    with_dir_lock(dir, || load_provider_store(dir))
+}
+
+fn check_revision(store: &ProviderStore, expected_revision: u64) -> Result<(), String> {
+    if store.revision != expected_revision {
+        return Err(conflict_error(&format!(
+            "服务商列表已被其他操作修改（当前修订号 {}，期望 {}），请刷新后重试",
+            store.revision, expected_revision
+        )));
+    }
+    Ok(())
+}
+
+// 在目录锁内校验修订号后修改并保存存储，返回新的修订号与受影响的服务商
+fn mutate_provider_store<F>(
+    dir: &Path,
+    expected_revision: u64,
+    mutate: F,
+) -> Result<ProviderStoreChange, String>
+where
+    F: FnOnce(&mut ProviderStore) -> Result<Option<String>, String>,
+{
+    with_dir_lock(dir, || {
+        let mut store = load_provider_store(dir)?;
+        check_revision(&store, expected_revision)?;
+
+        let provider_id = mutate(&mut store)?;
+        store.revision += 1;
+        let serialized = serde_json::to_string_pretty(&store)
+            .map_err(|e| format!("序列化服务商存储失败: {}", e))?;
+        write_text_file(&provider_store_path(dir), &serialized)?;
+
+        Ok(ProviderStoreChange {
+            revision: store.revision,
+            provider_id,
+        })
+    })
+}
+
+fn new_provider_id(existing: &[Provider]) -> String {
+    let mut seed = chrono::Utc::now()
+        .timestamp_nanos_opt()
+        .unwrap_or_default()
+        .unsigned_abs();
+    loop {
+        let id = format!("pv{:x}", seed);
+        if !existing.iter().any(|provider| provider.id == id) {
+            return id;
+        }
+        seed += 1;
+    }
+}
+
+fn normalize_provider(provider: Provider) -> Result<Provider, String> {
+    let mut tools = Vec::new();
+    for tool in provider.tools {
+        if !PROVIDER_TOOLS.contains(&tool) {
+            return Err(format!("服务商不支持派生 {} 渠道", tool_label(tool)));
+        }
+        if !tools.contains(&tool) {
+            tools.push(tool);
+        }
+    }
+
+    let provider = Provider {
+        id: provider.id.trim().to_string(),
+        name: provider.name.trim().to_string(),
+        api_key: provider.api_key.trim().to_string(),
+        anthropic_base_url: provider.anthropic_base_url.trim().to_string(),
+        openai_base_url: provider.openai_base_url.trim().to_string(),
+        factory_base_url: provider.factory_base_url.trim().to_string(),
+        claude_model: provider.claude_model.trim().to_string(),
+        codex_model: provider.codex_model.trim().to_string(),
+        tools,
+        links: BTreeMap::new(),
+    };
+
+    if provider.name.is_empty() {
+        return Err("请输入服务商名称".to_string());
+    }
+    // 名称同时用作各派生渠道的名称与文件名，按最严格的 Codex 规则校验
+    if !is_valid_codex_name(&provider.name) {
+        return Err("服务商名称仅支持英文字母、数字、-、_，最多18位".to_string());
+    }
+    if provider.api_key.is_empty() {
+        return Err("请输入 API Key".to_string());
+    }
+    if provider.tools.is_empty() {
+        return Err("请至少选择一个工具".to_string());
+    }
+    Ok(provider)
+}
+
+fn tool_label(tool: ToolKind) -> &'static str {
+    match tool {
+        ToolKind::Claude => "Claude",
+        ToolKind::Codex => "Codex",
+        ToolKind::Droid => "Droid",
+        ToolKind::Gemini => "Gemini",
+        ToolKind::Adapter => "适配器",
+    }
+}
+
+// 服务商、Claude 与 Droid 渠道都在 Claude 配置目录中，Codex 渠道在 Codex 配置目录中
+struct ProviderDirs<'a> {
+    claude: &'a Path,
+    codex: &'a Path,
+}
+
+impl ProviderDirs<'_> {
+    fn backend(&self, tool: ToolKind) -> Result<Box<dyn ChannelBackend>, String> {
+        let dir = match tool {
+            ToolKind::Codex => self.codex,
+            _ => self.claude,
+        };
+        channel_backend(tool, dir, None)
+    }
+
+    // 调用方已持有 Claude 配置目录锁时使用；Codex 渠道在另一目录，仍自行加锁
+    fn backend_under_lock(&self, tool: ToolKind) -> Result<Box<dyn ChannelBackend>, String> {
+        match tool {
+            ToolKind::Codex => self.backend(tool),
+            _ => channel_backend_with_lock_held(tool, self.claude),
+        }
+    }
+}
+
+fn derived_channel(provider: &Provider, tool: ToolKind, id: String) -> Channel {
+    let (base_url, model) = match tool {
+        ToolKind::Claude => (&provider.anthropic_base_url, &provider.claude_model),
+        ToolKind::Codex => (&provider.openai_base_url, &provider.codex_model),
+        _ => (&provider.factory_base_url, &String::new()),
+    };
+    Channel {
+        id,
+        name: provider.name.clone(),
+        base_url: base_url.clone(),
+        api_key: provider.api_key.clone(),
+        model: model.clone(),
+    }
+}
+
+// 同步失败时撤销已完成的修改：新建的删除，更新或删除的恢复为原来的内容
+enum DerivedChange {
+    Created(String),
+    Updated { id: String, previous: Channel },
+    // 重新创建后 id 可能变化，原来的关联在下次同步时会按缺失重新创建
+    Deleted(Channel),
+}
+
+fn undo_derived_change(backend: &dyn ChannelBackend, change: DerivedChange) -> Result<(), String> {
+    match change {
+        DerivedChange::Created(id) => backend.delete(&id),
+        DerivedChange::Updated { id, previous } => {
+            backend.save(Channel { id, ..previous }).map(|_| ())
+        }
+        DerivedChange::Deleted(previous) => backend
+            .save(Channel {
+                id: String::new(),
+                ..previous
+            })
+            .map(|_| ()),
+    }
+}
+
+// 按所选工具新建或更新派生渠道，取消选择的工具删除其派生渠道；任一工具失败时撤销之前工具的修改。
+// 调用方需持有 Claude 配置目录锁
+fn sync_derived_channels(
+    dirs: &ProviderDirs,
+    provider: &Provider,
+    previous_links: &BTreeMap<ToolKind, String>,
+) -> Result<BTreeMap<ToolKind, String>, String> {
+    let mut links = BTreeMap::new();
+    let mut changes: Vec<(ToolKind, DerivedChange)> = Vec::new();
+
+    let result: Result<(), String> = PROVIDER_TOOLS.iter().try_for_each(|&tool| {
+        let backend = dirs.backend_under_lock(tool)?;
+        // 派生渠道可能已在对应工具中被手动删除，此时重新创建
+        let previous = previous_links
+            .get(&tool)
+            .and_then(|id| backend.find(id).ok());
+
+        if provider.tools.contains(&tool) {
+            let id = previous.as_ref().map(|c| c.id.clone()).unwrap_or_default();
+            let id = backend
+                .save(derived_channel(provider, tool, id))
+                .map_err(|e| format!("{}: {}", tool_label(tool), e))?;
+            let change = match previous {
+                Some(previous) => DerivedChange::Updated {
+                    id: id.clone(),
+                    previous,
+                },
+                None => DerivedChange::Created(id.clone()),
+            };
+            changes.push((tool, change));
+            links.insert(tool, id);
+        } else if let Some(previous) = previous {
+            backend
+                .delete(&previous.id)
+                .map_err(|e| format!("{}: {}", tool_label(tool), e))?;
+            changes.push((tool, DerivedChange::Deleted(previous)));
+        }
+        Ok(())
+    });
+
+    let Err(e) = result else {
+        return Ok(links);
+    };
+    let mut message = e;
+    for (tool, change) in changes.into_iter().rev() {
+        let undone = dirs
+            .backend_under_lock(tool)
+            .and_then(|backend| undo_derived_change(backend.as_ref(), change));
+        if let Err(undo_error) = undone {
+            message.push_str(&format!(
+                "；恢复 {} 渠道失败: {}",
+                tool_label(tool),
+                undo_error
+            ));
+        }
+    }
+    Err(message)
+}
+
+// 派生渠道的同步与服务商存储的保存在同一把目录锁内完成，避免并发的保存或删除互相覆盖
+fn save_provider_in(
+    dirs: &ProviderDirs,
+    provider: Provider,
+    expected_revision: u64,
+) -> Result<ProviderStoreChange, String> {
+    let mut provider = normalize_provider(provider)?;
+    mutate_provider_store(dirs.claude, expected_revision, |store| {
+        let previous_links = if provider.id.is_empty() {
+            provider.id = new_provider_id(&store.providers);
+            BTreeMap::new()
+        } else {
+            store
+                .providers
+                .iter()
+                .find(|current| current.id == provider.id)
+                .map(|current| current.links.clone())
+                .ok_or_else(|| conflict_error("服务商已被删除，请刷新后重试"))?
+        };
+        let duplicate = store
+            .providers
+            .iter()
+            .any(|current| current.id != provider.id && current.name == provider.name);
+        if duplicate {
+            return Err("服务商名称已存在".to_string());
+        }
+
+        provider.links = sync_derived_channels(dirs, &provider, &previous_links)?;
+        let id = provider.id.clone();
+        match store.providers.iter_mut().find(|current| current.id == id) {
+            Some(current) => *current = provider,
+            None => store.providers.push(provider),
+        }
+        Ok(Some(id))
+    })
+}
+
+fn delete_provider_in(
+    dirs: &ProviderDirs,
+    provider_id: &str,
+    expected_revision: u64,
+) -> Result<ProviderStoreChange, String> {
+    mutate_provider_store(dirs.claude, expected_revision, |store| {
+        let provider = store
+            .providers
+            .iter()
+            .find(|provider| provider.id == provider_id)
+            .ok_or_else(|| conflict_error("服务商已被删除，请刷新后重试"))?;
+
+        for (&tool, id) in &provider.links {
+            let backend = dirs.backend_under_lock(tool)?;
+            if backend.find(id).is_ok() {
+                backend
+                    .delete(id)
+                    .map_err(|e| format!("{}: {}", tool_label(tool), e))?;
+            }
+        }
+
+        store
+            .providers
+            .retain(|provider| provider.id != provider_id);
+        Ok(None)
+    })
+}
+
+// 先记录所有关联工具的生效配置，再依次切换；任一工具失败时把已切换的工具全部恢复
+fn switch_provider_everywhere(dirs: &ProviderDirs, provider_id: &str) -> Result<(), String> {
+    let store = load_provider_store_locked(dirs.claude)?;
+    let provider = store
+        .providers
+        .iter()
+        .find(|provider| provider.id == provider_id)
+        .ok_or_else(|| "服务商不存在".to_string())?;
+
+    let mut targets = Vec::new();
+    for tool in PROVIDER_TOOLS {
+        if let Some(id) = provider.links.get(&tool) {
+            targets.push((tool, dirs.backend(tool)?, id));
+        }
+    }
+    if targets.is_empty() {
+        return Err("该服务商尚未关联任何工具".to_string());
+    }
+
+    // 每个工具在切换前才记录快照，切换成功后记录写入结果；恢复时跳过之后又被修改的文件
+    let mut snapshots = Vec::new();
+    for (tool, backend, id) in &targets {
+        let switched = backend.switch_snapshot().and_then(|mut snapshot| {
+            let result = backend.switch(id);
+            let result = result.and_then(|_| snapshot.mark_switched());
+            snapshots.push(snapshot);
+            result
+        });
+        if let Err(e) = switched {
+            let mut restored = Ok(());
+            for snapshot in snapshots.into_iter().rev() {
+                restored = restored.and(snapshot.restore());
+            }
+            return Err(match restored {
+                Ok(_) => format!(
+                    "{} 切换失败，已恢复所有工具的配置: {}",
+                    tool_label(*tool),
+                    e
+                ),
+                Err(restore_error) => format!(
+                    "{} 切换失败: {}；恢复配置时出错: {}",
+                    tool_label(*tool),
+                    e,
+                    restore_error
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn list_providers_in(dirs: &ProviderDirs) -> Result<ProviderList, String> {
+    let store = load_provider_store_locked(dirs.claude)?;
+
+    let mut current = BTreeMap::new();
+    for tool in PROVIDER_TOOLS {
+        if let Ok(Some(id)) = dirs.backend(tool).and_then(|backend| backend.current()) {
+            current.insert(tool, id);
+        }
+    }
+    let active = store
+        .providers
+        .iter()
+        .find(|provider| {
+            !provider.links.is_empty()
+                && provider
+                    .links
+                    .iter()
+                    .all(|(tool, id)| current.get(tool) == Some(id))
+        })
+        .map(|provider| provider.id.clone());
+
+    Ok(ProviderList {
+        revision: store.revision,
+        providers: store.providers,
+        active,
+    })
+}
+
+// ==================== 服务商管理 ====================
+
+#[tauri::command]
+pub(crate) async fn get_providers(
+    config_path: String,
+    codex_config_path: String,
+) -> ApiResponse<ProviderList> {
+    let result = run_blocking(move || {
+        let dirs = ProviderDirs {
+            claude: Path::new(&config_path),
+            codex: Path::new(&codex_config_path),
+        };
+        list_providers_in(&dirs)
+    })
+    .await;
+    data_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn save_provider(
+    config_path: String,
+    codex_config_path: String,
+    provider: Provider,
+    expected_revision: u64,
+) -> ApiResponse<ProviderStoreChange> {
+    let result = run_blocking(move || {
+        let dirs = ProviderDirs {
+            claude: Path::new(&config_path),
+            codex: Path::new(&codex_config_path),
+        };
+        save_provider_in(&dirs, provider, expected_revision)
+    })
+    .await;
+    data_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn delete_provider(
+    config_path: String,
+    codex_config_path: String,
+    provider_id: String,
+    expected_revision: u64,
+) -> ApiResponse<ProviderStoreChange> {
+    let result = run_blocking(move || {
+        let dirs = ProviderDirs {
+            claude: Path::new(&config_path),
+            codex: Path::new(&codex_config_path),
+        };
+        delete_provider_in(&dirs, &provider_id, expected_revision)
+    })
+    .await;
+    data_response(result)
+}
+
+#[tauri::command]
+pub(crate) async fn switch_everywhere(
+    config_path: String,
+    codex_config_path: String,
+    provider_id: String,
+) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        let dirs = ProviderDirs {
+            claude: Path::new(&config_path),
+            codex: Path::new(&codex_config_path),
+        };
+        switch_provider_everywhere(&dirs, &provider_id)
+    })
+    .await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codex::CODEX_STORE_FILE_NAME;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-provider-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("claude")).unwrap();
+        fs::create_dir_all(dir.join("codex")).unwrap();
+        dir
+    }
+
+    fn dirs(root: &Path) -> (PathBuf, PathBuf) {
+        (root.join("claude"), root.join("codex"))
+    }
+
+    // Droid 切换会修改进程环境变量，测试只派生 Claude 与 Codex 渠道
+    fn provider(name: &str, api_key: &str) -> Provider {
+        Provider {
+            id: String::new(),
+            name: name.to_string(),
+            api_key: api_key.to_string(),
+            anthropic_base_url: "https://relay.example.com".to_string(),
+            openai_base_url: "https://relay.example.com/v1".to_string(),
+            factory_base_url: String::new(),
+            claude_model: String::new(),
+            codex_model: "gpt-5".to_string(),
+            tools: vec![ToolKind::Claude, ToolKind::Codex],
+            links: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn derives_channels_and_keeps_them_in_sync() {
+        let root = create_temp_dir("derive");
+        let (claude, codex) = dirs(&root);
+        let dirs = ProviderDirs {
+            claude: &claude,
+            codex: &codex,
+        };
+
+        let change = save_provider_in(&dirs, provider("relay", "sk-1"), 0).unwrap();
+        let id = change.provider_id.unwrap();
+        let list = list_providers_in(&dirs).unwrap();
+        let saved = &list.providers[0];
+        assert_eq!(saved.links.len(), 2);
+        assert!(claude.join("settings-relay.json").exists());
+
+        let codex_backend = dirs.backend(ToolKind::Codex).unwrap();
+        let codex_channel = codex_backend.find(&saved.links[&ToolKind::Codex]).unwrap();
+        assert_eq!(codex_channel.base_url, "https://relay.example.com/v1");
+        assert_eq!(codex_channel.model, "gpt-5");
+
+        // 改名并取消 Codex：Claude 渠道随之改名，Codex 派生渠道被删除
+        let renamed = Provider {
+            id: id.clone(),
+            name: "relay-2".to_string(),
+            api_key: "sk-2".to_string(),
+            tools: vec![ToolKind::Claude],
+            ..provider("relay", "sk-1")
+        };
+        save_provider_in(&dirs, renamed, change.revision).unwrap();
+        assert!(!claude.join("settings-relay.json").exists());
+        assert!(claude.join("settings-relay-2.json").exists());
+        assert!(codex_backend.list().unwrap().is_empty());
+
+        let stale = save_provider_in(&dirs, provider("other", "sk-3"), change.revision);
+        assert!(stale.unwrap_err().starts_with("[CONFLICT]"));
+
+        delete_provider_in(&dirs, &id, change.revision + 1).unwrap();
+        assert!(!claude.join("settings-relay-2.json").exists());
+        assert!(list_providers_in(&dirs).unwrap().providers.is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn switches_all_linked_tools() {
+        let root = create_temp_dir("switch");
+        let (claude, codex) = dirs(&root);
+        let dirs = ProviderDirs {
+            claude: &claude,
+            codex: &codex,
+        };
+        save_provider_in(&dirs, provider("a", "sk-a"), 0).unwrap();
+        let change = save_provider_in(&dirs, provider("b", "sk-b"), 1).unwrap();
+        let id = change.provider_id.unwrap();
+
+        switch_provider_everywhere(&dirs, &id).unwrap();
+        assert_eq!(list_providers_in(&dirs).unwrap().active, Some(id));
+        let auth = fs::read_to_string(codex.join("auth.json")).unwrap();
+        assert!(auth.contains("sk-b"));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn restores_every_tool_when_one_switch_fails() {
+        let root = create_temp_dir("rollback");
+        let (claude, codex) = dirs(&root);
+        let dirs = ProviderDirs {
+            claude: &claude,
+            codex: &codex,
+        };
+        let original_settings = r#"{"env":{"ANTHROPIC_AUTH_TOKEN":"sk-old"}}"#;
+        fs::write(claude.join("settings.json"), original_settings).unwrap();
+
+        let change = save_provider_in(&dirs, provider("relay", "sk-1"), 0).unwrap();
+        let id = change.provider_id.unwrap();
+        // 在 Codex 中手动删除派生渠道，使 Claude 切换成功后 Codex 切换失败
+        let codex_backend = dirs.backend(ToolKind::Codex).unwrap();
+        let codex_id = codex_backend.list().unwrap()[0].id.clone();
+        codex_backend.delete(&codex_id).unwrap();
+
+        let error = switch_provider_everywhere(&dirs, &id).unwrap_err();
+        assert!(error.contains("Codex"), "{}", error);
+        assert_eq!(
+            fs::read_to_string(claude.join("settings.json")).unwrap(),
+            original_settings
+        );
+        assert!(!codex.join("auth.json").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn rejects_provider_names_that_are_not_valid_channel_names() {
+        let root = create_temp_dir("names");
+        let (claude, codex) = dirs(&root);
+        let dirs = ProviderDirs {
+            claude: &claude,
+            codex: &codex,
+        };
+
+        for name in ["../evil", "a/b", "relay.v2", "name-longer-than-18-chars"] {
+            let error = save_provider_in(&dirs, provider(name, "sk-1"), 0).unwrap_err();
+            assert!(error.contains("服务商名称"), "{}: {}", name, error);
+        }
+        assert!(fs::read_dir(&claude).unwrap().next().is_none());
+        assert!(fs::read_dir(&codex).unwrap().next().is_none());
+        assert!(!root.join("evil").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn restores_earlier_tools_when_syncing_a_later_tool_fails() {
+        let root = create_temp_dir("sync-rollback");
+        let (claude, codex) = dirs(&root);
+        let dirs = ProviderDirs {
+            claude: &claude,
+            codex: &codex,
+        };
+        let change = save_provider_in(&dirs, provider("relay", "sk-1"), 0).unwrap();
+        let id = change.provider_id.unwrap();
+        let read_claude = |name: &str| -> serde_json::Value {
+            serde_json::from_str(&fs::read_to_string(claude.join(name)).unwrap()).unwrap()
+        };
+        let original = read_claude("settings-relay.json");
+        // Codex 渠道存储损坏，Codex 同步必然失败
+        fs::write(codex.join(CODEX_STORE_FILE_NAME), "{ broken").unwrap();
+
+        // Claude 渠道已改名并更新 Key，需恢复
+        let renamed = Provider {
+            id: id.clone(),
+            name: "relay-2".to_string(),
+            ..provider("relay", "sk-2")
+        };
+        let error = save_provider_in(&dirs, renamed, change.revision).unwrap_err();
+        assert!(error.contains("Codex"), "{}", error);
+        assert!(!claude.join("settings-relay-2.json").exists());
+        assert_eq!(read_claude("settings-relay.json"), original);
+
+        // 取消 Claude 时已删除的派生渠道需重新创建
+        let without_claude = Provider {
+            id,
+            tools: vec![ToolKind::Codex],
+            ..provider("relay", "sk-1")
+        };
+        assert!(save_provider_in(&dirs, without_claude, change.revision).is_err());
+        assert!(claude.join("settings-relay.json").exists());
+        assert_eq!(load_provider_store_locked(&claude).unwrap().revision, 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    // Droid 切换会改写进程环境变量与 Shell 环境变量文件，这里把后者重定向到临时目录；
+    // Windows 上还会写入用户环境变量，因此只在其他平台运行
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn restores_the_droid_key_when_the_droid_switch_fails() {
+        let root = create_temp_dir("droid-rollback");
+        let (claude, codex) = dirs(&root);
+        let dirs = ProviderDirs {
+            claude: &claude,
+            codex: &codex,
+        };
+        let original_settings = r#"{"env":{"ANTHROPIC_AUTH_TOKEN":"sk-old"}}"#;
+        fs::write(claude.join("settings.json"), original_settings).unwrap();
+
+        let with_droid = Provider {
+            tools: vec![ToolKind::Claude, ToolKind::Codex, ToolKind::Droid],
+            ..provider("relay", "sk-1")
+        };
+        let change = save_provider_in(&dirs, with_droid, 0).unwrap();
+        let id = change.provider_id.unwrap();
+        // 在 Droid 中手动删除派生渠道，使 Claude、Codex 切换成功后 Droid 切换失败
+        let droid_backend = dirs.backend(ToolKind::Droid).unwrap();
+        droid_backend.delete("relay").unwrap();
+
+        let xdg = root.join("xdg");
+        let previous_xdg = std::env::var_os("XDG_CONFIG_HOME");
+        let previous_key = std::env::var_os("FACTORY_API_KEY");
+        std::env::set_var("XDG_CONFIG_HOME", &xdg);
+        std::env::set_var("FACTORY_API_KEY", "fk-before");
+
+        let error = switch_provider_everywhere(&dirs, &id).unwrap_err();
+
+        let restored_key = std::env::var("FACTORY_API_KEY");
+        let env_sh = fs::read_to_string(xdg.join("claude-channel-switcher").join("env.sh"));
+        match previous_xdg {
+            Some(value) => std::env::set_var("XDG_CONFIG_HOME", value),
+            None => std::env::remove_var("XDG_CONFIG_HOME"),
+        }
+        match previous_key {
+            Some(value) => std::env::set_var("FACTORY_API_KEY", value),
+            None => std::env::remove_var("FACTORY_API_KEY"),
+        }
+
+        assert!(error.contains("Droid"), "{}", error);
+        assert_eq!(restored_key.as_deref(), Ok("fk-before"));
+        // 原来的 Key 被重新应用到 Shell 环境变量文件
+        assert!(env_sh.unwrap().contains("fk-before"));
+        assert_eq!(
+            fs::read_to_string(claude.join("settings.json")).unwrap(),
+            original_settings
+        );
+        assert!(!codex.join("auth.json").exists());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,2686 @@
+use crate::fs_utils::{ensure_unchanged, file_stamp, run_blocking, with_dir_lock, write_text_file};
+use crate::ApiResponse;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const STATUSLINE_SPEC_VERSION: u32 = 1;
+const STATUSLINE_FILE_PREFIX: &str = "statusline_";
+const UTF8_BOM: &str = "\u{FEFF}";
+
+// ==================== StatusLine 样式定义 ====================
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StatuslineSegmentKind {
+    Model,
+    Context,
+    Tokens,
+    Cache,
+    Cost,
+    #[serde(alias = "cwd")]
+    Dir,
+    Time,
+    Date,
+    User,
+    GitBranch,
+    Channel,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct StatuslineSegment {
+    kind: StatuslineSegmentKind,
+    #[serde(default = "default_segment_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    emoji: String,
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    show_label: bool,
+    color: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct StatuslineSeparator {
+    text: String,
+    color: u8,
+    #[serde(default)]
+    show_start: bool,
+    #[serde(default)]
+    show_end: bool,
+}
+
+// 声明式样式：持久化为 statusline_<name>.json，脚本由其生成
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub(crate) struct StatuslineSpec {
+    #[serde(default = "statusline_spec_version")]
+    version: u32,
+    separator: StatuslineSeparator,
+    #[serde(default)]
+    segments: Vec<StatuslineSegment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StatuslineShell {
+    Powershell,
+    Posix,
+}
+
+fn default_segment_enabled() -> bool {
+    true
+}
+
+fn statusline_spec_version() -> u32 {
+    STATUSLINE_SPEC_VERSION
+}
+
+fn segment(
+    kind: StatuslineSegmentKind,
+    enabled: bool,
+    emoji: &str,
+    label: &str,
+    color: u8,
+) -> StatuslineSegment {
+    StatuslineSegment {
+        kind,
+        enabled,
+        emoji: emoji.to_string(),
+        label: label.to_string(),
+        show_label: false,
+        color,
+    }
+}
+
+// 与前端默认配置保持一致，新增的项目默认禁用
+fn default_statusline_spec() -> StatuslineSpec {
+    use StatuslineSegmentKind::*;
+
+    StatuslineSpec {
+        version: STATUSLINE_SPEC_VERSION,
+        separator: StatuslineSeparator {
+            text: "|".to_string(),
+            color: 252,
+            show_start: false,
+            show_end: false,
+        },
+        segments: vec![
+            segment(Model, true, "🤖", "Model", 81),
+            segment(Context, true, "⏳", "Ctx", 221),
+            segment(Tokens, true, "🧮", "Token", 153),
+            segment(Cache, true, "🎭", "Cache", 183),
+            segment(Cost, true, "💰", "Cost", 222),
+            segment(Dir, true, "📁", "Dir", 147),
+            segment(Time, true, "🕐", "Time", 117),
+            segment(Date, false, "📅", "Date", 117),
+            segment(User, false, "👤", "User", 180),
+            segment(GitBranch, false, "🌿", "Branch", 114),
+            segment(Channel, false, "🔀", "Channel", 216),
+        ],
+    }
+}
+
+fn is_valid_statusline_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().count() <= 32
+        && name.chars().all(|ch| {
+            ch.is_ascii_alphanumeric()
+                || ch == '-'
+                || ch == '_'
+                || ('\u{4e00}'..='\u{9fa5}').contains(&ch)
+        })
+}
+
+fn validate_display_text(text: &str, max_chars: usize, field: &str) -> Result<(), String> {
+    if text.chars().count() > max_chars {
+        return Err(format!("{}最多 {} 个字符", field, max_chars));
+    }
+    if text.chars().any(char::is_control) {
+        return Err(format!("{}不能包含控制字符", field));
+    }
+    Ok(())
+}
+
+fn validate_statusline_spec(spec: &StatuslineSpec) -> Result<(), String> {
+    if spec.version != STATUSLINE_SPEC_VERSION {
+        return Err(format!("不支持的 StatusLine 样式版本: {}", spec.version));
+    }
+
+    validate_display_text(&spec.separator.text, 8, "分隔符")?;
+
+    let mut seen = HashSet::new();
+    for segment in &spec.segments {
+        if !seen.insert(segment.kind) {
+            return Err(format!("显示项目重复: {:?}", segment.kind));
+        }
+        validate_display_text(&segment.emoji, 8, "图标")?;
+        validate_display_text(&segment.label, 20, "名称")?;
+    }
+
+    Ok(())
+}
+
+// 兼容旧版前端生成的脚本：从首行 `# CONFIG:{...}` 注释还原样式
+fn spec_from_legacy_script(content: &str) -> Option<StatuslineSpec> {
+    let config_line = content
+        .lines()
+        .find_map(|line| line.trim_start_matches(UTF8_BOM).strip_prefix("# CONFIG:"))?;
+    let legacy: serde_json::Value = serde_json::from_str(config_line.trim()).ok()?;
+
+    let mut spec = default_statusline_spec();
+
+    if let Some(sep) = legacy.get("sep").and_then(|v| v.as_str()) {
+        spec.separator.text = sep.to_string();
+    }
+    if let Some(color) = legacy.get("sepColor").and_then(|v| v.as_u64()) {
+        spec.separator.color = color.min(255) as u8;
+    }
+    spec.separator.show_start = legacy.get("sepStart").and_then(|v| v.as_u64()) == Some(1);
+    spec.separator.show_end = legacy.get("sepEnd").and_then(|v| v.as_u64()) == Some(1);
+
+    // 旧格式的项目: [type, emoji, label, showLabel, enabled, color]
+    if let Some(items) = legacy.get("items").and_then(|v| v.as_array()) {
+        let mut ordered = Vec::new();
+        for item in items.iter().filter_map(|v| v.as_array()) {
+            let Some(kind) = item
+                .first()
+                .and_then(|v| serde_json::from_value::<StatuslineSegmentKind>(v.clone()).ok())
+            else {
+                continue;
+            };
+            let Some(pos) = spec.segments.iter().position(|s| s.kind == kind) else {
+                continue;
+            };
+            let mut current = spec.segments.remove(pos);
+            if let Some(emoji) = item.get(1).and_then(|v| v.as_str()) {
+                current.emoji = emoji.to_string();
+            }
+            if let Some(label) = item.get(2).and_then(|v| v.as_str()) {
+                current.label = label.to_string();
+            }
+            current.show_label = item.get(3).and_then(|v| v.as_u64()) == Some(1);
+            current.enabled = item.get(4).and_then(|v| v.as_u64()) == Some(1);
+            if let Some(color) = item.get(5).and_then(|v| v.as_u64()) {
+                current.color = color.min(255) as u8;
+            }
+            ordered.push(current);
+        }
+        ordered.append(&mut spec.segments);
+        spec.segments = ordered;
+    }
+
+    Some(spec)
+}
+
+// ==================== StatusLine 脚本生成 ====================
+
+fn enabled_segments(spec: &StatuslineSpec) -> Vec<&StatuslineSegment> {
+    spec.segments.iter().filter(|s| s.enabled).collect()
+}
+
+fn uses_any(segments: &[&StatuslineSegment], kinds: &[StatuslineSegmentKind]) -> bool {
+    segments.iter().any(|s| kinds.contains(&s.kind))
+}
+
+// 可能为空的项目，为空时整项不输出
+fn is_optional_segment(kind: StatuslineSegmentKind) -> bool {
+    matches!(
+        kind,
+        StatuslineSegmentKind::GitBranch | StatuslineSegmentKind::Channel
+    )
+}
+
+// PowerShell 双引号字符串转义，弯引号在 PowerShell 中同样是字符串定界符
+fn escape_powershell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '`' | '$' | '"' | '\u{201C}' | '\u{201D}' | '\u{201E}') {
+            escaped.push('`');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+// POSIX sh 双引号字符串转义
+fn escape_posix(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '"' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+fn powershell_icon(emoji: &str) -> String {
+    let parts: Vec<String> = emoji
+        .chars()
+        .map(|ch| format!("[char]::ConvertFromUtf32(0x{:X})", ch as u32))
+        .collect();
+    if parts.len() == 1 {
+        parts[0].clone()
+    } else {
+        format!("({})", parts.join(" + "))
+    }
+}
+
+fn powershell_value(kind: StatuslineSegmentKind) -> &'static str {
+    match kind {
+        StatuslineSegmentKind::Model => "$($data.model.display_name)",
+        StatuslineSegmentKind::Context => "$pct% ($usedK/$(K $maxTk))",
+        StatuslineSegmentKind::Tokens => "$(K ($inTk+$outTk)) (I:$(K $inTk) O:$(K $outTk))",
+        StatuslineSegmentKind::Cache => "R$(K $cacheR) W$(K $cacheW)",
+        StatuslineSegmentKind::Cost => "`$$cost",
+        StatuslineSegmentKind::Dir => "$currentDir",
+        StatuslineSegmentKind::Time => "$currentTime",
+        StatuslineSegmentKind::Date => "$currentDate",
+        StatuslineSegmentKind::User => "$currentUser",
+        StatuslineSegmentKind::GitBranch => "$gitBranch",
+        StatuslineSegmentKind::Channel => "$channelName",
+    }
+}
+
+fn powershell_condition(kind: StatuslineSegmentKind) -> &'static str {
+    match kind {
+        StatuslineSegmentKind::GitBranch => "$gitBranch",
+        _ => "$channelName",
+    }
+}
+
+fn posix_value(kind: StatuslineSegmentKind) -> &'static str {
+    match kind {
+        StatuslineSegmentKind::Model => "${model}",
+        StatuslineSegmentKind::Context => "${pct}% (${usedK}/$(K \"$maxTk\"))",
+        StatuslineSegmentKind::Tokens => {
+            "$(K $((inTk + outTk))) (I:$(K \"$inTk\") O:$(K \"$outTk\"))"
+        }
+        StatuslineSegmentKind::Cache => "R$(K \"$cacheR\") W$(K \"$cacheW\")",
+        StatuslineSegmentKind::Cost => "\\$${cost}",
+        StatuslineSegmentKind::Dir => "${currentDir}",
+        StatuslineSegmentKind::Time => "${currentTime}",
+        StatuslineSegmentKind::Date => "${currentDate}",
+        StatuslineSegmentKind::User => "${currentUser}",
+        StatuslineSegmentKind::GitBranch => "${gitBranch}",
+        StatuslineSegmentKind::Channel => "${channelName}",
+    }
+}
+
+fn posix_condition(kind: StatuslineSegmentKind) -> &'static str {
+    match kind {
+        StatuslineSegmentKind::GitBranch => "$gitBranch",
+        _ => "$channelName",
+    }
+}
+
+fn used_colors(spec: &StatuslineSpec, segments: &[&StatuslineSegment]) -> Vec<u8> {
+    let mut colors = vec![spec.separator.color];
+    for segment in segments {
+        if segment.kind != StatuslineSegmentKind::Context && !colors.contains(&segment.color) {
+            colors.push(segment.color);
+        }
+    }
+    colors
+}
+
+fn render_powershell(spec: &StatuslineSpec) -> String {
+    use StatuslineSegmentKind::*;
+
+    let segments = enabled_segments(spec);
+    let mut lines: Vec<String> = Vec::new();
+    let mut push = |line: &str| lines.push(line.to_string());
+
+    push("# ============================================================");
+    push("# Claude Code 自定义状态栏脚本 (Windows PowerShell)");
+    push("# 由 Claude 渠道切换器根据样式配置生成，请在应用内修改样式");
+    push("# ============================================================");
+    push("");
+    push("[Console]::OutputEncoding = [System.Text.Encoding]::UTF8");
+    push("$esc = [char]27");
+    push("$reset = \"$esc[0m\"");
+    push("");
+    push("# 读取 Claude Code 传入的 JSON 数据");
+    push("$data = [Console]::In.ReadToEnd() | ConvertFrom-Json");
+    push("");
+    push("# 格式化数字 (1000+ 显示为 k)");
+    push("function K($n) { if ($n -ge 1000) { \"$([math]::Round($n/1000.0,1))k\" } else { \"$n\" } }");
+    push("");
+
+    if uses_any(&segments, &[Dir, GitBranch]) {
+        push("$fullPath = if ($data.cwd) { $data.cwd } else { (Get-Location).Path }");
+    }
+    if uses_any(&segments, &[Dir]) {
+        push("# 目录显示: D:\\first\\A~\\B~\\last (中间目录用首字母~缩写)");
+        push("$pathParts = $fullPath -split '\\\\'");
+        push("$currentDir = if ($pathParts.Length -le 3) { $fullPath } else {");
+        push("    $first = $pathParts[0]");
+        push("    $second = $pathParts[1]");
+        push(
+            "    $middle = $pathParts[2..($pathParts.Length-2)] | ForEach-Object { \"$($_[0])~\" }",
+        );
+        push("    $last = $pathParts[-1]");
+        push("    (@($first, $second) + $middle + $last) -join '\\'");
+        push("}");
+        push("");
+    }
+    if uses_any(&segments, &[Context, Tokens, Cache]) {
+        push("# 提取数值");
+        push("$pct = [math]::Round($data.context_window.used_percentage, 1)");
+        push("$inTk = $data.context_window.total_input_tokens");
+        push("$outTk = $data.context_window.total_output_tokens");
+        push("$maxTk = if ($data.context_window.max_tokens) { $data.context_window.max_tokens } else { 200000 }");
+        push("$cacheR = if ($data.context_window.current_usage.cache_read_input_tokens) { $data.context_window.current_usage.cache_read_input_tokens } else { 0 }");
+        push("$cacheW = if ($data.context_window.current_usage.cache_creation_input_tokens) { $data.context_window.current_usage.cache_creation_input_tokens } else { 0 }");
+        push("$usedK = K ([math]::Round($maxTk * $pct / 100))");
+        push("");
+    }
+    if uses_any(&segments, &[Cost]) {
+        push("$cost = if ($data.cost.total_cost_usd) { [math]::Round($data.cost.total_cost_usd, 4) } else { 0 }");
+    }
+    if uses_any(&segments, &[Time]) {
+        push("$currentTime = (Get-Date).ToString(\"HH:mm\")");
+    }
+    if uses_any(&segments, &[Date]) {
+        push("$currentDate = (Get-Date).ToString(\"yyyy-MM-dd\")");
+    }
+    if uses_any(&segments, &[User]) {
+        push("$currentUser = [Environment]::UserName");
+    }
+    if uses_any(&segments, &[GitBranch]) {
+        push("$gitBranch = try { git -C $fullPath branch --show-current 2>$null } catch { \"\" }");
+    }
+    if uses_any(&segments, &[Channel]) {
+        push("# 当前渠道: 用 settings.json 的 env 匹配 settings-<渠道>.json");
+        push("$channelName = \"\"");
+        push("$claudeDir = Join-Path $HOME '.claude'");
+        push("try {");
+        push("    $activeEnv = (Get-Content (Join-Path $claudeDir 'settings.json') -Raw | ConvertFrom-Json).env");
+        push("    foreach ($f in Get-ChildItem -Path $claudeDir -Filter 'settings-*.json') {");
+        push("        $candidate = (Get-Content $f.FullName -Raw | ConvertFrom-Json).env");
+        push("        if ($candidate.ANTHROPIC_AUTH_TOKEN -eq $activeEnv.ANTHROPIC_AUTH_TOKEN -and $candidate.ANTHROPIC_BASE_URL -eq $activeEnv.ANTHROPIC_BASE_URL) {");
+        push("            $channelName = $f.BaseName.Substring(9)");
+        push("            break");
+        push("        }");
+        push("    }");
+        push("} catch { }");
+    }
+    push("");
+
+    push("# 颜色定义 (256色)");
+    for color in used_colors(spec, &segments) {
+        push(&format!("$c{} = \"$esc[38;5;{}m\"", color, color));
+    }
+    if uses_any(&segments, &[Context]) {
+        push("# 上下文颜色: 绿(<50%) / 黄(50-80%) / 红(>80%)");
+        push("$cPct = if ($pct -gt 80) { \"$esc[38;5;210m\" } elseif ($pct -gt 50) { \"$esc[38;5;221m\" } else { \"$esc[38;5;114m\" }");
+    }
+    push("");
+
+    push("# 图标 (Unicode Emoji)");
+    for (idx, segment) in segments.iter().enumerate() {
+        if !segment.emoji.is_empty() {
+            push(&format!("$i{} = {}", idx, powershell_icon(&segment.emoji)));
+        }
+    }
+    push("");
+
+    push("# 定义所有项目");
+    push("$items = @()");
+    for (idx, segment) in segments.iter().enumerate() {
+        let color_var = if segment.kind == Context {
+            "${cPct}".to_string()
+        } else {
+            format!("${{c{}}}", segment.color)
+        };
+        let icon = if segment.emoji.is_empty() {
+            String::new()
+        } else {
+            format!("${{i{}}} ", idx)
+        };
+        let label = if segment.show_label {
+            format!("{}:", escape_powershell(&segment.label))
+        } else {
+            String::new()
+        };
+        let item = format!(
+            "$items += \"{}{}{}{}$reset\"",
+            color_var,
+            icon,
+            label,
+            powershell_value(segment.kind)
+        );
+        if is_optional_segment(segment.kind) {
+            push(&format!(
+                "if ({}) {{ {} }}",
+                powershell_condition(segment.kind),
+                item
+            ));
+        } else {
+            push(&item);
+        }
+    }
+    push("");
+
+    push("# 获取终端宽度");
+    push("$termWidth = $Host.UI.RawUI.WindowSize.Width");
+    push("if (-not $termWidth -or $termWidth -lt 40) { $termWidth = 120 }");
+    push(&format!(
+        "$sep = \"${{c{}}}{}$reset\"",
+        spec.separator.color,
+        escape_powershell(&spec.separator.text)
+    ));
+    push("");
+    push("# 计算显示宽度（去除ANSI转义序列）");
+    push("function Get-DisplayWidth($str) {");
+    push("    $clean = $str -replace \"$esc\\[[0-9;]*m\", ''");
+    push("    # Emoji占2个字符宽度");
+    push("    $emojiCount = ([regex]::Matches($clean, '[\\uD800-\\uDBFF][\\uDC00-\\uDFFF]|[\\u2600-\\u26FF\\u2700-\\u27BF]')).Count");
+    push("    return $clean.Length + $emojiCount");
+    push("}");
+    push("");
+    push("$sepLen = (Get-DisplayWidth $sep) + 2");
+    push("");
+    push("# 智能换行组装");
+    if spec.separator.show_start {
+        push("$currentLine = \"$sep \"");
+        push("$currentWidth = $sepLen - 1");
+    } else {
+        push("$currentLine = \"\"");
+        push("$currentWidth = 0");
+    }
+    push("$lines = @()");
+    push("");
+    push("foreach ($item in $items) {");
+    push("    $itemWidth = Get-DisplayWidth $item");
+    push("    $needed = $itemWidth + $sepLen");
+    push("    if (($currentWidth + $needed) -gt $termWidth -and $currentWidth -gt 2) {");
+    push("        # 当前行放不下这个项目，换行");
+    push("        $lines += $currentLine.TrimEnd()");
+    push("        $currentLine = \"   $item $sep \"  # 新行缩进");
+    push("        $currentWidth = 3 + $itemWidth + $sepLen");
+    push("    } else {");
+    push("        $currentLine += \"$item $sep \"");
+    push("        $currentWidth += $needed");
+    push("    }");
+    push("}");
+    push("");
+    push("# 移除末尾的分隔符");
+    push("$sepPattern = \" \" + $sep + \" \"");
+    push("if ($currentLine.EndsWith($sepPattern)) {");
+    push("    $currentLine = $currentLine.Substring(0, $currentLine.Length - $sepPattern.Length)");
+    push("}");
+    if spec.separator.show_end {
+        push("$currentLine = $currentLine + \" $sep\"");
+    }
+    push("$lines += $currentLine");
+    push("");
+    push("Write-Output ($lines -join \"`n\")");
+    push("");
+
+    lines.join("\r\n")
+}
+
+fn render_posix(spec: &StatuslineSpec) -> String {
+    use StatuslineSegmentKind::*;
+
+    let segments = enabled_segments(spec);
+    let mut lines: Vec<String> = Vec::new();
+    let mut push = |line: &str| lines.push(line.to_string());
+
+    push("#!/bin/sh");
+    push("# ============================================================");
+    push("# Claude Code 自定义状态栏脚本 (POSIX sh)");
+    push("# 由 Claude 渠道切换器根据样式配置生成，请在应用内修改样式");
+    push("# 依赖: jq");
+    push("# ============================================================");
+    push("");
+    push("input=$(cat)");
+    push("esc=$(printf '\\033')");
+    push("reset=\"${esc}[0m\"");
+    push("");
+    push("# 读取 Claude Code 传入的 JSON 字段");
+    push("field() { printf '%s' \"$input\" | jq -r \"$1 // empty\" 2>/dev/null; }");
+    push("# 格式化数字 (1000+ 显示为 k)");
+    push("K() { awk -v n=\"${1:-0}\" 'BEGIN { if (n >= 1000) print int(n / 100 + 0.5) / 10 \"k\"; else printf \"%d\", n }'; }");
+    push("");
+
+    if uses_any(&segments, &[Model]) {
+        push("model=$(field '.model.display_name')");
+    }
+    if uses_any(&segments, &[Dir, GitBranch]) {
+        push("fullPath=$(field '.cwd')");
+        push("[ -n \"$fullPath\" ] || fullPath=$(pwd)");
+    }
+    if uses_any(&segments, &[Dir]) {
+        push("case \"$fullPath\" in");
+        push("    \"$HOME\"*) currentDir=\"~${fullPath#\"$HOME\"}\" ;;");
+        push("    *) currentDir=$fullPath ;;");
+        push("esac");
+    }
+    if uses_any(&segments, &[Context, Tokens, Cache]) {
+        push("# 提取数值");
+        push("pct=$(awk -v p=\"$(field '.context_window.used_percentage')\" 'BEGIN { print int(p * 10 + 0.5) / 10 }')");
+        push("inTk=$(field '.context_window.total_input_tokens'); inTk=${inTk:-0}");
+        push("outTk=$(field '.context_window.total_output_tokens'); outTk=${outTk:-0}");
+        push("maxTk=$(field '.context_window.max_tokens'); maxTk=${maxTk:-200000}");
+        push("cacheR=$(field '.context_window.current_usage.cache_read_input_tokens'); cacheR=${cacheR:-0}");
+        push("cacheW=$(field '.context_window.current_usage.cache_creation_input_tokens'); cacheW=${cacheW:-0}");
+        push("usedK=$(K \"$(awk -v m=\"$maxTk\" -v p=\"$pct\" 'BEGIN { printf \"%d\", m * p / 100 + 0.5 }')\")");
+    }
+    if uses_any(&segments, &[Cost]) {
+        push("cost=$(awk -v c=\"$(field '.cost.total_cost_usd')\" 'BEGIN { printf \"%.4f\", c + 0 }')");
+    }
+    if uses_any(&segments, &[Time]) {
+        push("currentTime=$(date +%H:%M)");
+    }
+    if uses_any(&segments, &[Date]) {
+        push("currentDate=$(date +%Y-%m-%d)");
+    }
+    if uses_any(&segments, &[User]) {
+        push("currentUser=${USER:-$(id -un)}");
+    }
+    if uses_any(&segments, &[GitBranch]) {
+        push("gitBranch=$(git -C \"$fullPath\" branch --show-current 2>/dev/null)");
+    }
+    if uses_any(&segments, &[Channel]) {
+        push("# 当前渠道: 用 settings.json 的 env 匹配 settings-<渠道>.json");
+        push("channelName=''");
+        push("claudeDir=\"$HOME/.claude\"");
+        push("envOf() { jq -r '(.env.ANTHROPIC_AUTH_TOKEN // \"\") + \" \" + (.env.ANTHROPIC_BASE_URL // \"\")' \"$1\" 2>/dev/null; }");
+        push("activeEnv=$(envOf \"$claudeDir/settings.json\")");
+        push("if [ -n \"${activeEnv% }\" ]; then");
+        push("    for f in \"$claudeDir\"/settings-*.json; do");
+        push("        [ -f \"$f\" ] || continue");
+        push("        if [ \"$(envOf \"$f\")\" = \"$activeEnv\" ]; then");
+        push("            channelName=${f##*/settings-}");
+        push("            channelName=${channelName%.json}");
+        push("            break");
+        push("        fi");
+        push("    done");
+        push("fi");
+    }
+    push("");
+
+    push("# 颜色定义 (256色)");
+    for color in used_colors(spec, &segments) {
+        push(&format!("c{}=\"${{esc}}[38;5;{}m\"", color, color));
+    }
+    if uses_any(&segments, &[Context]) {
+        push("# 上下文颜色: 绿(<50%) / 黄(50-80%) / 红(>80%)");
+        push("cPct=\"${esc}[38;5;$(awk -v p=\"$pct\" 'BEGIN { if (p > 80) print 210; else if (p > 50) print 221; else print 114 }')m\"");
+    }
+    push(&format!(
+        "sep=\"${{c{}}}{}${{reset}}\"",
+        spec.separator.color,
+        escape_posix(&spec.separator.text)
+    ));
+    push("");
+
+    push("# 组装输出");
+    push("out=''");
+    push("add() { if [ -n \"$out\" ]; then out=\"$out $sep $1\"; else out=$1; fi; }");
+    for segment in &segments {
+        let color_var = if segment.kind == Context {
+            "${cPct}".to_string()
+        } else {
+            format!("${{c{}}}", segment.color)
+        };
+        let icon = if segment.emoji.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", escape_posix(&segment.emoji))
+        };
+        let label = if segment.show_label {
+            format!("{}:", escape_posix(&segment.label))
+        } else {
+            String::new()
+        };
+        let item = format!(
+            "add \"{}{}{}{}${{reset}}\"",
+            color_var,
+            icon,
+            label,
+            posix_value(segment.kind)
+        );
+        if is_optional_segment(segment.kind) {
+            push(&format!(
+                "if [ -n \"{}\" ]; then {}; fi",
+                posix_condition(segment.kind),
+                item
+            ));
+        } else {
+            push(&item);
+        }
+    }
+    if spec.separator.show_start {
+        push("out=\"$sep $out\"");
+    }
+    if spec.separator.show_end {
+        push("out=\"$out $sep\"");
+    }
+    push("printf '%s\\n' \"$out\"");
+    push("");
+
+    lines.join("\n")
+}
+
+pub(crate) fn render_statusline_script(spec: &StatuslineSpec, shell: StatuslineShell) -> String {
+    match shell {
+        StatuslineShell::Powershell => render_powershell(spec),
+        StatuslineShell::Posix => render_posix(spec),
+    }
+}
+
+// ==================== 内置 StatusLine 渲染器 ====================
+
+// 会话 JSON 之外的运行环境信息，单独收集便于测试
+struct StatuslineEnvironment {
+    time: String,
+    date: String,
+    user: String,
+    home: Option<String>,
+    git_branch: Option<String>,
+    channel: Option<String>,
+}
+
+fn session_f64(session: &serde_json::Value, path: &[&str]) -> Option<f64> {
+    path.iter()
+        .try_fold(session, |value, key| value.get(key))
+        .and_then(serde_json::Value::as_f64)
+}
+
+fn session_str<'a>(session: &'a serde_json::Value, path: &[&str]) -> Option<&'a str> {
+    path.iter()
+        .try_fold(session, |value, key| value.get(key))
+        .and_then(serde_json::Value::as_str)
+        .filter(|value| !value.is_empty())
+}
+
+fn round_to(value: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (value * factor).round() / factor
+}
+
+// 与脚本中的 K 函数一致：1000+ 显示为 k
+fn format_k(n: f64) -> String {
+    if n >= 1000.0 {
+        format!("{}k", round_to(n / 1000.0, 1))
+    } else {
+        format!("{}", n.round() as i64)
+    }
+}
+
+// 反斜杠路径按 PowerShell 脚本缩写中间目录，其他路径将主目录替换为 ~
+fn shorten_dir(full_path: &str, home: Option<&str>) -> String {
+    if full_path.contains('\\') {
+        let parts: Vec<&str> = full_path.split('\\').collect();
+        if parts.len() <= 3 {
+            return full_path.to_string();
+        }
+        let mut shortened = vec![parts[0].to_string(), parts[1].to_string()];
+        for part in &parts[2..parts.len() - 1] {
+            if let Some(first) = part.chars().next() {
+                shortened.push(format!("{}~", first));
+            }
+        }
+        shortened.push(parts[parts.len() - 1].to_string());
+        return shortened.join("\\");
+    }
+
+    match home.and_then(|home| full_path.strip_prefix(home)) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("~{}", rest),
+        _ => full_path.to_string(),
+    }
+}
+
+fn context_color(pct: f64) -> u8 {
+    if pct > 80.0 {
+        210
+    } else if pct > 50.0 {
+        221
+    } else {
+        114
+    }
+}
+
+fn session_dir(session: &serde_json::Value) -> Option<String> {
+    session_str(session, &["cwd"])
+        .or_else(|| session_str(session, &["workspace", "current_dir"]))
+        .map(str::to_string)
+        .or_else(|| {
+            std::env::current_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().to_string())
+        })
+}
+
+fn segment_value(
+    kind: StatuslineSegmentKind,
+    session: &serde_json::Value,
+    env: &StatuslineEnvironment,
+) -> Option<String> {
+    let context = |key: &str| session_f64(session, &["context_window", key]).unwrap_or(0.0);
+    let pct = round_to(context("used_percentage"), 1);
+    let max_tokens = session_f64(session, &["context_window", "max_tokens"])
+        .filter(|value| *value > 0.0)
+        .unwrap_or(200000.0);
+
+    let value = match kind {
+        StatuslineSegmentKind::Model => session_str(session, &["model", "display_name"])
+            .unwrap_or("")
+            .to_string(),
+        StatuslineSegmentKind::Context => format!(
+            "{}% ({}/{})",
+            pct,
+            format_k((max_tokens * pct / 100.0).round()),
+            format_k(max_tokens)
+        ),
+        StatuslineSegmentKind::Tokens => {
+            let input = context("total_input_tokens");
+            let output = context("total_output_tokens");
+            format!(
+                "{} (I:{} O:{})",
+                format_k(input + output),
+                format_k(input),
+                format_k(output)
+            )
+        }
+        StatuslineSegmentKind::Cache => {
+            let usage = |key: &str| {
+                session_f64(session, &["context_window", "current_usage", key]).unwrap_or(0.0)
+            };
+            format!(
+                "R{} W{}",
+                format_k(usage("cache_read_input_tokens")),
+                format_k(usage("cache_creation_input_tokens"))
+            )
+        }
+        StatuslineSegmentKind::Cost => format!(
+            "${}",
+            round_to(
+                session_f64(session, &["cost", "total_cost_usd"]).unwrap_or(0.0),
+                4
+            )
+        ),
+        StatuslineSegmentKind::Dir => shorten_dir(
+            &session_dir(session).unwrap_or_default(),
+            env.home.as_deref(),
+        ),
+        StatuslineSegmentKind::Time => env.time.clone(),
+        StatuslineSegmentKind::Date => env.date.clone(),
+        StatuslineSegmentKind::User => env.user.clone(),
+        StatuslineSegmentKind::GitBranch => env.git_branch.clone()?,
+        StatuslineSegmentKind::Channel => env.channel.clone()?,
+    };
+
+    Some(value)
+}
+
+fn colorize(color: u8, text: &str) -> String {
+    format!("\x1b[38;5;{}m{}\x1b[0m", color, text)
+}
+
+fn render_statusline_native(
+    spec: &StatuslineSpec,
+    session: &serde_json::Value,
+    env: &StatuslineEnvironment,
+) -> String {
+    let items: Vec<String> = enabled_segments(spec)
+        .into_iter()
+        .filter_map(|segment| {
+            let value = segment_value(segment.kind, session, env)?;
+            if is_optional_segment(segment.kind) && value.is_empty() {
+                return None;
+            }
+
+            let mut text = String::new();
+            if !segment.emoji.is_empty() {
+                text.push_str(&segment.emoji);
+                text.push(' ');
+            }
+            if segment.show_label {
+                text.push_str(&segment.label);
+                text.push(':');
+            }
+            text.push_str(&value);
+
+            let color = if segment.kind == StatuslineSegmentKind::Context {
+                context_color(round_to(
+                    session_f64(session, &["context_window", "used_percentage"]).unwrap_or(0.0),
+                    1,
+                ))
+            } else {
+                segment.color
+            };
+            Some(colorize(color, &text))
+        })
+        .collect();
+
+    let sep = colorize(spec.separator.color, &spec.separator.text);
+    let mut line = items.join(&format!(" {} ", sep));
+    if spec.separator.show_start {
+        line = format!("{} {}", sep, line);
+    }
+    if spec.separator.show_end {
+        line = format!("{} {}", line, sep);
+    }
+    line
+}
+
+// 直接读取 .git/HEAD，避免每次刷新都启动 git 进程
+fn read_git_branch(dir: &Path) -> Option<String> {
+    for ancestor in dir.ancestors() {
+        let git_path = ancestor.join(".git");
+        let git_dir = if git_path.is_dir() {
+            git_path
+        } else if git_path.is_file() {
+            // worktree / submodule: .git 文件内容为 "gitdir: <路径>"
+            let content = fs::read_to_string(&git_path).ok()?;
+            let target = content.trim().strip_prefix("gitdir:")?.trim().to_string();
+            ancestor.join(target)
+        } else {
+            continue;
+        };
+
+        let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        return head
+            .trim()
+            .strip_prefix("ref: refs/heads/")
+            .map(str::to_string);
+    }
+    None
+}
+
+fn collect_environment(
+    spec: &StatuslineSpec,
+    session: &serde_json::Value,
+) -> StatuslineEnvironment {
+    let segments = enabled_segments(spec);
+    let home = std::env::var("USERPROFILE")
+        .or_else(|_| std::env::var("HOME"))
+        .ok();
+    let now = chrono::Local::now();
+
+    let git_branch = if uses_any(&segments, &[StatuslineSegmentKind::GitBranch]) {
+        session_dir(session).and_then(|dir| read_git_branch(Path::new(&dir)))
+    } else {
+        None
+    };
+    let channel = match (
+        &home,
+        uses_any(&segments, &[StatuslineSegmentKind::Channel]),
+    ) {
+        (Some(home), true) => {
+            crate::find_active_channel_name(&Path::new(home).join(".claude").to_string_lossy())
+        }
+        _ => None,
+    };
+
+    StatuslineEnvironment {
+        time: now.format("%H:%M").to_string(),
+        date: now.format("%Y-%m-%d").to_string(),
+        user: std::env::var("USERNAME")
+            .or_else(|_| std::env::var("USER"))
+            .unwrap_or_default(),
+        home,
+        git_branch,
+        channel,
+    }
+}
+
+// 命令行入口：claude-channel-switcher statusline [--style <名称>]
+// 从标准输入读取 Claude Code 的会话 JSON，向标准输出写入一行状态栏
+pub(crate) fn run_statusline_renderer(args: &[String]) -> i32 {
+    let style = args
+        .windows(2)
+        .find(|pair| pair[0] == "--style")
+        .map(|pair| pair[1].clone());
+
+    let spec = match style {
+        Some(name) => match statusline_dir().and_then(|dir| load_statusline_spec(&dir, &name)) {
+            Ok(spec) => spec,
+            Err(e) => {
+                println!("StatusLine 样式加载失败: {}", e);
+                return 1;
+            }
+        },
+        None => default_statusline_spec(),
+    };
+
+    let mut input = String::new();
+    let _ = std::io::stdin().read_to_string(&mut input);
+    let session: serde_json::Value =
+        serde_json::from_str(&input).unwrap_or_else(|_| serde_json::json!({}));
+
+    let env = collect_environment(&spec, &session);
+    println!("{}", render_statusline_native(&spec, &session, &env));
+    0
+}
+
+// ==================== StatusLine 预览 ====================
+
+const STATUSLINE_PREVIEW_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StatuslinePreviewMode {
+    Native,
+    Powershell,
+    Posix,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct StatuslineSpan {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    bold: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct StatuslinePreview {
+    output: String,
+    lines: Vec<Vec<StatuslineSpan>>,
+}
+
+// 与前端预览使用同一组模拟数据
+fn sample_session(cwd: &str) -> serde_json::Value {
+    serde_json::json!({
+        "hook_event_name": "Status",
+        "session_id": "statusline-preview",
+        "transcript_path": "",
+        "cwd": cwd,
+        "model": {
+            "id": "claude-opus-4-5",
+            "display_name": "Opus 4.5"
+        },
+        "workspace": {
+            "current_dir": cwd,
+            "project_dir": cwd
+        },
+        "version": "preview",
+        "cost": {
+            "total_cost_usd": 0.0523,
+            "total_duration_ms": 45000,
+            "total_api_duration_ms": 2300,
+            "total_lines_added": 156,
+            "total_lines_removed": 23
+        },
+        "context_window": {
+            "used_percentage": 45.2,
+            "total_input_tokens": 85000,
+            "total_output_tokens": 12000,
+            "max_tokens": 200000,
+            "current_usage": {
+                "cache_read_input_tokens": 45000,
+                "cache_creation_input_tokens": 8500
+            }
+        }
+    })
+}
+
+// ANSI 256 色转十六进制，与前端 ansiToHex 一致
+fn ansi_256_to_hex(color: u8) -> String {
+    const STANDARD: [&str; 16] = [
+        "#000000", "#800000", "#008000", "#808000", "#000080", "#800080", "#008080", "#c0c0c0",
+        "#808080", "#ff0000", "#00ff00", "#ffff00", "#0000ff", "#ff00ff", "#00ffff", "#ffffff",
+    ];
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+    match color {
+        0..=15 => STANDARD[color as usize].to_string(),
+        16..=231 => {
+            let n = color - 16;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                LEVELS[(n / 36) as usize],
+                LEVELS[((n / 6) % 6) as usize],
+                LEVELS[(n % 6) as usize]
+            )
+        }
+        _ => {
+            let gray = 8 + (color - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+        }
+    }
+}
+
+// 按 SGR 参数更新当前样式，只处理状态栏常用的颜色与粗体
+fn apply_sgr(params: &str, color: &mut Option<String>, bold: &mut bool) {
+    let codes: Vec<u32> = params
+        .split(';')
+        .map(|code| code.parse().unwrap_or(0))
+        .collect();
+    let mut idx = 0;
+    while idx < codes.len() {
+        match codes[idx] {
+            0 => {
+                *color = None;
+                *bold = false;
+            }
+            1 => *bold = true,
+            22 => *bold = false,
+            39 => *color = None,
+            code @ 30..=37 => *color = Some(ansi_256_to_hex((code - 30) as u8)),
+            code @ 90..=97 => *color = Some(ansi_256_to_hex((code - 90 + 8) as u8)),
+            38 => match codes.get(idx + 1) {
+                Some(5) => {
+                    if let Some(&value) = codes.get(idx + 2) {
+                        *color = Some(ansi_256_to_hex(value.min(255) as u8));
+                    }
+                    idx += 2;
+                }
+                Some(2) => {
+                    if let (Some(r), Some(g), Some(b)) =
+                        (codes.get(idx + 2), codes.get(idx + 3), codes.get(idx + 4))
+                    {
+                        *color = Some(format!(
+                            "#{:02x}{:02x}{:02x}",
+                            (*r).min(255),
+                            (*g).min(255),
+                            (*b).min(255)
+                        ));
+                    }
+                    idx += 4;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        idx += 1;
+    }
+}
+
+// 将 ANSI 输出拆分为带样式的文本片段，每行一组
+fn ansi_to_spans(output: &str) -> Vec<Vec<StatuslineSpan>> {
+    let mut lines = Vec::new();
+
+    for raw_line in output.trim_end_matches(['\r', '\n']).split('\n') {
+        let line = raw_line.trim_end_matches('\r');
+        let mut spans: Vec<StatuslineSpan> = Vec::new();
+        let mut color: Option<String> = None;
+        let mut bold = false;
+        let mut text = String::new();
+        let mut chars = line.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            if ch != '\x1b' {
+                text.push(ch);
+                continue;
+            }
+            if chars.peek() != Some(&'[') {
+                continue;
+            }
+            chars.next();
+
+            // CSI 序列：参数字节后跟一个终止字节，只有 m 影响样式
+            let mut params = String::new();
+            let mut final_byte = None;
+            for next in chars.by_ref() {
+                if next.is_ascii_digit() || next == ';' {
+                    params.push(next);
+                } else {
+                    final_byte = Some(next);
+                    break;
+                }
+            }
+            if final_byte != Some('m') {
+                continue;
+            }
+
+            if !text.is_empty() {
+                spans.push(StatuslineSpan {
+                    text: std::mem::take(&mut text),
+                    color: color.clone(),
+                    bold,
+                });
+            }
+            apply_sgr(&params, &mut color, &mut bold);
+        }
+
+        if !text.is_empty() {
+            spans.push(StatuslineSpan { text, color, bold });
+        }
+        lines.push(spans);
+    }
+
+    lines
+}
+
+async fn run_statusline_script(
+    program: &str,
+    args: &[&str],
+    input: &str,
+    timeout: Duration,
+) -> Result<String, String> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut command = tokio::process::Command::new(program);
+    command
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("启动 {} 失败: {}", program, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(input.as_bytes()).await;
+    }
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("预览超时（{} 秒）", timeout.as_secs()))?
+        .map_err(|e| format!("执行脚本失败: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() && stdout.trim().is_empty() {
+        return Err(format!(
+            "脚本执行失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(stdout)
+}
+
+async fn run_statusline_preview(
+    spec: &StatuslineSpec,
+    mode: StatuslinePreviewMode,
+    cwd: &str,
+) -> Result<String, String> {
+    let session = sample_session(cwd);
+
+    let (extension, script) = match mode {
+        StatuslinePreviewMode::Native => {
+            let env = collect_environment(spec, &session);
+            return Ok(render_statusline_native(spec, &session, &env));
+        }
+        StatuslinePreviewMode::Powershell => {
+            ("ps1", format!("{}{}", UTF8_BOM, render_powershell(spec)))
+        }
+        StatuslinePreviewMode::Posix => ("sh", render_posix(spec)),
+    };
+
+    let script_path = std::env::temp_dir().join(format!(
+        "claude-channel-switcher-preview-{}-{}.{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+        extension
+    ));
+    fs::write(&script_path, script).map_err(|e| format!("写入预览脚本失败: {}", e))?;
+    let script_arg = script_path.to_string_lossy().to_string();
+
+    let result = match mode {
+        StatuslinePreviewMode::Powershell => {
+            let program = if cfg!(target_os = "windows") {
+                "powershell"
+            } else {
+                "pwsh"
+            };
+            run_statusline_script(
+                program,
+                &[
+                    "-NoProfile",
+                    "-ExecutionPolicy",
+                    "Bypass",
+                    "-File",
+                    &script_arg,
+                ],
+                &session.to_string(),
+                STATUSLINE_PREVIEW_TIMEOUT,
+            )
+            .await
+        }
+        _ => {
+            run_statusline_script(
+                "sh",
+                &[&script_arg],
+                &session.to_string(),
+                STATUSLINE_PREVIEW_TIMEOUT,
+            )
+            .await
+        }
+    };
+
+    let _ = fs::remove_file(&script_path);
+    result
+}
+
+// ==================== StatusLine 样式存储 ====================
+
+// Windows 使用 PowerShell 脚本，其他平台使用同名的 sh 脚本；对应脚本不存在时报错，
+// 避免 settings.json 指向缺失的文件（旧版样式只生成了 ps1）
+fn statusline_command(dir: &Path, file_name: &str) -> Result<String, String> {
+    let script_path = statusline_script_path(dir, file_name)?;
+    let extension = if cfg!(target_os = "windows") {
+        "ps1"
+    } else {
+        "sh"
+    };
+    let script_path = script_path.with_extension(extension);
+    if !script_path.exists() {
+        return Err(format!(
+            "StatusLine 脚本不存在: {}，请重新保存该样式或改用内置渲染器",
+            script_path.display()
+        ));
+    }
+
+    if cfg!(target_os = "windows") {
+        // Windows 路径不会包含双引号，直接加引号即可支持带空格的用户目录
+        Ok(format!(
+            "powershell -NoProfile -ExecutionPolicy Bypass -File \"{}\"",
+            script_path.to_string_lossy()
+        ))
+    } else {
+        Ok(format!(
+            "sh '{}'",
+            script_path.to_string_lossy().replace('\'', "'\\''")
+        ))
+    }
+}
+
+// 使用应用自身作为渲染器，样式名已校验，无需额外转义
+fn native_statusline_command(file_name: &str) -> Result<String, String> {
+    let name = style_name_from_file_name(file_name);
+    if !is_valid_statusline_name(name) {
+        return Err("名称只能包含字母、数字、下划线、-和中文".to_string());
+    }
+
+    let exe = std::env::current_exe().map_err(|e| format!("获取程序路径失败: {}", e))?;
+    Ok(format!(
+        "\"{}\" statusline --style {}",
+        exe.to_string_lossy(),
+        name
+    ))
+}
+
+pub(crate) fn statusline_dir() -> Result<PathBuf, String> {
+    std::env::var("USERPROFILE")
+        .or_else(|_| std::env::var("HOME"))
+        .map(|home| Path::new(&home).join(".claude").join("statusline"))
+        .map_err(|_| "Failed to get home directory".to_string())
+}
+
+fn statusline_file_path(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    dir.join(format!("{}{}.{}", STATUSLINE_FILE_PREFIX, name, extension))
+}
+
+fn load_statusline_spec(dir: &Path, name: &str) -> Result<StatuslineSpec, String> {
+    let spec_path = statusline_file_path(dir, name, "json");
+    if spec_path.exists() {
+        let content = fs::read_to_string(&spec_path)
+            .map_err(|e| format!("读取 StatusLine 样式失败: {}", e))?;
+        let spec: StatuslineSpec = serde_json::from_str(&content)
+            .map_err(|e| format!("解析 StatusLine 样式失败: {}", e))?;
+        validate_statusline_spec(&spec)?;
+        return Ok(spec);
+    }
+
+    let script_path = statusline_file_path(dir, name, "ps1");
+    if script_path.exists() {
+        let content = fs::read_to_string(&script_path)
+            .map_err(|e| format!("读取 StatusLine 脚本失败: {}", e))?;
+        return Ok(spec_from_legacy_script(&content).unwrap_or_else(default_statusline_spec));
+    }
+
+    Err("StatusLine 样式不存在".to_string())
+}
+
+fn save_statusline_spec_files(dir: &Path, name: &str, spec: &StatuslineSpec) -> Result<(), String> {
+    if !is_valid_statusline_name(name) {
+        return Err("名称只能包含字母、数字、下划线、-和中文".to_string());
+    }
+    validate_statusline_spec(spec)?;
+
+    let serialized = serde_json::to_string_pretty(spec)
+        .map_err(|e| format!("序列化 StatusLine 样式失败: {}", e))?;
+    write_text_file(&statusline_file_path(dir, name, "json"), &serialized)?;
+
+    // PowerShell 5.1 需要 UTF-8 BOM 才能正确识别中文和 Emoji；sh 脚本不能带 BOM
+    let powershell = format!("{}{}", UTF8_BOM, render_powershell(spec));
+    write_text_file(&statusline_file_path(dir, name, "ps1"), &powershell)?;
+    write_text_file(&statusline_file_path(dir, name, "sh"), &render_posix(spec))?;
+
+    Ok(())
+}
+
+// ==================== StatusLine 应用状态 ====================
+
+// 记录首次应用样式前 settings.json 中的 statusLine，便于取消应用时恢复
+const PREVIOUS_STATUSLINE_FILE: &str = ".previous_statusline.json";
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct AppliedStatusline {
+    command: Option<String>,
+    name: Option<String>,
+    file_name: Option<String>,
+    native: bool,
+    has_previous: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PreviousStatusline {
+    status_line: Option<serde_json::Value>,
+}
+
+fn style_name_from_file_name(file_name: &str) -> &str {
+    let name = file_name
+        .strip_prefix(STATUSLINE_FILE_PREFIX)
+        .unwrap_or(file_name);
+    name.strip_suffix(".ps1")
+        .or_else(|| name.strip_suffix(".sh"))
+        .unwrap_or(name)
+}
+
+// 从 statusLine 命令中识别本应用管理的样式，返回样式名以及是否为内置渲染器
+fn applied_style_from_command(command: &str) -> Option<(String, bool)> {
+    if let Some(index) = command.find(" statusline --style ") {
+        let name = command[index + " statusline --style ".len()..]
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'');
+        return is_valid_statusline_name(name).then(|| (name.to_string(), true));
+    }
+
+    let normalized = command.replace('\\', "/");
+    let marker = format!("/.claude/statusline/{}", STATUSLINE_FILE_PREFIX);
+    let rest = &normalized[normalized.rfind(&marker)? + marker.len()..];
+    let name = [".ps1", ".sh"]
+        .iter()
+        .filter_map(|extension| rest.find(extension).map(|index| &rest[..index]))
+        .min_by_key(|name| name.len())?;
+
+    is_valid_statusline_name(name).then(|| (name.to_string(), false))
+}
+
+fn read_settings_json(settings_path: &Path) -> Result<serde_json::Value, String> {
+    if !settings_path.exists() {
+        return Ok(serde_json::json!({}));
+    }
+
+    let content =
+        fs::read_to_string(settings_path).map_err(|e| format!("读取 settings.json 失败: {}", e))?;
+    if content.trim().is_empty() {
+        return Ok(serde_json::json!({}));
+    }
+
+    let value: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("解析 settings.json 失败: {}", e))?;
+    if !value.is_object() {
+        return Err("settings.json 格式错误：顶层必须是对象".to_string());
+    }
+    Ok(value)
+}
+
+fn write_settings_json(settings_path: &Path, value: &serde_json::Value) -> Result<(), String> {
+    let serialized = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    write_text_file(settings_path, &serialized)
+}
+
+fn previous_statusline_path(claude_dir: &Path) -> PathBuf {
+    claude_dir.join("statusline").join(PREVIOUS_STATUSLINE_FILE)
+}
+
+fn read_applied_statusline(claude_dir: &Path) -> Result<AppliedStatusline, String> {
+    let settings = read_settings_json(&claude_dir.join("settings.json"))?;
+    let command = settings
+        .get("statusLine")
+        .and_then(|status_line| status_line.get("command"))
+        .and_then(|command| command.as_str())
+        .map(str::to_string);
+    let style = command.as_deref().and_then(applied_style_from_command);
+
+    Ok(AppliedStatusline {
+        name: style.as_ref().map(|(name, _)| name.clone()),
+        file_name: style
+            .as_ref()
+            .map(|(name, _)| format!("{}{}.ps1", STATUSLINE_FILE_PREFIX, name)),
+        native: style.map(|(_, native)| native).unwrap_or(false),
+        has_previous: previous_statusline_path(claude_dir).exists(),
+        command,
+    })
+}
+
+// settings.json 与渠道切换共用目录锁，写回前确认读取后未被 Claude Code 等程序修改
+fn apply_statusline_command(claude_dir: &Path, command: &str) -> Result<(), String> {
+    with_dir_lock(claude_dir, || {
+        apply_statusline_command_locked(claude_dir, command)
+    })
+}
+
+fn apply_statusline_command_locked(claude_dir: &Path, command: &str) -> Result<(), String> {
+    let settings_path = claude_dir.join("settings.json");
+    let settings_stamp = file_stamp(&settings_path)?;
+    let mut settings = read_settings_json(&settings_path)?;
+
+    // 只在当前 statusLine 不是本应用的样式时记录，切换样式不会覆盖最初的值
+    let current = settings.get("statusLine").cloned();
+    let is_managed = current
+        .as_ref()
+        .and_then(|status_line| status_line.get("command"))
+        .and_then(|command| command.as_str())
+        .and_then(applied_style_from_command)
+        .is_some();
+    let previous_path = previous_statusline_path(claude_dir);
+    ensure_unchanged(&settings_path, &settings_stamp)?;
+    if !is_managed {
+        let previous = serde_json::to_string_pretty(&PreviousStatusline {
+            status_line: current,
+        })
+        .map_err(|e| format!("序列化 statusLine 失败: {}", e))?;
+        write_text_file(&previous_path, &previous)?;
+    }
+
+    if let Some(obj) = settings.as_object_mut() {
+        // 使用正确的对象格式
+        obj.insert(
+            "statusLine".to_string(),
+            serde_json::json!({
+                "type": "command",
+                "command": command
+            }),
+        );
+    }
+
+    write_settings_json(&settings_path, &settings)
+}
+
+fn clear_applied_statusline(claude_dir: &Path, restore_previous: bool) -> Result<(), String> {
+    with_dir_lock(claude_dir, || {
+        clear_applied_statusline_locked(claude_dir, restore_previous)
+    })
+}
+
+fn clear_applied_statusline_locked(
+    claude_dir: &Path,
+    restore_previous: bool,
+) -> Result<(), String> {
+    let settings_path = claude_dir.join("settings.json");
+    let settings_stamp = file_stamp(&settings_path)?;
+    let mut settings = read_settings_json(&settings_path)?;
+    let previous_path = previous_statusline_path(claude_dir);
+
+    let previous = if restore_previous && previous_path.exists() {
+        let content = fs::read_to_string(&previous_path)
+            .map_err(|e| format!("读取原 statusLine 失败: {}", e))?;
+        serde_json::from_str::<PreviousStatusline>(&content)
+            .map_err(|e| format!("解析原 statusLine 失败: {}", e))?
+            .status_line
+    } else {
+        None
+    };
+
+    if let Some(obj) = settings.as_object_mut() {
+        match previous {
+            Some(status_line) => {
+                obj.insert("statusLine".to_string(), status_line);
+            }
+            None => {
+                obj.remove("statusLine");
+            }
+        }
+    }
+
+    ensure_unchanged(&settings_path, &settings_stamp)?;
+    write_settings_json(&settings_path, &settings)?;
+
+    if previous_path.exists() {
+        fs::remove_file(&previous_path)
+            .map_err(|e| format!("删除原 statusLine 记录失败: {}", e))?;
+    }
+    Ok(())
+}
+
+// ==================== StatusLine 导入导出 ====================
+
+const STATUSLINE_BUNDLE_FORMAT: &str = "claude-channel-switcher/statusline";
+const STATUSLINE_BUNDLE_VERSION: u32 = 1;
+const STATUSLINE_BUNDLE_MAX_BYTES: u64 = 512 * 1024;
+
+// 分享用的样式包：脚本仅供没有安装本应用的用户直接使用，导入时只信任 spec 并重新生成脚本
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StatuslineBundle {
+    format: String,
+    version: u32,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exported_at: Option<String>,
+    #[serde(default)]
+    shells: Vec<StatuslineShell>,
+    spec: StatuslineSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scripts: Option<StatuslineBundleScripts>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StatuslineBundleScripts {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    powershell: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    posix: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StatuslineConflictStrategy {
+    // 自动追加序号，例如 简约-2
+    Rename,
+    Overwrite,
+    Fail,
+}
+
+fn build_statusline_bundle(
+    dir: &Path,
+    name: &str,
+    author: Option<String>,
+) -> Result<StatuslineBundle, String> {
+    let spec = load_statusline_spec(dir, name)?;
+    let author = author
+        .map(|author| author.trim().to_string())
+        .filter(|author| !author.is_empty());
+    if let Some(author) = &author {
+        if author.chars().count() > 64 || author.chars().any(char::is_control) {
+            return Err("作者名称不能超过 64 个字符且不能包含控制字符".to_string());
+        }
+    }
+
+    Ok(StatuslineBundle {
+        format: STATUSLINE_BUNDLE_FORMAT.to_string(),
+        version: STATUSLINE_BUNDLE_VERSION,
+        name: name.to_string(),
+        author,
+        exported_at: Some(chrono::Local::now().to_rfc3339()),
+        shells: vec![StatuslineShell::Powershell, StatuslineShell::Posix],
+        scripts: Some(StatuslineBundleScripts {
+            powershell: Some(render_powershell(&spec)),
+            posix: Some(render_posix(&spec)),
+        }),
+        spec,
+    })
+}
+
+fn parse_statusline_bundle(content: &str) -> Result<StatuslineBundle, String> {
+    let content = content.strip_prefix(UTF8_BOM).unwrap_or(content);
+    let bundle: StatuslineBundle =
+        serde_json::from_str(content).map_err(|e| format!("样式包格式错误: {}", e))?;
+
+    if bundle.format != STATUSLINE_BUNDLE_FORMAT {
+        return Err("不是有效的 StatusLine 样式包".to_string());
+    }
+    if bundle.version == 0 || bundle.version > STATUSLINE_BUNDLE_VERSION {
+        return Err(format!(
+            "不支持的样式包版本 {}，请升级应用后再导入",
+            bundle.version
+        ));
+    }
+    if !is_valid_statusline_name(&bundle.name) {
+        return Err("样式包名称只能包含字母、数字、下划线、-和中文".to_string());
+    }
+    validate_statusline_spec(&bundle.spec)?;
+
+    Ok(bundle)
+}
+
+// 名称冲突时按策略处理，返回最终使用的样式名
+fn resolve_import_name(
+    dir: &Path,
+    name: &str,
+    strategy: StatuslineConflictStrategy,
+) -> Result<String, String> {
+    let exists = |candidate: &str| {
+        ["json", "ps1"]
+            .iter()
+            .any(|extension| statusline_file_path(dir, candidate, extension).exists())
+    };
+
+    if !exists(name) {
+        return Ok(name.to_string());
+    }
+
+    match strategy {
+        StatuslineConflictStrategy::Overwrite => Ok(name.to_string()),
+        StatuslineConflictStrategy::Fail => Err(format!("样式「{}」已存在", name)),
+        StatuslineConflictStrategy::Rename => (2..1000)
+            .map(|index| {
+                let suffix = format!("-{}", index);
+                let base: String = name.chars().take(32 - suffix.len()).collect();
+                format!("{}{}", base, suffix)
+            })
+            .find(|candidate| !exists(candidate))
+            .ok_or_else(|| format!("样式「{}」已存在", name)),
+    }
+}
+
+fn import_statusline_bundle(
+    dir: &Path,
+    content: &str,
+    name: Option<&str>,
+    strategy: StatuslineConflictStrategy,
+) -> Result<String, String> {
+    let bundle = parse_statusline_bundle(content)?;
+    let requested = name
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&bundle.name);
+    if !is_valid_statusline_name(requested) {
+        return Err("名称只能包含字母、数字、下划线、-和中文".to_string());
+    }
+
+    let final_name = resolve_import_name(dir, requested, strategy)?;
+    save_statusline_spec_files(dir, &final_name, &bundle.spec)?;
+    Ok(final_name)
+}
+
+fn read_statusline_bundle_file(path: &Path) -> Result<String, String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("读取样式包失败: {}", e))?;
+    if metadata.len() > STATUSLINE_BUNDLE_MAX_BYTES {
+        return Err("样式包文件过大".to_string());
+    }
+    fs::read_to_string(path).map_err(|e| format!("读取样式包失败: {}", e))
+}
+
+// ==================== StatusLine 命令 ====================
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StatuslineFile {
+    name: String,
+    file_name: String,
+    path: String,
+    modified: i64,
+}
+
+#[tauri::command]
+pub(crate) async fn get_statusline_files() -> ApiResponse<Vec<StatuslineFile>> {
+    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
+        Ok(home) => home,
+        Err(_) => {
+            return ApiResponse {
+                success: true,
+                error: None,
+                channels: None,
+                config: None,
+                data: Some(vec![]),
+            };
+        }
+    };
+
+    let statusline_dir = Path::new(&home_dir).join(".claude").join("statusline");
+
+    if !statusline_dir.exists() {
+        if let Err(e) = fs::create_dir_all(&statusline_dir) {
+            return ApiResponse {
+                success: false,
+                error: Some(format!("Failed to create statusline directory: {}", e)),
+                channels: None,
+                config: None,
+                data: Some(vec![]),
+            };
+        }
+        return ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(vec![]),
+        };
+    }
+
+    let mut files = Vec::new();
+
+    let entries = match fs::read_dir(&statusline_dir) {
+        Ok(e) => e,
+        Err(_) => {
+            return ApiResponse {
+                success: true,
+                error: None,
+                channels: None,
+                config: None,
+                data: Some(vec![]),
+            };
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("ps1") {
+            continue;
+        }
+
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        // 提取显示名：先尝试移除 statusline_ 前缀和 .ps1 后缀
+        // 如果不匹配，只移除 .ps1 后缀
+        let name = file_name
+            .strip_prefix("statusline_")
+            .and_then(|s| s.strip_suffix(".ps1"))
+            .or_else(|| file_name.strip_suffix(".ps1"))
+            .unwrap_or(&file_name)
+            .to_string();
+
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        files.push(StatuslineFile {
+            name,
+            file_name,
+            path: path.to_string_lossy().to_string(),
+            modified,
+        });
+    }
+
+    files.sort_by(|a, b| b.modified.cmp(&a.modified));
+
+    ApiResponse {
+        success: true,
+        error: None,
+        channels: None,
+        config: None,
+        data: Some(files),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn read_statusline_file(file_name: String) -> ApiResponse<String> {
+    let result = statusline_dir().and_then(|dir| {
+        let file_path = statusline_script_path(&dir, &file_name)?;
+        if !file_path.exists() {
+            return Err("File not found".to_string());
+        }
+        fs::read_to_string(&file_path).map_err(|e| format!("Failed to read file: {}", e))
+    });
+
+    match result {
+        Ok(content) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(content),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn save_statusline_file(file_name: String, content: String) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        let file_path = statusline_script_path(&statusline_dir()?, &file_name)?;
+        // 使用 UTF-8 BOM 编码写入，Windows PowerShell 5 才能正确识别中文
+        write_text_file(&file_path, &format!("\u{feff}{}", content))
+    })
+    .await;
+
+    match result {
+        Ok(()) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+// 只接受本应用生成的 .ps1 / .sh 脚本名，避免通过 ../ 等路径删除目录外的文件
+fn statusline_script_path(dir: &Path, file_name: &str) -> Result<PathBuf, String> {
+    let is_script = file_name.ends_with(".ps1") || file_name.ends_with(".sh");
+    if !is_script || !is_valid_statusline_name(style_name_from_file_name(file_name)) {
+        return Err(format!("无效的 StatusLine 文件名: {}", file_name));
+    }
+    Ok(dir.join(file_name))
+}
+
+fn delete_statusline_style(
+    claude_dir: &Path,
+    file_name: &str,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let file_path = statusline_script_path(&claude_dir.join("statusline"), file_name)?;
+
+    // 检查、删除与恢复 settings.json 在同一把目录锁内完成，避免其间被重新应用
+    with_dir_lock(claude_dir, || {
+        // 正在使用的样式默认不允许删除，避免 settings.json 指向不存在的脚本；
+        // settings.json 无法读取时报错，而不是当作未应用
+        let style_name = style_name_from_file_name(file_name);
+        let is_applied = read_applied_statusline(claude_dir)?.name.as_deref() == Some(style_name);
+        if is_applied && !force.unwrap_or(false) {
+            return Err(format!(
+                "样式「{}」正在使用中，请先取消应用后再删除",
+                style_name
+            ));
+        }
+
+        if file_path.exists() {
+            fs::remove_file(&file_path).map_err(|e| format!("Failed to delete file: {}", e))?;
+        }
+
+        // 同时清理同名的样式定义和脚本
+        for extension in ["json", "ps1", "sh"] {
+            let sibling = file_path.with_extension(extension);
+            if sibling.exists() {
+                let _ = fs::remove_file(sibling);
+            }
+        }
+
+        // 强制删除正在使用的样式时，一并恢复应用前的 statusLine
+        if is_applied {
+            clear_applied_statusline_locked(claude_dir, true)?;
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn delete_statusline_file(
+    file_name: String,
+    force: Option<bool>,
+) -> ApiResponse<()> {
+    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
+        Ok(home) => home,
+        Err(_) => {
+            return ApiResponse {
+                success: false,
+                error: Some("Failed to get home directory".to_string()),
+                channels: None,
+                config: None,
+                data: None,
+            };
+        }
+    };
+
+    let claude_dir = Path::new(&home_dir).join(".claude");
+    let result =
+        run_blocking(move || delete_statusline_style(&claude_dir, &file_name, force)).await;
+    match result {
+        Ok(()) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn apply_statusline_to_settings(
+    file_name: String,
+    use_native_renderer: Option<bool>,
+) -> ApiResponse<()> {
+    let home_dir = match std::env::var("USERPROFILE").or_else(|_| std::env::var("HOME")) {
+        Ok(home) => home,
+        Err(_) => {
+            return ApiResponse {
+                success: false,
+                error: Some("Failed to get home directory".to_string()),
+                channels: None,
+                config: None,
+                data: None,
+            };
+        }
+    };
+
+    let claude_dir = Path::new(&home_dir).join(".claude");
+
+    let command = if use_native_renderer.unwrap_or(false) {
+        native_statusline_command(&file_name)
+    } else {
+        statusline_command(&claude_dir.join("statusline"), &file_name)
+    };
+    let command = match command {
+        Ok(command) => command,
+        Err(e) => return ApiResponse::error(e),
+    };
+
+    let result = run_blocking(move || apply_statusline_command(&claude_dir, &command)).await;
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_applied_statusline() -> ApiResponse<AppliedStatusline> {
+    let result = statusline_dir().and_then(|dir| {
+        let claude_dir = dir.parent().map(Path::to_path_buf).unwrap_or(dir);
+        read_applied_statusline(&claude_dir)
+    });
+
+    match result {
+        Ok(applied) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(applied),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn clear_statusline(restore_previous: Option<bool>) -> ApiResponse<()> {
+    let result = run_blocking(move || {
+        let dir = statusline_dir()?;
+        let claude_dir = dir.parent().map(Path::to_path_buf).unwrap_or(dir);
+        clear_applied_statusline(&claude_dir, restore_previous.unwrap_or(true))
+    })
+    .await;
+
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_statusline_spec(name: String) -> ApiResponse<StatuslineSpec> {
+    let name = name.trim();
+    let result = if is_valid_statusline_name(name) {
+        statusline_dir().and_then(|dir| load_statusline_spec(&dir, name))
+    } else {
+        Err(format!("无效的 StatusLine 样式名: {}", name))
+    };
+
+    match result {
+        Ok(spec) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(spec),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn save_statusline_spec(name: String, spec: StatuslineSpec) -> ApiResponse<()> {
+    let result =
+        statusline_dir().and_then(|dir| save_statusline_spec_files(&dir, name.trim(), &spec));
+
+    match result {
+        Ok(_) => ApiResponse::success(),
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn generate_statusline_script(
+    spec: StatuslineSpec,
+    shell: StatuslineShell,
+) -> ApiResponse<String> {
+    if let Err(e) = validate_statusline_spec(&spec) {
+        return ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        };
+    }
+
+    ApiResponse {
+        success: true,
+        error: None,
+        channels: None,
+        config: None,
+        data: Some(render_statusline_script(&spec, shell)),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn preview_statusline(
+    spec: StatuslineSpec,
+    mode: Option<StatuslinePreviewMode>,
+    cwd: Option<String>,
+) -> ApiResponse<StatuslinePreview> {
+    let result = match validate_statusline_spec(&spec) {
+        Ok(_) => {
+            let cwd = cwd.filter(|dir| !dir.trim().is_empty()).unwrap_or_else(|| {
+                std::env::current_dir()
+                    .map(|dir| dir.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+            run_statusline_preview(&spec, mode.unwrap_or(StatuslinePreviewMode::Native), &cwd).await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(output) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(StatuslinePreview {
+                lines: ansi_to_spans(&output),
+                output,
+            }),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn export_statusline(
+    name: String,
+    author: Option<String>,
+    target_path: Option<String>,
+) -> ApiResponse<String> {
+    let result = statusline_dir().and_then(|dir| {
+        let bundle = build_statusline_bundle(&dir, name.trim(), author)?;
+        let serialized = serde_json::to_string_pretty(&bundle)
+            .map_err(|e| format!("序列化样式包失败: {}", e))?;
+        if let Some(target) = target_path.filter(|path| !path.trim().is_empty()) {
+            write_text_file(Path::new(&target), &serialized)?;
+        }
+        Ok(serialized)
+    });
+
+    match result {
+        Ok(content) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(content),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn import_statusline(
+    source_path: Option<String>,
+    content: Option<String>,
+    name: Option<String>,
+    on_conflict: Option<StatuslineConflictStrategy>,
+) -> ApiResponse<String> {
+    let result = statusline_dir().and_then(|dir| {
+        let content = match (content, source_path) {
+            (Some(content), _) => content,
+            (None, Some(path)) => read_statusline_bundle_file(Path::new(&path))?,
+            (None, None) => return Err("缺少样式包内容".to_string()),
+        };
+        fs::create_dir_all(&dir).map_err(|e| format!("创建 StatusLine 目录失败: {}", e))?;
+        import_statusline_bundle(
+            &dir,
+            &content,
+            name.as_deref(),
+            on_conflict.unwrap_or(StatuslineConflictStrategy::Rename),
+        )
+    });
+
+    match result {
+        Ok(name) => ApiResponse {
+            success: true,
+            error: None,
+            channels: None,
+            config: None,
+            data: Some(name),
+        },
+        Err(e) => ApiResponse {
+            success: false,
+            error: Some(e),
+            channels: None,
+            config: None,
+            data: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-statusline-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn spec_with(kinds: &[StatuslineSegmentKind]) -> StatuslineSpec {
+        let mut spec = default_statusline_spec();
+        for segment in &mut spec.segments {
+            segment.enabled = kinds.contains(&segment.kind);
+        }
+        spec
+    }
+
+    #[test]
+    fn spec_round_trips_through_json() {
+        let spec = default_statusline_spec();
+        let json = serde_json::to_string(&spec).unwrap();
+        let parsed: StatuslineSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, spec);
+        assert!(json.contains("\"kind\":\"git_branch\""));
+
+        let cwd: StatuslineSegment = serde_json::from_str(r#"{"kind":"cwd","color":147}"#).unwrap();
+        assert_eq!(cwd.kind, StatuslineSegmentKind::Dir);
+        assert!(cwd.enabled);
+    }
+
+    #[test]
+    fn rejects_duplicate_segments_and_control_characters() {
+        let mut spec = default_statusline_spec();
+        spec.segments
+            .push(segment(StatuslineSegmentKind::Model, true, "", "", 81));
+        assert!(validate_statusline_spec(&spec)
+            .unwrap_err()
+            .contains("显示项目重复"));
+
+        let mut spec = default_statusline_spec();
+        spec.segments[0].label = "a\nb".to_string();
+        assert!(validate_statusline_spec(&spec).is_err());
+    }
+
+    #[test]
+    fn renders_only_enabled_segments_in_powershell() {
+        use StatuslineSegmentKind::*;
+
+        let script = render_powershell(&spec_with(&[Model, Context, GitBranch]));
+        assert!(script.contains("$items += \"${c81}${i0} $($data.model.display_name)$reset\""));
+        assert!(script.contains("$items += \"${cPct}${i1} $pct% ($usedK/$(K $maxTk))$reset\""));
+        assert!(script.contains("if ($gitBranch) { $items += "));
+        assert!(script.contains("$i0 = [char]::ConvertFromUtf32(0x1F916)"));
+        assert!(!script.contains("$currentTime"));
+        assert!(!script.contains("$channelName"));
+        assert!(script.contains("\r\n"));
+    }
+
+    #[test]
+    fn escapes_user_text_in_generated_scripts() {
+        let mut spec = spec_with(&[StatuslineSegmentKind::Cost]);
+        spec.separator.text = "$(x)".to_string();
+        let cost = spec
+            .segments
+            .iter_mut()
+            .find(|s| s.kind == StatuslineSegmentKind::Cost)
+            .unwrap();
+        cost.label = "`$\"".to_string();
+        cost.show_label = true;
+
+        let powershell = render_powershell(&spec);
+        assert!(powershell.contains("``$`\":`$$cost"));
+        assert!(powershell.contains("$sep = \"${c252}`$(x)$reset\""));
+
+        let posix = render_posix(&spec);
+        assert!(posix.contains("\\`\\$\\\":\\$${cost}"));
+        assert!(posix.contains("sep=\"${c252}\\$(x)${reset}\""));
+    }
+
+    #[test]
+    fn renders_posix_script_with_channel_lookup() {
+        use StatuslineSegmentKind::*;
+
+        let mut spec = spec_with(&[Dir, Channel]);
+        spec.separator.show_end = true;
+        let script = render_posix(&spec);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("for f in \"$claudeDir\"/settings-*.json; do"));
+        assert!(script.contains("add \"${c147}📁 ${currentDir}${reset}\""));
+        assert!(script.contains("if [ -n \"$channelName\" ]; then add "));
+        assert!(script.contains("out=\"$out $sep\""));
+        assert!(!script.contains('\r'));
+    }
+
+    #[test]
+    fn parses_legacy_config_comment() {
+        let legacy = "\u{FEFF}# CONFIG:{\"sep\":\"→\",\"sepColor\":240,\"sepStart\":1,\"sepEnd\":0,\"items\":[[\"time\",\"⌚\",\"T\",1,1,99],[\"model\",\"🤖\",\"Model\",0,0,81]]}\r\n\r\n$esc = [char]27";
+
+        let spec = spec_from_legacy_script(legacy).unwrap();
+        assert_eq!(spec.separator.text, "→");
+        assert_eq!(spec.separator.color, 240);
+        assert!(spec.separator.show_start);
+        assert!(!spec.separator.show_end);
+        assert_eq!(spec.segments[0].kind, StatuslineSegmentKind::Time);
+        assert_eq!(spec.segments[0].emoji, "⌚");
+        assert!(spec.segments[0].show_label);
+        assert_eq!(spec.segments[0].color, 99);
+        assert_eq!(spec.segments[1].kind, StatuslineSegmentKind::Model);
+        assert!(!spec.segments[1].enabled);
+        assert_eq!(
+            spec.segments.len(),
+            default_statusline_spec().segments.len()
+        );
+    }
+
+    #[test]
+    fn saves_spec_with_generated_scripts() {
+        let dir = create_temp_dir("save");
+        let spec = default_statusline_spec();
+
+        save_statusline_spec_files(&dir, "简约", &spec).unwrap();
+
+        let ps1 = fs::read(dir.join("statusline_简约.ps1")).unwrap();
+        assert_eq!(&ps1[..3], &[0xEF, 0xBB, 0xBF]);
+        let sh = fs::read(dir.join("statusline_简约.sh")).unwrap();
+        assert!(sh.starts_with(b"#!/bin/sh"));
+        assert_eq!(load_statusline_spec(&dir, "简约").unwrap(), spec);
+
+        assert!(save_statusline_spec_files(&dir, "bad name", &spec).is_err());
+        assert!(load_statusline_spec(&dir, "missing").is_err());
+    }
+
+    fn test_environment() -> StatuslineEnvironment {
+        StatuslineEnvironment {
+            time: "09:05".to_string(),
+            date: "2026-01-02".to_string(),
+            user: "dev".to_string(),
+            home: Some("/home/dev".to_string()),
+            git_branch: None,
+            channel: Some("relay".to_string()),
+        }
+    }
+
+    fn sample_session() -> serde_json::Value {
+        serde_json::json!({
+            "model": { "display_name": "Opus 4.5" },
+            "cwd": "/home/dev/project",
+            "cost": { "total_cost_usd": 0.05234 },
+            "context_window": {
+                "used_percentage": 85.24,
+                "total_input_tokens": 85000,
+                "total_output_tokens": 12000,
+                "max_tokens": 200000,
+                "current_usage": {
+                    "cache_read_input_tokens": 45000,
+                    "cache_creation_input_tokens": 850
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn renders_session_json_natively() {
+        use StatuslineSegmentKind::*;
+
+        let mut spec = spec_with(&[Model, Context, Tokens, Cache, Cost, Dir, GitBranch, Channel]);
+        spec.separator.show_end = true;
+        let output = render_statusline_native(&spec, &sample_session(), &test_environment());
+
+        let sep = "\x1b[38;5;252m|\x1b[0m";
+        let expected = [
+            "\x1b[38;5;81m🤖 Opus 4.5\x1b[0m",
+            "\x1b[38;5;210m⏳ 85.2% (170.4k/200k)\x1b[0m",
+            "\x1b[38;5;153m🧮 97k (I:85k O:12k)\x1b[0m",
+            "\x1b[38;5;183m🎭 R45k W850\x1b[0m",
+            "\x1b[38;5;222m💰 $0.0523\x1b[0m",
+            "\x1b[38;5;147m📁 ~/project\x1b[0m",
+            "\x1b[38;5;216m🔀 relay\x1b[0m",
+        ]
+        .join(&format!(" {} ", sep));
+        assert_eq!(output, format!("{} {}", expected, sep));
+    }
+
+    #[test]
+    fn shortens_windows_and_home_paths() {
+        assert_eq!(
+            shorten_dir("D:\\work\\alpha\\beta\\repo", None),
+            "D:\\work\\a~\\b~\\repo"
+        );
+        assert_eq!(shorten_dir("C:\\Users\\dev", None), "C:\\Users\\dev");
+        assert_eq!(shorten_dir("/home/dev", Some("/home/dev")), "~");
+        assert_eq!(
+            shorten_dir("/home/developer", Some("/home/dev")),
+            "/home/developer"
+        );
+    }
+
+    #[test]
+    fn reads_git_branch_from_head_file() {
+        let dir = create_temp_dir("git");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join(".git").join("HEAD"), "ref: refs/heads/feature/x\n").unwrap();
+        let nested = dir.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(read_git_branch(&nested), Some("feature/x".to_string()));
+
+        fs::write(dir.join(".git").join("HEAD"), "0123456789abcdef\n").unwrap();
+        assert_eq!(read_git_branch(&nested), None);
+    }
+
+    #[test]
+    fn script_command_requires_an_existing_script_under_the_statusline_dir() {
+        let dir = create_temp_dir("script command").join("statusline");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("statusline_old.ps1"), "").unwrap();
+
+        assert!(statusline_command(&dir, "../settings.ps1").is_err());
+        let extension = if cfg!(target_os = "windows") {
+            "ps1"
+        } else {
+            "sh"
+        };
+        let error = statusline_command(&dir, "statusline_a.ps1").unwrap_err();
+        assert!(error.contains("不存在"), "{}", error);
+        if !cfg!(target_os = "windows") {
+            // 只有 ps1 的旧版样式无法在其他平台运行
+            assert!(statusline_command(&dir, "statusline_old.ps1").is_err());
+        }
+
+        fs::write(dir.join(format!("statusline_a.{}", extension)), "").unwrap();
+        let command = statusline_command(&dir, "statusline_a.ps1").unwrap();
+        let quoted = if cfg!(target_os = "windows") {
+            '"'
+        } else {
+            '\''
+        };
+        assert!(command.ends_with(&format!("statusline_a.{}{}", extension, quoted)));
+        assert!(command.contains(&format!("{}{}", quoted, dir.display())));
+
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn builds_native_command_for_valid_style_names() {
+        let command = native_statusline_command("statusline_简约.ps1").unwrap();
+        assert!(command.starts_with('"'));
+        assert!(command.ends_with("\" statusline --style 简约"));
+        assert!(native_statusline_command("statusline_a b.ps1").is_err());
+    }
+
+    #[test]
+    fn converts_ansi_output_to_spans() {
+        let output = "\x1b[38;5;81m🤖 Opus\x1b[0m \x1b[1;31mhot\x1b[0m\x1b[2K\r\n  plain\n";
+        let lines = ansi_to_spans(output);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            vec![
+                StatuslineSpan {
+                    text: "🤖 Opus".to_string(),
+                    color: Some("#5fd7ff".to_string()),
+                    bold: false,
+                },
+                StatuslineSpan {
+                    text: " ".to_string(),
+                    color: None,
+                    bold: false,
+                },
+                StatuslineSpan {
+                    text: "hot".to_string(),
+                    color: Some("#800000".to_string()),
+                    bold: true,
+                },
+            ]
+        );
+        assert_eq!(lines[1][0].text, "  plain");
+    }
+
+    #[test]
+    fn maps_ansi_256_colors_to_hex() {
+        assert_eq!(ansi_256_to_hex(9), "#ff0000");
+        assert_eq!(ansi_256_to_hex(221), "#ffd75f");
+        assert_eq!(ansi_256_to_hex(252), "#d0d0d0");
+    }
+
+    #[test]
+    fn previews_native_renderer_with_sample_session() {
+        let spec = spec_with(&[StatuslineSegmentKind::Model, StatuslineSegmentKind::Cost]);
+        let output = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run_statusline_preview(
+                &spec,
+                StatuslinePreviewMode::Native,
+                "/tmp",
+            ))
+            .unwrap();
+
+        let lines = ansi_to_spans(&output);
+        let text: String = lines[0].iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(text, "🤖 Opus 4.5 | 💰 $0.0523");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn times_out_slow_scripts() {
+        let result = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run_statusline_script(
+                "sh",
+                &["-c", "sleep 5"],
+                "{}",
+                Duration::from_millis(100),
+            ));
+
+        assert!(result.unwrap_err().contains("预览超时"));
+    }
+
+    #[test]
+    fn detects_applied_style_from_command() {
+        assert_eq!(
+            applied_style_from_command(
+                "powershell -NoProfile -ExecutionPolicy Bypass -File C:\\Users\\me\\.claude\\statusline\\statusline_彩色.ps1"
+            ),
+            Some(("彩色".to_string(), false))
+        );
+        assert_eq!(
+            applied_style_from_command("sh '/home/me/.claude/statusline/statusline_basic.sh'"),
+            Some(("basic".to_string(), false))
+        );
+        assert_eq!(
+            applied_style_from_command(
+                "\"/opt/app/claude-channel-switcher\" statusline --style basic"
+            ),
+            Some(("basic".to_string(), true))
+        );
+        assert_eq!(applied_style_from_command("bash ~/my-statusline.sh"), None);
+    }
+
+    #[test]
+    fn apply_and_clear_restores_previous_statusline() {
+        let dir = create_temp_dir("apply-clear");
+        let settings_path = dir.join("settings.json");
+        fs::write(
+            &settings_path,
+            r#"{"model":"opus","statusLine":{"type":"command","command":"bash ~/custom.sh"}}"#,
+        )
+        .unwrap();
+
+        apply_statusline_command(&dir, "sh '/x/.claude/statusline/statusline_a.sh'").unwrap();
+        // 切换到另一个样式不能覆盖最初记录的 statusLine
+        apply_statusline_command(&dir, "sh '/x/.claude/statusline/statusline_b.sh'").unwrap();
+
+        let applied = read_applied_statusline(&dir).unwrap();
+        assert_eq!(applied.name.as_deref(), Some("b"));
+        assert_eq!(applied.file_name.as_deref(), Some("statusline_b.ps1"));
+        assert!(applied.has_previous);
+
+        clear_applied_statusline(&dir, true).unwrap();
+        let settings: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert_eq!(settings["statusLine"]["command"], "bash ~/custom.sh");
+        assert_eq!(settings["model"], "opus");
+        assert!(!previous_statusline_path(&dir).exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn clear_removes_statusline_when_nothing_was_set_before() {
+        let dir = create_temp_dir("clear-empty");
+        let settings_path = dir.join("settings.json");
+        fs::write(&settings_path, r#"{"model":"opus"}"#).unwrap();
+
+        apply_statusline_command(&dir, "sh '/x/.claude/statusline/statusline_a.sh'").unwrap();
+        clear_applied_statusline(&dir, true).unwrap();
+
+        let settings: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&settings_path).unwrap()).unwrap();
+        assert!(settings.get("statusLine").is_none());
+        assert_eq!(
+            read_applied_statusline(&dir).unwrap(),
+            AppliedStatusline {
+                command: None,
+                name: None,
+                file_name: None,
+                native: false,
+                has_previous: false,
+            }
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn delete_refuses_names_outside_statusline_dir() {
+        let dir = create_temp_dir("delete-traversal");
+        let statusline = dir.join("statusline");
+        let spec = default_statusline_spec();
+        save_statusline_spec_files(&statusline, "a", &spec).unwrap();
+        fs::write(dir.join("settings.json"), r#"{"model":"opus"}"#).unwrap();
+        fs::write(dir.join("settings.ps1"), "").unwrap();
+
+        for file_name in [
+            "../settings.ps1",
+            "..\\settings.ps1",
+            "statusline_a.json",
+            "",
+        ] {
+            assert!(delete_statusline_style(&dir, file_name, Some(true)).is_err());
+        }
+        assert!(dir.join("settings.json").exists());
+        assert!(dir.join("settings.ps1").exists());
+        assert!(statusline_file_path(&statusline, "a", "json").exists());
+
+        delete_statusline_style(&dir, "statusline_a.sh", None).unwrap();
+        for extension in ["json", "ps1", "sh"] {
+            assert!(!statusline_file_path(&statusline, "a", extension).exists());
+        }
+        assert!(dir.join("settings.json").exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn refuses_to_overwrite_invalid_settings_json() {
+        let dir = create_temp_dir("invalid-settings");
+        fs::write(dir.join("settings.json"), "{ broken").unwrap();
+
+        assert!(apply_statusline_command(&dir, "sh 'x.sh'").is_err());
+        assert_eq!(
+            fs::read_to_string(dir.join("settings.json")).unwrap(),
+            "{ broken"
+        );
+
+        // 无法判断样式是否正在使用时不删除
+        let statusline = dir.join("statusline");
+        save_statusline_spec_files(&statusline, "a", &default_statusline_spec()).unwrap();
+        assert!(delete_statusline_style(&dir, "statusline_a.ps1", None).is_err());
+        assert!(statusline_file_path(&statusline, "a", "json").exists());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn exports_and_imports_bundle_with_rename_on_conflict() {
+        let dir = create_temp_dir("bundle");
+        let spec = spec_with(&[StatuslineSegmentKind::Model, StatuslineSegmentKind::Cost]);
+        save_statusline_spec_files(&dir, "团队", &spec).unwrap();
+
+        let bundle = build_statusline_bundle(&dir, "团队", Some("alice".to_string())).unwrap();
+        let content = serde_json::to_string(&bundle).unwrap();
+
+        let imported =
+            import_statusline_bundle(&dir, &content, None, StatuslineConflictStrategy::Rename)
+                .unwrap();
+        assert_eq!(imported, "团队-2");
+        assert_eq!(load_statusline_spec(&dir, "团队-2").unwrap(), spec);
+        assert!(statusline_file_path(&dir, "团队-2", "sh").exists());
+
+        assert!(
+            import_statusline_bundle(&dir, &content, None, StatuslineConflictStrategy::Fail)
+                .is_err()
+        );
+        assert_eq!(
+            import_statusline_bundle(
+                &dir,
+                &content,
+                Some("团队"),
+                StatuslineConflictStrategy::Overwrite
+            )
+            .unwrap(),
+            "团队"
+        );
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rejects_invalid_bundles() {
+        let spec = serde_json::to_value(default_statusline_spec()).unwrap();
+        let bundle = |format: &str, version: u32, name: &str| {
+            serde_json::json!({
+                "format": format,
+                "version": version,
+                "name": name,
+                "spec": spec,
+            })
+            .to_string()
+        };
+
+        assert!(parse_statusline_bundle(&bundle(STATUSLINE_BUNDLE_FORMAT, 1, "ok")).is_ok());
+        assert!(parse_statusline_bundle(&bundle("other", 1, "ok")).is_err());
+        assert!(parse_statusline_bundle(&bundle(STATUSLINE_BUNDLE_FORMAT, 99, "ok")).is_err());
+        assert!(parse_statusline_bundle(&bundle(STATUSLINE_BUNDLE_FORMAT, 1, "../evil")).is_err());
+        assert!(parse_statusline_bundle("not json").is_err());
+    }
+}
@@ -0,0 +1,434 @@
+// 终端启动与用户环境变量：外部输入只通过参数数组、工作目录或环境变量传递，不拼接进脚本文本
+// 命令构造函数只在 Windows 上使用，其他平台仅由测试覆盖
+#![cfg_attr(not(target_os = "windows"), allow(dead_code))]
+
+use crate::ApiResponse;
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+pub(crate) const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// PowerShell 脚本是常量，变量名和值由子进程环境变量传入
+const ENV_NAME_VAR: &str = "CCS_ENV_NAME";
+const ENV_VALUE_VAR: &str = "CCS_ENV_VALUE";
+const SET_USER_ENV_SCRIPT: &str =
+    "[Environment]::SetEnvironmentVariable($env:CCS_ENV_NAME, $env:CCS_ENV_VALUE, 'User')";
+const GET_USER_ENV_SCRIPT: &str =
+    "[Environment]::GetEnvironmentVariable($env:CCS_ENV_NAME, 'User')";
+// 单次启动的程序与参数同样经环境变量传入，参数为 JSON 数组，读取后即从会话中移除
+const LAUNCH_PROGRAM_VAR: &str = "CCS_LAUNCH_PROGRAM";
+const LAUNCH_ARGS_VAR: &str = "CCS_LAUNCH_ARGS";
+const LAUNCH_WITH_ARGS_SCRIPT: &str = "$ccsProgram = $env:CCS_LAUNCH_PROGRAM; \
+$ccsArgs = @($env:CCS_LAUNCH_ARGS | ConvertFrom-Json); \
+Remove-Item Env:CCS_LAUNCH_PROGRAM, Env:CCS_LAUNCH_ARGS; \
+& $ccsProgram @ccsArgs";
+
+// 单次启动：参数与环境变量只作用于新终端中的子进程，不修改任何配置文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LaunchSpec {
+    pub(crate) program: &'static str,
+    pub(crate) args: Vec<String>,
+    pub(crate) env: Vec<(String, String)>,
+}
+
+fn is_valid_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|ch: char| ch.is_ascii_digit())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+// 启动的程序名由应用内部决定，这里再做一次白名单校验
+fn is_safe_program_name(program: &str) -> bool {
+    !program.is_empty()
+        && program
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.')
+}
+
+fn powershell_script_command(script: &'static str) -> Command {
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", script]);
+    command
+}
+
+fn set_user_env_command(name: &str, value: &str) -> Result<Command, String> {
+    if !is_valid_env_name(name) {
+        return Err(format!("无效的环境变量名: {}", name));
+    }
+    if value.contains('\0') {
+        return Err("环境变量值不能包含空字符".to_string());
+    }
+
+    let mut command = powershell_script_command(SET_USER_ENV_SCRIPT);
+    command.env(ENV_NAME_VAR, name).env(ENV_VALUE_VAR, value);
+    Ok(command)
+}
+
+fn get_user_env_command(name: &str) -> Result<Command, String> {
+    if !is_valid_env_name(name) {
+        return Err(format!("无效的环境变量名: {}", name));
+    }
+
+    let mut command = powershell_script_command(GET_USER_ENV_SCRIPT);
+    command.env(ENV_NAME_VAR, name).env_remove(ENV_VALUE_VAR);
+    Ok(command)
+}
+
+// 设置用户级别环境变量（写入注册表并广播变更，新终端可用）
+#[cfg(target_os = "windows")]
+pub(crate) fn set_user_env_var(name: &str, value: &str) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+
+    let output = set_user_env_command(name, value)?
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("设置环境变量失败: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "设置环境变量失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn get_user_env_var(name: &str) -> Option<String> {
+    use std::os::windows::process::CommandExt;
+
+    let output = get_user_env_command(name)
+        .ok()?
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+// wt 会把未转义的 ; 当作子命令分隔符
+fn escape_wt_argument(value: &str) -> String {
+    value.replace(';', "\\;")
+}
+
+// 直接启动 wt，目录作为独立参数传入，不再经过 PowerShell Start-Process 拼接
+fn wt_launch_command(dir: &str, shell: &str, program: &str) -> Command {
+    let mut command = Command::new("wt");
+    command
+        .args(["-p", "PowerShell", "-d"])
+        .arg(escape_wt_argument(dir))
+        .args([shell, "-NoExit", "-Command", program]);
+    command
+}
+
+// 回退方案：工作目录通过 current_dir 设置，不再拼接 cd 命令
+fn shell_launch_command(dir: &str, shell: &str, program: &str) -> Command {
+    let mut command = Command::new(shell);
+    command
+        .args(["-NoExit", "-Command", program])
+        .current_dir(dir);
+    command
+}
+
+fn launch_with_spec_command(dir: &str, shell: &str, spec: &LaunchSpec) -> Result<Command, String> {
+    if !is_safe_program_name(spec.program) {
+        return Err(format!("不支持的启动命令: {}", spec.program));
+    }
+    for (name, value) in &spec.env {
+        if !is_valid_env_name(name) {
+            return Err(format!("无效的环境变量名: {}", name));
+        }
+        if value.contains('\0') {
+            return Err("环境变量值不能包含空字符".to_string());
+        }
+    }
+    if spec.args.iter().any(|arg| arg.contains('\0')) {
+        return Err("启动参数不能包含空字符".to_string());
+    }
+    let args = serde_json::to_string(&spec.args).map_err(|e| e.to_string())?;
+
+    let mut command = Command::new(shell);
+    command
+        .args(["-NoExit", "-Command", LAUNCH_WITH_ARGS_SCRIPT])
+        .current_dir(dir)
+        .envs(spec.env.iter().map(|(name, value)| (name, value)))
+        .env(LAUNCH_PROGRAM_VAR, spec.program)
+        .env(LAUNCH_ARGS_VAR, args);
+    Ok(command)
+}
+
+#[cfg(target_os = "windows")]
+fn command_exists(cmd: &str) -> bool {
+    use std::os::windows::process::CommandExt;
+    Command::new("where")
+        .arg(cmd)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+pub(crate) fn open_terminal(command: &str, dir: &str) -> ApiResponse<()> {
+    if !is_safe_program_name(command) {
+        return ApiResponse::error(format!("不支持的启动命令: {}", command));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::path::Path;
+
+        // 检查工作目录是否存在，不存在则回退到用户主目录
+        let work_dir = if Path::new(dir).is_dir() {
+            dir.to_string()
+        } else {
+            std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\".to_string())
+        };
+
+        // 检查是否有 pwsh (PowerShell 7)，没有则使用 powershell
+        let shell = if command_exists("pwsh") {
+            "pwsh"
+        } else {
+            "powershell"
+        };
+
+        // 优先尝试 Windows Terminal，失败则回退到直接启动 PowerShell
+        if wt_launch_command(&work_dir, shell, command).spawn().is_ok() {
+            return ApiResponse::success();
+        }
+
+        match shell_launch_command(&work_dir, shell, command).spawn() {
+            Ok(_) => ApiResponse::success(),
+            Err(e) => ApiResponse::error(format!("启动终端失败: {}", e)),
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        ApiResponse::error("仅支持 Windows".to_string())
+    }
+}
+
+// Windows Terminal 的新标签页可能由已运行的实例创建，不继承本进程传入的环境变量，因此直接启动 PowerShell
+pub(crate) fn open_terminal_with(spec: &LaunchSpec, dir: &str) -> ApiResponse<()> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::path::Path;
+
+        let work_dir = if Path::new(dir).is_dir() {
+            dir.to_string()
+        } else {
+            std::env::var("USERPROFILE").unwrap_or_else(|_| "C:\\".to_string())
+        };
+        let shell = if command_exists("pwsh") {
+            "pwsh"
+        } else {
+            "powershell"
+        };
+
+        match launch_with_spec_command(&work_dir, shell, spec)
+            .and_then(|mut command| command.spawn().map_err(|e| format!("启动终端失败: {}", e)))
+        {
+            Ok(_) => ApiResponse::success(),
+            Err(e) => ApiResponse::error(e),
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (spec, dir);
+        ApiResponse::error("仅支持 Windows".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-terminal-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    const HOSTILE_INPUTS: [&str; 8] = [
+        "fk-1'; Remove-Item -Recurse C:\\; '",
+        "fk-`whoami`",
+        "fk-$(calc.exe)",
+        "fk-${env:PATH}",
+        "fk-1\nStart-Process calc",
+        "fk-\"quoted\" & echo pwned",
+        "fk-1; touch pwned",
+        "C:\\Users\\me\\",
+    ];
+
+    fn args_of(command: &Command) -> Vec<String> {
+        command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    fn env_of(command: &Command, name: &str) -> Option<String> {
+        command
+            .get_envs()
+            .find(|(key, _)| *key == OsStr::new(name))
+            .and_then(|(_, value)| value)
+            .map(|value| value.to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn passes_env_values_outside_of_the_script() {
+        for value in HOSTILE_INPUTS {
+            let command = set_user_env_command("FACTORY_API_KEY", value).unwrap();
+
+            assert_eq!(command.get_program(), "powershell");
+            assert_eq!(
+                args_of(&command),
+                vec![
+                    "-NoProfile",
+                    "-NonInteractive",
+                    "-Command",
+                    SET_USER_ENV_SCRIPT
+                ]
+            );
+            assert_eq!(
+                env_of(&command, ENV_NAME_VAR).as_deref(),
+                Some("FACTORY_API_KEY")
+            );
+            assert_eq!(env_of(&command, ENV_VALUE_VAR).as_deref(), Some(value));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_env_names_and_values() {
+        for name in ["", "1KEY", "KEY NAME", "KEY;calc", "KEY'", "KEY$()"] {
+            assert!(set_user_env_command(name, "value").is_err());
+            assert!(get_user_env_command(name).is_err());
+        }
+        assert!(set_user_env_command("FACTORY_API_KEY", "a\0b").is_err());
+    }
+
+    #[test]
+    fn passes_launch_directory_as_a_single_argument() {
+        for dir in HOSTILE_INPUTS {
+            let command = wt_launch_command(dir, "pwsh", "claude");
+            let args = args_of(&command);
+
+            assert_eq!(command.get_program(), "wt");
+            assert_eq!(args.len(), 8);
+            assert_eq!(args[..3], ["-p", "PowerShell", "-d"]);
+            assert_eq!(args[3], escape_wt_argument(dir));
+            assert_eq!(args[4..], ["pwsh", "-NoExit", "-Command", "claude"]);
+
+            let fallback = shell_launch_command(dir, "powershell", "claude");
+            assert_eq!(args_of(&fallback), vec!["-NoExit", "-Command", "claude"]);
+            assert_eq!(fallback.get_current_dir(), Some(Path::new(dir)));
+        }
+    }
+
+    #[test]
+    fn passes_launch_arguments_and_env_outside_of_the_script() {
+        for value in HOSTILE_INPUTS {
+            let spec = LaunchSpec {
+                program: "codex",
+                args: vec!["-c".to_string(), format!("model={}", value)],
+                env: vec![("OPENAI_API_KEY".to_string(), value.to_string())],
+            };
+            let command = launch_with_spec_command(value, "pwsh", &spec).unwrap();
+
+            assert_eq!(
+                args_of(&command),
+                vec!["-NoExit", "-Command", LAUNCH_WITH_ARGS_SCRIPT]
+            );
+            assert_eq!(command.get_current_dir(), Some(Path::new(value)));
+            assert_eq!(env_of(&command, "OPENAI_API_KEY").as_deref(), Some(value));
+            assert_eq!(
+                env_of(&command, LAUNCH_PROGRAM_VAR).as_deref(),
+                Some("codex")
+            );
+            let args: Vec<String> =
+                serde_json::from_str(&env_of(&command, LAUNCH_ARGS_VAR).unwrap()).unwrap();
+            assert_eq!(args, spec.args);
+        }
+
+        let invalid = LaunchSpec {
+            program: "claude",
+            args: vec![],
+            env: vec![("KEY;calc".to_string(), "v".to_string())],
+        };
+        assert!(launch_with_spec_command(".", "pwsh", &invalid).is_err());
+    }
+
+    #[test]
+    fn escapes_wt_subcommand_separator() {
+        assert_eq!(
+            escape_wt_argument("D:\\a;new-tab calc"),
+            "D:\\a\\;new-tab calc"
+        );
+    }
+
+    #[test]
+    fn rejects_unsafe_launch_programs() {
+        for program in ["claude", "droid", "codex", "gemini-cli"] {
+            assert!(is_safe_program_name(program));
+        }
+        for program in HOSTILE_INPUTS
+            .iter()
+            .chain(["", "claude --x", "a/b"].iter())
+        {
+            assert!(!is_safe_program_name(program));
+            assert!(!open_terminal(program, ".").success);
+        }
+    }
+
+    // 在 pwsh 中执行 SET_USER_ENV_SCRIPT，目标改为 Process 以免改动真实的用户环境变量；
+    // 恶意内容只会被原样写入变量，不会被执行。需要 pwsh，使用 cargo test -- --ignored 运行
+    #[test]
+    #[ignore = "requires pwsh"]
+    fn set_user_env_script_never_executes_hostile_values() {
+        assert!(SET_USER_ENV_SCRIPT.ends_with(", 'User')"));
+        let script = format!(
+            "{}; [Console]::Out.Write([Environment]::GetEnvironmentVariable($env:{}, 'Process'))",
+            SET_USER_ENV_SCRIPT.replace("'User'", "'Process'"),
+            ENV_NAME_VAR
+        );
+        let dir = create_temp_dir("hostile-env");
+
+        for value in HOSTILE_INPUTS {
+            let built = set_user_env_command("CCS_TEST_VALUE", value).unwrap();
+            let mut command = Command::new("pwsh");
+            command
+                .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+                .current_dir(&dir);
+            for (key, value) in built.get_envs() {
+                if let Some(value) = value {
+                    command.env(key, value);
+                }
+            }
+
+            let output = command.output().unwrap();
+            assert!(output.status.success());
+            assert_eq!(String::from_utf8_lossy(&output.stdout), value);
+        }
+        assert!(!dir.join("pwned").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
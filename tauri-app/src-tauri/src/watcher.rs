@@ -0,0 +1,386 @@
+// 监听 Claude、Codex、Droid、Gemini 与 StatusLine 目录，外部修改后推送事件给前端刷新
+use crate::codex::CODEX_STORE_FILE_NAME;
+use crate::droid::{DROID_LEGACY_FILE_NAME, DROID_STORE_FILE_NAME};
+use crate::gemini::{GEMINI_ENV_FILE_NAME, GEMINI_SETTINGS_FILE_NAME, GEMINI_STORE_FILE_NAME};
+use crate::statusline::statusline_dir;
+use crate::ApiResponse;
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+const GEMINI_ACTIVE_FILE_NAMES: [&str; 2] = [GEMINI_ENV_FILE_NAME, GEMINI_SETTINGS_FILE_NAME];
+const DROID_STORE_FILE_NAMES: [&str; 2] = [DROID_STORE_FILE_NAME, DROID_LEGACY_FILE_NAME];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum WatchEvent {
+    Channels,
+    Active,
+    Statusline,
+}
+
+impl WatchEvent {
+    fn name(self) -> &'static str {
+        match self {
+            WatchEvent::Channels => "channels-changed",
+            WatchEvent::Active => "active-changed",
+            WatchEvent::Statusline => "statusline-changed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WatchSource {
+    Claude,
+    Codex,
+    Droid,
+    Gemini,
+    Statusline,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ConfigChange {
+    source: WatchSource,
+    paths: Vec<String>,
+}
+
+// 被监听的目录；statusline 的上级目录中的 settings.json 决定当前应用的样式
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WatchDirs {
+    claude: PathBuf,
+    codex: PathBuf,
+    gemini: Option<PathBuf>,
+    statusline: Option<PathBuf>,
+}
+
+// 统一为真实路径后再比较：配置目录可能带末尾分隔符、是软链接，或在 macOS 上位于 /private 下。
+// 尚不存在的路径无法解析，保持原样
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl WatchDirs {
+    fn canonical(self) -> Self {
+        WatchDirs {
+            claude: canonical_path(&self.claude),
+            codex: canonical_path(&self.codex),
+            gemini: self.gemini.as_deref().map(canonical_path),
+            statusline: self.statusline.as_deref().map(canonical_path),
+        }
+    }
+
+    fn watched_paths(&self) -> BTreeSet<PathBuf> {
+        let mut paths = BTreeSet::new();
+        paths.insert(self.claude.clone());
+        paths.insert(self.codex.clone());
+        if let Some(gemini) = &self.gemini {
+            paths.insert(gemini.clone());
+        }
+        if let Some(statusline) = &self.statusline {
+            paths.insert(statusline.clone());
+            if let Some(parent) = statusline.parent() {
+                paths.insert(parent.to_path_buf());
+            }
+        }
+        paths
+    }
+}
+
+// 持有监听器，替换或释放时停止原来的监听
+#[derive(Default)]
+pub(crate) struct ConfigWatcher(Mutex<Option<Debouncer<RecommendedWatcher>>>);
+
+fn classify_change(dirs: &WatchDirs, path: &Path) -> Vec<(WatchEvent, WatchSource)> {
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let file_name = file_name.to_string_lossy();
+    let parent = canonical_path(parent);
+    let parent = parent.as_path();
+
+    // Gemini 的 .env 本身以点开头，需在忽略点文件之前判断
+    let mut changes = Vec::new();
+    if dirs.gemini.as_deref() == Some(parent) {
+        if GEMINI_ACTIVE_FILE_NAMES.contains(&file_name.as_ref()) {
+            changes.push((WatchEvent::Active, WatchSource::Gemini));
+        } else if file_name == GEMINI_STORE_FILE_NAME {
+            changes.push((WatchEvent::Channels, WatchSource::Gemini));
+        }
+    }
+
+    // 原子写入的临时文件、锁文件等以点开头，忽略
+    if file_name.starts_with('.') {
+        return changes;
+    }
+
+    if parent == dirs.claude {
+        if file_name == "settings.json" {
+            changes.push((WatchEvent::Active, WatchSource::Claude));
+        } else if file_name.starts_with("settings-") && file_name.ends_with(".json") {
+            changes.push((WatchEvent::Channels, WatchSource::Claude));
+        } else if DROID_STORE_FILE_NAMES.contains(&file_name.as_ref()) {
+            changes.push((WatchEvent::Channels, WatchSource::Droid));
+        }
+    }
+
+    if parent == dirs.codex {
+        if file_name == "config.toml" || file_name == "auth.json" {
+            changes.push((WatchEvent::Active, WatchSource::Codex));
+        } else if file_name == CODEX_STORE_FILE_NAME {
+            changes.push((WatchEvent::Channels, WatchSource::Codex));
+        }
+    }
+
+    if let Some(statusline) = &dirs.statusline {
+        let settings_changed = file_name == "settings.json" && statusline.parent() == Some(parent);
+        if parent == statusline.as_path() || settings_changed {
+            changes.push((WatchEvent::Statusline, WatchSource::Statusline));
+        }
+    }
+
+    changes
+}
+
+// 合并一批去抖后的路径，每种事件与来源只推送一次
+fn collect_changes(
+    dirs: &WatchDirs,
+    paths: &[PathBuf],
+) -> BTreeMap<(WatchEvent, WatchSource), BTreeSet<String>> {
+    let mut changes: BTreeMap<_, BTreeSet<String>> = BTreeMap::new();
+    for path in paths {
+        for key in classify_change(dirs, path) {
+            changes
+                .entry(key)
+                .or_default()
+                .insert(path.display().to_string());
+        }
+    }
+    changes
+}
+
+fn emit_changes(app: &AppHandle, dirs: &WatchDirs, result: DebounceEventResult) {
+    let Ok(events) = result else {
+        return;
+    };
+    let paths: Vec<PathBuf> = events.into_iter().map(|event| event.path).collect();
+
+    for ((event, source), paths) in collect_changes(dirs, &paths) {
+        let _ = app.emit(
+            event.name(),
+            ConfigChange {
+                source,
+                paths: paths.into_iter().collect(),
+            },
+        );
+    }
+}
+
+fn start_watcher(app: AppHandle, dirs: WatchDirs) -> Result<Debouncer<RecommendedWatcher>, String> {
+    let handler_dirs = dirs.clone();
+    let mut debouncer = new_debouncer(WATCH_DEBOUNCE, move |result| {
+        emit_changes(&app, &handler_dirs, result)
+    })
+    .map_err(|e| format!("创建文件监听失败: {}", e))?;
+
+    // 尚未创建的目录跳过，例如从未使用过 Codex
+    for path in dirs
+        .watched_paths()
+        .into_iter()
+        .filter(|path| path.is_dir())
+    {
+        debouncer
+            .watcher()
+            .watch(&path, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("监听 {} 失败: {}", path.display(), e))?;
+    }
+    Ok(debouncer)
+}
+
+// 前端启动或修改配置目录后调用，重新建立监听
+#[tauri::command]
+pub(crate) fn watch_config_dirs(
+    app: AppHandle,
+    watcher: State<'_, ConfigWatcher>,
+    config_path: String,
+    codex_config_path: String,
+    gemini_config_path: Option<String>,
+) -> ApiResponse<()> {
+    let dirs = WatchDirs {
+        claude: PathBuf::from(config_path),
+        codex: PathBuf::from(codex_config_path),
+        gemini: gemini_config_path.map(PathBuf::from),
+        statusline: statusline_dir().ok(),
+    }
+    .canonical();
+
+    let mut current = match watcher.0.lock() {
+        Ok(current) => current,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    // 先停止旧的监听，避免同一目录重复推送
+    current.take();
+
+    match start_watcher(app, dirs) {
+        Ok(debouncer) => {
+            *current = Some(debouncer);
+            ApiResponse::success()
+        }
+        Err(e) => ApiResponse::error(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TEST_ID: AtomicU64 = AtomicU64::new(1);
+
+    fn create_temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "claude-channel-switcher-watcher-{}-{}",
+            label,
+            NEXT_TEST_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("failed to create temp dir");
+        dir
+    }
+
+    fn dirs() -> WatchDirs {
+        WatchDirs {
+            claude: PathBuf::from("/home/u/.claude"),
+            codex: PathBuf::from("/home/u/.codex"),
+            gemini: Some(PathBuf::from("/home/u/.gemini")),
+            statusline: Some(PathBuf::from("/home/u/.claude/statusline")),
+        }
+    }
+
+    fn classify(path: &str) -> Vec<(WatchEvent, WatchSource)> {
+        classify_change(&dirs(), Path::new(path))
+    }
+
+    #[test]
+    fn classifies_claude_and_droid_files() {
+        assert_eq!(
+            classify("/home/u/.claude/settings-relay.json"),
+            vec![(WatchEvent::Channels, WatchSource::Claude)]
+        );
+        assert_eq!(
+            classify("/home/u/.claude/settings.json"),
+            vec![
+                (WatchEvent::Active, WatchSource::Claude),
+                (WatchEvent::Statusline, WatchSource::Statusline)
+            ]
+        );
+        assert_eq!(
+            classify("/home/u/.claude/droid_channels.json"),
+            vec![(WatchEvent::Channels, WatchSource::Droid)]
+        );
+        assert!(classify("/home/u/.claude/settings-relay.json.del").is_empty());
+        assert!(classify("/home/u/.claude/history.jsonl").is_empty());
+    }
+
+    #[test]
+    fn classifies_codex_and_statusline_files() {
+        assert_eq!(
+            classify("/home/u/.codex/auth.json"),
+            vec![(WatchEvent::Active, WatchSource::Codex)]
+        );
+        assert_eq!(
+            classify("/home/u/.codex/channels.json"),
+            vec![(WatchEvent::Channels, WatchSource::Codex)]
+        );
+        assert_eq!(
+            classify("/home/u/.claude/statusline/statusline_a.ps1"),
+            vec![(WatchEvent::Statusline, WatchSource::Statusline)]
+        );
+        assert!(classify("/home/u/.codex/sessions").is_empty());
+    }
+
+    #[test]
+    fn classifies_gemini_env_settings_and_store() {
+        assert_eq!(
+            classify("/home/u/.gemini/.env"),
+            vec![(WatchEvent::Active, WatchSource::Gemini)]
+        );
+        assert_eq!(
+            classify("/home/u/.gemini/settings.json"),
+            vec![(WatchEvent::Active, WatchSource::Gemini)]
+        );
+        assert_eq!(
+            classify("/home/u/.gemini/channels.json"),
+            vec![(WatchEvent::Channels, WatchSource::Gemini)]
+        );
+        assert!(classify("/home/u/.gemini/.channel-switcher.lock").is_empty());
+    }
+
+    #[test]
+    fn ignores_temporary_and_lock_files() {
+        assert!(classify("/home/u/.claude/.settings.json.123.456.tmp").is_empty());
+        assert!(classify("/home/u/.codex/.channel-switcher.lock").is_empty());
+        assert!(classify("/home/u/.codex/.applied_extra_config.json").is_empty());
+    }
+
+    #[test]
+    fn merges_a_burst_of_events_into_one_change_per_source() {
+        let paths = vec![
+            PathBuf::from("/home/u/.claude/settings-a.json"),
+            PathBuf::from("/home/u/.claude/settings-b.json"),
+            PathBuf::from("/home/u/.claude/settings-a.json"),
+            PathBuf::from("/home/u/.codex/config.toml"),
+        ];
+
+        let changes = collect_changes(&dirs(), &paths);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changes[&(WatchEvent::Channels, WatchSource::Claude)].len(),
+            2
+        );
+        assert!(changes.contains_key(&(WatchEvent::Active, WatchSource::Codex)));
+    }
+
+    #[test]
+    fn watches_statusline_parent_once_when_it_is_the_claude_dir() {
+        let watched = dirs().watched_paths();
+        assert_eq!(watched.len(), 4);
+        assert!(watched.contains(Path::new("/home/u/.claude")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn matches_events_under_symlinked_config_dirs() {
+        let root = create_temp_dir("symlink");
+        let real = root.join("real-claude");
+        fs::create_dir_all(&real).unwrap();
+        let link = root.join("claude");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        // 目录以软链接加末尾分隔符传入，事件报告的是真实路径
+        let dirs = WatchDirs {
+            claude: PathBuf::from(format!("{}/", link.display())),
+            codex: root.join("codex"),
+            gemini: None,
+            statusline: None,
+        }
+        .canonical();
+        assert_eq!(
+            classify_change(&dirs, &real.join("settings-relay.json")),
+            vec![(WatchEvent::Channels, WatchSource::Claude)]
+        );
+        assert_eq!(
+            classify_change(&dirs, &link.join("settings.json")),
+            vec![(WatchEvent::Active, WatchSource::Claude)]
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -58,6 +58,7 @@ async function initializeApp() {
     settings.init();
     codex.init();
     droid.init();
+    gemini.init();
     statusline.init();
 
     // 更新 UI 语言
@@ -67,6 +68,51 @@ async function initializeApp() {
     await channels.loadChannels();
     await codex.loadChannels();
     await droid.loadChannels();
+    await gemini.loadChannels();
+
+    // 监听外部对配置文件的修改
+    await setupConfigWatcher();
+}
+
+/**
+ * 订阅配置目录变更事件并开始监听
+ * 事件 payload: { source: 'claude' | 'codex' | 'droid' | 'gemini' | 'statusline', paths: string[] }
+ */
+async function setupConfigWatcher() {
+    const reloaders = {
+        claude: () => channels.loadChannels(),
+        codex: () => codex.loadChannels(),
+        droid: () => droid.loadChannels(),
+        gemini: () => gemini.loadChannels(),
+        statusline: () => statusline.refreshFromDisk()
+    };
+    const handleChange = (payload) => {
+        const reload = reloaders[payload?.source];
+        if (reload) {
+            reload();
+        }
+    };
+
+    try {
+        await api.listen('channels-changed', handleChange);
+        await api.listen('active-changed', handleChange);
+        await api.listen('statusline-changed', handleChange);
+    } catch (error) {
+        ErrorHandler.handle(error, 'Listen config changes');
+    }
+
+    await watchConfigDirs();
+}
+
+/**
+ * 按当前配置目录重新建立监听，修改配置目录后调用
+ */
+async function watchConfigDirs() {
+    try {
+        await api.watchConfigDirs(state.configPath, state.codexConfigPath, state.geminiConfigPath);
+    } catch (error) {
+        ErrorHandler.handle(error, 'Watch config dirs');
+    }
 }
 
 /**
@@ -143,6 +189,7 @@ function updateUILanguage() {
     channels.updateLanguage();
     codex.updateLanguage();
     droid.updateLanguage();
+    gemini.updateLanguage();
     statusline.updateLanguage();
     settings.updateLanguage();
     modal.updateLanguage();
@@ -151,3 +198,4 @@ function updateUILanguage() {
 
 // 将函数暴露到全局作用域，以便其他模块可以调用
 window.updateUILanguage = updateUILanguage;
+window.watchConfigDirs = watchConfigDirs;
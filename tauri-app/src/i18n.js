@@ -9,6 +9,7 @@ const translations = {
             statusline: 'Claude 状态栏',
             codex: 'Codex 渠道',
             droid: 'Droid 渠道',
+            gemini: 'Gemini 渠道',
             settings: '设置'
         },
         channels: {
@@ -53,13 +54,24 @@ const translations = {
             },
             status: {
                 active: '当前激活',
-                inactive: '待激活'
+                inactive: '待激活',
+                lastSwitched: '切换于 {time}'
             },
             actions: {
                 launch: '启动',
                 switch: '切换',
                 edit: '编辑',
-                delete: '删除'
+                delete: '删除',
+                test: '检测'
+            },
+            test: {
+                ok: '可用 · {latency} ms',
+                unauthorized: 'API Key 无效或无权限',
+                rate_limited: '请求过于频繁',
+                server_error: '服务端错误',
+                unexpected_status: '响应异常',
+                timeout: '请求超时',
+                network: '网络连接失败'
             },
             modal: {
                 titleNew: '新建 Droid 渠道',
@@ -68,7 +80,11 @@ const translations = {
                     name: '渠道名称',
                     namePlaceholder: '例如：渠道1、主渠道',
                     apiKey: 'API Key',
-                    apiKeyPlaceholder: 'fx-xxxxxx...'
+                    apiKeyPlaceholder: 'fx-xxxxxx...',
+                    baseUrl: 'Base URL（可选）',
+                    baseUrlPlaceholder: 'https://api.factory.ai',
+                    notes: '备注（可选）',
+                    notesPlaceholder: '例如：团队共享账号'
                 },
                 buttons: {
                     cancel: '取消',
@@ -86,6 +102,76 @@ const translations = {
                 errorApiKeyRequired: '请输入 API Key',
                 errorNameDuplicate: '渠道名称已存在，请使用其他名称',
                 confirmDelete: '确定要删除 Droid 渠道「{name}」吗？'
+            },
+            shellHook: {
+                button: '写入 Shell 配置',
+                confirmTitle: '写入 Shell 配置',
+                confirmMessage: '将在 Shell 配置文件（如 ~/.bashrc、~/.zshrc）中加入一段加载代码，新终端会自动使用当前 Droid 渠道的 API Key。重复执行不会重复写入。',
+                confirm: '写入',
+                installed: '已写入：\n{files}'
+            }
+        },
+        gemini: {
+            title: 'Gemini 渠道管理',
+            count: '个渠道',
+            refresh: '刷新',
+            add: '新建渠道',
+            officialEndpoint: '官方地址',
+            empty: {
+                icon: '✨',
+                text: '暂无 Gemini 渠道配置',
+                hint: '点击右上角「➕ 新建渠道」开始添加'
+            },
+            status: {
+                active: '当前激活',
+                inactive: '待激活'
+            },
+            actions: {
+                launch: '启动',
+                switch: '切换',
+                edit: '编辑',
+                delete: '删除',
+                test: '检测'
+            },
+            test: {
+                ok: '可用 · {latency} ms',
+                unauthorized: 'API Key 无效或无权限',
+                rate_limited: '请求过于频繁',
+                server_error: '服务端错误',
+                unexpected_status: '响应异常',
+                timeout: '请求超时',
+                network: '网络连接失败'
+            },
+            modal: {
+                titleNew: '新建 Gemini 渠道',
+                titleEdit: '编辑 Gemini 渠道',
+                fields: {
+                    name: '渠道名称',
+                    namePlaceholder: '例如：官方、中转1',
+                    apiKey: 'API Key',
+                    apiKeyPlaceholder: 'AIza...',
+                    baseUrl: 'Base URL（可选）',
+                    baseUrlPlaceholder: '留空使用官方地址',
+                    model: '模型（可选）',
+                    modelPlaceholder: '例如：gemini-2.5-pro，留空沿用当前设置'
+                },
+                buttons: {
+                    cancel: '取消',
+                    save: '保存'
+                }
+            },
+            messages: {
+                channelCreated: 'Gemini 渠道已创建',
+                channelUpdated: 'Gemini 渠道已更新',
+                channelDeleted: 'Gemini 渠道 {name} 已删除',
+                channelSwitched: '已切换到 Gemini 渠道：{name}',
+                channelLaunched: '已在新窗口中启动 Gemini CLI\n当前渠道：{name}',
+                channelsRefreshed: 'Gemini 渠道列表已刷新',
+                errorNameRequired: '请输入渠道名称',
+                errorApiKeyRequired: '请输入 API Key',
+                storeConflict: '渠道列表已在其他窗口中修改，已重新加载，请重试',
+                switchConflict: '.env 或 settings.json 刚被其他程序修改，已重新加载，请重试',
+                confirmDelete: '确定要删除 Gemini 渠道「{name}」吗？'
             }
         },
         codex: {
@@ -108,7 +194,17 @@ const translations = {
                 switch: '切换',
                 switching: '切换中',
                 edit: '编辑',
-                delete: '删除'
+                delete: '删除',
+                pin: '置顶',
+                unpin: '取消置顶',
+                duplicate: '复制',
+                moveUp: '上移',
+                moveDown: '下移'
+            },
+            authMode: {
+                apiKey: 'API Key',
+                chatgpt: 'ChatGPT 登录',
+                defaultModel: '默认模型'
             },
             modal: {
                 titleNew: '新建 Codex 渠道',
@@ -116,12 +212,23 @@ const translations = {
                 fields: {
                     name: '渠道名称',
                     namePlaceholder: '仅英文字母、数字、-、_',
+                    authMode: '认证方式（ChatGPT 登录会保存当前 auth.json）',
                     baseurl: 'Base URL',
                     baseurlPlaceholder: 'https://api.example.com',
                     apikey: 'API Key',
                     apikeyPlaceholder: 'sk-xxxxxx...',
                     model: '模型名称',
-                    modelPlaceholder: '例如：o3-mini'
+                    modelPlaceholder: '例如：o3-mini',
+                    provider: 'Provider ID（可选）',
+                    providerPlaceholder: '留空则沿用 config.toml 中的 provider',
+                    wireApi: 'Wire API',
+                    wireApiDefault: '默认',
+                    envKey: 'API Key 环境变量（可选）',
+                    envKeyPlaceholder: '例如：AZURE_OPENAI_API_KEY',
+                    queryParams: '查询参数（可选）',
+                    queryParamsPlaceholder: '例如：api-version=2025-04-01-preview',
+                    extraConfig: '附加配置（TOML，可选）',
+                    extraConfigPlaceholder: 'model_reasoning_effort = "high"\napproval_policy = "on-request"\n\n[provider]\nhttp_headers = { "X-Team" = "core" }'
                 },
                 buttons: {
                     cancel: '取消',
@@ -132,6 +239,8 @@ const translations = {
                 channelCreated: 'Codex 渠道已创建',
                 channelUpdated: 'Codex 渠道已更新',
                 channelDeleted: 'Codex 渠道已删除',
+                channelDuplicated: 'Codex 渠道已复制',
+                storeConflict: '渠道列表已被其他操作修改，已重新加载，请重试',
                 channelSwitched: '已切换到 Codex 渠道',
                 channelLaunched: '已在新窗口中启动 Codex',
                 channelsRefreshed: 'Codex 渠道列表已刷新',
@@ -142,6 +251,8 @@ const translations = {
                 errorBaseurlInvalid: 'Base URL 格式无效，请输入 http:// 或 https:// 地址',
                 errorApikeyRequired: '请输入 API Key',
                 errorModelRequired: '请输入模型名称',
+                errorProviderInvalid: 'Provider ID 仅支持英文字母、数字、-、_，最多18位',
+                storeReadOnly: 'Codex 渠道存储版本 v{version} 高于当前支持的 v{supported}，已切换为只读模式，请升级应用后再修改渠道',
                 confirmDelete: '确定要删除该 Codex 渠道吗？'
             }
         },
@@ -182,6 +293,11 @@ const translations = {
                 nameExists: '该名称已存在，请使用其他名称',
                 confirmDeleteTitle: '确认删除',
                 confirmDeleteMessage: '确定要删除样式「{name}」吗？',
+                confirmDeleteAppliedMessage: '样式「{name}」正在使用中，删除后将恢复应用前的 StatusLine 配置。确定要删除吗？',
+                applied: '使用中',
+                clearApplied: '取消应用',
+                import: '导入',
+                export: '导出样式包',
                 saveChoiceMessage: '要保存对样式「{name}」的修改吗？',
                 overwrite: '覆盖保存',
                 saveAs: '另存为',
@@ -195,6 +311,9 @@ const translations = {
                 fileSaved: '样式「{name}」已保存',
                 fileDeleted: '样式「{name}」已删除',
                 applied: '已应用样式「{name}」',
+                cleared: '已取消应用 StatusLine',
+                exported: '样式「{name}」已导出',
+                imported: '已导入样式「{name}」',
                 reset: '配置已重置为默认'
             }
         },
@@ -246,6 +365,12 @@ const translations = {
                 browse: '浏览',
                 label: 'Codex 配置文件路径'
             },
+            geminiPath: {
+                title: 'Gemini 配置文件路径',
+                description: '设置 Gemini CLI 配置文件（.env、settings.json）的存储位置',
+                browse: '浏览',
+                label: 'Gemini 配置文件路径'
+            },
             terminal: {
                 title: '终端程序',
                 description: '选择启动 Claude 时使用的终端'
@@ -276,6 +401,7 @@ const translations = {
             channelsRefreshed: '渠道列表已刷新',
             pathUpdated: '配置路径已更新',
             codexPathUpdated: 'Codex 配置路径已更新',
+            geminiPathUpdated: 'Gemini 配置路径已更新',
             terminalSet: '终端已设置为：{terminal}',
             terminalDirUpdated: '终端工作目录已更新',
             themeChanged: '已切换到{theme}主题',
@@ -303,6 +429,9 @@ const translations = {
             addCodex: '新建 Codex 渠道',
             codexPage: 'Codex 渠道',
             droidPage: 'Droid 渠道管理',
+            refreshGemini: '刷新 Gemini 渠道列表',
+            addGemini: '新建 Gemini 渠道',
+            geminiPage: 'Gemini 渠道管理',
             statuslinePage: 'Claude 状态栏配置页面',
             browseFolder: '浏览文件夹',
             closeDialog: '关闭对话框',
@@ -332,6 +461,7 @@ const translations = {
             statusline: 'Claude StatusLine',
             codex: 'Codex',
             droid: 'Droid Channels',
+            gemini: 'Gemini Channels',
             settings: 'Settings'
         },
         channels: {
@@ -376,13 +506,24 @@ const translations = {
             },
             status: {
                 active: 'Active',
-                inactive: 'Inactive'
+                inactive: 'Inactive',
+                lastSwitched: 'switched {time}'
             },
             actions: {
                 launch: 'Launch',
                 switch: 'Switch',
                 edit: 'Edit',
-                delete: 'Delete'
+                delete: 'Delete',
+                test: 'Test'
+            },
+            test: {
+                ok: 'Available · {latency} ms',
+                unauthorized: 'Invalid API Key or no permission',
+                rate_limited: 'Rate limited',
+                server_error: 'Server error',
+                unexpected_status: 'Unexpected response',
+                timeout: 'Request timed out',
+                network: 'Network error'
             },
             modal: {
                 titleNew: 'Add Droid Channel',
@@ -391,7 +532,11 @@ const translations = {
                     name: 'Channel Name',
                     namePlaceholder: 'e.g., Channel1, Main',
                     apiKey: 'API Key',
-                    apiKeyPlaceholder: 'fx-xxxxxx...'
+                    apiKeyPlaceholder: 'fx-xxxxxx...',
+                    baseUrl: 'Base URL (optional)',
+                    baseUrlPlaceholder: 'https://api.factory.ai',
+                    notes: 'Notes (optional)',
+                    notesPlaceholder: 'e.g., Shared team account'
                 },
                 buttons: {
                     cancel: 'Cancel',
@@ -409,6 +554,76 @@ const translations = {
                 errorApiKeyRequired: 'Please enter API Key',
                 errorNameDuplicate: 'Channel name already exists, please use another name',
                 confirmDelete: 'Are you sure you want to delete Droid channel "{name}"?'
+            },
+            shellHook: {
+                button: 'Shell Setup',
+                confirmTitle: 'Set up shell',
+                confirmMessage: 'A small loader block will be added to your shell rc file (e.g. ~/.bashrc, ~/.zshrc) so new terminals pick up the active Droid API Key. Running it again will not add duplicates.',
+                confirm: 'Set up',
+                installed: 'Updated:\n{files}'
+            }
+        },
+        gemini: {
+            title: 'Gemini Channel Management',
+            count: 'channels',
+            refresh: 'Refresh',
+            add: 'Add Channel',
+            officialEndpoint: 'Official endpoint',
+            empty: {
+                icon: '✨',
+                text: 'No Gemini channels configured',
+                hint: 'Click "➕ Add Channel" in the top right to get started'
+            },
+            status: {
+                active: 'Active',
+                inactive: 'Inactive'
+            },
+            actions: {
+                launch: 'Launch',
+                switch: 'Switch',
+                edit: 'Edit',
+                delete: 'Delete',
+                test: 'Test'
+            },
+            test: {
+                ok: 'Available · {latency} ms',
+                unauthorized: 'Invalid API Key or no permission',
+                rate_limited: 'Rate limited',
+                server_error: 'Server error',
+                unexpected_status: 'Unexpected response',
+                timeout: 'Request timed out',
+                network: 'Network error'
+            },
+            modal: {
+                titleNew: 'Add Gemini Channel',
+                titleEdit: 'Edit Gemini Channel',
+                fields: {
+                    name: 'Channel Name',
+                    namePlaceholder: 'e.g., Official, Relay1',
+                    apiKey: 'API Key',
+                    apiKeyPlaceholder: 'AIza...',
+                    baseUrl: 'Base URL (optional)',
+                    baseUrlPlaceholder: 'Leave empty for the official endpoint',
+                    model: 'Model (optional)',
+                    modelPlaceholder: 'e.g., gemini-2.5-pro; empty keeps the current setting'
+                },
+                buttons: {
+                    cancel: 'Cancel',
+                    save: 'Save'
+                }
+            },
+            messages: {
+                channelCreated: 'Gemini channel created',
+                channelUpdated: 'Gemini channel updated',
+                channelDeleted: 'Gemini channel {name} deleted',
+                channelSwitched: 'Switched to Gemini channel: {name}',
+                channelLaunched: 'Gemini CLI launched in new window\nChannel: {name}',
+                channelsRefreshed: 'Gemini channels refreshed',
+                errorNameRequired: 'Please enter channel name',
+                errorApiKeyRequired: 'Please enter API Key',
+                storeConflict: 'The channel list was changed in another window and has been reloaded. Please try again',
+                switchConflict: '.env or settings.json was just modified by another program. Reloaded, please try again',
+                confirmDelete: 'Are you sure you want to delete Gemini channel "{name}"?'
             }
         },
         codex: {
@@ -429,7 +644,17 @@ const translations = {
                 launch: 'Launch',
                 switch: 'Switch',
                 edit: 'Edit',
-                delete: 'Delete'
+                delete: 'Delete',
+                pin: 'Pin',
+                unpin: 'Unpin',
+                duplicate: 'Duplicate',
+                moveUp: 'Move up',
+                moveDown: 'Move down'
+            },
+            authMode: {
+                apiKey: 'API Key',
+                chatgpt: 'ChatGPT login',
+                defaultModel: 'default model'
             },
             modal: {
                 titleNew: 'New Codex Channel',
@@ -437,12 +662,23 @@ const translations = {
                 fields: {
                     name: 'Channel Name',
                     namePlaceholder: 'Letters, digits, - and _ only',
+                    authMode: 'Auth mode (ChatGPT login saves the current auth.json)',
                     baseurl: 'Base URL',
                     baseurlPlaceholder: 'https://api.example.com',
                     apikey: 'API Key',
                     apikeyPlaceholder: 'sk-xxxxxx...',
                     model: 'Model Name',
-                    modelPlaceholder: 'e.g. o3-mini'
+                    modelPlaceholder: 'e.g. o3-mini',
+                    provider: 'Provider ID (optional)',
+                    providerPlaceholder: 'Leave empty to reuse the provider in config.toml',
+                    wireApi: 'Wire API',
+                    wireApiDefault: 'Default',
+                    envKey: 'API key env var (optional)',
+                    envKeyPlaceholder: 'e.g. AZURE_OPENAI_API_KEY',
+                    queryParams: 'Query params (optional)',
+                    queryParamsPlaceholder: 'e.g. api-version=2025-04-01-preview',
+                    extraConfig: 'Extra config (TOML, optional)',
+                    extraConfigPlaceholder: 'model_reasoning_effort = "high"\napproval_policy = "on-request"\n\n[provider]\nhttp_headers = { "X-Team" = "core" }'
                 },
                 buttons: {
                     cancel: 'Cancel',
@@ -453,6 +689,8 @@ const translations = {
                 channelCreated: 'Codex channel created',
                 channelUpdated: 'Codex channel updated',
                 channelDeleted: 'Codex channel deleted',
+                channelDuplicated: 'Codex channel duplicated',
+                storeConflict: 'The channel list was changed elsewhere and has been reloaded. Please try again',
                 channelSwitched: 'Switched to Codex channel',
                 channelLaunched: 'Codex launched in new window',
                 channelsRefreshed: 'Codex channel list refreshed',
@@ -463,6 +701,8 @@ const translations = {
                 errorBaseurlInvalid: 'Base URL must be a valid http:// or https:// URL',
                 errorApikeyRequired: 'API Key is required',
                 errorModelRequired: 'Model name is required',
+                errorProviderInvalid: 'Provider ID may only contain letters, digits, - and _ (max 18)',
+                storeReadOnly: 'Codex channel store v{version} is newer than supported v{supported}; opened read-only. Upgrade the app to edit channels',
                 confirmDelete: 'Are you sure you want to delete this Codex channel?'
             }
         },
@@ -503,6 +743,11 @@ const translations = {
                 nameExists: 'This name already exists, please use a different name',
                 confirmDeleteTitle: 'Confirm Delete',
                 confirmDeleteMessage: 'Are you sure you want to delete style "{name}"?',
+                confirmDeleteAppliedMessage: 'Style "{name}" is currently applied. Deleting it will restore the previous StatusLine setting. Continue?',
+                applied: 'In use',
+                clearApplied: 'Unapply',
+                import: 'Import',
+                export: 'Export bundle',
                 saveChoiceMessage: 'Save changes to style "{name}"?',
                 overwrite: 'Overwrite',
                 saveAs: 'Save As',
@@ -516,6 +761,9 @@ const translations = {
                 fileSaved: 'Style "{name}" saved',
                 fileDeleted: 'Style "{name}" deleted',
                 applied: 'Style "{name}" applied',
+                cleared: 'StatusLine unapplied',
+                exported: 'Style "{name}" exported',
+                imported: 'Style "{name}" imported',
                 reset: 'Configuration reset to default'
             }
         },
@@ -567,6 +815,12 @@ const translations = {
                 browse: 'Browse',
                 label: 'Codex configuration path'
             },
+            geminiPath: {
+                title: 'Gemini Config Path',
+                description: 'Set the Gemini CLI configuration location (.env, settings.json)',
+                browse: 'Browse',
+                label: 'Gemini configuration path'
+            },
             terminal: {
                 title: 'Terminal Program',
                 description: 'Choose the terminal to launch Claude'
@@ -597,6 +851,7 @@ const translations = {
             channelsRefreshed: 'Channels refreshed',
             pathUpdated: 'Config path updated',
             codexPathUpdated: 'Codex configuration path updated',
+            geminiPathUpdated: 'Gemini configuration path updated',
             terminalSet: 'Terminal set to: {terminal}',
             terminalDirUpdated: 'Terminal working directory updated',
             themeChanged: 'Switched to {theme} theme',
@@ -624,6 +879,9 @@ const translations = {
             addCodex: 'Add new Codex channel',
             codexPage: 'Codex Channels',
             droidPage: 'Droid Channel Management',
+            refreshGemini: 'Refresh Gemini channel list',
+            addGemini: 'Add new Gemini channel',
+            geminiPage: 'Gemini Channel Management',
             statuslinePage: 'Claude StatusLine Configuration Page',
             browseFolder: 'Browse folder',
             closeDialog: 'Close dialog',
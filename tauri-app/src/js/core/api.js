@@ -6,6 +6,7 @@ class TauriAPI {
     constructor() {
         this.invoke = null;
         this.openDialog = null;
+        this.saveDialog = null;
         this.initialized = false;
     }
 
@@ -19,6 +20,9 @@ class TauriAPI {
             this.openDialog = async (options) => {
                 return await this.invoke('plugin:dialog|open', { options });
             };
+            this.saveDialog = async (options) => {
+                return await this.invoke('plugin:dialog|save', { options });
+            };
             this.initialized = true;
             return true;
         }
@@ -68,7 +72,7 @@ class TauriAPI {
 
     /**
      * 保存渠道配置
-     * @param {object} params - 渠道参数 {configPath, channelName, token, url, model, oldName, balanceUrl, balanceMethod, balanceField}
+     * @param {object} params - 渠道参数 {configPath, channelName, token, url, model, oldName, balanceUrl, balanceMethod, balanceField, expectedCtime}
      * @returns {Promise<ApiResponse>} 保存结果
      */
     async saveChannel(params) {
@@ -81,7 +85,8 @@ class TauriAPI {
             oldName: params.oldName,
             balanceUrl: params.balanceUrl || '',
             balanceMethod: params.balanceMethod || 'POST',
-            balanceField: params.balanceField || ''
+            balanceField: params.balanceField || '',
+            expectedCtime: params.expectedCtime ?? null
         });
     }
 
@@ -114,6 +119,27 @@ class TauriAPI {
         return await this.safeInvoke('launch_claude', { terminalDir });
     }
 
+    /**
+     * 监听配置目录，外部修改后后端推送 channels-changed / active-changed / statusline-changed 事件
+     * @param {string} configPath - Claude 配置目录（同时存放 Droid 渠道）
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @param {string} geminiConfigPath - Gemini 配置目录
+     * @returns {Promise<ApiResponse>} 监听结果
+     */
+    async watchConfigDirs(configPath, codexConfigPath, geminiConfigPath) {
+        return await this.safeInvoke('watch_config_dirs', { configPath, codexConfigPath, geminiConfigPath });
+    }
+
+    /**
+     * 订阅后端事件
+     * @param {string} event - 事件名称
+     * @param {Function} handler - 回调，参数为事件 payload
+     * @returns {Promise<Function>} 取消订阅函数
+     */
+    async listen(event, handler) {
+        return await window.__TAURI__.event.listen(event, (e) => handler(e.payload));
+    }
+
     /**
      * 获取用户主目录
      * @returns {Promise<string>} 主目录路径
@@ -190,23 +216,44 @@ class TauriAPI {
     }
 
     /**
-     * 切换 Droid 渠道（设置环境变量）
-     * @param {string} apiKey - API Key
+     * 获取当前激活的 Droid 渠道（按 FACTORY_API_KEY 匹配）
+     * @param {string} configPath - 配置文件路径
+     * @returns {Promise<ApiResponse>} 渠道名称、Key 与最近切换时间
+     */
+    async getActiveDroidChannel(configPath) {
+        return await this.safeInvoke('get_active_droid_channel', { configPath });
+    }
+
+    /**
+     * 切换 Droid 渠道（设置环境变量并记录激活标记）
+     * @param {string} configPath - 配置文件路径
+     * @param {string} name - 渠道名称
      * @returns {Promise<ApiResponse>} 切换结果
      */
-    async switchDroidChannel(apiKey) {
-        return await this.safeInvoke('switch_droid_channel', { apiKey });
+    async switchDroidChannel(configPath, name) {
+        return await this.safeInvoke('switch_droid_channel', { configPath, name });
     }
 
     /**
      * 保存 Droid 渠道
-     * @param {object} params - {configPath, name, apiKey, oldName}
+     * @param {object} params - {configPath, name, apiKey, oldName, baseUrl, notes}
      * @returns {Promise<ApiResponse>} 保存结果
      */
     async saveDroidChannel(params) {
         return await this.safeInvoke('save_droid_channel', params);
     }
 
+    /**
+     * 检测 Droid 渠道的 API Key 是否可用
+     * @param {string} configPath - 配置文件路径
+     * @param {string} name - 渠道名称
+     * @param {string|null} endpoint - 自定义检测地址（可选）
+     * @returns {Promise<ApiResponse>} 检测结果（valid、category、latency_ms）
+     */
+    async testDroidChannel(configPath, name, endpoint = null) {
+        return await this.safeInvoke('test_droid_channel', { configPath, name, endpoint });
+    }
+
     /**
      * 删除 Droid 渠道
      * @param {string} configPath - 配置文件路径
@@ -217,6 +264,186 @@ class TauriAPI {
         return await this.safeInvoke('delete_droid_channel', { configPath, name });
     }
 
+    /**
+     * 以统一模型列出任意工具的渠道
+     * @param {string} tool - claude / codex / droid / gemini / adapter
+     * @param {string} configPath - 该工具的配置目录
+     * @param {string|null} adapter - tool 为 adapter 时的适配器 id
+     * @returns {Promise<ApiResponse>} 渠道列表与当前渠道 id
+     */
+    async listToolChannels(tool, configPath, adapter = null) {
+        return await this.safeInvoke('list_tool_channels', { tool, configPath, adapter });
+    }
+
+    /**
+     * 保存渠道，id 为空时新建
+     * @param {string} tool - claude / codex / droid / gemini / adapter
+     * @param {string} configPath - 该工具的配置目录
+     * @param {object} channel - { id, name, base_url, api_key, model }
+     * @param {string|null} adapter - tool 为 adapter 时的适配器 id
+     * @returns {Promise<ApiResponse>} 保存后的渠道 id
+     */
+    async saveToolChannel(tool, configPath, channel, adapter = null) {
+        return await this.safeInvoke('save_tool_channel', { tool, configPath, adapter, channel });
+    }
+
+    /**
+     * 删除渠道
+     * @param {string} tool - claude / codex / droid / gemini / adapter
+     * @param {string} configPath - 该工具的配置目录
+     * @param {string} channelId - 渠道 id
+     * @param {string|null} adapter - tool 为 adapter 时的适配器 id
+     * @returns {Promise<ApiResponse>} 删除结果
+     */
+    async deleteToolChannel(tool, configPath, channelId, adapter = null) {
+        return await this.safeInvoke('delete_tool_channel', { tool, configPath, adapter, channelId });
+    }
+
+    /**
+     * 切换到指定渠道
+     * @param {string} tool - claude / codex / droid / gemini / adapter
+     * @param {string} configPath - 该工具的配置目录
+     * @param {string} channelId - 渠道 id
+     * @param {string|null} adapter - tool 为 adapter 时的适配器 id
+     * @returns {Promise<ApiResponse>} 切换结果
+     */
+    async switchToolChannel(tool, configPath, channelId, adapter = null) {
+        return await this.safeInvoke('switch_tool_channel', { tool, configPath, adapter, channelId });
+    }
+
+    /**
+     * 检测渠道连通性
+     * @param {string} tool - claude / codex / droid / gemini / adapter
+     * @param {string} configPath - 该工具的配置目录
+     * @param {string} channelId - 渠道 id
+     * @param {number|null} timeoutMs - 超时时间，默认 10 秒
+     * @param {string|null} adapter - tool 为 adapter 时的适配器 id
+     * @returns {Promise<ApiResponse>} 检测结果（valid、category、latency_ms）
+     */
+    async testChannel(tool, configPath, channelId, timeoutMs = null, adapter = null) {
+        return await this.safeInvoke('test_channel', { tool, configPath, adapter, channelId, timeoutMs });
+    }
+
+    /**
+     * 以指定渠道在新终端中启动 CLI，渠道只注入该子进程，不修改全局配置
+     * @param {string} tool - claude / codex / droid
+     * @param {string} configPath - 该工具的配置目录
+     * @param {string} channelId - 渠道 id
+     * @param {string} terminalDir - 终端工作目录
+     * @returns {Promise<ApiResponse>} 启动结果
+     */
+    async launchWithChannel(tool, configPath, channelId, terminalDir) {
+        return await this.safeInvoke('launch_with_channel', { tool, configPath, channelId, terminalDir });
+    }
+
+    /**
+     * 列出内置与 tool_adapters.json 中自定义的工具适配器
+     * @param {string} configPath - Claude 配置目录
+     * @returns {Promise<ApiResponse>} 适配器 spec 及是否可回滚
+     */
+    async listToolAdapters(configPath) {
+        return await this.safeInvoke('list_tool_adapters', { configPath });
+    }
+
+    /**
+     * 恢复适配器最近一次切换前的配置文件
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} adapterId - 适配器 id
+     * @returns {Promise<ApiResponse>} 回滚结果
+     */
+    async rollbackAdapterSwitch(configPath, adapterId) {
+        return await this.safeInvoke('rollback_adapter_switch', { configPath, adapterId });
+    }
+
+    /**
+     * 获取服务商列表
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @returns {Promise<ApiResponse>} 服务商、修订号与当前服务商 id
+     */
+    async getProviders(configPath, codexConfigPath) {
+        return await this.safeInvoke('get_providers', { configPath, codexConfigPath });
+    }
+
+    /**
+     * 保存服务商并同步派生的 Claude / Codex / Droid 渠道，id 为空时新建
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @param {object} provider - { id, name, api_key, anthropic_base_url, openai_base_url, factory_base_url, claude_model, codex_model, tools }
+     * @param {number} expectedRevision - 加载列表时的修订号
+     * @returns {Promise<ApiResponse>} 新的修订号与服务商 id
+     */
+    async saveProvider(configPath, codexConfigPath, provider, expectedRevision) {
+        return await this.safeInvoke('save_provider', { configPath, codexConfigPath, provider, expectedRevision });
+    }
+
+    /**
+     * 删除服务商及其派生渠道
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @param {string} providerId - 服务商 id
+     * @param {number} expectedRevision - 加载列表时的修订号
+     * @returns {Promise<ApiResponse>} 新的修订号
+     */
+    async deleteProvider(configPath, codexConfigPath, providerId, expectedRevision) {
+        return await this.safeInvoke('delete_provider', { configPath, codexConfigPath, providerId, expectedRevision });
+    }
+
+    /**
+     * 把所有关联工具切换到该服务商，任一工具失败时全部恢复
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @param {string} providerId - 服务商 id
+     * @returns {Promise<ApiResponse>} 切换结果
+     */
+    async switchEverywhere(configPath, codexConfigPath, providerId) {
+        return await this.safeInvoke('switch_everywhere', { configPath, codexConfigPath, providerId });
+    }
+
+    /**
+     * 把 Claude 渠道应用到项目的 .claude/settings.local.json
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} projectPath - 项目目录
+     * @param {string} channelName - 渠道名称
+     * @returns {Promise<ApiResponse>} 切换结果
+     */
+    async switchProjectChannel(configPath, projectPath, channelName) {
+        return await this.safeInvoke('switch_project_channel', { configPath, projectPath, channelName });
+    }
+
+    /**
+     * 为项目写入 Codex profile
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @param {string} projectPath - 项目目录
+     * @param {string} channelId - Codex 渠道 id
+     * @returns {Promise<ApiResponse>} profile 名称，以 codex --profile <name> 启动
+     */
+    async switchProjectCodexChannel(configPath, codexConfigPath, projectPath, channelId) {
+        return await this.safeInvoke('switch_project_codex_channel', { configPath, codexConfigPath, projectPath, channelId });
+    }
+
+    /**
+     * 列出设置了项目级渠道的项目
+     * @param {string} configPath - Claude 配置目录
+     * @returns {Promise<ApiResponse>} 项目路径、各工具渠道与目录是否已不存在
+     */
+    async listProjectOverrides(configPath) {
+        return await this.safeInvoke('list_project_overrides', { configPath });
+    }
+
+    /**
+     * 清除项目级渠道，恢复项目原有配置
+     * @param {string} configPath - Claude 配置目录
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @param {string} projectPath - 项目目录
+     * @param {string|null} tool - claude / codex，为空时全部清除
+     * @returns {Promise<ApiResponse>} 清除结果
+     */
+    async clearProjectOverride(configPath, codexConfigPath, projectPath, tool = null) {
+        return await this.safeInvoke('clear_project_override', { configPath, codexConfigPath, projectPath, tool });
+    }
+
     /**
      * 启动 Droid
      * @param {string} terminalDir - 终端工作目录
@@ -226,6 +453,24 @@ class TauriAPI {
         return await this.safeInvoke('launch_droid', { terminalDir });
     }
 
+    /**
+     * 在 Shell 配置文件中加载 FACTORY_API_KEY（仅 Linux / macOS）
+     * @param {string[]|null} shells - bash / zsh / fish，为空时根据 $SHELL 推断
+     * @returns {Promise<ApiResponse>} 修改过的配置文件路径
+     */
+    async installDroidShellHook(shells = null) {
+        return await this.safeInvoke('install_droid_shell_hook', { shells });
+    }
+
+    /**
+     * 从 Shell 配置文件中移除加载代码
+     * @param {string[]|null} shells - 为空时处理全部支持的 Shell
+     * @returns {Promise<ApiResponse>} 修改过的配置文件路径
+     */
+    async uninstallDroidShellHook(shells = null) {
+        return await this.safeInvoke('uninstall_droid_shell_hook', { shells });
+    }
+
     /**
      * 查询渠道余额
      * @param {string} url - 查询地址（包含 {apikey} 占位符）
@@ -243,19 +488,76 @@ class TauriAPI {
         return await this.safeInvoke('get_codex_channels', { codexConfigPath });
     }
 
+    /**
+     * 获取 Codex 渠道存储的版本状态
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @returns {Promise<Object>} data: { version, supported_version, read_only }
+     */
+    async getCodexStoreStatus(codexConfigPath) {
+        return await this.safeInvoke('get_codex_store_status', { codexConfigPath });
+    }
+
+    /**
+     * 新建或编辑 Codex 渠道，id 为空时新建
+     * @param {Object} params - 渠道字段、codexConfigPath 与 expectedRevision
+     * @returns {Promise<Object>} data: { revision, channel_id }
+     */
     async saveCodexChannel(params) {
         return await this.safeInvoke('save_codex_channel', {
             codexConfigPath: params.codexConfigPath,
-            name: params.name,
-            baseurl: params.baseurl,
-            apikey: params.apikey,
-            model: params.model,
-            editIndex: params.editIndex
+            channel: {
+                id: params.id || '',
+                name: params.name,
+                baseurl: params.baseurl,
+                apikey: params.apikey,
+                model: params.model,
+                provider: params.provider || null,
+                extra_config: params.extraConfig || '',
+                auth_mode: params.authMode || 'api_key'
+            },
+            expectedRevision: params.expectedRevision
+        });
+    }
+
+    async deleteCodexChannel(codexConfigPath, channelId, expectedRevision) {
+        return await this.safeInvoke('delete_codex_channel', {
+            codexConfigPath,
+            channelId,
+            expectedRevision
+        });
+    }
+
+    /**
+     * 移动 Codex 渠道，目标位置限制在置顶区或非置顶区内
+     * @param {string} codexConfigPath - Codex 配置目录
+     * @param {string} channelId - 渠道 ID
+     * @param {number} toIndex - 目标位置
+     * @param {number} expectedRevision - 当前列表修订号
+     */
+    async moveCodexChannel(codexConfigPath, channelId, toIndex, expectedRevision) {
+        return await this.safeInvoke('move_codex_channel', {
+            codexConfigPath,
+            channelId,
+            toIndex,
+            expectedRevision
+        });
+    }
+
+    async duplicateCodexChannel(codexConfigPath, channelId, expectedRevision) {
+        return await this.safeInvoke('duplicate_codex_channel', {
+            codexConfigPath,
+            channelId,
+            expectedRevision
         });
     }
 
-    async deleteCodexChannel(codexConfigPath, deleteIndex) {
-        return await this.safeInvoke('delete_codex_channel', { codexConfigPath, deleteIndex });
+    async setCodexChannelPinned(codexConfigPath, channelId, pinned, expectedRevision) {
+        return await this.safeInvoke('set_codex_channel_pinned', {
+            codexConfigPath,
+            channelId,
+            pinned,
+            expectedRevision
+        });
     }
 
     async getCurrentCodexEnv(codexConfigPath) {
@@ -273,6 +575,47 @@ class TauriAPI {
         return await this.safeInvoke('launch_codex', { terminalDir });
     }
 
+    // ==================== Gemini 渠道 API ====================
+
+    async getGeminiChannels(geminiConfigPath) {
+        return await this.safeInvoke('get_gemini_channels', { geminiConfigPath });
+    }
+
+    /**
+     * 保存 Gemini 渠道，channel.id 为空时新建
+     * @param {string} geminiConfigPath - Gemini 配置目录
+     * @param {object} channel - { id, name, api_key, base_url, model }
+     * @param {number} expectedRevision - 加载列表时的修订号
+     * @returns {Promise<ApiResponse>} 新的修订号与渠道 id
+     */
+    async saveGeminiChannel(geminiConfigPath, channel, expectedRevision) {
+        return await this.safeInvoke('save_gemini_channel', {
+            geminiConfigPath,
+            channel,
+            expectedRevision
+        });
+    }
+
+    async deleteGeminiChannel(geminiConfigPath, channelId, expectedRevision) {
+        return await this.safeInvoke('delete_gemini_channel', {
+            geminiConfigPath,
+            channelId,
+            expectedRevision
+        });
+    }
+
+    async switchGeminiChannel(geminiConfigPath, channelId) {
+        return await this.safeInvoke('switch_gemini_channel', { geminiConfigPath, channelId });
+    }
+
+    async getCurrentGeminiEnv(geminiConfigPath) {
+        return await this.safeInvoke('get_current_gemini_env', { geminiConfigPath });
+    }
+
+    async launchGemini(terminalDir) {
+        return await this.safeInvoke('launch_gemini', { terminalDir });
+    }
+
     // ==================== StatusLine 管理 API ====================
 
     /**
@@ -305,19 +648,99 @@ class TauriAPI {
     /**
      * 删除 StatusLine 文件
      * @param {string} fileName - 文件名
+     * @param {boolean} force - 样式正在使用时是否仍然删除（会恢复应用前的配置）
      * @returns {Promise<ApiResponse>} 删除结果
      */
-    async deleteStatuslineFile(fileName) {
-        return await this.safeInvoke('delete_statusline_file', { fileName });
+    async deleteStatuslineFile(fileName, force = false) {
+        return await this.safeInvoke('delete_statusline_file', { fileName, force });
     }
 
     /**
      * 应用 StatusLine 到 settings.json
      * @param {string} fileName - 文件名
+     * @param {boolean} useNativeRenderer - 是否使用内置渲染器（不启动 PowerShell）
      * @returns {Promise<ApiResponse>} 应用结果
      */
-    async applyStatuslineToSettings(fileName) {
-        return await this.safeInvoke('apply_statusline_to_settings', { fileName });
+    async applyStatuslineToSettings(fileName, useNativeRenderer = true) {
+        return await this.safeInvoke('apply_statusline_to_settings', { fileName, useNativeRenderer });
+    }
+
+    /**
+     * 获取当前应用到 settings.json 的 StatusLine
+     * @returns {Promise<ApiResponse>} 当前命令、样式名及是否可恢复
+     */
+    async getAppliedStatusline() {
+        return await this.safeInvoke('get_applied_statusline');
+    }
+
+    /**
+     * 取消应用 StatusLine
+     * @param {boolean} restorePrevious - 是否恢复应用前的 statusLine
+     * @returns {Promise<ApiResponse>} 操作结果
+     */
+    async clearStatusline(restorePrevious = true) {
+        return await this.safeInvoke('clear_statusline', { restorePrevious });
+    }
+
+    /**
+     * 导出样式包
+     * @param {string} name - 样式名称
+     * @param {string|null} author - 作者（可选）
+     * @param {string|null} targetPath - 保存路径，为空时只返回内容
+     * @returns {Promise<ApiResponse>} 样式包 JSON 内容
+     */
+    async exportStatusline(name, author = null, targetPath = null) {
+        return await this.safeInvoke('export_statusline', { name, author, targetPath });
+    }
+
+    /**
+     * 导入样式包
+     * @param {object} options - sourcePath / content 二选一，name 可覆盖样式名
+     * @param {string} options.onConflict - rename / overwrite / fail
+     * @returns {Promise<ApiResponse>} 最终保存的样式名称
+     */
+    async importStatusline({ sourcePath = null, content = null, name = null, onConflict = 'rename' } = {}) {
+        return await this.safeInvoke('import_statusline', { sourcePath, content, name, onConflict });
+    }
+
+    /**
+     * 读取 StatusLine 样式定义（旧版脚本自动转换）
+     * @param {string} name - 样式名称
+     * @returns {Promise<ApiResponse>} 样式定义
+     */
+    async getStatuslineSpec(name) {
+        return await this.safeInvoke('get_statusline_spec', { name });
+    }
+
+    /**
+     * 保存 StatusLine 样式定义，并生成 PowerShell 与 sh 脚本
+     * @param {string} name - 样式名称
+     * @param {object} spec - 样式定义
+     * @returns {Promise<ApiResponse>} 保存结果
+     */
+    async saveStatuslineSpec(name, spec) {
+        return await this.safeInvoke('save_statusline_spec', { name, spec });
+    }
+
+    /**
+     * 根据样式定义生成脚本内容
+     * @param {object} spec - 样式定义
+     * @param {string} shell - powershell / posix
+     * @returns {Promise<ApiResponse>} 脚本内容
+     */
+    async generateStatuslineScript(spec, shell) {
+        return await this.safeInvoke('generate_statusline_script', { spec, shell });
+    }
+
+    /**
+     * 使用模拟会话数据预览样式输出
+     * @param {object} spec - 样式定义
+     * @param {string} mode - native / powershell / posix
+     * @param {string} cwd - 预览使用的工作目录
+     * @returns {Promise<ApiResponse>} 预览结果（原始输出与样式片段）
+     */
+    async previewStatusline(spec, mode = 'native', cwd = null) {
+        return await this.safeInvoke('preview_statusline', { spec, mode, cwd });
     }
 }
 
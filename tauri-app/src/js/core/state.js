@@ -6,6 +6,7 @@ class AppState {
     constructor() {
         this.configPath = '';
         this.codexConfigPath = '';
+        this.geminiConfigPath = '';
         this.terminalDir = '';
         this.appVersion = '';
         this.theme = localStorage.getItem('theme') || 'dark';
@@ -22,7 +23,7 @@ class AppState {
      */
     save(key, value) {
         this[key] = value;
-        if (['theme', 'language', 'configPath', 'codexConfigPath', 'terminalDir'].includes(key)) {
+        if (['theme', 'language', 'configPath', 'codexConfigPath', 'geminiConfigPath', 'terminalDir'].includes(key)) {
             localStorage.setItem(key, value);
         }
     }
@@ -44,8 +45,10 @@ class AppState {
     initConfigPath(homeDirectory) {
         const defaultConfigPath = `${homeDirectory}\\.claude`;
         const defaultCodexConfigPath = `${homeDirectory}\\.codex`;
+        const defaultGeminiConfigPath = `${homeDirectory}\\.gemini`;
         this.configPath = this.load('configPath', defaultConfigPath);
         this.codexConfigPath = this.load('codexConfigPath', defaultCodexConfigPath);
+        this.geminiConfigPath = this.load('geminiConfigPath', defaultGeminiConfigPath);
         this.terminalDir = this.load('terminalDir', homeDirectory);
     }
 }
@@ -267,12 +267,18 @@ class ChannelManager {
                 this.switchingChannelName = null;
                 this.renderChannels();
                 ErrorHandler.showError(result.error, '切换失败');
+                if (ErrorHandler.isConflict(result.error)) {
+                    await this.loadChannels();
+                }
             }
         } catch (error) {
             await this.ensureSwitchingVisible(switchStartedAt);
             this.switchingChannelName = null;
             this.renderChannels();
             ErrorHandler.showError(error, '切换失败');
+            if (ErrorHandler.isConflict(error)) {
+                await this.loadChannels();
+            }
         }
     }
 
@@ -397,7 +403,9 @@ class ChannelManager {
                 oldName: state.editingChannel || '',
                 balanceUrl: balanceUrl || '',
                 balanceMethod: balanceMethod || 'POST',
-                balanceField: balanceField || ''
+                balanceField: balanceField || '',
+                // 编辑时携带加载时的修改时间，文件已被其他程序修改则保存失败
+                expectedCtime: state.channels[state.editingChannel]?.ctime ?? null
             });
 
             if (result.success) {
@@ -412,9 +420,15 @@ class ChannelManager {
                 await this.loadChannels(isEditingActiveChannel);
             } else {
                 ErrorHandler.showError(result.error, '保存失败');
+                if (ErrorHandler.isConflict(result.error)) {
+                    await this.loadChannels();
+                }
             }
         } catch (error) {
             ErrorHandler.showError(error, '保存失败');
+            if (ErrorHandler.isConflict(error)) {
+                await this.loadChannels();
+            }
         }
     }
 
@@ -3,6 +3,7 @@
  * 负责 Codex 渠道的 CRUD 操作、列表渲染和状态管理
  * 渠道数据存储在 codexConfigPath/channels.json 中
  * 通过 auth.json 中的 OPENAI_API_KEY 判断激活状态和执行切换
+ * 渠道按 ID 寻址，每次修改都携带列表修订号，修订号不一致时重新加载
  */
 const CODEX_SWITCHING_MIN_DURATION = 300;
 
@@ -11,14 +12,23 @@ class CodexManager {
         this.codexList = null;
         this.codexCount = null;
         this.codexChannels = [];
-        this.currentApiKey = '';
+        this.revision = 0;
+        this.activeChannelName = null;
         this.switchingChannelName = null;
-        this.editingIndex = -1;
+        this.editingId = '';
         this.modal = null;
         this.nameInput = null;
         this.baseurlInput = null;
         this.apikeyInput = null;
         this.modelInput = null;
+        this.providerInput = null;
+        this.wireApiSelect = null;
+        this.envKeyInput = null;
+        this.queryParamsInput = null;
+        this.extraConfigInput = null;
+        this.authModeSelect = null;
+        this.readOnly = false;
+        this.readOnlyNotified = false;
     }
 
     init() {
@@ -29,6 +39,12 @@ class CodexManager {
         this.baseurlInput = document.getElementById('codex-baseurl-input');
         this.apikeyInput = document.getElementById('codex-apikey-input');
         this.modelInput = document.getElementById('codex-model-input');
+        this.providerInput = document.getElementById('codex-provider-input');
+        this.wireApiSelect = document.getElementById('codex-wireapi-select');
+        this.envKeyInput = document.getElementById('codex-envkey-input');
+        this.queryParamsInput = document.getElementById('codex-queryparams-input');
+        this.extraConfigInput = document.getElementById('codex-extra-input');
+        this.authModeSelect = document.getElementById('codex-authmode-select');
 
         this.setupEventListeners();
     }
@@ -44,6 +60,8 @@ class CodexManager {
             addBtn.addEventListener('click', () => this.openNewModal());
         }
 
+        this.authModeSelect?.addEventListener('change', () => this.updateAuthModeFields());
+
         if (refreshBtn) {
             const debouncedRefresh = debounce(() => this.refreshChannels(), 300);
             refreshBtn.addEventListener('click', debouncedRefresh);
@@ -66,9 +84,9 @@ class CodexManager {
         try {
             const envResult = await api.getCurrentCodexEnv(state.codexConfigPath);
             if (envResult.success && envResult.data) {
-                this.currentApiKey = envResult.data.api_key || '';
+                this.activeChannelName = envResult.data.active_channel || null;
             } else {
-                this.currentApiKey = '';
+                this.activeChannelName = null;
             }
 
             const result = await api.getCodexChannels(state.codexConfigPath);
@@ -80,14 +98,16 @@ class CodexManager {
                 return;
             }
 
-            this.codexChannels = (result.data || []).map(channel => ({
+            this.revision = result.data?.revision || 0;
+            this.codexChannels = (result.data?.channels || []).map(channel => ({
                 ...channel,
                 baseurl: this.normalizeBaseUrl(channel.baseurl || '')
             }));
+            await this.loadStoreStatus();
             this.renderChannels();
         } catch (error) {
             ErrorHandler.handle(error, 'Load Codex channels');
-            this.currentApiKey = '';
+            this.activeChannelName = null;
             this.codexChannels = [];
             this.renderChannels();
         }
@@ -97,6 +117,26 @@ class CodexManager {
         return this.loadChannels();
     }
 
+    // 存储由更新版本的应用写入时只允许切换，不允许新建、编辑和删除
+    async loadStoreStatus() {
+        try {
+            const result = await api.getCodexStoreStatus(state.codexConfigPath);
+            this.readOnly = !!result.data?.read_only;
+            if (this.readOnly && !this.readOnlyNotified) {
+                this.readOnlyNotified = true;
+                toast.show(i18n.t('codex.messages.storeReadOnly', {
+                    version: result.data.version,
+                    supported: result.data.supported_version
+                }));
+            }
+        } catch (error) {
+            this.readOnly = false;
+            ErrorHandler.handle(error, 'Load Codex store status');
+        }
+
+        DOMUtils.setElementState(document.getElementById('add-codex-btn'), this.readOnly);
+    }
+
     normalizeBaseUrl(baseurl) {
         const raw = String(baseurl || '').trim();
         if (!raw) {
@@ -124,7 +164,11 @@ class CodexManager {
     }
 
     isChannelActive(channel) {
-        return !!this.currentApiKey && channel.apikey === this.currentApiKey;
+        return !!this.activeChannelName && channel.name === this.activeChannelName;
+    }
+
+    isChatgptChannel(channel) {
+        return channel.auth_mode === 'chatgpt';
     }
 
     renderChannels() {
@@ -159,11 +203,13 @@ class CodexManager {
 
     createChannelCard(channel, index, isActive, isSwitching, isAnySwitching) {
         const card = document.createElement('div');
-        card.className = `channel-card${isActive ? ' active' : ''}${isSwitching ? ' switching' : ''}`;
+        card.className = `channel-card${isActive ? ' active' : ''}${isSwitching ? ' switching' : ''}${channel.pinned ? ' pinned' : ''}`;
         card.setAttribute('aria-busy', isSwitching ? 'true' : 'false');
 
         const displayName = channel.name || '-';
-        const displayModel = channel.model || '-';
+        const displayModel = this.isChatgptChannel(channel)
+            ? `${i18n.t('codex.authMode.chatgpt')} · ${channel.model || i18n.t('codex.authMode.defaultModel')}`
+            : channel.model || '-';
 
         let statusClass = '';
         let statusText = i18n.t('codex.status.inactive');
@@ -178,7 +224,11 @@ class CodexManager {
         const switchLabel = isSwitching
             ? `⏳ ${i18n.t('codex.actions.switching')}`
             : `⚡ ${i18n.t('codex.actions.switch')}`;
-        const actionsDisabled = isAnySwitching ? 'disabled' : '';
+        const actionsDisabled = isAnySwitching || this.readOnly ? 'disabled' : '';
+        const { canMoveUp, canMoveDown } = this.getMoveBounds(channel, index);
+        const pinLabel = channel.pinned
+            ? i18n.t('codex.actions.unpin')
+            : i18n.t('codex.actions.pin');
         const switchingBadge = isSwitching
             ? `<div class="channel-switching-badge"><span class="channel-switching-spinner" aria-hidden="true"></span><span>${i18n.t('codex.actions.switching')}</span></div>`
             : '';
@@ -188,7 +238,7 @@ class CodexManager {
             <div class="channel-header">
                 <div class="channel-icon">📘</div>
                 <div class="channel-info">
-                    <div class="channel-name">${DOMUtils.escapeHtml(displayName)}</div>
+                    <div class="channel-name">${channel.pinned ? '📌 ' : ''}${DOMUtils.escapeHtml(displayName)}</div>
                     <div class="channel-url">${DOMUtils.escapeHtml(displayModel)}</div>
                     <div class="channel-status">${statusIndicator}</div>
                 </div>
@@ -199,12 +249,29 @@ class CodexManager {
                 <button class="btn btn-edit btn-small edit-btn" ${actionsDisabled}>✏️ ${i18n.t('codex.actions.edit')}</button>
                 <button class="btn btn-danger btn-small delete-btn" ${actionsDisabled}>🗑️ ${i18n.t('codex.actions.delete')}</button>
             </div>
+            <div class="channel-actions channel-order-actions">
+                <button class="btn btn-secondary btn-small pin-btn" ${actionsDisabled}>📌 ${pinLabel}</button>
+                <button class="btn btn-secondary btn-small duplicate-btn" ${actionsDisabled}>📄 ${i18n.t('codex.actions.duplicate')}</button>
+                <button class="btn btn-secondary btn-small move-up-btn" aria-label="${i18n.t('codex.actions.moveUp')}" title="${i18n.t('codex.actions.moveUp')}" ${actionsDisabled || (canMoveUp ? '' : 'disabled')}>↑</button>
+                <button class="btn btn-secondary btn-small move-down-btn" aria-label="${i18n.t('codex.actions.moveDown')}" title="${i18n.t('codex.actions.moveDown')}" ${actionsDisabled || (canMoveDown ? '' : 'disabled')}>↓</button>
+            </div>
         `;
 
         this.attachCardEventListeners(card, channel, index, isActive, isSwitching, isAnySwitching);
         return card;
     }
 
+    // 置顶渠道只能在置顶区内移动，其余渠道只能在非置顶区内移动
+    getMoveBounds(channel, index) {
+        const pinnedCount = this.codexChannels.filter(item => item.pinned).length;
+        const start = channel.pinned ? 0 : pinnedCount;
+        const end = channel.pinned ? pinnedCount - 1 : this.codexChannels.length - 1;
+        return {
+            canMoveUp: index > start,
+            canMoveDown: index < end
+        };
+    }
+
     attachCardEventListeners(card, channel, index, isActive, isSwitching, isAnySwitching) {
         if (isActive) {
             const launchBtn = card.querySelector('.launch-btn');
@@ -219,13 +286,71 @@ class CodexManager {
         }
 
         const editBtn = card.querySelector('.edit-btn');
-        if (!isAnySwitching) {
-            editBtn?.addEventListener('click', () => this.openEditModal(channel, index));
+        if (!isAnySwitching && !this.readOnly) {
+            editBtn?.addEventListener('click', () => this.openEditModal(channel));
         }
 
         const deleteBtn = card.querySelector('.delete-btn');
-        if (!isAnySwitching) {
-            deleteBtn?.addEventListener('click', () => this.deleteChannel(index));
+        if (!isAnySwitching && !this.readOnly) {
+            deleteBtn?.addEventListener('click', () => this.deleteChannel(channel));
+        }
+
+        if (isAnySwitching || this.readOnly) {
+            return;
+        }
+
+        card.querySelector('.pin-btn')?.addEventListener('click', () => {
+            this.runStoreMutation(
+                () => api.setCodexChannelPinned(state.codexConfigPath, channel.id, !channel.pinned, this.revision),
+                null,
+                '置顶失败'
+            );
+        });
+        card.querySelector('.duplicate-btn')?.addEventListener('click', () => {
+            this.runStoreMutation(
+                () => api.duplicateCodexChannel(state.codexConfigPath, channel.id, this.revision),
+                i18n.t('codex.messages.channelDuplicated'),
+                '复制失败'
+            );
+        });
+        card.querySelector('.move-up-btn')?.addEventListener('click', () => this.moveChannel(channel, index - 1));
+        card.querySelector('.move-down-btn')?.addEventListener('click', () => this.moveChannel(channel, index + 1));
+    }
+
+    moveChannel(channel, toIndex) {
+        return this.runStoreMutation(
+            () => api.moveCodexChannel(state.codexConfigPath, channel.id, toIndex, this.revision),
+            null,
+            '移动失败'
+        );
+    }
+
+    // 执行一次带修订号的存储修改；修订号冲突时重新加载列表并提示重试
+    async runStoreMutation(request, successMessage, errorContext) {
+        try {
+            const result = await request();
+
+            if (result.success) {
+                if (successMessage) {
+                    toast.show(successMessage);
+                }
+                await this.loadChannels();
+                return true;
+            }
+
+            await this.handleMutationError(result.error, errorContext);
+        } catch (error) {
+            await this.handleMutationError(error, errorContext);
+        }
+        return false;
+    }
+
+    async handleMutationError(error, errorContext) {
+        if (ErrorHandler.isConflict(error)) {
+            toast.show(i18n.t('codex.messages.storeConflict'));
+            await this.loadChannels();
+        } else {
+            ErrorHandler.showError(error, errorContext);
         }
     }
 
@@ -285,7 +410,7 @@ class CodexManager {
     }
 
     openNewModal() {
-        this.editingIndex = -1;
+        this.editingId = '';
         const titleEl = document.getElementById('codex-modal-title');
         if (titleEl) {
             titleEl.textContent = i18n.t('codex.modal.titleNew');
@@ -294,11 +419,15 @@ class CodexManager {
         this.baseurlInput.value = '';
         this.apikeyInput.value = '';
         this.modelInput.value = '';
+        this.fillProviderFields(null);
+        this.extraConfigInput.value = '';
+        this.authModeSelect.value = 'api_key';
+        this.updateAuthModeFields();
         this.modal.classList.add('active');
     }
 
-    openEditModal(channel, index) {
-        this.editingIndex = index;
+    openEditModal(channel) {
+        this.editingId = channel.id || '';
         const titleEl = document.getElementById('codex-modal-title');
         if (titleEl) {
             titleEl.textContent = i18n.t('codex.modal.titleEdit');
@@ -307,12 +436,59 @@ class CodexManager {
         this.baseurlInput.value = channel.baseurl || '';
         this.apikeyInput.value = channel.apikey || '';
         this.modelInput.value = channel.model || '';
+        this.fillProviderFields(channel.provider);
+        this.extraConfigInput.value = channel.extra_config || '';
+        this.authModeSelect.value = channel.auth_mode || 'api_key';
+        this.updateAuthModeFields();
         this.modal.classList.add('active');
     }
 
+    // ChatGPT 登录渠道使用保存的 auth.json，不需要地址、Key 和 provider
+    updateAuthModeFields() {
+        const isApiKey = this.authModeSelect.value !== 'chatgpt';
+        this.modal.querySelectorAll('[data-auth-mode="api_key"]').forEach(group => {
+            group.hidden = !isApiKey;
+        });
+    }
+
+    fillProviderFields(provider) {
+        this.providerInput.value = provider?.id || '';
+        this.wireApiSelect.value = provider?.wire_api || '';
+        this.envKeyInput.value = provider?.env_key || '';
+        this.queryParamsInput.value = Object.entries(provider?.query_params || {})
+            .map(([key, value]) => `${key}=${value}`)
+            .join('&');
+    }
+
+    // Provider ID 为空表示沿用 config.toml 中已有的 provider
+    readProviderFields() {
+        const id = this.providerInput.value.trim();
+        if (!id) {
+            return null;
+        }
+
+        const queryParams = {};
+        const rawQuery = this.queryParamsInput.value.trim();
+        if (rawQuery) {
+            for (const [key, value] of new URLSearchParams(rawQuery)) {
+                if (key.trim()) {
+                    queryParams[key.trim()] = value.trim();
+                }
+            }
+        }
+
+        return {
+            id: id,
+            name: id,
+            wire_api: this.wireApiSelect.value || null,
+            env_key: this.envKeyInput.value.trim() || null,
+            query_params: queryParams
+        };
+    }
+
     closeModal() {
         this.modal.classList.remove('active');
-        this.editingIndex = -1;
+        this.editingId = '';
     }
 
     async saveChannel() {
@@ -320,6 +496,9 @@ class CodexManager {
         const baseurl = this.baseurlInput.value.trim();
         const apikey = this.apikeyInput.value.trim();
         const model = this.modelInput.value.trim();
+        const extraConfig = this.extraConfigInput.value.trim();
+        const authMode = this.authModeSelect.value === 'chatgpt' ? 'chatgpt' : 'api_key';
+        const isApiKey = authMode === 'api_key';
 
         if (!name) {
             toast.show(i18n.t('codex.messages.errorNameRequired'));
@@ -331,66 +510,74 @@ class CodexManager {
             return;
         }
 
-        const duplicateName = this.codexChannels.some((channel, index) =>
-            channel.name === name && index !== this.editingIndex
+        const duplicateName = this.codexChannels.some(channel =>
+            channel.name === name && channel.id !== this.editingId
         );
         if (duplicateName) {
             toast.show(i18n.t('codex.messages.errorNameDuplicate'));
             return;
         }
 
-        if (!baseurl) {
+        if (isApiKey && !baseurl) {
             toast.show(i18n.t('codex.messages.errorBaseurlRequired'));
             return;
         }
 
         let normalizedBaseUrl = '';
-        try {
-            const parsedUrl = new URL(baseurl);
-            if (parsedUrl.protocol !== 'http:' && parsedUrl.protocol !== 'https:') {
-                throw new Error('invalid protocol');
+        if (isApiKey) {
+            try {
+                const parsedUrl = new URL(baseurl);
+                if (parsedUrl.protocol !== 'http:' && parsedUrl.protocol !== 'https:') {
+                    throw new Error('invalid protocol');
+                }
+                normalizedBaseUrl = this.normalizeBaseUrl(baseurl);
+            } catch {
+                toast.show(i18n.t('codex.messages.errorBaseurlInvalid'));
+                return;
             }
-            normalizedBaseUrl = this.normalizeBaseUrl(baseurl);
-        } catch {
-            toast.show(i18n.t('codex.messages.errorBaseurlInvalid'));
-            return;
         }
 
-        if (!apikey) {
+        if (isApiKey && !apikey) {
             toast.show(i18n.t('codex.messages.errorApikeyRequired'));
             return;
         }
 
-        if (!model) {
+        if (isApiKey && !model) {
             toast.show(i18n.t('codex.messages.errorModelRequired'));
             return;
         }
 
-        try {
-            const result = await api.saveCodexChannel({
+        const provider = isApiKey ? this.readProviderFields() : null;
+        if (provider && !/^[A-Za-z0-9\-_]{1,18}$/.test(provider.id)) {
+            toast.show(i18n.t('codex.messages.errorProviderInvalid'));
+            return;
+        }
+
+        const isEditing = !!this.editingId;
+        const saved = await this.runStoreMutation(
+            () => api.saveCodexChannel({
                 codexConfigPath: state.codexConfigPath,
+                id: this.editingId,
                 name: name,
                 baseurl: normalizedBaseUrl,
                 apikey: apikey,
                 model: model,
-                editIndex: this.editingIndex
-            });
-
-            if (result.success) {
-                toast.show(this.editingIndex >= 0
-                    ? i18n.t('codex.messages.channelUpdated')
-                    : i18n.t('codex.messages.channelCreated'));
-                this.closeModal();
-                await this.loadChannels();
-            } else {
-                ErrorHandler.showError(result.error, '保存失败');
-            }
-        } catch (error) {
-            ErrorHandler.showError(error, '保存失败');
+                provider: provider,
+                extraConfig: extraConfig,
+                authMode: authMode,
+                expectedRevision: this.revision
+            }),
+            isEditing
+                ? i18n.t('codex.messages.channelUpdated')
+                : i18n.t('codex.messages.channelCreated'),
+            '保存失败'
+        );
+        if (saved) {
+            this.closeModal();
         }
     }
 
-    async deleteChannel(index) {
+    async deleteChannel(channel) {
         const confirmed = await confirmDialog.show({
             title: i18n.t('confirm.deleteTitle'),
             message: i18n.t('codex.messages.confirmDelete'),
@@ -400,18 +587,11 @@ class CodexManager {
 
         if (!confirmed) return;
 
-        try {
-            const result = await api.deleteCodexChannel(state.codexConfigPath, index);
-
-            if (result.success) {
-                toast.show(i18n.t('codex.messages.channelDeleted'));
-                await this.loadChannels();
-            } else {
-                ErrorHandler.showError(result.error, '删除失败');
-            }
-        } catch (error) {
-            ErrorHandler.showError(error, '删除失败');
-        }
+        await this.runStoreMutation(
+            () => api.deleteCodexChannel(state.codexConfigPath, channel.id, this.revision),
+            i18n.t('codex.messages.channelDeleted'),
+            '删除失败'
+        );
     }
 
     async refreshChannels() {
@@ -448,16 +628,31 @@ class CodexManager {
         }
 
         const modalTitle = document.getElementById('codex-modal-title');
-        if (modalTitle && this.editingIndex < 0) {
+        if (modalTitle && !this.editingId) {
             modalTitle.textContent = i18n.t('codex.modal.titleNew');
         }
 
-        const labels = this.modal?.querySelectorAll('.form-label');
-        if (labels && labels.length >= 4) {
-            labels[0].textContent = i18n.t('codex.modal.fields.name');
-            labels[1].textContent = i18n.t('codex.modal.fields.baseurl');
-            labels[2].textContent = i18n.t('codex.modal.fields.apikey');
-            labels[3].textContent = i18n.t('codex.modal.fields.model');
+        const labelKeys = {
+            'codex-name-input': 'name',
+            'codex-authmode-select': 'authMode',
+            'codex-baseurl-input': 'baseurl',
+            'codex-apikey-input': 'apikey',
+            'codex-model-input': 'model',
+            'codex-provider-input': 'provider',
+            'codex-wireapi-select': 'wireApi',
+            'codex-envkey-input': 'envKey',
+            'codex-queryparams-input': 'queryParams',
+            'codex-extra-input': 'extraConfig'
+        };
+        Object.entries(labelKeys).forEach(([inputId, key]) => {
+            const label = this.modal?.querySelector(`label[for="${inputId}"]`);
+            if (label) {
+                label.textContent = i18n.t(`codex.modal.fields.${key}`);
+            }
+        });
+        if (this.authModeSelect) {
+            this.authModeSelect.options[0].textContent = i18n.t('codex.authMode.apiKey');
+            this.authModeSelect.options[1].textContent = i18n.t('codex.authMode.chatgpt');
         }
 
         if (this.nameInput) {
@@ -472,6 +667,21 @@ class CodexManager {
         if (this.modelInput) {
             this.modelInput.placeholder = i18n.t('codex.modal.fields.modelPlaceholder');
         }
+        if (this.providerInput) {
+            this.providerInput.placeholder = i18n.t('codex.modal.fields.providerPlaceholder');
+        }
+        if (this.wireApiSelect?.options.length) {
+            this.wireApiSelect.options[0].textContent = i18n.t('codex.modal.fields.wireApiDefault');
+        }
+        if (this.envKeyInput) {
+            this.envKeyInput.placeholder = i18n.t('codex.modal.fields.envKeyPlaceholder');
+        }
+        if (this.queryParamsInput) {
+            this.queryParamsInput.placeholder = i18n.t('codex.modal.fields.queryParamsPlaceholder');
+        }
+        if (this.extraConfigInput) {
+            this.extraConfigInput.placeholder = i18n.t('codex.modal.fields.extraConfigPlaceholder');
+        }
 
         const cancelBtn = document.getElementById('codex-modal-cancel-btn');
         if (cancelBtn) {
@@ -7,11 +7,13 @@ class DroidManager {
         this.droidList = null;
         this.droidCount = null;
         this.droidChannels = [];
-        this.currentApiKey = '';  // 当前环境变量中的 FACTORY_API_KEY
+        this.activeInfo = null;  // 后端按 FACTORY_API_KEY 匹配到的激活渠道
         this.editingDroidChannel = null;
         this.modal = null;
         this.nameInput = null;
         this.apiKeyInput = null;
+        this.baseUrlInput = null;
+        this.notesInput = null;
     }
 
     /**
@@ -23,6 +25,8 @@ class DroidManager {
         this.modal = document.getElementById('droid-modal');
         this.nameInput = document.getElementById('droid-name-input');
         this.apiKeyInput = document.getElementById('droid-apikey-input');
+        this.baseUrlInput = document.getElementById('droid-baseurl-input');
+        this.notesInput = document.getElementById('droid-notes-input');
 
         this.setupEventListeners();
     }
@@ -57,6 +61,13 @@ class DroidManager {
         if (saveBtn) {
             saveBtn.addEventListener('click', () => this.saveChannel());
         }
+
+        // Windows 通过用户环境变量持久化，只在其他平台显示
+        const shellHookBtn = document.getElementById('droid-shell-hook-btn');
+        if (shellHookBtn && !navigator.userAgent.includes('Windows')) {
+            shellHookBtn.hidden = false;
+            shellHookBtn.addEventListener('click', () => this.installShellHook());
+        }
     }
 
     /**
@@ -64,13 +75,8 @@ class DroidManager {
      */
     async loadChannels() {
         try {
-            // 获取当前环境变量中的 FACTORY_API_KEY
-            const envResult = await api.getCurrentFactoryApiKey();
-            if (envResult.success) {
-                this.currentApiKey = envResult.data || '';
-            } else {
-                this.currentApiKey = '';
-            }
+            const activeResult = await api.getActiveDroidChannel(state.configPath);
+            this.activeInfo = activeResult.success ? activeResult.data : null;
 
             const result = await api.getDroidChannels(state.configPath);
 
@@ -112,8 +118,7 @@ class DroidManager {
 
         const fragment = document.createDocumentFragment();
         this.droidChannels.forEach((channel) => {
-            // 根据 api_key 与当前环境变量对比判断激活状态
-            const isActive = this.currentApiKey && channel.api_key === this.currentApiKey;
+            const isActive = this.activeInfo?.name === channel.name;
             const card = this.createChannelCard(channel, isActive);
             fragment.appendChild(card);
         });
@@ -129,7 +134,11 @@ class DroidManager {
         const card = document.createElement('div');
         card.className = `channel-card${isActive ? ' active' : ''}`;
 
-        const statusText = isActive ? i18n.t('droid.status.active') : i18n.t('droid.status.inactive');
+        let statusText = isActive ? i18n.t('droid.status.active') : i18n.t('droid.status.inactive');
+        if (channel.last_switched_at) {
+            const switchedAt = new Date(channel.last_switched_at * 1000).toLocaleString();
+            statusText += ` · ${i18n.t('droid.status.lastSwitched', { time: switchedAt })}`;
+        }
         const statusIndicator = `<span class="status-indicator ${isActive ? 'active' : ''}"></span> ${statusText}`;
 
         card.innerHTML = `
@@ -138,11 +147,14 @@ class DroidManager {
                 <div class="channel-info">
                     <div class="channel-name">${DOMUtils.escapeHtml(channel.name)}</div>
                     <div class="channel-status">${statusIndicator}</div>
+                    ${channel.notes ? `<div class="channel-notes">${DOMUtils.escapeHtml(channel.notes)}</div>` : ''}
+                    ${this.renderTestResult(channel.last_test)}
                 </div>
             </div>
             <div class="channel-actions">
                 ${isActive ? `<button class="btn btn-success btn-small launch-btn">🚀 ${i18n.t('droid.actions.launch')}</button>` : ''}
                 <button class="btn btn-primary btn-small switch-btn" ${isActive ? 'disabled' : ''}>⚡ ${i18n.t('droid.actions.switch')}</button>
+                <button class="btn btn-secondary btn-small test-btn">🩺 ${i18n.t('droid.actions.test')}</button>
                 <button class="btn btn-edit btn-small edit-btn">✏️ ${i18n.t('droid.actions.edit')}</button>
                 <button class="btn btn-danger btn-small delete-btn">🗑️ ${i18n.t('droid.actions.delete')}</button>
             </div>
@@ -161,6 +173,9 @@ class DroidManager {
             launchBtn?.addEventListener('click', () => this.launchDroid(channel));
         }
 
+        const testBtn = card.querySelector('.test-btn');
+        testBtn?.addEventListener('click', () => this.testChannel(channel, testBtn));
+
         const editBtn = card.querySelector('.edit-btn');
         editBtn?.addEventListener('click', () => this.openEditModal(channel));
 
@@ -184,6 +199,8 @@ class DroidManager {
         }
         this.nameInput.value = '';
         this.apiKeyInput.value = '';
+        if (this.baseUrlInput) this.baseUrlInput.value = '';
+        if (this.notesInput) this.notesInput.value = '';
         this.modal.classList.add('active');
     }
 
@@ -198,6 +215,8 @@ class DroidManager {
         }
         this.nameInput.value = channel.name;
         this.apiKeyInput.value = channel.api_key;
+        if (this.baseUrlInput) this.baseUrlInput.value = channel.base_url || '';
+        if (this.notesInput) this.notesInput.value = channel.notes || '';
         this.modal.classList.add('active');
     }
 
@@ -240,7 +259,9 @@ class DroidManager {
                 configPath: state.configPath,
                 name: name,
                 apiKey: apiKey,
-                oldName: this.editingDroidChannel || ''
+                oldName: this.editingDroidChannel || '',
+                baseUrl: this.baseUrlInput?.value.trim() || null,
+                notes: this.notesInput?.value.trim() || null
             });
 
             if (result.success) {
@@ -262,12 +283,11 @@ class DroidManager {
      */
     async switchChannel(channel) {
         try {
-            const result = await api.switchDroidChannel(channel.api_key);
+            const result = await api.switchDroidChannel(state.configPath, channel.name);
 
             if (result.success) {
-                this.currentApiKey = channel.api_key;
                 toast.show(i18n.t('droid.messages.channelSwitched', { name: channel.name }));
-                this.renderChannels();
+                await this.loadChannels();
             } else {
                 ErrorHandler.showError(result.error, '切换失败');
             }
@@ -276,6 +296,45 @@ class DroidManager {
         }
     }
 
+    /**
+     * 渲染最近一次连通性检测结果
+     */
+    renderTestResult(result) {
+        if (!result) return '';
+        return `<div class="channel-notes">${DOMUtils.escapeHtml(this.getTestResultText(result))}</div>`;
+    }
+
+    /**
+     * 检测结果文案
+     */
+    getTestResultText(result) {
+        const text = result.valid
+            ? i18n.t('droid.test.ok', { latency: result.latency_ms })
+            : i18n.t(`droid.test.${result.category}`);
+        return `${result.valid ? '🟢' : '🔴'} ${text}`;
+    }
+
+    /**
+     * 检测渠道 API Key 是否可用
+     */
+    async testChannel(channel, button) {
+        DOMUtils.setElementState(button, true);
+        try {
+            const result = await api.testDroidChannel(state.configPath, channel.name);
+
+            if (result.success && result.data) {
+                toast.show(`${channel.name}: ${this.getTestResultText(result.data)}`);
+                await this.loadChannels();
+            } else {
+                ErrorHandler.showError(result.error, '检测失败');
+            }
+        } catch (error) {
+            ErrorHandler.showError(error, '检测失败');
+        } finally {
+            DOMUtils.setElementState(button, false);
+        }
+    }
+
     /**
      * 删除渠道
      */
@@ -322,6 +381,34 @@ class DroidManager {
         }
     }
 
+    /**
+     * 在 Shell 配置文件中加载当前 Droid 渠道的 API Key
+     */
+    async installShellHook() {
+        const confirmed = await confirmDialog.show({
+            title: i18n.t('droid.shellHook.confirmTitle'),
+            message: i18n.t('droid.shellHook.confirmMessage'),
+            confirmText: i18n.t('droid.shellHook.confirm'),
+            cancelText: i18n.t('confirm.cancel')
+        });
+
+        if (!confirmed) {
+            return;
+        }
+
+        try {
+            const result = await api.installDroidShellHook();
+
+            if (result.success) {
+                toast.show(i18n.t('droid.shellHook.installed', { files: (result.data || []).join('\n') }));
+            } else {
+                ErrorHandler.showError(result.error, '写入失败');
+            }
+        } catch (error) {
+            ErrorHandler.showError(error, '写入失败');
+        }
+    }
+
     /**
      * 刷新渠道列表
      */
@@ -355,6 +442,12 @@ class DroidManager {
             refreshBtn.setAttribute('aria-label', i18n.t('aria.refreshDroid'));
         }
 
+        const shellHookBtn = document.getElementById('droid-shell-hook-btn');
+        if (shellHookBtn) {
+            shellHookBtn.querySelector('span:last-child').textContent = i18n.t('droid.shellHook.button');
+            shellHookBtn.setAttribute('aria-label', i18n.t('droid.shellHook.button'));
+        }
+
         const addBtn = document.getElementById('add-droid-btn');
         if (addBtn) {
             addBtn.querySelector('span:last-child').textContent = i18n.t('droid.add');
@@ -368,9 +461,11 @@ class DroidManager {
         }
 
         const labels = this.modal?.querySelectorAll('.form-label');
-        if (labels && labels.length >= 2) {
+        if (labels && labels.length >= 4) {
             labels[0].textContent = i18n.t('droid.modal.fields.name');
             labels[1].textContent = i18n.t('droid.modal.fields.apiKey');
+            labels[2].textContent = i18n.t('droid.modal.fields.baseUrl');
+            labels[3].textContent = i18n.t('droid.modal.fields.notes');
         }
 
         if (this.nameInput) {
@@ -379,6 +474,12 @@ class DroidManager {
         if (this.apiKeyInput) {
             this.apiKeyInput.placeholder = i18n.t('droid.modal.fields.apiKeyPlaceholder');
         }
+        if (this.baseUrlInput) {
+            this.baseUrlInput.placeholder = i18n.t('droid.modal.fields.baseUrlPlaceholder');
+        }
+        if (this.notesInput) {
+            this.notesInput.placeholder = i18n.t('droid.modal.fields.notesPlaceholder');
+        }
 
         const cancelBtn = document.getElementById('droid-modal-cancel-btn');
         if (cancelBtn) {
@@ -0,0 +1,409 @@
+/**
+ * Gemini 渠道管理功能模块
+ * 渠道数据存储在 geminiConfigPath/channels.json 中，切换时写入 .env 与 settings.json
+ */
+class GeminiManager {
+    constructor() {
+        this.geminiList = null;
+        this.geminiCount = null;
+        this.geminiChannels = [];
+        this.revision = 0;  // 存储修订号，修改时用于冲突检测
+        this.activeInfo = null;  // 后端按 .env 中的 Key 与 Base URL 匹配到的激活渠道
+        this.editingId = null;
+        this.modal = null;
+        this.nameInput = null;
+        this.apiKeyInput = null;
+        this.baseUrlInput = null;
+        this.modelInput = null;
+    }
+
+    /**
+     * 初始化 Gemini 管理
+     */
+    init() {
+        this.geminiList = document.getElementById('gemini-list');
+        this.geminiCount = document.querySelector('.gemini-channel-count');
+        this.modal = document.getElementById('gemini-modal');
+        this.nameInput = document.getElementById('gemini-name-input');
+        this.apiKeyInput = document.getElementById('gemini-apikey-input');
+        this.baseUrlInput = document.getElementById('gemini-baseurl-input');
+        this.modelInput = document.getElementById('gemini-model-input');
+
+        this.setupEventListeners();
+    }
+
+    /**
+     * 设置事件监听
+     */
+    setupEventListeners() {
+        const addBtn = document.getElementById('add-gemini-btn');
+        const refreshBtn = document.getElementById('refresh-gemini-btn');
+        const closeBtn = document.getElementById('gemini-modal-close-btn');
+        const cancelBtn = document.getElementById('gemini-modal-cancel-btn');
+        const saveBtn = document.getElementById('gemini-modal-save-btn');
+
+        if (addBtn) {
+            addBtn.addEventListener('click', () => this.openNewModal());
+        }
+
+        if (refreshBtn) {
+            const debouncedRefresh = debounce(() => this.refreshChannels(), 300);
+            refreshBtn.addEventListener('click', debouncedRefresh);
+        }
+
+        if (closeBtn) {
+            closeBtn.addEventListener('click', () => this.closeModal());
+        }
+
+        if (cancelBtn) {
+            cancelBtn.addEventListener('click', () => this.closeModal());
+        }
+
+        if (saveBtn) {
+            saveBtn.addEventListener('click', () => this.saveChannel());
+        }
+    }
+
+    /**
+     * 加载 Gemini 渠道列表
+     */
+    async loadChannels() {
+        try {
+            const envResult = await api.getCurrentGeminiEnv(state.geminiConfigPath);
+            this.activeInfo = envResult.success ? envResult.data : null;
+
+            const result = await api.getGeminiChannels(state.geminiConfigPath);
+            this.geminiChannels = result.data?.channels || [];
+            this.revision = result.data?.revision || 0;
+        } catch (error) {
+            ErrorHandler.handle(error, 'Load Gemini channels');
+            this.geminiChannels = [];
+        }
+        this.renderChannels();
+    }
+
+    /**
+     * 渲染 Gemini 渠道列表
+     */
+    renderChannels() {
+        if (!this.geminiList || !this.geminiCount) return;
+
+        const count = this.geminiChannels.length;
+        this.geminiCount.textContent = `${count} ${i18n.t('gemini.count')}`;
+
+        if (count === 0) {
+            this.geminiList.innerHTML = `
+                <div class="empty-state">
+                    <div class="empty-icon">${i18n.t('gemini.empty.icon')}</div>
+                    <div class="empty-text">${i18n.t('gemini.empty.text')}</div>
+                    <div class="empty-hint">${i18n.t('gemini.empty.hint')}</div>
+                </div>
+            `;
+            return;
+        }
+
+        const fragment = document.createDocumentFragment();
+        this.geminiChannels.forEach((channel) => {
+            const isActive = this.activeInfo?.active_channel === channel.id;
+            fragment.appendChild(this.createChannelCard(channel, isActive));
+        });
+
+        this.geminiList.innerHTML = '';
+        this.geminiList.appendChild(fragment);
+    }
+
+    /**
+     * 创建 Gemini 渠道卡片
+     */
+    createChannelCard(channel, isActive) {
+        const card = document.createElement('div');
+        card.className = `channel-card${isActive ? ' active' : ''}`;
+
+        const statusText = isActive ? i18n.t('gemini.status.active') : i18n.t('gemini.status.inactive');
+        const statusIndicator = `<span class="status-indicator ${isActive ? 'active' : ''}"></span> ${statusText}`;
+        const details = [channel.base_url || i18n.t('gemini.officialEndpoint'), channel.model]
+            .filter(Boolean)
+            .join(' · ');
+
+        card.innerHTML = `
+            <div class="channel-header">
+                <div class="channel-icon">✨</div>
+                <div class="channel-info">
+                    <div class="channel-name">${DOMUtils.escapeHtml(channel.name)}</div>
+                    <div class="channel-status">${statusIndicator}</div>
+                    <div class="channel-notes">${DOMUtils.escapeHtml(details)}</div>
+                </div>
+            </div>
+            <div class="channel-actions">
+                ${isActive ? `<button class="btn btn-success btn-small launch-btn">🚀 ${i18n.t('gemini.actions.launch')}</button>` : ''}
+                <button class="btn btn-primary btn-small switch-btn" ${isActive ? 'disabled' : ''}>⚡ ${i18n.t('gemini.actions.switch')}</button>
+                <button class="btn btn-secondary btn-small test-btn">🩺 ${i18n.t('gemini.actions.test')}</button>
+                <button class="btn btn-edit btn-small edit-btn">✏️ ${i18n.t('gemini.actions.edit')}</button>
+                <button class="btn btn-danger btn-small delete-btn">🗑️ ${i18n.t('gemini.actions.delete')}</button>
+            </div>
+        `;
+
+        this.attachCardEventListeners(card, channel, isActive);
+        return card;
+    }
+
+    /**
+     * 为渠道卡片附加事件监听器
+     */
+    attachCardEventListeners(card, channel, isActive) {
+        if (isActive) {
+            card.querySelector('.launch-btn')?.addEventListener('click', () => this.launchGemini(channel));
+        } else {
+            card.querySelector('.switch-btn')?.addEventListener('click', () => this.switchChannel(channel));
+        }
+
+        const testBtn = card.querySelector('.test-btn');
+        testBtn?.addEventListener('click', () => this.testChannel(channel, testBtn));
+        card.querySelector('.edit-btn')?.addEventListener('click', () => this.openEditModal(channel));
+        card.querySelector('.delete-btn')?.addEventListener('click', () => this.deleteChannel(channel));
+    }
+
+    /**
+     * 打开新建模态框
+     */
+    openNewModal() {
+        this.editingId = null;
+        const titleEl = document.getElementById('gemini-modal-title');
+        if (titleEl) {
+            titleEl.textContent = i18n.t('gemini.modal.titleNew');
+        }
+        this.nameInput.value = '';
+        this.apiKeyInput.value = '';
+        this.baseUrlInput.value = '';
+        this.modelInput.value = '';
+        this.modal.classList.add('active');
+    }
+
+    /**
+     * 打开编辑模态框
+     */
+    openEditModal(channel) {
+        this.editingId = channel.id;
+        const titleEl = document.getElementById('gemini-modal-title');
+        if (titleEl) {
+            titleEl.textContent = i18n.t('gemini.modal.titleEdit');
+        }
+        this.nameInput.value = channel.name;
+        this.apiKeyInput.value = channel.api_key;
+        this.baseUrlInput.value = channel.base_url || '';
+        this.modelInput.value = channel.model || '';
+        this.modal.classList.add('active');
+    }
+
+    /**
+     * 关闭模态框
+     */
+    closeModal() {
+        this.modal.classList.remove('active');
+        this.editingId = null;
+    }
+
+    /**
+     * 保存渠道
+     */
+    async saveChannel() {
+        const name = this.nameInput.value.trim();
+        const apiKey = this.apiKeyInput.value.trim();
+
+        if (!name) {
+            toast.show(i18n.t('gemini.messages.errorNameRequired'));
+            return;
+        }
+
+        if (!apiKey) {
+            toast.show(i18n.t('gemini.messages.errorApiKeyRequired'));
+            return;
+        }
+
+        const isEditing = Boolean(this.editingId);
+        const saved = await this.runStoreMutation(
+            () => api.saveGeminiChannel(state.geminiConfigPath, {
+                id: this.editingId || '',
+                name,
+                api_key: apiKey,
+                base_url: this.baseUrlInput.value.trim(),
+                model: this.modelInput.value.trim()
+            }, this.revision),
+            isEditing ? i18n.t('gemini.messages.channelUpdated') : i18n.t('gemini.messages.channelCreated'),
+            '保存失败'
+        );
+
+        if (saved) {
+            this.closeModal();
+        }
+    }
+
+    // 执行一次带修订号的存储修改；修订号冲突时重新加载列表并提示重试
+    async runStoreMutation(request, successMessage, errorContext) {
+        try {
+            await request();
+            toast.show(successMessage);
+            await this.loadChannels();
+            return true;
+        } catch (error) {
+            if (ErrorHandler.isConflict(error)) {
+                toast.show(i18n.t('gemini.messages.storeConflict'));
+                await this.loadChannels();
+            } else {
+                ErrorHandler.showError(error, errorContext);
+            }
+        }
+        return false;
+    }
+
+    /**
+     * 切换渠道
+     */
+    async switchChannel(channel) {
+        try {
+            await api.switchGeminiChannel(state.geminiConfigPath, channel.id);
+            toast.show(i18n.t('gemini.messages.channelSwitched', { name: channel.name }));
+        } catch (error) {
+            if (ErrorHandler.isConflict(error)) {
+                toast.show(i18n.t('gemini.messages.switchConflict'));
+            } else {
+                ErrorHandler.showError(error, '切换失败');
+            }
+        }
+        await this.loadChannels();
+    }
+
+    /**
+     * 检测渠道连通性
+     */
+    async testChannel(channel, button) {
+        DOMUtils.setElementState(button, true);
+        try {
+            const result = await api.testChannel('gemini', state.geminiConfigPath, channel.id);
+            const data = result.data;
+            const text = data.valid
+                ? i18n.t('gemini.test.ok', { latency: data.latency_ms })
+                : i18n.t(`gemini.test.${data.category}`);
+            toast.show(`${channel.name}: ${data.valid ? '🟢' : '🔴'} ${text}`);
+        } catch (error) {
+            ErrorHandler.showError(error, '检测失败');
+        } finally {
+            DOMUtils.setElementState(button, false);
+        }
+    }
+
+    /**
+     * 删除渠道
+     */
+    async deleteChannel(channel) {
+        const confirmed = await confirmDialog.show({
+            title: i18n.t('confirm.deleteTitle'),
+            message: i18n.t('gemini.messages.confirmDelete', { name: channel.name }),
+            confirmText: i18n.t('confirm.delete'),
+            cancelText: i18n.t('confirm.cancel')
+        });
+
+        if (!confirmed) {
+            return;
+        }
+
+        await this.runStoreMutation(
+            () => api.deleteGeminiChannel(state.geminiConfigPath, channel.id, this.revision),
+            i18n.t('gemini.messages.channelDeleted', { name: channel.name }),
+            '删除失败'
+        );
+    }
+
+    /**
+     * 启动 Gemini CLI
+     */
+    async launchGemini(channel) {
+        try {
+            await api.launchGemini(state.terminalDir);
+            toast.show(i18n.t('gemini.messages.channelLaunched', { name: channel.name }));
+        } catch (error) {
+            ErrorHandler.showError(error, '启动失败');
+        }
+    }
+
+    /**
+     * 刷新渠道列表
+     */
+    async refreshChannels() {
+        const refreshBtn = document.getElementById('refresh-gemini-btn');
+
+        DOMUtils.setElementState(refreshBtn, true);
+        DOMUtils.setElementState(this.geminiList, true);
+
+        await this.loadChannels();
+
+        setTimeout(() => {
+            DOMUtils.setElementState(this.geminiList, false);
+            DOMUtils.setElementState(refreshBtn, false);
+            toast.show(i18n.t('gemini.messages.channelsRefreshed'));
+        }, 300);
+    }
+
+    /**
+     * 更新页面语言
+     */
+    updateLanguage() {
+        const pageTitle = document.querySelector('#gemini-page .page-title');
+        if (pageTitle) {
+            pageTitle.textContent = i18n.t('gemini.title');
+        }
+
+        const refreshBtn = document.getElementById('refresh-gemini-btn');
+        if (refreshBtn) {
+            refreshBtn.querySelector('span:last-child').textContent = i18n.t('gemini.refresh');
+            refreshBtn.setAttribute('aria-label', i18n.t('aria.refreshGemini'));
+        }
+
+        const addBtn = document.getElementById('add-gemini-btn');
+        if (addBtn) {
+            addBtn.querySelector('span:last-child').textContent = i18n.t('gemini.add');
+            addBtn.setAttribute('aria-label', i18n.t('aria.addGemini'));
+        }
+
+        const modalTitle = document.getElementById('gemini-modal-title');
+        if (modalTitle && !this.editingId) {
+            modalTitle.textContent = i18n.t('gemini.modal.titleNew');
+        }
+
+        const labels = this.modal?.querySelectorAll('.form-label');
+        if (labels && labels.length >= 4) {
+            labels[0].textContent = i18n.t('gemini.modal.fields.name');
+            labels[1].textContent = i18n.t('gemini.modal.fields.apiKey');
+            labels[2].textContent = i18n.t('gemini.modal.fields.baseUrl');
+            labels[3].textContent = i18n.t('gemini.modal.fields.model');
+        }
+
+        if (this.nameInput) {
+            this.nameInput.placeholder = i18n.t('gemini.modal.fields.namePlaceholder');
+        }
+        if (this.apiKeyInput) {
+            this.apiKeyInput.placeholder = i18n.t('gemini.modal.fields.apiKeyPlaceholder');
+        }
+        if (this.baseUrlInput) {
+            this.baseUrlInput.placeholder = i18n.t('gemini.modal.fields.baseUrlPlaceholder');
+        }
+        if (this.modelInput) {
+            this.modelInput.placeholder = i18n.t('gemini.modal.fields.modelPlaceholder');
+        }
+
+        const cancelBtn = document.getElementById('gemini-modal-cancel-btn');
+        if (cancelBtn) {
+            cancelBtn.textContent = i18n.t('gemini.modal.buttons.cancel');
+        }
+
+        const saveBtn = document.getElementById('gemini-modal-save-btn');
+        if (saveBtn) {
+            saveBtn.textContent = i18n.t('gemini.modal.buttons.save');
+        }
+
+        this.renderChannels();
+    }
+}
+
+// 创建全局实例
+const gemini = new GeminiManager();
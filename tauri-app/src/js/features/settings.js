@@ -5,6 +5,7 @@ class SettingsManager {
     init() {
         this.setupPathSettings();
         this.setupCodexPathSettings();
+        this.setupGeminiPathSettings();
         this.setupTerminalDirSettings();
         this.setupThemeSettings();
         this.setupLanguageSettings();
@@ -36,6 +37,19 @@ class SettingsManager {
         }
     }
 
+    setupGeminiPathSettings() {
+        const pathInput = document.getElementById('gemini-path-input');
+        const browseBtn = document.getElementById('browse-gemini-path-btn');
+
+        if (pathInput) {
+            pathInput.value = state.geminiConfigPath;
+        }
+
+        if (browseBtn) {
+            browseBtn.addEventListener('click', () => this.handleBrowseGeminiPath(pathInput));
+        }
+    }
+
     setupTerminalDirSettings() {
         const terminalDirInput = document.getElementById('terminal-dir-input');
         const browseTerminalDirBtn = document.getElementById('browse-terminal-dir-btn');
@@ -79,6 +93,10 @@ class SettingsManager {
             if (result) {
                 state.save(stateKey, result);
 
+                if (['configPath', 'codexConfigPath', 'geminiConfigPath'].includes(stateKey)) {
+                    await window.watchConfigDirs?.();
+                }
+
                 if (inputElement) {
                     inputElement.value = result;
                 }
@@ -116,6 +134,15 @@ class SettingsManager {
         );
     }
 
+    async handleBrowseGeminiPath(pathInput) {
+        await this.handleBrowseFolder(
+            pathInput,
+            'geminiConfigPath',
+            'messages.geminiPathUpdated',
+            () => gemini.loadChannels()
+        );
+    }
+
     async handleBrowseTerminalDir(terminalDirInput) {
         await this.handleBrowseFolder(
             terminalDirInput,
@@ -171,6 +198,7 @@ class SettingsManager {
 
         this.updatePathCard();
         this.updateCodexPathCard();
+        this.updateGeminiPathCard();
         this.updateTerminalDirCard();
         this.updateThemeCard();
         this.updateLanguageCard();
@@ -213,6 +241,24 @@ class SettingsManager {
         }
     }
 
+    updateGeminiPathCard() {
+        const card = document.getElementById('setting-gemini-path');
+        if (!card) return;
+
+        const title = card.querySelector('.setting-title');
+        const description = card.querySelector('.setting-description');
+        const input = card.querySelector('#gemini-path-input');
+        const browseBtn = card.querySelector('#browse-gemini-path-btn');
+
+        if (title) title.textContent = i18n.t('settings.geminiPath.title');
+        if (description) description.textContent = i18n.t('settings.geminiPath.description');
+        if (input) input.value = state.geminiConfigPath;
+        if (browseBtn) {
+            browseBtn.textContent = i18n.t('settings.geminiPath.browse');
+            browseBtn.setAttribute('aria-label', i18n.t('aria.browseFolder'));
+        }
+    }
+
     updateTerminalDirCard() {
         const card = document.getElementById('setting-terminal-dir');
         if (!card) return;
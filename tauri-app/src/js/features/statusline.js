@@ -13,7 +13,6 @@ const ITEM_TEMPLATES = {
         label: 'Model',
         showLabel: false,
         color: 81,
-        template: '$($data.model.display_name)',
         description: '显示当前使用的 Claude 模型名称'
     },
     context: {
@@ -30,7 +29,6 @@ const ITEM_TEMPLATES = {
             { threshold: 80, color: 221 },
             { threshold: 100, color: 210 }
         ],
-        template: '$pct% ($usedK/$(K $maxTk))',
         description: '上下文窗口使用情况，颜色随使用率变化'
     },
     tokens: {
@@ -41,7 +39,6 @@ const ITEM_TEMPLATES = {
         label: 'Token',
         showLabel: false,
         color: 153,
-        template: '$(K($inTk+$outTk)) (I:$(K $inTk) O:$(K $outTk))',
         description: '显示输入和输出的 Token 统计'
     },
     cache: {
@@ -52,7 +49,6 @@ const ITEM_TEMPLATES = {
         label: 'Cache',
         showLabel: false,
         color: 183,
-        template: 'R$(K $cacheR) W$(K $cacheW)',
         description: '缓存读取和写入的 Token 数量'
     },
     cost: {
@@ -63,7 +59,6 @@ const ITEM_TEMPLATES = {
         label: 'Cost',
         showLabel: false,
         color: 222,
-        template: '`$$cost',
         description: '本次会话的累计费用（美元）'
     },
     dir: {
@@ -74,7 +69,6 @@ const ITEM_TEMPLATES = {
         label: 'Dir',
         showLabel: false,
         color: 147,
-        template: '$currentDir',
         description: '当前工作目录路径（自动缩写）'
     },
     time: {
@@ -85,8 +79,47 @@ const ITEM_TEMPLATES = {
         label: 'Time',
         showLabel: false,
         color: 117,
-        template: '$currentTime',
         description: '当前时间（HH:mm 格式）'
+    },
+    date: {
+        id: 'date',
+        type: 'date',
+        enabled: false,
+        emoji: '📅',
+        label: 'Date',
+        showLabel: false,
+        color: 117,
+        description: '当前日期（yyyy-MM-dd 格式）'
+    },
+    user: {
+        id: 'user',
+        type: 'user',
+        enabled: false,
+        emoji: '👤',
+        label: 'User',
+        showLabel: false,
+        color: 180,
+        description: '当前系统用户名'
+    },
+    git_branch: {
+        id: 'git_branch',
+        type: 'git_branch',
+        enabled: false,
+        emoji: '🌿',
+        label: 'Branch',
+        showLabel: false,
+        color: 114,
+        description: '工作目录所在的 Git 分支（非 Git 仓库时不显示）'
+    },
+    channel: {
+        id: 'channel',
+        type: 'channel',
+        enabled: false,
+        emoji: '🔀',
+        label: 'Channel',
+        showLabel: false,
+        color: 216,
+        description: '当前生效的 Claude 渠道名称'
     }
 };
 
@@ -106,7 +139,11 @@ const DEFAULT_CONFIG = {
         { ...ITEM_TEMPLATES.cache },
         { ...ITEM_TEMPLATES.cost },
         { ...ITEM_TEMPLATES.dir },
-        { ...ITEM_TEMPLATES.time }
+        { ...ITEM_TEMPLATES.time },
+        { ...ITEM_TEMPLATES.date },
+        { ...ITEM_TEMPLATES.user },
+        { ...ITEM_TEMPLATES.git_branch },
+        { ...ITEM_TEMPLATES.channel }
     ]
 };
 
@@ -124,6 +161,7 @@ class StatuslineManager {
         this.config = JSON.parse(JSON.stringify(DEFAULT_CONFIG));
         this.currentFile = null;
         this.files = [];
+        this.appliedName = null;
         this.draggedItem = null;
         this.isNewFile = false;
         this.initialized = false;
@@ -204,6 +242,20 @@ class StatuslineManager {
             this._addEventListener(refreshBtn, 'click', handleRefresh);
         }
 
+        // 导入样式包按钮
+        const importBtn = document.getElementById('import-statusline-btn');
+        if (importBtn) {
+            const handleImport = () => this.importBundle();
+            this._addEventListener(importBtn, 'click', handleImport);
+        }
+
+        // 取消应用按钮
+        const clearBtn = document.getElementById('clear-statusline-btn');
+        if (clearBtn) {
+            const handleClear = () => this.clearApplied();
+            this._addEventListener(clearBtn, 'click', handleClear);
+        }
+
         // 创建新文件按钮
         const createBtn = document.getElementById('create-new-btn');
         if (createBtn) {
@@ -345,6 +397,7 @@ class StatuslineManager {
             const result = await api.getStatuslineFiles();
             if (result.success) {
                 this.files = result.data || [];
+                await this.loadAppliedStatus();
                 this.renderFilesList();
 
                 // 如果有当前选中的文件，保持选中状态
@@ -361,6 +414,37 @@ class StatuslineManager {
         }
     }
 
+    /**
+     * 文件被外部修改后刷新列表与应用状态，不切换当前选中的文件以免丢失未保存的编辑
+     */
+    async refreshFromDisk() {
+        try {
+            const result = await api.getStatuslineFiles();
+            if (result.success) {
+                this.files = result.data || [];
+                await this.loadAppliedStatus();
+                this.renderFilesList();
+            }
+        } catch (error) {
+            ErrorHandler.handle(error, 'Refresh files');
+        }
+    }
+
+    /**
+     * 读取当前应用到 settings.json 的样式
+     */
+    async loadAppliedStatus() {
+        try {
+            const result = await api.getAppliedStatusline();
+            this.appliedName = result.success ? (result.data?.name || null) : null;
+        } catch (error) {
+            this.appliedName = null;
+        }
+
+        const clearBtn = document.getElementById('clear-statusline-btn');
+        if (clearBtn) clearBtn.disabled = !this.appliedName;
+    }
+
     /**
      * 渲染文件列表
      */
@@ -391,7 +475,8 @@ class StatuslineManager {
      */
     createFileElement(file) {
         const div = document.createElement('div');
-        div.className = `file-item${this.currentFile?.file_name === file.file_name ? ' active' : ''}`;
+        const isApplied = this.appliedName === file.name;
+        div.className = `file-item${this.currentFile?.file_name === file.file_name ? ' active' : ''}${isApplied ? ' applied' : ''}`;
         div.dataset.fileName = file.file_name;
 
         // 转义文件名防止 XSS
@@ -401,11 +486,12 @@ class StatuslineManager {
         div.innerHTML = `
             <div class="file-icon">📄</div>
             <div class="file-info">
-                <div class="file-name">${safeName}</div>
+                <div class="file-name">${safeName}${isApplied ? `<span class="file-applied-badge">${i18n.t('statusline.files.applied')}</span>` : ''}</div>
                 <div class="file-meta">${this.formatDate(file.modified)}</div>
             </div>
             <div class="file-actions">
                 <button class="btn-icon btn-apply" data-file="${safeFileName}" title="${i18n.t('statusline.files.apply')}">☑️</button>
+                <button class="btn-icon btn-export" data-file="${safeFileName}" title="${i18n.t('statusline.files.export')}">📤</button>
                 <button class="btn-icon btn-delete" data-file="${safeFileName}" title="${i18n.t('statusline.files.delete')}">🗑️</button>
             </div>
         `;
@@ -423,6 +509,12 @@ class StatuslineManager {
             this.applyToFileSettings(file);
         });
 
+        // 导出按钮
+        div.querySelector('.btn-export')?.addEventListener('click', (e) => {
+            e.stopPropagation();
+            this.exportBundle(file);
+        });
+
         // 删除按钮
         div.querySelector('.btn-delete')?.addEventListener('click', (e) => {
             e.stopPropagation();
@@ -474,11 +566,11 @@ class StatuslineManager {
             this.currentFileName.textContent = file.name;
         }
 
-        // 加载文件内容并回显设置
+        // 加载样式定义并回显设置
         try {
-            const result = await api.readStatuslineFile(file.file_name);
+            const result = await api.getStatuslineSpec(file.name);
             if (result.success && result.data) {
-                this.parseConfigFromPS1(result.data);
+                this.applySpec(result.data);
                 this.renderItems();
                 this.renderSeparator();
                 this.updatePreview();
@@ -489,63 +581,66 @@ class StatuslineManager {
     }
 
     /**
-     * 从 PS1 内容解析配置
+     * 从后端样式定义回显配置
      */
-    parseConfigFromPS1(ps1Content) {
+    applySpec(spec) {
         // 重置为默认配置
         this.config = JSON.parse(JSON.stringify(DEFAULT_CONFIG));
 
-        // 从 CONFIG 注释中解析
-        const configStart = ps1Content.indexOf('# CONFIG:');
-        if (configStart === -1) return;
-
-        // 找到这一行的结束位置
-        let configEnd = ps1Content.indexOf('\n', configStart);
-        if (configEnd === -1) configEnd = ps1Content.length;
-
-        // 提取 CONFIG JSON
-        const configLine = ps1Content.substring(configStart + 9, configEnd).trim();
-
-        try {
-            const c = JSON.parse(configLine);
+        if (spec.separator) {
+            this.config.separator.custom = spec.separator.text;
+            this.config.separator.style = 'custom';
+            this.config.separator.color = spec.separator.color;
+            this.config.separator.showStart = !!spec.separator.show_start;
+            this.config.separator.showEnd = !!spec.separator.show_end;
+        }
 
-            // 回显分隔线
-            if (c.sep) {
-                this.config.separator.custom = c.sep;
-                this.config.separator.style = 'custom';
-            }
-            if (c.sepColor !== undefined) {
-                this.config.separator.color = c.sepColor;
+        const orderedItems = [];
+        (spec.segments || []).forEach(segment => {
+            const item = this.config.items.find(i => i.type === segment.kind);
+            if (item) {
+                item.emoji = segment.emoji;
+                item.label = segment.label;
+                item.showLabel = !!segment.show_label;
+                item.enabled = !!segment.enabled;
+                item.color = segment.color;
+                orderedItems.push(item);
             }
-            this.config.separator.showStart = c.sepStart === 1;
-            this.config.separator.showEnd = c.sepEnd === 1;
-
-            // 回显项目: [type, emoji, label, showLabel, enabled, color]
-            if (c.items && Array.isArray(c.items)) {
-                const orderedItems = [];
-                c.items.forEach(arr => {
-                    const [type, emoji, label, showLabel, enabled, color] = arr;
-                    const item = this.config.items.find(i => i.type === type);
-                    if (item) {
-                        item.emoji = emoji;
-                        item.label = label;
-                        item.showLabel = showLabel === 1;
-                        item.enabled = enabled === 1;
-                        item.color = color;
-                        orderedItems.push(item);
-                    }
-                });
-                // 添加未保存的默认项
-                this.config.items.forEach(item => {
-                    if (!orderedItems.includes(item)) {
-                        orderedItems.push(item);
-                    }
-                });
-                this.config.items = orderedItems;
+        });
+        // 添加未保存的默认项
+        this.config.items.forEach(item => {
+            if (!orderedItems.includes(item)) {
+                orderedItems.push(item);
             }
-        } catch (e) {
-            console.error('[Statusline] 解析失败:', e);
-        }
+        });
+        this.config.items = orderedItems;
+    }
+
+    /**
+     * 转换为后端样式定义，脚本由后端生成
+     */
+    toSpec() {
+        const separator = this.config.separator.style === 'custom'
+            ? this.config.separator.custom
+            : (SEPARATOR_STYLES[this.config.separator.style] || this.config.separator.custom || '|');
+
+        return {
+            version: 1,
+            separator: {
+                text: separator,
+                color: Number(this.config.separator.color),
+                show_start: this.config.separator.showStart,
+                show_end: this.config.separator.showEnd
+            },
+            segments: this.config.items.map(item => ({
+                kind: item.type,
+                enabled: item.enabled,
+                emoji: item.emoji,
+                label: item.label,
+                show_label: item.showLabel,
+                color: Number(item.color)
+            }))
+        };
     }
 
     /**
@@ -745,10 +840,10 @@ class StatuslineManager {
      * 写入文件
      */
     async writeToFile(fileName) {
-        const ps1Content = this.generatePS1();
+        const name = fileName.replace('statusline_', '').replace('.ps1', '');
 
         try {
-            const result = await api.saveStatuslineFile(fileName, ps1Content);
+            const result = await api.saveStatuslineSpec(name, this.toSpec());
             if (result.success) {
                 toast.show(i18n.t('statusline.messages.fileSaved', { name: fileName.replace('statusline_', '').replace('.ps1', '') }));
                 this.currentFile = {
@@ -773,9 +868,14 @@ class StatuslineManager {
      * 删除文件
      */
     async deleteFile(file) {
+        // 正在使用的样式需要额外提示，确认后会恢复应用前的 statusLine
+        const isApplied = this.appliedName === file.name;
         const confirmed = await confirmDialog.show({
             title: i18n.t('statusline.files.confirmDeleteTitle'),
-            message: i18n.t('statusline.files.confirmDeleteMessage', { name: file.name }),
+            message: i18n.t(
+                isApplied ? 'statusline.files.confirmDeleteAppliedMessage' : 'statusline.files.confirmDeleteMessage',
+                { name: file.name }
+            ),
             confirmText: i18n.t('confirm.delete'),
             cancelText: i18n.t('confirm.cancel')
         });
@@ -783,7 +883,7 @@ class StatuslineManager {
         if (!confirmed) return;
 
         try {
-            const result = await api.deleteStatuslineFile(file.file_name);
+            const result = await api.deleteStatuslineFile(file.file_name, isApplied);
             if (result.success) {
                 toast.show(i18n.t('statusline.messages.fileDeleted', { name: file.name }));
 
@@ -808,6 +908,8 @@ class StatuslineManager {
             const result = await api.applyStatuslineToSettings(file.file_name);
             if (result.success) {
                 toast.show(i18n.t('statusline.messages.applied', { name: file.name }));
+                await this.loadAppliedStatus();
+                this.renderFilesList();
             } else {
                 ErrorHandler.showError(result.error, 'Apply failed');
             }
@@ -816,6 +918,69 @@ class StatuslineManager {
         }
     }
 
+    /**
+     * 导出样式包，便于分享给他人
+     */
+    async exportBundle(file) {
+        try {
+            const targetPath = await api.saveDialog({
+                defaultPath: `${file.name}.statusline.json`,
+                filters: [{ name: 'StatusLine', extensions: ['json'] }]
+            });
+            if (!targetPath) return;
+
+            const result = await api.exportStatusline(file.name, null, targetPath);
+            if (result.success) {
+                toast.show(i18n.t('statusline.messages.exported', { name: file.name }));
+            }
+        } catch (error) {
+            ErrorHandler.handle(error, 'Export statusline');
+        }
+    }
+
+    /**
+     * 导入样式包，重名时自动追加序号
+     */
+    async importBundle() {
+        try {
+            const sourcePath = await api.openDialog({
+                multiple: false,
+                directory: false,
+                filters: [{ name: 'StatusLine', extensions: ['json'] }]
+            });
+            if (!sourcePath) return;
+
+            const result = await api.importStatusline({ sourcePath, onConflict: 'rename' });
+            if (result.success) {
+                toast.show(i18n.t('statusline.messages.imported', { name: result.data }));
+                this.currentFile = null;
+                await this.loadFiles();
+                const imported = this.files.find(file => file.name === result.data);
+                if (imported) this.selectFile(imported);
+            }
+        } catch (error) {
+            ErrorHandler.handle(error, 'Import statusline');
+        }
+    }
+
+    /**
+     * 取消应用，恢复应用前的 statusLine
+     */
+    async clearApplied() {
+        try {
+            const result = await api.clearStatusline(true);
+            if (result.success) {
+                toast.show(i18n.t('statusline.messages.cleared'));
+                await this.loadAppliedStatus();
+                this.renderFilesList();
+            } else {
+                ErrorHandler.showError(result.error, 'Clear failed');
+            }
+        } catch (error) {
+            ErrorHandler.handle(error, 'Clear statusline');
+        }
+    }
+
     /**
      * 重置当前配置
      */
@@ -1064,8 +1229,34 @@ class StatuslineManager {
         const previewElement = document.getElementById('preview-statusline');
         if (!previewElement) return;
 
-        const previewHtml = this.generatePreviewHtml();
-        previewElement.innerHTML = previewHtml;
+        // 先用本地模拟结果即时刷新，再用后端真实渲染结果替换
+        previewElement.innerHTML = this.generatePreviewHtml();
+
+        if (!this.config.items.some(item => item.enabled)) return;
+
+        const seq = (this.previewSeq || 0) + 1;
+        this.previewSeq = seq;
+        const cwd = (typeof state !== 'undefined' && state.terminalDir) ? state.terminalDir : null;
+
+        api.previewStatusline(this.toSpec(), 'native', cwd).then(result => {
+            // 丢弃过期的预览结果
+            if (seq !== this.previewSeq || !result.success || !result.data) return;
+            previewElement.innerHTML = this.renderPreviewSpans(result.data.lines);
+        }).catch(error => {
+            console.warn('StatusLine 预览失败，使用本地预览:', error);
+        });
+    }
+
+    /**
+     * 将后端返回的样式片段渲染为 HTML
+     */
+    renderPreviewSpans(lines) {
+        return lines.map(spans => spans.map(span => {
+            const styles = [];
+            if (span.color) styles.push(`color: ${span.color}`);
+            if (span.bold) styles.push('font-weight: bold');
+            return `<span class="preview-item" style="${styles.join('; ')}">${this.escapeHtml(span.text)}</span>`;
+        }).join('')).join('<br>');
     }
 
     /**
@@ -1127,7 +1318,10 @@ class StatuslineManager {
             cacheR: 45000,
             cacheW: 8500,
             cost: 0.0523,
-            currentDir: this.shortenPath(realDir)
+            currentDir: this.shortenPath(realDir),
+            user: 'user',
+            gitBranch: 'main',
+            channel: (typeof state !== 'undefined' && state.activeChannelName) || 'default'
         };
 
         // 根据类型生成值部分
@@ -1155,6 +1349,19 @@ class StatuslineManager {
                 const now = new Date();
                 value = `${now.getHours().toString().padStart(2, '0')}:${now.getMinutes().toString().padStart(2, '0')}`;
                 break;
+            case 'date':
+                const today = new Date();
+                value = `${today.getFullYear()}-${(today.getMonth() + 1).toString().padStart(2, '0')}-${today.getDate().toString().padStart(2, '0')}`;
+                break;
+            case 'user':
+                value = mockData.user;
+                break;
+            case 'git_branch':
+                value = mockData.gitBranch;
+                break;
+            case 'channel':
+                value = mockData.channel;
+                break;
             default:
                 value = '';
         }
@@ -1365,317 +1572,6 @@ class StatuslineManager {
         };
     }
 
-    /**
-     * 生成 PS1 脚本
-     */
-    generatePS1() {
-        const lines = [];
-
-        // 只保存用户可修改的设置
-        const userConfig = {
-            // 分隔线：文本、颜色、开头、结尾
-            sep: this.config.separator.custom || '|',
-            sepColor: this.config.separator.color,
-            sepStart: this.config.separator.showStart ? 1 : 0,
-            sepEnd: this.config.separator.showEnd ? 1 : 0,
-            // 项目：图标、名称、勾选状态、禁用状态、字体颜色
-            items: this.config.items.map(item => ([
-                item.type,
-                item.emoji,
-                item.label,
-                item.showLabel ? 1 : 0,
-                item.enabled ? 1 : 0,
-                item.color
-            ]))
-        };
-        lines.push(`# CONFIG:${JSON.stringify(userConfig)}`);
-        lines.push('');
-
-        // 头部 - 完全匹配参考格式
-        lines.push('# ============================================================');
-        lines.push('# Claude Code 自定义状态栏脚本 (Windows PowerShell)');
-        lines.push('# ============================================================');
-        lines.push('#');
-        lines.push('# 功能: 在 Claude Code CLI 底部显示美化的状态信息');
-        lines.push('#');
-        lines.push('# 安装方法:');
-        lines.push('#   将此文件保存到: ~/.claude/statusline.ps1');
-        lines.push('#   在 ~/.claude/settings.json 中添加:');
-        lines.push('#      { "statusLine": "powershell -File ~/.claude/statusline.ps1" }');
-        lines.push('#   重启 Claude Code 即可生效');
-        lines.push('#');
-        lines.push('# ============================================================');
-        lines.push('');
-        lines.push('[Console]::OutputEncoding = [System.Text.Encoding]::UTF8');
-        lines.push('$esc = [char]27');
-        lines.push('$reset = "$esc[0m"');
-        lines.push('');
-
-        // 数据读取
-        lines.push('# 读取 Claude Code 传入的 JSON 数据');
-        lines.push('$data = [Console]::In.ReadToEnd() | ConvertFrom-Json');
-        lines.push('');
-
-        // 目录处理 - 完全匹配参考格式
-        lines.push('# 目录显示: D:\\first\\A~\\B~\\last (中间目录用首字母~缩写)');
-        lines.push('$fullPath = if ($data.cwd) { $data.cwd } else { (Get-Location).Path }');
-        lines.push('$pathParts = $fullPath -split \'\\\\\'');
-        lines.push('$currentDir = if ($pathParts.Length -le 3) { $fullPath } else {');
-        lines.push('    $first = $pathParts[0]');
-        lines.push('    $second = $pathParts[1]');
-        lines.push('    $middle = $pathParts[2..($pathParts.Length-2)] | ForEach-Object { "$($_[0])~" }');
-        lines.push('    $last = $pathParts[-1]');
-        lines.push('    (@($first, $second) + $middle + $last) -join \'\\\'');
-        lines.push('}');
-        lines.push('');
-
-        // 数据提取
-        lines.push('# 提取数值');
-        lines.push('$pct = [math]::Round($data.context_window.used_percentage, 1)');
-        lines.push('$inTk = $data.context_window.total_input_tokens');
-        lines.push('$outTk = $data.context_window.total_output_tokens');
-        lines.push('$maxTk = if ($data.context_window.max_tokens) { $data.context_window.max_tokens } else { 200000 }');
-        lines.push('$cacheR = if ($data.context_window.current_usage.cache_read_input_tokens) { $data.context_window.current_usage.cache_read_input_tokens } else { 0 }');
-        lines.push('$cacheW = if ($data.context_window.current_usage.cache_creation_input_tokens) { $data.context_window.current_usage.cache_creation_input_tokens } else { 0 }');
-        lines.push('$cost = if ($data.cost.total_cost_usd) { [math]::Round($data.cost.total_cost_usd, 4) } else { 0 }');
-        lines.push('');
-
-        lines.push('# 格式化数字 (1000+ 显示为 k)');
-        lines.push('function K($n) { if ($n -ge 1000) { "$([math]::Round($n/1000.0,1))k" } else { "$n" } }');
-        lines.push('$usedK = K ([math]::Round($maxTk * $pct / 100))');
-        lines.push('');
-
-        lines.push('# 当前时间 (HH:mm 格式)');
-        lines.push('$currentTime = (Get-Date).ToString("HH:mm")');
-        lines.push('');
-
-        // 颜色定义
-        lines.push('# 颜色定义 (256色)');
-        lines.push(this.generateColorCode());
-        lines.push('');
-
-        // 图标定义
-        lines.push('# 图标 (Unicode Emoji)');
-        lines.push(this.generateIconCode());
-        lines.push('');
-
-        // 分隔符和输出
-        lines.push('');
-        lines.push(this.generateSeparatorAndOutput());
-        lines.push('');
-        lines.push('Write-Output $output');
-        lines.push('');
-
-        return lines.join('\r\n');
-    }
-
-    /**
-     * 生成颜色代码
-     */
-    generateColorCode() {
-        const lines = [];
-        const usedColors = new Set();
-        usedColors.add(this.config.separator.color);
-
-        this.config.items.forEach(item => {
-            if (item.enabled) {
-                usedColors.add(item.color);
-            }
-        });
-
-        // 完全匹配参考格式的颜色名称和注释对齐
-        const colorNames = {
-            81: { var: '$cModel', comment: '# 青色 - 模型', align: '   ' },
-            153: { var: '$cNum', comment: '# 淡蓝 - 数字', align: '  ' },
-            222: { var: '$cCost', comment: '# 金色 - 费用', align: '  ' },
-            183: { var: '$cCache', comment: '# 淡紫 - 缓存', align: ' ' },
-            147: { var: '$cDir', comment: '# 紫色 - 目录', align: '   ' },
-            117: { var: '$cTime', comment: '# 青绿 - 时间', align: '  ' },
-            114: { var: '$cGreen', comment: '# 绿色', align: '' },
-            210: { var: '$cRed', comment: '# 红色', align: '' },
-            221: { var: '$cYellow', comment: '# 黄色', align: '' },
-            252: { var: '$cSep', comment: '# 灰白 - 分隔符', align: ' ' }
-        };
-
-        const definedVars = {};
-        usedColors.forEach(color => {
-            const info = colorNames[color] || { var: `$c${color}`, comment: '', align: '   ' };
-            definedVars[color] = info.var;
-            // 格式: $cModel = "$esc[38;5;81m"   # 青色 - 模型
-            lines.push(`${info.var} = "$esc[38;5;${color}m"${info.align} ${info.comment}`);
-        });
-
-        // 添加动态颜色（如果需要） - 完全匹配参考格式
-        const hasDynamicContext = this.config.items.some(item =>
-            item.enabled && item.dynamicColor && item.colorRanges
-        );
-        if (hasDynamicContext) {
-            lines.push('# 上下文颜色: 绿(<50%) / 黄(50-80%) / 红(>80%)');
-            lines.push('$cPct = if ($pct -gt 80) { "$esc[38;5;210m" } elseif ($pct -gt 50) { "$esc[38;5;221m" } else { "$esc[38;5;114m" }');
-        }
-
-        this.colorVarMap = definedVars;
-        return lines.join('\r\n');
-    }
-
-    /**
-     * 生成图标代码
-     */
-    generateIconCode() {
-        const lines = [];
-        const usedEmojis = new Set();
-
-        // 收集所有启用的项目的 emoji
-        this.config.items.forEach(item => {
-            if (item.enabled) {
-                usedEmojis.add({ emoji: item.emoji, id: item.id });
-            }
-        });
-
-        // 完全匹配参考格式的图标变量名和注释对齐
-        const emojiInfo = {
-            '🤖': { var: '$iModel', code: '0x1F916', comment: '# 🤖', align: '   ' },
-            '⏳': { var: '$iCtx', code: '0x23F3', comment: '# ⏳', align: '   ' },
-            '🧮': { var: '$iTotal', code: '0x1F9EE', comment: '# 🧮', align: '  ' },
-            '🎭': { var: '$iCache', code: '0x1F3AD', comment: '# 🎭', align: '  ' },
-            '💰': { var: '$iCost', code: '0x1F4B0', comment: '# 💰', align: '   ' },
-            '📁': { var: '$iDir', code: '0x1F4C1', comment: '# 📁', align: '   ' },
-            '🕐': { var: '$iTime', code: '0x1F550', comment: '# 🕐', align: '   ' }
-        };
-
-        this.iconVarMap = {};
-        usedEmojis.forEach(({ emoji, id }) => {
-            const info = emojiInfo[emoji] || {
-                var: `$i${id}`,
-                code: '0x' + emoji.codePointAt(0).toString(16).toUpperCase(),
-                comment: '',
-                align: '   '
-            };
-            this.iconVarMap[emoji] = info.var;
-            // 格式: $iModel = [char]::ConvertFromUtf32(0x1F916)  # 🤖
-            lines.push(`${info.var} = [char]::ConvertFromUtf32(${info.code})${info.align} ${info.comment}`);
-        });
-
-        return lines.join('\r\n');
-    }
-
-    /**
-     * 生成分隔符和输出代码
-     */
-    generateSeparatorAndOutput() {
-        const lines = [];
-
-        // 智能换行逻辑
-        lines.push('# ============================================================');
-        lines.push('# 智能换行逻辑');
-        lines.push('# ============================================================');
-        lines.push('');
-        lines.push('# 获取终端宽度');
-        lines.push('$termWidth = $Host.UI.RawUI.WindowSize.Width');
-        lines.push('if (-not $termWidth -or $termWidth -lt 40) { $termWidth = 120 }');
-        lines.push('');
-
-        // 分隔符定义
-        const sepChar = SEPARATOR_STYLES[this.config.separator.style] || this.config.separator.custom;
-        const sepColorVar = this.colorVarMap[this.config.separator.color] || '$cSep';
-        lines.push(`$sep = "${sepColorVar}${sepChar}$reset"`);
-        lines.push('');
-
-        // 定义所有项目数组
-        lines.push('# 定义所有项目');
-        lines.push('$items = @(');
-
-        const enabledItems = this.config.items.filter(item => item.enabled);
-
-        if (enabledItems.length === 0) {
-            lines.push('    ""');
-        } else {
-            enabledItems.forEach((item, index) => {
-                let colorVar;
-                let iconVar;
-
-                if (item.dynamicColor && item.colorRanges) {
-                    colorVar = '$cPct';
-                } else {
-                    colorVar = this.colorVarMap[item.color] || '$cModel';
-                }
-
-                iconVar = this.iconVarMap[item.emoji] || '"?"';
-
-                // 根据 showLabel 决定是否包含项目名称
-                // 注意：在 PowerShell 双引号中，冒号后紧跟 $ 可能导致解析问题
-                // 使用 $() 子表达式确保变量正确展开
-                const content = item.showLabel
-                    ? `${item.label}:$(${item.template})`
-                    : item.template;
-                lines.push(`    "${colorVar}${iconVar} ${content}$reset"`);
-            });
-        }
-
-        lines.push(')');
-        lines.push('');
-
-        // 添加计算显示宽度函数
-        lines.push('# 计算显示宽度（去除ANSI转义序列）');
-        lines.push('function Get-DisplayWidth($str) {');
-        lines.push('    $clean = $str -replace "$esc\\[[0-9;]*m", \'\'');
-        lines.push('    # Emoji占2个字符宽度');
-        lines.push('    $emojiCount = ([regex]::Matches($clean, \'[\\uD800-\\uDBFF][\\uDC00-\\uDFFF]|[\\u2600-\\u26FF\\u2700-\\u27BF]\')).Count');
-        lines.push('    return $clean.Length + $emojiCount');
-        lines.push('}');
-        lines.push('');
-
-        lines.push('$sepLen = 3  # " | " 的显示宽度');
-        lines.push('');
-
-        // 智能换行组装
-        lines.push('# 智能换行组装');
-
-        // 根据开头分隔符设置初始化
-        if (this.config.separator.showStart) {
-            lines.push('$currentLine = "$sep "');
-            lines.push('$currentWidth = 2');
-        } else {
-            lines.push('$currentLine = ""');
-            lines.push('$currentWidth = 0');
-        }
-
-        lines.push('$lines = @()');
-        lines.push('');
-        lines.push('foreach ($item in $items) {');
-        lines.push('    $itemWidth = Get-DisplayWidth $item');
-        lines.push('    $needed = $itemWidth + $sepLen');
-        lines.push('    ');
-        lines.push('    if (($currentWidth + $needed) -gt $termWidth -and $currentWidth -gt 2) {');
-        lines.push('        # 当前行放不下这个项目，换行');
-        lines.push('        $lines += $currentLine.TrimEnd()');
-        lines.push('        $currentLine = "   $item $sep "  # 新行缩进');
-        lines.push('        $currentWidth = 3 + $itemWidth + $sepLen');
-        lines.push('    } else {');
-        lines.push('        $currentLine += "$item $sep "');
-        lines.push('        $currentWidth += $needed');
-        lines.push('    }');
-        lines.push('}');
-        lines.push('');
-
-        // 处理结尾 - 移除最后一个 " $sep "
-        // 注意：不能用 TrimEnd(ToCharArray())，因为 $sep 包含 ANSI 转义序列中的数字字符
-        // 会错误地移除时间等内容末尾的数字
-        lines.push('# 移除末尾的分隔符');
-        lines.push('$sepPattern = " " + $sep + " "');
-        lines.push('if ($currentLine.EndsWith($sepPattern)) {');
-        lines.push('    $currentLine = $currentLine.Substring(0, $currentLine.Length - $sepPattern.Length)');
-        lines.push('}');
-        if (this.config.separator.showEnd) {
-            lines.push('$currentLine = $currentLine + " $sep"');
-        }
-        lines.push('$lines += $currentLine');
-        lines.push('');
-        lines.push('$output = $lines -join "`n"');
-
-        return lines.join('\r\n');
-    }
-
     /**
      * ANSI 256 色转十六进制
      */
@@ -1832,6 +1728,18 @@ class StatuslineManager {
             refreshBtnText.textContent = i18n.t('statusline.files.refresh');
         }
 
+        const importBtn = document.getElementById('import-statusline-btn');
+        const importBtnText = importBtn?.querySelector('span:last-child');
+        if (importBtnText) {
+            importBtnText.textContent = i18n.t('statusline.files.import');
+        }
+
+        const clearBtn = document.getElementById('clear-statusline-btn');
+        const clearBtnText = clearBtn?.querySelector('span:last-child');
+        if (clearBtnText) {
+            clearBtnText.textContent = i18n.t('statusline.files.clearApplied');
+        }
+
         const terminalTitle = document.querySelector('.terminal-title');
         if (terminalTitle) {
             terminalTitle.textContent = i18n.t('statusline.preview.terminalTitle');
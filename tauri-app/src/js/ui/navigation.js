@@ -40,6 +40,10 @@ class NavigationManager {
             droid.loadChannels();
         }
 
+        if (pageName === 'gemini' && typeof gemini !== 'undefined') {
+            gemini.loadChannels();
+        }
+
         if (pageName === 'codex' && typeof codex !== 'undefined') {
             codex.render();
         }
@@ -82,6 +86,8 @@ class NavigationManager {
                 ariaKey = 'codexPage';
             } else if (page === 'droid') {
                 ariaKey = 'droidPage';
+            } else if (page === 'gemini') {
+                ariaKey = 'geminiPage';
             } else if (page === 'statusline') {
                 ariaKey = 'statuslinePage';
             }
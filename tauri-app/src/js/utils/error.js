@@ -2,6 +2,8 @@
  * 错误处理工具类
  * 提供统一的错误处理和格式化功能
  */
+const CONFLICT_ERROR_PREFIX = '[CONFLICT] ';
+
 class ErrorHandler {
     /**
      * 处理错误并返回格式化的错误消息
@@ -26,17 +28,35 @@ class ErrorHandler {
      */
     static formatError(error) {
         if (error instanceof Error) {
-            return error.message;
+            return this.stripConflictPrefix(error.message);
         }
         if (typeof error === 'string') {
-            return error;
+            return this.stripConflictPrefix(error);
         }
         if (error?.error) {
-            return error.error;
+            return this.stripConflictPrefix(error.error);
         }
         return '未知错误';
     }
 
+    /**
+     * 判断是否为后端返回的冲突错误（数据已被其他窗口或程序修改）
+     * @param {Error|string|object} error - 错误对象
+     * @returns {boolean} 是否为冲突错误
+     */
+    static isConflict(error) {
+        const raw = error instanceof Error
+            ? error.message
+            : typeof error === 'string' ? error : error?.error;
+        return typeof raw === 'string' && raw.startsWith(CONFLICT_ERROR_PREFIX);
+    }
+
+    static stripConflictPrefix(message) {
+        return message.startsWith(CONFLICT_ERROR_PREFIX)
+            ? message.slice(CONFLICT_ERROR_PREFIX.length)
+            : message;
+    }
+
     /**
      * 显示错误提示（需要 toast 模块已加载）
      * @param {Error|string|object} error - 错误对象